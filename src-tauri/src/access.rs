@@ -0,0 +1,855 @@
+use crate::keyspace::{self, ServerVersion};
+use redis::{Connection, ConnectionLike, ErrorKind, RedisError, RedisResult, Value};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+// How long a confirmation token can be approved, and how long the approved
+// command may then take to be re-submitted
+const CONFIRMATION_TOKEN_TTL: Duration = Duration::from_secs(300);
+const CONFIRMATION_WINDOW: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Error)]
+pub enum AccessError {
+    #[error("Connection not found")]
+    NotFound,
+    #[error("Connection is read-only")]
+    ReadOnly,
+    #[error("{command} is blocked on a read-only connection ({reason})")]
+    BlockedCommand { command: String, reason: String },
+    #[error("Cannot check {command} on a read-only connection: {message}")]
+    Unverified { command: String, message: String },
+    // Policy errors start with a server-style code the frontend can match on
+    #[error("DENIED: {command} is denied by the connection's command policy")]
+    Denied { command: String },
+    #[error("DENIED: {command} is not in the connection's allow list")]
+    NotAllowed { command: String },
+    #[error("DENIED: Malformed command")]
+    Malformed,
+    #[error("CONFIRM: {token} {command} requires confirmation")]
+    ConfirmationRequired { command: String, token: String },
+    #[error("Unknown or expired confirmation token")]
+    InvalidToken,
+}
+
+// Commands return plain strings to the frontend
+impl From<AccessError> for String {
+    fn from(error: AccessError) -> Self {
+        error.to_string()
+    }
+}
+
+// Raised from inside a guarded connection, so it is shaped like a server
+// error reply: `code()` returns DENIED or CONFIRM
+impl From<AccessError> for RedisError {
+    fn from(error: AccessError) -> Self {
+        let message = error.to_string();
+        let reply = match message.split_once(": ") {
+            Some((code, detail)) => format!("-{} {}\r\n", code, detail),
+            None => format!("-DENIED {}\r\n", message),
+        };
+        match redis::parse_redis_value(reply.as_bytes()).and_then(|v| v.extract_error()) {
+            Err(e) => e,
+            Ok(_) => (ErrorKind::ClientError, "Blocked by command policy", message).into(),
+        }
+    }
+}
+
+// Flags and ACL categories from one COMMAND INFO entry. Categories are only
+// reported by Redis 7.0+.
+#[derive(Debug, Clone, Default)]
+pub struct CommandInfo {
+    pub flags: Vec<String>,
+    pub categories: Vec<String>,
+    pub first_key: usize, // Argument position of the first key; 0 if none
+}
+
+impl CommandInfo {
+    // Whether the command can change data or server state
+    pub fn may_modify(&self) -> bool {
+        self.flags
+            .iter()
+            .any(|flag| BLOCKED_FLAGS.contains(&flag.as_str()))
+    }
+}
+
+const BLOCKED_FLAGS: &[&str] = &["write", "admin", "may_replicate"];
+const BLOCKED_CATEGORIES: &[&str] = &["@dangerous"];
+
+// Scripts and functions run code the flags only describe from these
+// versions: EVAL gains may_replicate in 6.2, and SCRIPT/FUNCTION
+// subcommands get their own flags and categories in 7.0. Older servers
+// cannot prove them safe.
+const SCRIPT_COMMANDS: &[(&str, ServerVersion)] = &[
+    ("EVAL", (6, 2, 0)),
+    ("EVALSHA", (6, 2, 0)),
+    ("EVAL_RO", (7, 0, 0)),
+    ("EVALSHA_RO", (7, 0, 0)),
+    ("FCALL", (7, 0, 0)),
+    ("FCALL_RO", (7, 0, 0)),
+    ("SCRIPT", (7, 0, 0)),
+    ("FUNCTION", (7, 0, 0)),
+];
+
+fn strings(value: Option<&Value>) -> Vec<String> {
+    match value {
+        Some(Value::Array(items)) | Some(Value::Set(items)) => {
+            items.iter().filter_map(keyspace::value_to_string).collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+fn parse_entry(value: &Value) -> Option<CommandInfo> {
+    let Value::Array(fields) = value else {
+        return None;
+    };
+    Some(CommandInfo {
+        flags: strings(fields.get(2)),
+        categories: strings(fields.get(6)),
+        first_key: fields
+            .get(3)
+            .and_then(keyspace::value_to_i64)
+            .and_then(|position| usize::try_from(position).ok())
+            .unwrap_or(0),
+    })
+}
+
+// Looks up `name` and, for container commands on Redis 7.0+, `name|sub`;
+// the subcommand entry is preferred since the container has no flags
+pub fn command_info(
+    conn: &mut dyn ConnectionLike,
+    name: &str,
+    subcommand: Option<&str>,
+) -> redis::RedisResult<Option<CommandInfo>> {
+    let name = name.to_lowercase();
+    let mut cmd = redis::cmd("COMMAND");
+    cmd.arg("INFO").arg(&name);
+    if let Some(subcommand) = subcommand {
+        cmd.arg(format!("{}|{}", name, subcommand.to_lowercase()));
+    }
+    let entries: Vec<Value> = cmd.query(conn)?;
+    Ok(entries.iter().rev().find_map(parse_entry))
+}
+
+// Rejects anything that may modify data or the server. Unknown commands are
+// left for the server to reject; a failed lookup blocks the command.
+pub fn check_read_only_command(
+    conn: &mut dyn ConnectionLike,
+    args: &[&str],
+) -> Result<(), AccessError> {
+    let Some(name) = args.first() else {
+        return Ok(());
+    };
+    let command = name.to_uppercase();
+    if let Some((_, minimum)) = SCRIPT_COMMANDS
+        .iter()
+        .find(|(script, _)| *script == command)
+    {
+        if keyspace::server_version(conn).is_none_or(|version| version < *minimum) {
+            return Err(AccessError::BlockedCommand {
+                command,
+                reason: format!("cannot be checked before Redis {}.{}", minimum.0, minimum.1),
+            });
+        }
+    }
+    let info =
+        command_info(conn, name, args.get(1).copied()).map_err(|e| AccessError::Unverified {
+            command: command.clone(),
+            message: e.to_string(),
+        })?;
+    let Some(info) = info else {
+        return Ok(());
+    };
+
+    let reason = info
+        .flags
+        .iter()
+        .find(|flag| BLOCKED_FLAGS.contains(&flag.as_str()))
+        .map(|flag| format!("{} flag", flag))
+        .or_else(|| {
+            info.categories
+                .iter()
+                .find(|category| BLOCKED_CATEGORIES.contains(&category.as_str()))
+                .map(|category| format!("{} category", category))
+        });
+    match reason {
+        Some(reason) => Err(AccessError::BlockedCommand { command, reason }),
+        None => Ok(()),
+    }
+}
+
+// Replicas reject writes anyway, so they are treated as read-only
+pub fn is_replica(conn: &mut dyn ConnectionLike) -> bool {
+    redis::cmd("INFO")
+        .arg("replication")
+        .query::<String>(conn)
+        .map(|info| info.lines().any(|line| line.trim() == "role:slave"))
+        .unwrap_or(false)
+}
+
+// Per-connection guardrails, saved with the connection. Entries name a
+// command ("FLUSHALL") or one subcommand ("CONFIG SET"), case-insensitively.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CommandPolicy {
+    #[serde(default)]
+    pub deny: Vec<String>,
+    // When non-empty only these run, including the commands the app sends
+    // itself to browse keys. COMMAND INFO is always let through: it only
+    // describes commands and is how the console decides what to audit.
+    #[serde(default)]
+    pub allow: Vec<String>,
+    #[serde(default)]
+    pub confirm: Vec<String>,
+    pub max_scan_count: Option<u32>, // Larger SCAN/HSCAN/SSCAN/ZSCAN counts are lowered
+}
+
+impl CommandPolicy {
+    pub fn is_empty(&self) -> bool {
+        self.deny.is_empty()
+            && self.allow.is_empty()
+            && self.confirm.is_empty()
+            && self.max_scan_count.is_none()
+    }
+
+    // The undo journal snapshots keys with DUMP and PTTL before each edit,
+    // so an allow list must permit both while the journal is enabled
+    pub fn validate(&self, undo_journal: bool) -> Result<(), String> {
+        if !undo_journal || self.allow.is_empty() {
+            return Ok(());
+        }
+        let missing: Vec<&str> = ["DUMP", "PTTL"]
+            .into_iter()
+            .filter(|name| find_entry(&self.allow, name, None).is_none())
+            .collect();
+        if missing.is_empty() {
+            return Ok(());
+        }
+        Err(format!(
+            "The allow list must include {} while the undo journal is enabled",
+            missing.join(" and ")
+        ))
+    }
+
+    // Combine with another policy so the result is at least as strict as
+    // both: lists of denied and confirmed commands are joined, allow lists
+    // keep only what both permit, and the lower SCAN count wins
+    pub fn restrict(self, other: CommandPolicy) -> CommandPolicy {
+        let allow = match (self.allow.is_empty(), other.allow.is_empty()) {
+            (true, _) => other.allow,
+            (_, true) => self.allow,
+            _ => {
+                let mut allow = permitted_by(&self.allow, &other.allow);
+                for entry in permitted_by(&other.allow, &self.allow) {
+                    if !allow.contains(&entry) {
+                        allow.push(entry);
+                    }
+                }
+                allow
+            }
+        };
+        CommandPolicy {
+            deny: union(self.deny, other.deny),
+            allow,
+            confirm: union(self.confirm, other.confirm),
+            max_scan_count: match (self.max_scan_count, other.max_scan_count) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            },
+        }
+    }
+}
+
+fn union(mut entries: Vec<String>, other: Vec<String>) -> Vec<String> {
+    for entry in other {
+        if !entries.iter().any(|e| e.eq_ignore_ascii_case(&entry)) {
+            entries.push(entry);
+        }
+    }
+    entries
+}
+
+// Entries of `entries` that `allow` also lets through, normalised to upper
+// case. "CONFIG GET" survives an allow list holding "CONFIG", not the reverse.
+fn permitted_by(entries: &[String], allow: &[String]) -> Vec<String> {
+    entries
+        .iter()
+        .filter_map(|entry| {
+            let words: Vec<&str> = entry.split_whitespace().collect();
+            let allowed = match words.as_slice() {
+                [command] => find_entry(allow, command, None).is_some(),
+                [command, sub] => find_entry(allow, command, Some(sub)).is_some(),
+                _ => false,
+            };
+            allowed.then(|| words.join(" ").to_uppercase())
+        })
+        .collect()
+}
+
+// The entry matching `name`/`sub`, normalised to upper case
+fn find_entry(entries: &[String], name: &str, sub: Option<&str>) -> Option<String> {
+    entries.iter().find_map(|entry| {
+        let words: Vec<&str> = entry.split_whitespace().collect();
+        let matched = match words.as_slice() {
+            [command] => command.eq_ignore_ascii_case(name),
+            [command, subcommand] => {
+                command.eq_ignore_ascii_case(name)
+                    && sub.is_some_and(|sub| sub.eq_ignore_ascii_case(subcommand))
+            }
+            _ => false,
+        };
+        matched.then(|| words.join(" ").to_uppercase())
+    })
+}
+
+fn new_token() -> String {
+    format!("{:016x}", RandomState::new().build_hasher().finish())
+}
+
+// A command held back for confirmation. The token only ever lets this exact
+// command through, once.
+struct Confirmation {
+    entry: String,         // Policy entry that matched
+    command: Vec<Vec<u8>>, // Arguments as first submitted
+    issued_at: Instant,
+    approved_at: Option<Instant>,
+}
+
+// Applies a CommandPolicy to every request sent over a connection
+pub struct CommandGuard {
+    policy: CommandPolicy,
+    confirmations: Mutex<HashMap<String, Confirmation>>, // By token
+}
+
+impl CommandGuard {
+    pub fn new(policy: CommandPolicy) -> Self {
+        Self {
+            policy,
+            confirmations: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn policy(&self) -> &CommandPolicy {
+        &self.policy
+    }
+
+    // `token` is the approved confirmation the caller re-submitted the
+    // command with, if any. Redeeming it adds its entry to `approved`, which
+    // lets later commands under that entry through without asking again.
+    fn check(
+        &self,
+        args: &[Vec<u8>],
+        token: Option<&str>,
+        approved: &mut Vec<String>,
+    ) -> Result<(), AccessError> {
+        let name = String::from_utf8_lossy(&args[0]);
+        let sub = args.get(1).map(|sub| String::from_utf8_lossy(sub));
+        let (name, sub) = (name.as_ref(), sub.as_deref());
+
+        if let Some(entry) = find_entry(&self.policy.deny, name, sub) {
+            return Err(AccessError::Denied { command: entry });
+        }
+        let lookup = name.eq_ignore_ascii_case("COMMAND")
+            && sub.is_some_and(|sub| sub.eq_ignore_ascii_case("INFO"));
+        if !self.policy.allow.is_empty()
+            && !lookup
+            && find_entry(&self.policy.allow, name, sub).is_none()
+        {
+            return Err(AccessError::NotAllowed {
+                command: name.to_uppercase(),
+            });
+        }
+
+        let Some(entry) = find_entry(&self.policy.confirm, name, sub) else {
+            return Ok(());
+        };
+        if approved.contains(&entry) {
+            return Ok(());
+        }
+        let mut confirmations = self.confirmations.lock().unwrap();
+        let now = Instant::now();
+        confirmations.retain(|_, confirmation| match confirmation.approved_at {
+            Some(at) => now.duration_since(at) < CONFIRMATION_WINDOW,
+            None => now.duration_since(confirmation.issued_at) < CONFIRMATION_TOKEN_TTL,
+        });
+        let redeemable = token
+            .and_then(|token| confirmations.get(token))
+            .is_some_and(|c| c.approved_at.is_some() && c.command == args);
+        if redeemable {
+            confirmations.remove(token.unwrap_or_default());
+            approved.push(entry);
+            return Ok(());
+        }
+
+        let token = new_token();
+        confirmations.insert(
+            token.clone(),
+            Confirmation {
+                entry: entry.clone(),
+                command: args.to_vec(),
+                issued_at: now,
+                approved_at: None,
+            },
+        );
+        Err(AccessError::ConfirmationRequired {
+            command: entry,
+            token,
+        })
+    }
+
+    // Approve a token from a ConfirmationRequired error; returns the policy
+    // entry it was issued for. The command must then be re-submitted with
+    // the same token and arguments.
+    pub fn confirm(&self, token: &str) -> Result<String, AccessError> {
+        let mut confirmations = self.confirmations.lock().unwrap();
+        let confirmation = confirmations
+            .get_mut(token)
+            .filter(|c| c.approved_at.is_none())
+            .filter(|c| c.issued_at.elapsed() < CONFIRMATION_TOKEN_TTL)
+            .ok_or(AccessError::InvalidToken)?;
+        confirmation.approved_at = Some(Instant::now());
+        Ok(confirmation.entry.clone())
+    }
+
+    // Lower an oversized COUNT; returns true if an argument was changed
+    fn limit_scan_count(&self, args: &mut [Vec<u8>]) -> bool {
+        let Some(max) = self.policy.max_scan_count else {
+            return false;
+        };
+        let name = String::from_utf8_lossy(&args[0]).to_uppercase();
+        // Options start after the cursor
+        let mut i = match name.as_str() {
+            "SCAN" => 2,
+            "HSCAN" | "SSCAN" | "ZSCAN" => 3,
+            _ => return false,
+        };
+        let mut changed = false;
+        while i < args.len() {
+            if args[i].eq_ignore_ascii_case(b"NOVALUES") {
+                i += 1;
+                continue;
+            }
+            if args[i].eq_ignore_ascii_case(b"COUNT") && i + 1 < args.len() {
+                let count = std::str::from_utf8(&args[i + 1])
+                    .ok()
+                    .and_then(|count| count.parse::<u64>().ok());
+                if count.is_some_and(|count| count > max as u64) {
+                    args[i + 1] = max.to_string().into_bytes();
+                    changed = true;
+                }
+            }
+            i += 2;
+        }
+        changed
+    }
+
+    // Check every command in a packed request. Returns a re-packed request
+    // when a SCAN count was lowered.
+    fn apply(
+        &self,
+        packed: &[u8],
+        token: Option<&str>,
+        approved: &mut Vec<String>,
+    ) -> Result<Option<Vec<u8>>, AccessError> {
+        let mut commands = unpack_commands(packed).ok_or(AccessError::Malformed)?;
+        let mut changed = false;
+        for args in &mut commands {
+            self.check(args, token, approved)?;
+            changed |= self.limit_scan_count(args);
+        }
+        Ok(changed.then(|| pack_commands(&commands)))
+    }
+}
+
+// Read a "*N\r\n" or "$N\r\n" header
+fn read_header(data: &[u8], prefix: u8) -> Option<(usize, &[u8])> {
+    let rest = data.strip_prefix(&[prefix])?;
+    let end = rest.windows(2).position(|w| w == b"\r\n")?;
+    let n = std::str::from_utf8(&rest[..end]).ok()?.parse().ok()?;
+    Some((n, &rest[end + 2..]))
+}
+
+// Split a packed request (several commands when pipelined) into arguments
+fn unpack_commands(mut packed: &[u8]) -> Option<Vec<Vec<Vec<u8>>>> {
+    let mut commands = Vec::new();
+    while !packed.is_empty() {
+        let (count, rest) = read_header(packed, b'*')?;
+        if count == 0 {
+            return None;
+        }
+        packed = rest;
+        let mut args = Vec::with_capacity(count);
+        for _ in 0..count {
+            let (len, rest) = read_header(packed, b'$')?;
+            args.push(rest.get(..len)?.to_vec());
+            packed = rest.get(len + 2..)?;
+        }
+        commands.push(args);
+    }
+    Some(commands)
+}
+
+fn pack_commands(commands: &[Vec<Vec<u8>>]) -> Vec<u8> {
+    let mut packed = Vec::new();
+    for args in commands {
+        packed.extend_from_slice(format!("*{}\r\n", args.len()).as_bytes());
+        for arg in args {
+            packed.extend_from_slice(format!("${}\r\n", arg.len()).as_bytes());
+            packed.extend_from_slice(arg);
+            packed.extend_from_slice(b"\r\n");
+        }
+    }
+    packed
+}
+
+// A live connection whose requests pass through a CommandGuard first
+pub struct GuardedConnection<C = Connection> {
+    inner: C,
+    guard: Arc<CommandGuard>,
+    confirmation: Option<String>, // Approved token to redeem
+    approved: Vec<String>,        // Confirm entries redeemed on this connection
+}
+
+impl<C: ConnectionLike> GuardedConnection<C> {
+    pub fn new(inner: C, guard: Arc<CommandGuard>) -> Self {
+        Self {
+            inner,
+            guard,
+            confirmation: None,
+            approved: Vec::new(),
+        }
+    }
+
+    // Re-submit a command that was approved with `token`. Once redeemed, the
+    // approval covers the rest of this connection's commands under the same
+    // policy entry, so an edit or job that needs several of them is only
+    // confirmed once.
+    pub fn with_confirmation(mut self, token: Option<String>) -> Self {
+        self.confirmation = token;
+        self
+    }
+}
+
+impl<C: ConnectionLike> ConnectionLike for GuardedConnection<C> {
+    fn req_packed_command(&mut self, cmd: &[u8]) -> RedisResult<Value> {
+        let token = self.confirmation.as_deref();
+        match self.guard.apply(cmd, token, &mut self.approved)? {
+            Some(cmd) => self.inner.req_packed_command(&cmd),
+            None => self.inner.req_packed_command(cmd),
+        }
+    }
+
+    fn req_packed_commands(
+        &mut self,
+        cmd: &[u8],
+        offset: usize,
+        count: usize,
+    ) -> RedisResult<Vec<Value>> {
+        let token = self.confirmation.as_deref();
+        match self.guard.apply(cmd, token, &mut self.approved)? {
+            Some(cmd) => self.inner.req_packed_commands(&cmd, offset, count),
+            None => self.inner.req_packed_commands(cmd, offset, count),
+        }
+    }
+
+    fn get_db(&self) -> i64 {
+        self.inner.get_db()
+    }
+
+    fn check_connection(&mut self) -> bool {
+        self.inner.check_connection()
+    }
+
+    fn is_open(&self) -> bool {
+        self.inner.is_open()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(items: &[&str]) -> Vec<String> {
+        items.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn args(items: &[&str]) -> Vec<Vec<u8>> {
+        items.iter().map(|s| s.as_bytes().to_vec()).collect()
+    }
+
+    fn token(error: AccessError) -> String {
+        match error {
+            AccessError::ConfirmationRequired { token, .. } => token,
+            other => panic!("expected a confirmation, got {}", other),
+        }
+    }
+
+    #[test]
+    fn pack_and_unpack_round_trip() {
+        let commands = vec![args(&["SET", "k", "v\r\n"]), args(&["GET", ""])];
+        let packed = pack_commands(&commands);
+        assert_eq!(
+            packed,
+            b"*3\r\n$3\r\nSET\r\n$1\r\nk\r\n$3\r\nv\r\n\r\n*2\r\n$3\r\nGET\r\n$0\r\n\r\n"
+        );
+        assert_eq!(unpack_commands(&packed), Some(commands));
+
+        let cmd = redis::cmd("HSET").arg("h").arg(vec![0xff_u8, 0]).clone();
+        let unpacked = unpack_commands(&cmd.get_packed_command()).unwrap();
+        assert_eq!(
+            unpacked,
+            vec![vec![b"HSET".to_vec(), b"h".to_vec(), vec![0xff, 0]]]
+        );
+    }
+
+    #[test]
+    fn unpack_rejects_malformed_requests() {
+        for packed in [
+            b"*0\r\n".as_slice(),
+            b"*1\r\n$3\r\nGE",
+            b"*1\r\n$3\r\nGETX\r\n",
+            b"*2\r\n$3\r\nGET\r\n",
+            b"*x\r\n$3\r\nGET\r\n",
+            b"PING\r\n",
+        ] {
+            assert_eq!(unpack_commands(packed), None, "{:?}", packed);
+        }
+        assert_eq!(unpack_commands(b""), Some(Vec::new()));
+    }
+
+    #[test]
+    fn find_entry_matches_commands_and_subcommands() {
+        let list = entries(&["flushall", "config  set", "DEBUG SLEEP EXTRA"]);
+        assert_eq!(find_entry(&list, "FLUSHALL", None), Some("FLUSHALL".into()));
+        assert_eq!(
+            find_entry(&list, "FlushAll", Some("ASYNC")),
+            Some("FLUSHALL".into())
+        );
+        assert_eq!(
+            find_entry(&list, "CONFIG", Some("Set")),
+            Some("CONFIG SET".into())
+        );
+        assert_eq!(find_entry(&list, "CONFIG", Some("GET")), None);
+        assert_eq!(find_entry(&list, "CONFIG", None), None);
+        assert_eq!(find_entry(&list, "DEBUG", Some("SLEEP")), None);
+    }
+
+    #[test]
+    fn limit_scan_count_lowers_oversized_counts() {
+        let guard = CommandGuard::new(CommandPolicy {
+            max_scan_count: Some(100),
+            ..Default::default()
+        });
+
+        let mut scan = args(&["SCAN", "0", "MATCH", "*", "COUNT", "5000"]);
+        assert!(guard.limit_scan_count(&mut scan));
+        assert_eq!(scan, args(&["SCAN", "0", "MATCH", "*", "COUNT", "100"]));
+
+        let mut hscan = args(&["hscan", "h", "0", "NOVALUES", "count", "101"]);
+        assert!(guard.limit_scan_count(&mut hscan));
+        assert_eq!(hscan[5], b"100");
+
+        // The key of an SSCAN is not mistaken for an option
+        let mut small = args(&["SSCAN", "COUNT", "0", "COUNT", "50"]);
+        assert!(!guard.limit_scan_count(&mut small));
+        let mut other = args(&["LRANGE", "COUNT", "0", "5000"]);
+        assert!(!guard.limit_scan_count(&mut other));
+
+        let unlimited = CommandGuard::new(CommandPolicy::default());
+        let mut scan = args(&["SCAN", "0", "COUNT", "5000"]);
+        assert!(!unlimited.limit_scan_count(&mut scan));
+    }
+
+    #[test]
+    fn restrict_only_adds_restrictions() {
+        let stored = CommandPolicy {
+            deny: entries(&["FLUSHALL"]),
+            allow: entries(&["GET", "SCAN", "CONFIG"]),
+            confirm: entries(&["DEL"]),
+            max_scan_count: Some(500),
+        };
+        let caller = CommandPolicy {
+            deny: entries(&["flushall", "KEYS"]),
+            allow: entries(&["GET", "SET", "CONFIG GET"]),
+            confirm: Vec::new(),
+            max_scan_count: Some(1000),
+        };
+        let merged = stored.clone().restrict(caller);
+        assert_eq!(merged.deny, entries(&["FLUSHALL", "KEYS"]));
+        assert_eq!(merged.allow, entries(&["GET", "CONFIG GET"]));
+        assert_eq!(merged.confirm, entries(&["DEL"]));
+        assert_eq!(merged.max_scan_count, Some(500));
+
+        // An empty caller policy cannot loosen the stored one
+        let merged = stored.clone().restrict(CommandPolicy::default());
+        assert_eq!(merged.allow, stored.allow);
+        assert_eq!(merged.deny, stored.deny);
+        assert_eq!(merged.max_scan_count, Some(500));
+    }
+
+    #[test]
+    fn confirmations_are_bound_to_one_command_and_single_use() {
+        let guard = CommandGuard::new(CommandPolicy {
+            confirm: entries(&["DEL"]),
+            ..Default::default()
+        });
+        let del_a = args(&["DEL", "a"]);
+        let del_b = args(&["DEL", "b"]);
+
+        let first = token(guard.check(&del_a, None, &mut Vec::new()).unwrap_err());
+        // Not approved yet
+        assert!(guard.check(&del_a, Some(&first), &mut Vec::new()).is_err());
+        assert_eq!(guard.confirm(&first).unwrap(), "DEL");
+        assert!(matches!(
+            guard.confirm(&first),
+            Err(AccessError::InvalidToken)
+        ));
+
+        // Another command, or no token, does not ride on the approval
+        assert!(guard.check(&del_b, Some(&first), &mut Vec::new()).is_err());
+        assert!(guard.check(&del_a, None, &mut Vec::new()).is_err());
+
+        assert!(guard.check(&del_a, Some(&first), &mut Vec::new()).is_ok());
+        assert!(guard.check(&del_a, Some(&first), &mut Vec::new()).is_err());
+        assert!(guard
+            .check(&args(&["GET", "a"]), None, &mut Vec::new())
+            .is_ok());
+        assert!(matches!(
+            guard.confirm("unknown"),
+            Err(AccessError::InvalidToken)
+        ));
+    }
+
+    // Stands in for the server: records what gets through and replies 1
+    #[derive(Default)]
+    struct RecordingConnection {
+        received: Vec<Vec<u8>>,
+    }
+
+    impl ConnectionLike for RecordingConnection {
+        fn req_packed_command(&mut self, cmd: &[u8]) -> RedisResult<Value> {
+            self.received.push(cmd.to_vec());
+            Ok(Value::Int(1))
+        }
+
+        fn req_packed_commands(
+            &mut self,
+            cmd: &[u8],
+            _offset: usize,
+            count: usize,
+        ) -> RedisResult<Vec<Value>> {
+            self.received.push(cmd.to_vec());
+            Ok(vec![Value::Int(1); count])
+        }
+
+        fn get_db(&self) -> i64 {
+            0
+        }
+
+        fn check_connection(&mut self) -> bool {
+            true
+        }
+
+        fn is_open(&self) -> bool {
+            true
+        }
+    }
+
+    #[test]
+    fn confirmed_delete_key_goes_through() {
+        let guard = Arc::new(CommandGuard::new(CommandPolicy {
+            confirm: entries(&["DEL"]),
+            ..Default::default()
+        }));
+        // The command delete_key sends
+        let delete = || redis::cmd("DEL").arg("session:1").clone();
+
+        let mut conn = GuardedConnection::new(RecordingConnection::default(), guard.clone());
+        let error = delete().query::<i64>(&mut conn).unwrap_err();
+        assert_eq!(error.code(), Some("CONFIRM"));
+        assert!(conn.inner.received.is_empty());
+
+        let token = error
+            .detail()
+            .unwrap()
+            .split(' ')
+            .next()
+            .unwrap()
+            .to_string();
+        assert_eq!(guard.confirm(&token).unwrap(), "DEL");
+
+        let mut conn = GuardedConnection::new(RecordingConnection::default(), guard.clone())
+            .with_confirmation(Some(token.clone()));
+        assert_eq!(delete().query::<i64>(&mut conn).unwrap(), 1);
+        assert_eq!(conn.inner.received, vec![delete().get_packed_command()]);
+
+        // The token is spent; a new connection has to ask again
+        let mut conn = GuardedConnection::new(RecordingConnection::default(), guard)
+            .with_confirmation(Some(token));
+        assert!(delete().query::<i64>(&mut conn).is_err());
+        assert!(conn.inner.received.is_empty());
+    }
+
+    #[test]
+    fn redeemed_confirmations_cover_the_rest_of_the_connection() {
+        let guard = Arc::new(CommandGuard::new(CommandPolicy {
+            confirm: entries(&["UNLINK"]),
+            ..Default::default()
+        }));
+        let first = token(
+            guard
+                .check(&args(&["UNLINK", "a"]), None, &mut Vec::new())
+                .unwrap_err(),
+        );
+        guard.confirm(&first).unwrap();
+
+        let mut conn = GuardedConnection::new(RecordingConnection::default(), guard)
+            .with_confirmation(Some(first));
+        redis::cmd("UNLINK")
+            .arg("a")
+            .query::<i64>(&mut conn)
+            .unwrap();
+        redis::pipe()
+            .cmd("UNLINK")
+            .arg("b")
+            .cmd("UNLINK")
+            .arg("c")
+            .query::<Vec<i64>>(&mut conn)
+            .unwrap();
+        assert_eq!(conn.inner.received.len(), 2);
+    }
+
+    #[test]
+    fn allow_lists_let_command_info_through() {
+        let guard = CommandGuard::new(CommandPolicy {
+            allow: entries(&["GET"]),
+            ..Default::default()
+        });
+        let mut approved = Vec::new();
+        assert!(guard
+            .check(&args(&["COMMAND", "INFO", "set"]), None, &mut approved)
+            .is_ok());
+        assert!(guard
+            .check(&args(&["COMMAND", "DOCS"]), None, &mut approved)
+            .is_err());
+        assert!(guard
+            .check(&args(&["DUMP", "a"]), None, &mut approved)
+            .is_err());
+    }
+
+    #[test]
+    fn allow_lists_must_permit_undo_snapshots() {
+        let policy = CommandPolicy {
+            allow: entries(&["GET", "SET", "dump"]),
+            ..Default::default()
+        };
+        assert!(policy.validate(false).is_ok());
+        let error = policy.validate(true).unwrap_err();
+        assert!(error.contains("PTTL") && !error.contains("DUMP"));
+        assert!(CommandPolicy::default().validate(true).is_ok());
+    }
+}
@@ -0,0 +1,804 @@
+use crate::jobs::CancelToken;
+use crate::rdb;
+use crate::snapshot::glob_match;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AofFileKind {
+    Plain, // Single-file AOF (Redis < 7, or a file opened directly)
+    Base,  // Multi-part base file, AOF or RDB format
+    Incr,  // Multi-part incremental file
+}
+
+#[derive(Debug, Clone)]
+pub struct AofPart {
+    pub name: String,
+    pub path: PathBuf,
+    pub kind: AofFileKind,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct AofFilter {
+    pub key_pattern: Option<String>,
+    pub commands: Vec<String>, // Case-insensitive; empty matches every command
+    pub db: Option<u32>,
+    pub from_timestamp: Option<i64>, // Unix seconds, from #TS annotations
+    pub to_timestamp: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RdbPreamble {
+    pub rdb_version: u32,
+    pub keys: u64,
+    pub bytes: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AofFileSummary {
+    pub name: String,
+    pub kind: AofFileKind,
+    pub size: usize,
+    pub commands: u64,
+    pub rdb_preamble: Option<RdbPreamble>,
+    pub truncated: bool,       // Ends in the middle of a command
+    pub error: Option<String>, // Parsing stopped at corrupt data
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AofEntry {
+    pub file: String,
+    pub offset: usize,
+    pub db: u32,
+    pub timestamp: Option<i64>,
+    pub args: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AofCommandStats {
+    pub command: String,
+    pub count: u64,
+    pub first_timestamp: Option<i64>,
+    pub last_timestamp: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AofTimelineBucket {
+    pub start: i64, // Unix seconds
+    pub counts: BTreeMap<String, u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AofReport {
+    pub path: String,
+    pub files: Vec<AofFileSummary>,
+    pub total_commands: u64,
+    pub matched_commands: u64,
+    pub has_timestamps: bool,
+    pub commands: Vec<AofCommandStats>, // Matched commands, most frequent first
+    pub timeline: Vec<AofTimelineBucket>,
+    pub entries: Vec<AofEntry>,
+    pub cancelled: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AofExportFormat {
+    Resp, // Raw protocol, replayable with `redis-cli --pipe`
+    Cli,  // One quoted command per line
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AofExportSummary {
+    pub output_path: String,
+    pub commands_written: u64,
+    pub cancelled: bool,
+}
+
+// A command read from the log, with the database it ran against
+struct AofCommand<'a> {
+    file: &'a str,
+    offset: usize,
+    db: u32,
+    timestamp: Option<i64>,
+    args: Vec<&'a [u8]>,
+}
+
+impl AofCommand<'_> {
+    fn name(&self) -> String {
+        self.args
+            .first()
+            .map(|a| String::from_utf8_lossy(a).to_uppercase())
+            .unwrap_or_default()
+    }
+
+    // Key arguments for the commands that show up in an AOF. Commands not
+    // listed are assumed to take a single key as their first argument.
+    fn keys(&self) -> Vec<&[u8]> {
+        let args = &self.args[1.min(self.args.len())..];
+        match self.name().as_str() {
+            "SELECT" | "MULTI" | "EXEC" | "FLUSHALL" | "FLUSHDB" | "SWAPDB" | "FUNCTION"
+            | "SCRIPT" | "PING" => vec![],
+            "DEL" | "UNLINK" | "EXISTS" | "TOUCH" | "WATCH" | "PFMERGE" | "SINTERSTORE"
+            | "SUNIONSTORE" | "SDIFFSTORE" => args.to_vec(),
+            "MSET" | "MSETNX" => args.iter().step_by(2).copied().collect(),
+            "RENAME" | "RENAMENX" | "COPY" | "SMOVE" | "LMOVE" | "RPOPLPUSH" | "BLMOVE"
+            | "GEOSEARCHSTORE" => args.iter().take(2).copied().collect(),
+            "EVAL" | "EVALSHA" | "FCALL" => {
+                let count = args
+                    .get(1)
+                    .and_then(|n| std::str::from_utf8(n).ok()?.parse::<usize>().ok())
+                    .unwrap_or(0);
+                args.iter().skip(2).take(count).copied().collect()
+            }
+            _ => args.first().copied().into_iter().collect(),
+        }
+    }
+}
+
+impl AofFilter {
+    fn matches(&self, cmd: &AofCommand) -> bool {
+        if self.db.is_some_and(|db| db != cmd.db) {
+            return false;
+        }
+        if !self.commands.is_empty() {
+            let name = cmd.name();
+            if !self.commands.iter().any(|c| c.eq_ignore_ascii_case(&name)) {
+                return false;
+            }
+        }
+        if let Some(from) = self.from_timestamp {
+            if cmd.timestamp.is_none_or(|ts| ts < from) {
+                return false;
+            }
+        }
+        if let Some(to) = self.to_timestamp {
+            if cmd.timestamp.is_none_or(|ts| ts > to) {
+                return false;
+            }
+        }
+        if let Some(pattern) = self.key_pattern.as_deref().filter(|p| *p != "*") {
+            if !cmd
+                .keys()
+                .iter()
+                .any(|key| glob_match(pattern.as_bytes(), key))
+            {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+// Resolve a path to the files to read, in replay order. Accepts a plain AOF
+// file, a multi-part manifest, or the directory holding the manifest.
+pub fn resolve_parts(path: &str) -> Result<Vec<AofPart>, String> {
+    let path = Path::new(path);
+
+    let manifest = if path.is_dir() {
+        let entries = std::fs::read_dir(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let manifest = entries
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .find(|p| p.extension().is_some_and(|ext| ext == "manifest"))
+            .ok_or_else(|| format!("No AOF manifest found in {}", path.display()))?;
+        Some(manifest)
+    } else if path.extension().is_some_and(|ext| ext == "manifest") {
+        Some(path.to_path_buf())
+    } else {
+        None
+    };
+
+    let Some(manifest) = manifest else {
+        return Ok(vec![AofPart {
+            name: path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            path: path.to_path_buf(),
+            kind: AofFileKind::Plain,
+        }]);
+    };
+
+    let content = std::fs::read_to_string(&manifest)
+        .map_err(|e| format!("Failed to read {}: {}", manifest.display(), e))?;
+    let dir = manifest.parent().unwrap_or(Path::new("."));
+
+    // Lines look like: file appendonly.aof.1.base.rdb seq 1 type b
+    let mut base = None;
+    let mut incrs = Vec::new();
+    for line in content.lines().filter(|l| !l.trim().is_empty()) {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let field = |name: &str| {
+            tokens
+                .iter()
+                .position(|t| *t == name)
+                .and_then(|i| tokens.get(i + 1))
+                .map(|v| v.trim_matches('"'))
+        };
+
+        let name = field("file").ok_or_else(|| format!("Invalid manifest line: {}", line))?;
+        let seq: u64 = field("seq").and_then(|s| s.parse().ok()).unwrap_or(0);
+        let part = |kind| AofPart {
+            name: name.to_string(),
+            path: dir.join(name),
+            kind,
+        };
+
+        match field("type") {
+            Some("b") => base = Some(part(AofFileKind::Base)),
+            Some("i") => incrs.push((seq, part(AofFileKind::Incr))),
+            _ => {} // History files are no longer part of the dataset
+        }
+    }
+
+    incrs.sort_by_key(|(seq, _)| *seq);
+    Ok(base
+        .into_iter()
+        .chain(incrs.into_iter().map(|(_, part)| part))
+        .collect())
+}
+
+pub fn parts_size(parts: &[AofPart]) -> u64 {
+    parts
+        .iter()
+        .filter_map(|p| std::fs::metadata(&p.path).ok())
+        .map(|m| m.len())
+        .sum()
+}
+
+// Arguments of a parsed command and the offset just past it
+type ParsedCommand<'a> = (Vec<&'a [u8]>, usize);
+
+// Parse one `*<n>\r\n$<len>\r\n<arg>\r\n...` command. Returns None when the
+// data ends mid-command and Err on anything that is not RESP.
+fn parse_command(data: &[u8], mut pos: usize) -> Result<Option<ParsedCommand<'_>>, String> {
+    fn read_line(data: &[u8], pos: usize) -> Option<(&[u8], usize)> {
+        let end = data[pos..].windows(2).position(|w| w == b"\r\n")?;
+        Some((&data[pos..pos + end], pos + end + 2))
+    }
+    fn number(line: &[u8], prefix: u8) -> Result<usize, String> {
+        line.strip_prefix(&[prefix])
+            .and_then(|n| std::str::from_utf8(n).ok()?.parse().ok())
+            .ok_or_else(|| format!("Expected '{}' header", prefix as char))
+    }
+
+    let Some((header, next)) = read_line(data, pos) else {
+        return Ok(None);
+    };
+    let argc = number(header, b'*')?;
+    pos = next;
+
+    // The count comes from the file, so it never sizes more than the bytes
+    // that are left
+    let mut args = Vec::with_capacity(argc.min(data.len() - pos));
+    for _ in 0..argc {
+        let Some((header, next)) = read_line(data, pos) else {
+            return Ok(None);
+        };
+        let len = number(header, b'$')?;
+        let end = next
+            .checked_add(len)
+            .and_then(|end| end.checked_add(2))
+            .ok_or_else(|| format!("Impossible argument length {}", len))?;
+        if data.len() < end {
+            return Ok(None);
+        }
+        args.push(&data[next..next + len]);
+        pos = end;
+    }
+
+    Ok(Some((args, pos)))
+}
+
+// Walk every command in the AOF in replay order, tracking SELECT and the
+// `#TS:` timestamp annotations written when aof-timestamp-enabled is on
+fn walk(
+    parts: &[AofPart],
+    cancel: &CancelToken,
+    mut on_progress: impl FnMut(u64, u64),
+    mut visit: impl FnMut(&AofCommand),
+) -> Result<(Vec<AofFileSummary>, bool), String> {
+    let total_bytes = parts_size(parts);
+    let mut processed_bytes: u64 = 0;
+    let mut summaries = Vec::with_capacity(parts.len());
+    let mut db: u32 = 0;
+    let mut timestamp: Option<i64> = None;
+
+    for part in parts {
+        let data = std::fs::read(&part.path)
+            .map_err(|e| format!("Failed to read {}: {}", part.path.display(), e))?;
+        let mut summary = AofFileSummary {
+            name: part.name.clone(),
+            kind: part.kind,
+            size: data.len(),
+            commands: 0,
+            rdb_preamble: None,
+            truncated: false,
+            error: None,
+        };
+
+        let mut pos = 0;
+        if rdb::rdb_version(&data).is_some() {
+            // Without the end of the preamble the commands after it can't be
+            // found, so a bad preamble only ends this file
+            match rdb::scan(&data) {
+                Ok(preamble) => {
+                    summary.rdb_preamble = Some(RdbPreamble {
+                        rdb_version: preamble.version,
+                        keys: preamble.keys,
+                        bytes: preamble.bytes,
+                    });
+                    pos = preamble.bytes;
+                }
+                Err(e) => {
+                    summary.error = Some(format!("Unreadable RDB preamble: {}", e));
+                    pos = data.len();
+                }
+            }
+        }
+
+        let mut last_progress = 0;
+        while pos < data.len() {
+            if cancel.is_cancelled() {
+                summaries.push(summary);
+                return Ok((summaries, true));
+            }
+
+            match data[pos] {
+                b'#' => {
+                    // Annotation line, e.g. "#TS:1700000000"
+                    let end = data[pos..]
+                        .iter()
+                        .position(|b| *b == b'\n')
+                        .map(|i| pos + i + 1)
+                        .unwrap_or(data.len());
+                    let line = String::from_utf8_lossy(&data[pos..end]);
+                    if let Some(ts) = line.trim().strip_prefix("#TS:") {
+                        timestamp = ts.parse().ok().or(timestamp);
+                    }
+                    pos = end;
+                }
+                b'*' => match parse_command(&data, pos) {
+                    Ok(Some((args, next))) => {
+                        let command = AofCommand {
+                            file: &part.name,
+                            offset: pos,
+                            db,
+                            timestamp,
+                            args,
+                        };
+                        if command.name() == "SELECT" {
+                            db = command
+                                .args
+                                .get(1)
+                                .and_then(|n| std::str::from_utf8(n).ok()?.parse().ok())
+                                .unwrap_or(db);
+                        }
+                        summary.commands += 1;
+                        visit(&command);
+                        pos = next;
+                    }
+                    Ok(None) => {
+                        summary.truncated = true;
+                        break;
+                    }
+                    Err(e) => {
+                        summary.error = Some(format!("{} at offset {}", e, pos));
+                        break;
+                    }
+                },
+                b'\r' | b'\n' => pos += 1,
+                _ => {
+                    summary.error = Some(format!("Unexpected data at offset {}", pos));
+                    break;
+                }
+            }
+
+            if pos - last_progress >= 1 << 20 {
+                processed_bytes += (pos - last_progress) as u64;
+                last_progress = pos;
+                on_progress(processed_bytes, total_bytes);
+            }
+        }
+
+        processed_bytes += (data.len() - last_progress) as u64;
+        on_progress(processed_bytes, total_bytes);
+        summaries.push(summary);
+    }
+
+    Ok((summaries, false))
+}
+
+pub struct InspectOptions {
+    pub filter: AofFilter,
+    pub bucket_seconds: i64,
+    pub skip: usize,  // Matched entries to skip before collecting `limit`
+    pub limit: usize, // Matched entries returned in the report
+}
+
+pub fn inspect(
+    path: &str,
+    options: &InspectOptions,
+    cancel: &CancelToken,
+    on_progress: impl FnMut(u64, u64),
+) -> Result<AofReport, String> {
+    let parts = resolve_parts(path)?;
+    let bucket_seconds = options.bucket_seconds.max(1);
+
+    let mut total_commands: u64 = 0;
+    let mut matched_commands: u64 = 0;
+    let mut has_timestamps = false;
+    let mut stats: HashMap<String, AofCommandStats> = HashMap::new();
+    let mut timeline: BTreeMap<i64, BTreeMap<String, u64>> = BTreeMap::new();
+    let mut entries = Vec::new();
+
+    let (files, cancelled) = walk(&parts, cancel, on_progress, |cmd| {
+        total_commands += 1;
+        has_timestamps |= cmd.timestamp.is_some();
+        if !options.filter.matches(cmd) {
+            return;
+        }
+
+        let name = cmd.name();
+        let entry = stats
+            .entry(name.clone())
+            .or_insert_with(|| AofCommandStats {
+                command: name.clone(),
+                count: 0,
+                first_timestamp: cmd.timestamp,
+                last_timestamp: None,
+            });
+        entry.count += 1;
+        entry.first_timestamp = entry.first_timestamp.or(cmd.timestamp);
+        entry.last_timestamp = cmd.timestamp.or(entry.last_timestamp);
+
+        if let Some(ts) = cmd.timestamp {
+            let bucket = ts - ts.rem_euclid(bucket_seconds);
+            *timeline.entry(bucket).or_default().entry(name).or_insert(0) += 1;
+        }
+
+        if matched_commands as usize >= options.skip && entries.len() < options.limit {
+            entries.push(AofEntry {
+                file: cmd.file.to_string(),
+                offset: cmd.offset,
+                db: cmd.db,
+                timestamp: cmd.timestamp,
+                args: cmd
+                    .args
+                    .iter()
+                    .map(|a| String::from_utf8_lossy(a).to_string())
+                    .collect(),
+            });
+        }
+        matched_commands += 1;
+    })?;
+
+    let mut commands: Vec<AofCommandStats> = stats.into_values().collect();
+    commands.sort_by(|a, b| b.count.cmp(&a.count).then(a.command.cmp(&b.command)));
+
+    Ok(AofReport {
+        path: path.to_string(),
+        files,
+        total_commands,
+        matched_commands,
+        has_timestamps,
+        commands,
+        timeline: timeline
+            .into_iter()
+            .map(|(start, counts)| AofTimelineBucket { start, counts })
+            .collect(),
+        entries,
+        cancelled,
+    })
+}
+
+// Quote an argument the way redis-cli accepts it on its command line
+fn quote_cli_arg(arg: &[u8]) -> String {
+    let plain = !arg.is_empty()
+        && arg
+            .iter()
+            .all(|b| b.is_ascii_graphic() && !matches!(b, b'"' | b'\'' | b'\\'));
+    if plain {
+        return String::from_utf8_lossy(arg).to_string();
+    }
+
+    let mut quoted = String::from("\"");
+    for &b in arg {
+        match b {
+            b'"' => quoted.push_str("\\\""),
+            b'\\' => quoted.push_str("\\\\"),
+            b'\n' => quoted.push_str("\\n"),
+            b'\r' => quoted.push_str("\\r"),
+            b'\t' => quoted.push_str("\\t"),
+            b if b.is_ascii_graphic() || b == b' ' => quoted.push(b as char),
+            b => quoted.push_str(&format!("\\x{:02x}", b)),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn write_command(
+    out: &mut impl Write,
+    args: &[&[u8]],
+    format: AofExportFormat,
+) -> std::io::Result<()> {
+    match format {
+        AofExportFormat::Resp => {
+            write!(out, "*{}\r\n", args.len())?;
+            for arg in args {
+                write!(out, "${}\r\n", arg.len())?;
+                out.write_all(arg)?;
+                out.write_all(b"\r\n")?;
+            }
+        }
+        AofExportFormat::Cli => {
+            let line: Vec<String> = args.iter().map(|a| quote_cli_arg(a)).collect();
+            writeln!(out, "{}", line.join(" "))?;
+        }
+    }
+    Ok(())
+}
+
+// Write the matching commands to a script, inserting SELECT whenever the
+// database changes so the script replays against the right db
+pub fn export(
+    path: &str,
+    filter: &AofFilter,
+    output_path: &str,
+    format: AofExportFormat,
+    cancel: &CancelToken,
+    on_progress: impl FnMut(u64, u64),
+) -> Result<AofExportSummary, String> {
+    let parts = resolve_parts(path)?;
+    let file = std::fs::File::create(output_path)
+        .map_err(|e| format!("Failed to create {}: {}", output_path, e))?;
+    let mut out = std::io::BufWriter::new(file);
+
+    let mut current_db: Option<u32> = None;
+    let mut commands_written: u64 = 0;
+    let mut write_error: Option<std::io::Error> = None;
+
+    let (_, cancelled) = walk(&parts, cancel, on_progress, |cmd| {
+        if write_error.is_some() || !filter.matches(cmd) {
+            return;
+        }
+        // Replaying MULTI/EXEC or SELECT out of context would be wrong
+        if matches!(cmd.name().as_str(), "SELECT" | "MULTI" | "EXEC") {
+            return;
+        }
+
+        let result = (|| {
+            if current_db != Some(cmd.db) {
+                let db = cmd.db.to_string();
+                write_command(&mut out, &[b"SELECT", db.as_bytes()], format)?;
+                current_db = Some(cmd.db);
+            }
+            write_command(&mut out, &cmd.args, format)
+        })();
+
+        match result {
+            Ok(()) => commands_written += 1,
+            Err(e) => write_error = Some(e),
+        }
+    })?;
+
+    if let Some(e) = write_error {
+        return Err(format!("Failed to write {}: {}", output_path, e));
+    }
+    out.flush()
+        .map_err(|e| format!("Failed to write {}: {}", output_path, e))?;
+
+    Ok(AofExportSummary {
+        output_path: output_path.to_string(),
+        commands_written,
+        cancelled,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jobs::JobRegistry;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("aof-test-{}-{}", std::process::id(), name));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn resp(args: &[&str]) -> Vec<u8> {
+        let mut out = Vec::new();
+        write_command(
+            &mut out,
+            &args.iter().map(|a| a.as_bytes()).collect::<Vec<_>>(),
+            AofExportFormat::Resp,
+        )
+        .unwrap();
+        out
+    }
+
+    // RDB preamble holding a single string key
+    fn preamble() -> Vec<u8> {
+        [
+            b"REDIS0011".as_slice(),
+            &[0, 1, b'k', 1, b'v', 0xff],
+            &[0; 8],
+        ]
+        .concat()
+    }
+
+    fn inspect_all(path: &Path, filter: AofFilter) -> AofReport {
+        let (_, cancel) = JobRegistry::new().register("test", "test");
+        let options = InspectOptions {
+            filter,
+            bucket_seconds: 60,
+            skip: 0,
+            limit: 100,
+        };
+        inspect(path.to_str().unwrap(), &options, &cancel, |_, _| {}).unwrap()
+    }
+
+    #[test]
+    fn parse_command_reads_binary_arguments() {
+        let data = b"*2\r\n$3\r\nGET\r\n$4\r\na\r\nb\r\n*1\r\n$4\r\nPING\r\n";
+        let (args, next) = parse_command(data, 0).unwrap().unwrap();
+        assert_eq!(args, vec![b"GET".as_slice(), b"a\r\nb"]);
+        let (args, end) = parse_command(data, next).unwrap().unwrap();
+        assert_eq!(args, vec![b"PING".as_slice()]);
+        assert_eq!(end, data.len());
+    }
+
+    #[test]
+    fn parse_command_reports_truncation_and_garbage() {
+        let data = resp(&["SET", "key", "value"]);
+        for end in 1..data.len() {
+            assert!(
+                parse_command(&data[..end], 0).unwrap().is_none(),
+                "prefix of {} bytes",
+                end
+            );
+        }
+        assert!(parse_command(b"*1\r\n+OK\r\n", 0).is_err());
+        assert!(parse_command(b"*x\r\n", 0).is_err());
+
+        // Corrupt headers must neither allocate their claimed size nor panic
+        assert!(parse_command(b"*100000000000\r\n$3\r\nGET\r\n", 0)
+            .unwrap()
+            .is_none());
+        assert!(parse_command(b"*1\r\n$18446744073709551615\r\nGET\r\n", 0).is_err());
+        assert!(parse_command(b"*1\r\n$18446744073709551614\r\nGET\r\n", 0).is_err());
+        assert!(parse_command(b"*1\r\n$99999999999999999999\r\nGET\r\n", 0).is_err());
+        assert!(parse_command(b"*1\r\n$-1\r\n", 0).is_err());
+        assert!(parse_command(b"*1\r\n$1000\r\nGET\r\n", 0)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn manifest_orders_parts() {
+        let dir = temp_dir("manifest");
+        let manifest = "file appendonly.aof.3.incr.aof seq 3 type i\n\
+                        file \"appendonly.aof.1.base.rdb\" seq 1 type b\n\
+                        file appendonly.aof.2.incr.aof seq 2 type i\n\
+                        file appendonly.aof.0.base.aof seq 0 type h\n\n";
+        let path = dir.join("appendonly.aof.manifest");
+        std::fs::write(&path, manifest).unwrap();
+
+        let names = |parts: Vec<AofPart>| -> Vec<(String, AofFileKind)> {
+            parts.into_iter().map(|p| (p.name, p.kind)).collect()
+        };
+        let expected = vec![
+            ("appendonly.aof.1.base.rdb".to_string(), AofFileKind::Base),
+            ("appendonly.aof.2.incr.aof".to_string(), AofFileKind::Incr),
+            ("appendonly.aof.3.incr.aof".to_string(), AofFileKind::Incr),
+        ];
+        assert_eq!(
+            names(resolve_parts(dir.to_str().unwrap()).unwrap()),
+            expected
+        );
+        assert_eq!(
+            names(resolve_parts(path.to_str().unwrap()).unwrap()),
+            expected
+        );
+        assert_eq!(
+            resolve_parts(dir.join("appendonly.aof.1.base.rdb").to_str().unwrap()).unwrap()[0].kind,
+            AofFileKind::Plain
+        );
+
+        std::fs::write(&path, "seq 1 type b\n").unwrap();
+        assert!(resolve_parts(dir.to_str().unwrap()).is_err());
+        std::fs::remove_file(&path).unwrap();
+        assert!(resolve_parts(dir.to_str().unwrap()).is_err());
+    }
+
+    #[test]
+    fn timestamps_and_databases_follow_annotations() {
+        let dir = temp_dir("timestamps");
+        std::fs::write(
+            dir.join("appendonly.aof.manifest"),
+            "file base.rdb seq 1 type b\nfile incr.aof seq 1 type i\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("base.rdb"),
+            [preamble(), resp(&["SET", "a", "1"])].concat(),
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("incr.aof"),
+            [
+                b"#TS:1700000000\r\n".to_vec(),
+                resp(&["SELECT", "2"]),
+                resp(&["SET", "b", "2"]),
+                b"#TS:1700000090\r\n".to_vec(),
+                resp(&["DEL", "b", "c"]),
+            ]
+            .concat(),
+        )
+        .unwrap();
+
+        let report = inspect_all(&dir, AofFilter::default());
+        assert_eq!(report.total_commands, 4);
+        assert!(report.has_timestamps);
+        let preamble = report.files[0].rdb_preamble.as_ref().unwrap();
+        assert_eq!((preamble.rdb_version, preamble.keys), (11, 1));
+        assert_eq!(report.files[0].commands, 1);
+
+        let seen: Vec<(u32, Option<i64>, String)> = report
+            .entries
+            .iter()
+            .map(|e| (e.db, e.timestamp, e.args.join(" ")))
+            .collect();
+        assert_eq!(
+            seen,
+            vec![
+                (0, None, "SET a 1".to_string()),
+                (0, Some(1700000000), "SELECT 2".to_string()),
+                (2, Some(1700000000), "SET b 2".to_string()),
+                (2, Some(1700000090), "DEL b c".to_string()),
+            ]
+        );
+        assert_eq!(report.timeline.len(), 2);
+        assert_eq!(report.timeline[1].start, 1700000040);
+
+        let filter = AofFilter {
+            key_pattern: Some("c".to_string()),
+            from_timestamp: Some(1700000060),
+            ..AofFilter::default()
+        };
+        let report = inspect_all(&dir, filter);
+        assert_eq!(report.matched_commands, 1);
+        assert_eq!(report.entries[0].args[0], "DEL");
+    }
+
+    #[test]
+    fn corrupt_preamble_is_reported_per_file() {
+        let dir = temp_dir("corrupt");
+        std::fs::write(
+            dir.join("appendonly.aof.manifest"),
+            "file base.rdb seq 1 type b\nfile incr.aof seq 1 type i\n",
+        )
+        .unwrap();
+        let mut base = preamble();
+        base.truncate(base.len() - 10);
+        std::fs::write(dir.join("base.rdb"), base).unwrap();
+        std::fs::write(dir.join("incr.aof"), resp(&["SET", "a", "1"])).unwrap();
+
+        let report = inspect_all(&dir, AofFilter::default());
+        assert!(report.files[0]
+            .error
+            .as_deref()
+            .is_some_and(|e| e.starts_with("Unreadable RDB preamble")));
+        assert!(report.files[0].rdb_preamble.is_none());
+        assert_eq!(report.files[1].commands, 1);
+        assert_eq!(report.total_commands, 1);
+    }
+}
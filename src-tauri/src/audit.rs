@@ -0,0 +1,481 @@
+use anyhow::{Context, Result};
+use redis::{Arg, ConnectionLike, FromRedisValue};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::Manager;
+
+// audit.log is rotated to audit.log.1 ... audit.log.N once it grows past
+// MAX_LOG_BYTES; the oldest file is dropped
+const MAX_LOG_BYTES: u64 = 5 * 1024 * 1024;
+const MAX_ROTATED_FILES: usize = 5;
+
+// Values can be large, so only a prefix of each argument is kept
+const MAX_ARGS: usize = 16;
+const MAX_ARG_CHARS: usize = 200;
+
+const DEFAULT_QUERY_LIMIT: usize = 500;
+
+// Written in place of passwords, so the log never holds credentials
+const REDACTED: &str = "[redacted]";
+const SECRET_CONFIG: &[&str] = &[
+    "requirepass",
+    "masterauth",
+    "tls-key-file-pass",
+    "tls-client-key-file-pass",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub timestamp: u64, // Unix milliseconds
+    pub connection_id: String,
+    pub connection_name: Option<String>,
+    pub db: Option<u32>,
+    pub command: String, // Redis command, or the job kind for bulk jobs
+    pub key: Option<String>,
+    pub args: Vec<String>,
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AuditQuery {
+    pub connection_id: Option<String>,
+    pub command: Option<String>, // Case-insensitive exact match
+    pub text: Option<String>,    // Case-insensitive match on key, args and error
+    pub since: Option<u64>,
+    pub until: Option<u64>,
+    pub success: Option<bool>,
+    pub limit: Option<usize>,
+}
+
+impl AuditQuery {
+    fn matches(&self, entry: &AuditEntry) -> bool {
+        if self
+            .connection_id
+            .as_ref()
+            .is_some_and(|id| *id != entry.connection_id)
+        {
+            return false;
+        }
+        if self
+            .command
+            .as_ref()
+            .is_some_and(|command| !command.eq_ignore_ascii_case(&entry.command))
+        {
+            return false;
+        }
+        if self.since.is_some_and(|since| entry.timestamp < since)
+            || self.until.is_some_and(|until| entry.timestamp > until)
+            || self.success.is_some_and(|success| success != entry.success)
+        {
+            return false;
+        }
+        match &self.text {
+            Some(text) => {
+                let text = text.to_lowercase();
+                entry
+                    .key
+                    .iter()
+                    .chain(entry.args.iter())
+                    .chain(entry.error.iter())
+                    .chain(entry.connection_name.iter())
+                    .any(|field| field.to_lowercase().contains(&text))
+            }
+            None => true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditExportFormat {
+    Ndjson,
+    Csv,
+}
+
+impl AuditExportFormat {
+    pub fn from_path(path: &str) -> Self {
+        if path.to_lowercase().ends_with(".csv") {
+            AuditExportFormat::Csv
+        } else {
+            AuditExportFormat::Ndjson
+        }
+    }
+}
+
+fn truncate(arg: &str) -> String {
+    match arg.char_indices().nth(MAX_ARG_CHARS) {
+        Some((end, _)) => format!("{}... ({} bytes)", &arg[..end], arg.len()),
+        None => arg.to_string(),
+    }
+}
+
+// Blank out passwords in the arguments of `command` (key excluded)
+fn redact(command: &str, args: &mut [String]) {
+    let is = |arg: &String, word: &str| arg.eq_ignore_ascii_case(word);
+    match command.to_uppercase().as_str() {
+        // CONFIG SET name value [name value ...]
+        "CONFIG" if args.first().is_some_and(|sub| is(sub, "SET")) => {
+            for pair in args[1..].chunks_mut(2) {
+                if let [name, value] = pair {
+                    if SECRET_CONFIG.contains(&name.to_lowercase().as_str()) {
+                        *value = REDACTED.to_string();
+                    }
+                }
+            }
+        }
+        // ACL SETUSER name rule...; >pass, <pass, #hash and !hash carry secrets
+        "ACL" if args.first().is_some_and(|sub| is(sub, "SETUSER")) => {
+            for rule in args.iter_mut().skip(2) {
+                if rule.starts_with(['>', '<', '#', '!']) {
+                    *rule = format!("{}{}", &rule[..1], REDACTED);
+                }
+            }
+        }
+        // MIGRATE ... AUTH pass | AUTH2 user pass ... KEYS key...
+        "MIGRATE" => {
+            let mut i = 0;
+            while i < args.len() && !is(&args[i], "KEYS") {
+                let secret = if is(&args[i], "AUTH") {
+                    i + 1
+                } else if is(&args[i], "AUTH2") {
+                    i + 2
+                } else {
+                    i += 1;
+                    continue;
+                };
+                if let Some(password) = args.get_mut(secret) {
+                    *password = REDACTED.to_string();
+                }
+                i = secret + 1;
+            }
+        }
+        // AUTH [user] pass
+        "AUTH" => {
+            if let Some(password) = args.last_mut() {
+                *password = REDACTED.to_string();
+            }
+        }
+        // HELLO [ver] [AUTH user pass] [SETNAME name]
+        "HELLO" => {
+            if let Some(i) = args.iter().position(|arg| is(arg, "AUTH")) {
+                if let Some(password) = args.get_mut(i + 2) {
+                    *password = REDACTED.to_string();
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn write_entries(
+    out: &mut impl Write,
+    entries: &[AuditEntry],
+    format: AuditExportFormat,
+) -> std::io::Result<()> {
+    if format == AuditExportFormat::Csv {
+        writeln!(
+            out,
+            "timestamp,connection_id,connection_name,db,command,key,args,success,error"
+        )?;
+    }
+    for entry in entries {
+        match format {
+            AuditExportFormat::Ndjson => writeln!(out, "{}", serde_json::to_string(entry)?)?,
+            AuditExportFormat::Csv => {
+                let fields = [
+                    entry.timestamp.to_string(),
+                    entry.connection_id.clone(),
+                    entry.connection_name.clone().unwrap_or_default(),
+                    entry.db.map(|db| db.to_string()).unwrap_or_default(),
+                    entry.command.clone(),
+                    entry.key.clone().unwrap_or_default(),
+                    entry.args.join(" "),
+                    entry.success.to_string(),
+                    entry.error.clone().unwrap_or_default(),
+                ];
+                let line: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+                writeln!(out, "{}", line.join(","))?;
+            }
+        }
+    }
+    out.flush()
+}
+
+// Append-only log of mutating calls, one JSON object per line. Cheap to
+// clone so job threads can record their outcome.
+#[derive(Clone)]
+pub struct AuditLog {
+    dir: PathBuf,
+    names: Arc<Mutex<HashMap<String, String>>>,
+    write_lock: Arc<Mutex<()>>,
+}
+
+impl AuditLog {
+    pub fn new(app_handle: &tauri::AppHandle) -> Result<Self> {
+        let dir = app_handle
+            .path()
+            .app_data_dir()
+            .context("Failed to get app data directory")?
+            .join("audit");
+        fs::create_dir_all(&dir).context("Failed to create audit log directory")?;
+
+        Ok(Self {
+            dir,
+            names: Arc::new(Mutex::new(HashMap::new())),
+            write_lock: Arc::new(Mutex::new(())),
+        })
+    }
+
+    // Connection names are recorded with each entry, since ids mean little
+    // once the connection is deleted
+    pub fn set_connection_name(&self, connection_id: &str, name: &str) {
+        let mut names = self.names.lock().unwrap();
+        names.insert(connection_id.to_string(), name.to_string());
+    }
+
+    fn path(&self, index: usize) -> PathBuf {
+        match index {
+            0 => self.dir.join("audit.log"),
+            n => self.dir.join(format!("audit.log.{}", n)),
+        }
+    }
+
+    fn rotate(&self) -> std::io::Result<()> {
+        let _ = fs::remove_file(self.path(MAX_ROTATED_FILES));
+        for index in (0..MAX_ROTATED_FILES).rev() {
+            let from = self.path(index);
+            if from.exists() {
+                fs::rename(from, self.path(index + 1))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn append(&self, entry: &AuditEntry) -> std::io::Result<()> {
+        let mut line = serde_json::to_string(entry)?;
+        line.push('\n');
+
+        let _guard = self.write_lock.lock().unwrap();
+        let size = fs::metadata(self.path(0)).map(|m| m.len()).unwrap_or(0);
+        if size > 0 && size + line.len() as u64 > MAX_LOG_BYTES {
+            self.rotate()?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.path(0))?;
+        file.write_all(line.as_bytes())
+    }
+
+    // Record one mutating call. Failing to write the log never fails the
+    // call itself.
+    pub fn record<T>(
+        &self,
+        connection_id: &str,
+        db: i64,
+        command: &str,
+        key: Option<&str>,
+        args: &[String],
+        result: &Result<T, String>,
+    ) {
+        let mut args = args.to_vec();
+        redact(command, &mut args);
+        let mut recorded: Vec<String> = args.iter().take(MAX_ARGS).map(|a| truncate(a)).collect();
+        if args.len() > MAX_ARGS {
+            recorded.push(format!("... {} more", args.len() - MAX_ARGS));
+        }
+
+        let entry = AuditEntry {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or(0),
+            connection_id: connection_id.to_string(),
+            connection_name: self.names.lock().unwrap().get(connection_id).cloned(),
+            db: u32::try_from(db).ok(),
+            command: command.to_string(),
+            key: key.map(truncate),
+            args: recorded,
+            success: result.is_ok(),
+            error: result.as_ref().err().cloned(),
+        };
+        if let Err(e) = self.append(&entry) {
+            eprintln!("Audit: failed to write entry: {}", e);
+        }
+    }
+
+    // Run a mutating command and record it under its own name; the first
+    // argument is taken as the key
+    pub fn run<T: FromRedisValue>(
+        &self,
+        connection_id: &str,
+        conn: &mut dyn ConnectionLike,
+        cmd: &redis::Cmd,
+    ) -> Result<T, String> {
+        let result = cmd.query(conn).map_err(|e| e.to_string());
+
+        let args: Vec<String> = cmd
+            .args_iter()
+            .map(|arg| match arg {
+                Arg::Simple(bytes) => String::from_utf8_lossy(bytes).into_owned(),
+                Arg::Cursor => "0".to_string(),
+            })
+            .collect();
+        if let Some((command, rest)) = args.split_first() {
+            let key = rest.first().map(String::as_str);
+            let rest = rest.get(1..).unwrap_or_default();
+            self.record(
+                connection_id,
+                conn.get_db(),
+                &command.to_uppercase(),
+                key,
+                rest,
+                &result,
+            );
+        }
+        result
+    }
+
+    // Newest entries first, across the current and rotated files
+    pub fn query(&self, query: &AuditQuery) -> Result<Vec<AuditEntry>, String> {
+        let limit = query.limit.unwrap_or(DEFAULT_QUERY_LIMIT);
+        let mut entries = Vec::new();
+        let _guard = self.write_lock.lock().unwrap();
+
+        for index in 0..=MAX_ROTATED_FILES {
+            let file = match File::open(self.path(index)) {
+                Ok(file) => file,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e.to_string()),
+            };
+            let mut matched: Vec<AuditEntry> = BufReader::new(file)
+                .lines()
+                .map_while(|line| line.ok())
+                // A torn last line from a crash is skipped, not fatal
+                .filter_map(|line| serde_json::from_str::<AuditEntry>(&line).ok())
+                .filter(|entry| query.matches(entry))
+                .collect();
+            matched.reverse();
+            entries.extend(matched);
+            if entries.len() >= limit {
+                break;
+            }
+        }
+
+        entries.truncate(limit);
+        Ok(entries)
+    }
+
+    // Matching entries oldest first; without a limit, every entry is written
+    pub fn export(
+        &self,
+        query: &AuditQuery,
+        output_path: &str,
+        format: AuditExportFormat,
+    ) -> Result<usize, String> {
+        let mut query = query.clone();
+        query.limit.get_or_insert(usize::MAX);
+        let mut entries = self.query(&query)?;
+        entries.reverse();
+
+        let file = File::create(output_path).map_err(|e| e.to_string())?;
+        write_entries(&mut BufWriter::new(file), &entries, format).map_err(|e| e.to_string())?;
+        Ok(entries.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn redacted(command: &str, args: &[&str]) -> Vec<String> {
+        let mut args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+        redact(command, &mut args);
+        args
+    }
+
+    #[test]
+    fn config_set_passwords_are_redacted() {
+        assert_eq!(
+            redacted("CONFIG", &["SET", "requirepass", "s3cret"]),
+            ["SET", "requirepass", REDACTED]
+        );
+        assert_eq!(
+            redacted(
+                "config",
+                &["set", "maxmemory", "1gb", "MASTERAUTH", "s3cret"]
+            ),
+            ["set", "maxmemory", "1gb", "MASTERAUTH", REDACTED]
+        );
+        assert_eq!(
+            redacted("CONFIG", &["GET", "requirepass"]),
+            ["GET", "requirepass"]
+        );
+    }
+
+    #[test]
+    fn acl_setuser_passwords_are_redacted() {
+        assert_eq!(
+            redacted(
+                "ACL",
+                &["SETUSER", "alice", "on", ">s3cret", "<old", "#abc123", "~keys:*", "+get"]
+            ),
+            [
+                "SETUSER",
+                "alice",
+                "on",
+                ">[redacted]",
+                "<[redacted]",
+                "#[redacted]",
+                "~keys:*",
+                "+get"
+            ]
+        );
+        // The user name is never mistaken for a rule
+        assert_eq!(redacted("ACL", &["SETUSER", ">odd"]), ["SETUSER", ">odd"]);
+    }
+
+    #[test]
+    fn migrate_credentials_are_redacted() {
+        // The key is recorded separately, so it is not among the arguments
+        assert_eq!(
+            redacted(
+                "MIGRATE",
+                &["host", "6379", "0", "5000", "COPY", "AUTH", "s3cret"]
+            ),
+            ["host", "6379", "0", "5000", "COPY", "AUTH", REDACTED]
+        );
+        assert_eq!(
+            redacted(
+                "MIGRATE",
+                &["host", "6379", "0", "5000", "AUTH2", "bob", "s3cret", "KEYS", "AUTH", "k"]
+            ),
+            ["host", "6379", "0", "5000", "AUTH2", "bob", REDACTED, "KEYS", "AUTH", "k"]
+        );
+    }
+
+    #[test]
+    fn auth_and_hello_are_redacted() {
+        assert_eq!(redacted("AUTH", &["s3cret"]), [REDACTED]);
+        assert_eq!(redacted("AUTH", &["bob", "s3cret"]), ["bob", REDACTED]);
+        assert_eq!(
+            redacted("HELLO", &["3", "AUTH", "bob", "s3cret", "SETNAME", "cli"]),
+            ["3", "AUTH", "bob", REDACTED, "SETNAME", "cli"]
+        );
+        assert_eq!(redacted("SET", &["v"]), ["v"]);
+    }
+}
@@ -0,0 +1,442 @@
+use crate::export::BinaryString;
+use crate::jobs::CancelToken;
+use crate::keyspace::{self, KeyBatches};
+use crate::snapshot::glob_match;
+use redis::{ConnectionLike, Value};
+use serde::{Deserialize, Serialize};
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Take, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Archive layout (integers little-endian):
+//   MAGIC
+//   entries: u32 key length, key, i64 absolute expiry in ms (0 = none),
+//            u32 payload length, DUMP payload
+//   manifest JSON, u32 manifest length, MAGIC
+// The trailing manifest lets it be written after the key count is known
+// and read without walking the entries.
+const MAGIC: &[u8; 8] = b"RDTLBAK1";
+const FORMAT_VERSION: u32 = 1;
+const TRAILER_LEN: u64 = 4 + MAGIC.len() as u64;
+// RESTORE commands per pipelined round-trip
+const RESTORE_BATCH: usize = 100;
+const MAX_ERRORS: usize = 100;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupManifest {
+    pub format_version: u32,
+    pub created_at_ms: u64,
+    pub server_version: Option<String>,
+    pub rdb_version: Option<u16>, // Highest DUMP payload version in the archive
+    pub db: Option<u32>,
+    pub pattern: Option<String>, // None when an explicit key list was backed up
+    pub key_count: u64,
+    pub payload_bytes: u64,
+    pub complete: bool, // False if the backup was cancelled part way
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BackupSummary {
+    pub output_path: String,
+    pub manifest: BackupManifest,
+    pub skipped_keys: u64, // Deleted between SCAN and DUMP
+    pub cancelled: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct RestoreOptions {
+    pub replace: bool, // RESTORE ... REPLACE; existing keys are skipped otherwise
+    pub pattern: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RestoreError {
+    pub key: BinaryString,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RestoreReport {
+    pub input_path: String,
+    pub restored: u64,
+    pub skipped_existing: u64,
+    pub skipped_expired: u64,
+    pub filtered: u64, // Not matching the restore pattern
+    pub failed: u64,
+    pub errors: Vec<RestoreError>, // First MAX_ERRORS failures
+    pub cancelled: bool,
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+// A DUMP payload ends with a 2-byte RDB version and an 8-byte CRC64
+pub fn payload_rdb_version(payload: &[u8]) -> Option<u16> {
+    let footer = payload.len().checked_sub(10)?;
+    Some(u16::from_le_bytes([payload[footer], payload[footer + 1]]))
+}
+
+// Highest RDB version the server can RESTORE. Dumping an existing key is
+// exact; an empty database falls back to the version shipped with each
+// Redis release.
+pub fn server_rdb_version(conn: &mut dyn ConnectionLike) -> Option<u16> {
+    let key: Option<Vec<u8>> = redis::cmd("RANDOMKEY").query(conn).ok()?;
+    if let Some(key) = key {
+        let payload: Option<Vec<u8>> = redis::cmd("DUMP").arg(key).query(conn).ok()?;
+        if let Some(version) = payload.as_deref().and_then(payload_rdb_version) {
+            return Some(version);
+        }
+    }
+
+    let version = keyspace::server_version(conn)?;
+    Some(match version {
+        v if v >= (7, 4, 0) => 12,
+        v if v >= (7, 2, 0) => 11,
+        v if v >= (7, 0, 0) => 10,
+        v if v >= (5, 0, 0) => 9,
+        v if v >= (4, 0, 0) => 8,
+        v if v >= (3, 2, 0) => 7,
+        _ => 6,
+    })
+}
+
+fn write_entry(
+    out: &mut impl Write,
+    key: &[u8],
+    expire_at_ms: i64,
+    payload: &[u8],
+) -> std::io::Result<()> {
+    out.write_all(&(key.len() as u32).to_le_bytes())?;
+    out.write_all(key)?;
+    out.write_all(&expire_at_ms.to_le_bytes())?;
+    out.write_all(&(payload.len() as u32).to_le_bytes())?;
+    out.write_all(payload)
+}
+
+// DUMP and PTTL every key in one pipelined round-trip per batch and append
+// the payloads to the archive; the manifest is written last
+pub fn backup_keys(
+    conn: &mut dyn ConnectionLike,
+    pattern: &str,
+    keys: Option<Vec<BinaryString>>,
+    db: Option<u32>,
+    output_path: &str,
+    cancel: &CancelToken,
+    mut on_progress: impl FnMut(u64, u64),
+) -> Result<BackupSummary, String> {
+    let write_error = |e: std::io::Error| format!("Failed to write {}: {}", output_path, e);
+    let file = std::fs::File::create(output_path)
+        .map_err(|e| format!("Failed to create {}: {}", output_path, e))?;
+    let mut out = BufWriter::new(file);
+    out.write_all(MAGIC).map_err(write_error)?;
+
+    let mut manifest = BackupManifest {
+        format_version: FORMAT_VERSION,
+        created_at_ms: now_ms(),
+        server_version: keyspace::server_version(conn)
+            .map(|(major, minor, patch)| format!("{}.{}.{}", major, minor, patch)),
+        rdb_version: None,
+        db,
+        pattern: keys.is_none().then(|| pattern.to_string()),
+        key_count: 0,
+        payload_bytes: 0,
+        complete: false,
+    };
+    let mut batches = KeyBatches::new(conn, pattern, keys);
+    let total = batches.total();
+    let mut skipped_keys = 0;
+    let mut cancelled = false;
+
+    while let Some(batch) = batches.next_batch(conn).map_err(|e| e.to_string())? {
+        if cancel.is_cancelled() {
+            cancelled = true;
+            break;
+        }
+
+        let mut pipe = redis::pipe();
+        for key in &batch {
+            pipe.cmd("DUMP").arg(key);
+            pipe.cmd("PTTL").arg(key);
+        }
+        let replies = keyspace::query_pipeline_lenient(conn, &pipe, batch.len() * 2)
+            .map_err(|e| e.to_string())?;
+        let now = now_ms() as i64;
+
+        for (key, r) in batch.iter().zip(replies.chunks(2)) {
+            let Value::BulkString(payload) = &r[0] else {
+                skipped_keys += 1;
+                continue;
+            };
+            let expire_at_ms = match keyspace::value_to_i64(&r[1]) {
+                Some(pttl) if pttl >= 0 => now + pttl.max(1),
+                _ => 0,
+            };
+
+            write_entry(&mut out, &key.0, expire_at_ms, payload).map_err(write_error)?;
+            manifest.key_count += 1;
+            manifest.payload_bytes += payload.len() as u64;
+            manifest.rdb_version = manifest.rdb_version.max(payload_rdb_version(payload));
+        }
+
+        on_progress(batches.processed(), total);
+    }
+
+    manifest.complete = !cancelled;
+    let json = serde_json::to_vec(&manifest).map_err(|e| e.to_string())?;
+    out.write_all(&json).map_err(write_error)?;
+    out.write_all(&(json.len() as u32).to_le_bytes())
+        .map_err(write_error)?;
+    out.write_all(MAGIC).map_err(write_error)?;
+    out.flush().map_err(write_error)?;
+
+    Ok(BackupSummary {
+        output_path: output_path.to_string(),
+        manifest,
+        skipped_keys,
+        cancelled,
+    })
+}
+
+// Returns the manifest and the offset where the entries end
+fn read_trailer(file: &mut std::fs::File) -> Result<(BackupManifest, u64), String> {
+    let invalid = || "Not a backup archive".to_string();
+    let size = file.metadata().map_err(|e| e.to_string())?.len();
+
+    let mut magic = [0u8; 8];
+    file.read_exact(&mut magic).map_err(|_| invalid())?;
+    if &magic != MAGIC || size < MAGIC.len() as u64 + TRAILER_LEN {
+        return Err(invalid());
+    }
+
+    let mut trailer = [0u8; TRAILER_LEN as usize];
+    file.seek(SeekFrom::Start(size - TRAILER_LEN))
+        .and_then(|_| file.read_exact(&mut trailer))
+        .map_err(|e| e.to_string())?;
+    if &trailer[4..] != MAGIC {
+        return Err("Backup archive is truncated".to_string());
+    }
+
+    let manifest_len = u32::from_le_bytes([trailer[0], trailer[1], trailer[2], trailer[3]]) as u64;
+    let entries_end = (size - TRAILER_LEN)
+        .checked_sub(manifest_len)
+        .filter(|end| *end >= MAGIC.len() as u64)
+        .ok_or_else(invalid)?;
+
+    let mut json = vec![0u8; manifest_len as usize];
+    file.seek(SeekFrom::Start(entries_end))
+        .and_then(|_| file.read_exact(&mut json))
+        .map_err(|e| e.to_string())?;
+    let manifest =
+        serde_json::from_slice(&json).map_err(|e| format!("Invalid backup manifest: {}", e))?;
+
+    Ok((manifest, entries_end))
+}
+
+pub fn read_manifest(path: &str) -> Result<BackupManifest, String> {
+    let mut file =
+        std::fs::File::open(path).map_err(|e| format!("Failed to open {}: {}", path, e))?;
+    read_trailer(&mut file).map(|(manifest, _)| manifest)
+}
+
+// Refuse to restore payloads newer than the server understands, and servers
+// without RESTORE ... ABSTTL (Redis < 5.0). Checked before anything is
+// written so an incompatible restore never half-applies.
+pub fn check_compatibility(
+    conn: &mut dyn ConnectionLike,
+    manifest: &BackupManifest,
+) -> Result<(), String> {
+    if let Some(version) = keyspace::server_version(conn) {
+        if version < (5, 0, 0) {
+            return Err(format!(
+                "Restoring requires Redis 5.0 or later (server is {}.{}.{})",
+                version.0, version.1, version.2
+            ));
+        }
+    }
+
+    if let (Some(archive), Some(server)) = (manifest.rdb_version, server_rdb_version(conn)) {
+        if archive > server {
+            return Err(format!(
+                "Backup holds RDB version {} payloads (from Redis {}) but the server only supports up to version {}",
+                archive,
+                manifest.server_version.as_deref().unwrap_or("unknown"),
+                server
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+struct Entry {
+    key: Vec<u8>,
+    expire_at_ms: i64,
+    payload: Vec<u8>,
+}
+
+// Lengths come from the archive, so each is checked against the bytes left
+// before the trailer before anything is allocated for it
+fn read_entry<R: Read>(reader: &mut Take<R>) -> std::io::Result<Entry> {
+    fn read_len<R: Read>(reader: &mut Take<R>) -> std::io::Result<usize> {
+        let mut buf = [0u8; 4];
+        reader.read_exact(&mut buf)?;
+        let len = u32::from_le_bytes(buf);
+        if u64::from(len) > reader.limit() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("length {} runs past the end of the entries", len),
+            ));
+        }
+        Ok(len as usize)
+    }
+
+    let mut key = vec![0u8; read_len(reader)?];
+    reader.read_exact(&mut key)?;
+    let mut i64_buf = [0u8; 8];
+    reader.read_exact(&mut i64_buf)?;
+    let mut payload = vec![0u8; read_len(reader)?];
+    reader.read_exact(&mut payload)?;
+
+    Ok(Entry {
+        key,
+        expire_at_ms: i64::from_le_bytes(i64_buf),
+        payload,
+    })
+}
+
+pub fn restore_keys(
+    conn: &mut dyn ConnectionLike,
+    input_path: &str,
+    options: &RestoreOptions,
+    cancel: &CancelToken,
+    mut on_progress: impl FnMut(u64, u64),
+) -> Result<RestoreReport, String> {
+    let mut file = std::fs::File::open(input_path)
+        .map_err(|e| format!("Failed to open {}: {}", input_path, e))?;
+    let (manifest, entries_end) = read_trailer(&mut file)?;
+    file.seek(SeekFrom::Start(MAGIC.len() as u64))
+        .map_err(|e| e.to_string())?;
+    let mut reader = BufReader::new(file).take(entries_end - MAGIC.len() as u64);
+
+    let mut report = RestoreReport {
+        input_path: input_path.to_string(),
+        restored: 0,
+        skipped_existing: 0,
+        skipped_expired: 0,
+        filtered: 0,
+        failed: 0,
+        errors: Vec::new(),
+        cancelled: false,
+    };
+    let pattern = options.pattern.as_deref().filter(|p| *p != "*");
+    let mut processed: u64 = 0;
+
+    loop {
+        if cancel.is_cancelled() {
+            report.cancelled = true;
+            break;
+        }
+
+        let now = now_ms() as i64;
+        let mut batch = Vec::with_capacity(RESTORE_BATCH);
+        while batch.len() < RESTORE_BATCH && reader.limit() > 0 {
+            let entry =
+                read_entry(&mut reader).map_err(|e| format!("Backup archive is corrupt: {}", e))?;
+            processed += 1;
+
+            if pattern.is_some_and(|p| !glob_match(p.as_bytes(), &entry.key)) {
+                report.filtered += 1;
+            } else if entry.expire_at_ms > 0 && entry.expire_at_ms <= now {
+                report.skipped_expired += 1;
+            } else {
+                batch.push(entry);
+            }
+        }
+        if batch.is_empty() && reader.limit() == 0 {
+            break;
+        }
+
+        let mut pipe = redis::pipe();
+        for entry in &batch {
+            let cmd = pipe
+                .cmd("RESTORE")
+                .arg(&entry.key)
+                .arg(entry.expire_at_ms)
+                .arg(&entry.payload);
+            if options.replace {
+                cmd.arg("REPLACE");
+            }
+            cmd.arg("ABSTTL");
+        }
+        let replies = keyspace::query_pipeline_lenient(conn, &pipe, batch.len())
+            .map_err(|e| e.to_string())?;
+
+        for (entry, reply) in batch.iter().zip(replies) {
+            match reply.extract_error() {
+                Ok(_) => report.restored += 1,
+                Err(e) if e.code() == Some("BUSYKEY") => report.skipped_existing += 1,
+                Err(e) => {
+                    report.failed += 1;
+                    if report.errors.len() < MAX_ERRORS {
+                        report.errors.push(RestoreError {
+                            key: BinaryString(entry.key.clone()),
+                            message: e.to_string(),
+                        });
+                    }
+                }
+            }
+        }
+
+        on_progress(processed, manifest.key_count);
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(data: &[u8]) -> Take<&[u8]> {
+        data.take(data.len() as u64)
+    }
+
+    #[test]
+    fn entries_round_trip() {
+        let mut data = Vec::new();
+        write_entry(&mut data, &[0xff, b'k'], 1_700_000_000_000, b"payload").unwrap();
+        write_entry(&mut data, b"", -1, b"").unwrap();
+
+        let mut reader = entries(&data);
+        let entry = read_entry(&mut reader).unwrap();
+        assert_eq!(entry.key, [0xff, b'k']);
+        assert_eq!(entry.expire_at_ms, 1_700_000_000_000);
+        assert_eq!(entry.payload, b"payload");
+        let entry = read_entry(&mut reader).unwrap();
+        assert!(entry.key.is_empty() && entry.payload.is_empty());
+        assert_eq!(reader.limit(), 0);
+    }
+
+    #[test]
+    fn lengths_past_the_entries_are_rejected() {
+        // A key length claiming 4 GiB
+        let mut data = u32::MAX.to_le_bytes().to_vec();
+        data.extend_from_slice(b"key");
+        assert!(read_entry(&mut entries(&data)).is_err());
+
+        // A payload length one byte longer than what is left
+        let mut data = Vec::new();
+        write_entry(&mut data, b"k", 0, b"abc").unwrap();
+        let at = data.len() - 3 - 4;
+        data[at..at + 4].copy_from_slice(&4u32.to_le_bytes());
+        assert!(read_entry(&mut entries(&data)).is_err());
+
+        // The limit, not the underlying reader, bounds the lengths
+        let mut data = Vec::new();
+        write_entry(&mut data, b"key", 0, b"payload").unwrap();
+        assert!(read_entry(&mut data.as_slice().take(10)).is_err());
+    }
+}
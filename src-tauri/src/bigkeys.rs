@@ -0,0 +1,242 @@
+use crate::jobs::CancelToken;
+use crate::keyspace::{self, KeyScanner};
+use redis::{ConnectionLike, RedisResult};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BigKeysOptions {
+    pub pattern: String,
+    pub top_n: usize,
+    pub memory_samples: usize, // MEMORY USAGE ... SAMPLES for nested values (0 = all)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+pub struct BigKeyEntry {
+    pub key: String,
+    pub key_type: String,
+    pub memory_bytes: Option<u64>,
+    pub elements: Option<u64>, // Bytes for strings, members for collections
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TypeSummary {
+    pub key_type: String,
+    pub key_count: u64,
+    pub total_memory: u64,
+    pub total_elements: u64,
+    pub avg_memory: u64,
+    pub biggest_by_memory: Vec<BigKeyEntry>,
+    pub biggest_by_elements: Vec<BigKeyEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BigKeysReport {
+    pub pattern: String,
+    pub generated_at: u64, // Unix time in milliseconds
+    pub scanned_keys: u64,
+    pub total_memory: u64,
+    pub types: Vec<TypeSummary>,
+    pub top_by_memory: Vec<BigKeyEntry>,
+    pub cancelled: bool,
+}
+
+// Keeps the N entries with the largest score
+pub struct TopN<T: Ord> {
+    limit: usize,
+    heap: BinaryHeap<Reverse<(u64, T)>>,
+}
+
+impl<T: Ord + Clone> TopN<T> {
+    pub fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            heap: BinaryHeap::new(),
+        }
+    }
+
+    pub fn offer(&mut self, score: u64, entry: &T) {
+        if self.limit == 0 {
+            return;
+        }
+        if self.heap.len() < self.limit {
+            self.heap.push(Reverse((score, entry.clone())));
+        } else if self
+            .heap
+            .peek()
+            .is_some_and(|Reverse((min, _))| score > *min)
+        {
+            self.heap.pop();
+            self.heap.push(Reverse((score, entry.clone())));
+        }
+    }
+
+    // Entries with their scores, largest first
+    pub fn into_sorted_with_scores(self) -> Vec<(u64, T)> {
+        // Ascending order of Reverse is descending order of score
+        self.heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse(scored)| scored)
+            .collect()
+    }
+
+    pub fn into_sorted(self) -> Vec<T> {
+        self.into_sorted_with_scores()
+            .into_iter()
+            .map(|(_, entry)| entry)
+            .collect()
+    }
+}
+
+struct TypeStats {
+    key_count: u64,
+    total_memory: u64,
+    total_elements: u64,
+    by_memory: TopN<BigKeyEntry>,
+    by_elements: TopN<BigKeyEntry>,
+}
+
+fn length_command(key_type: &str) -> Option<&'static str> {
+    match key_type {
+        "string" => Some("STRLEN"),
+        "list" => Some("LLEN"),
+        "set" => Some("SCARD"),
+        "zset" => Some("ZCARD"),
+        "hash" => Some("HLEN"),
+        "stream" => Some("XLEN"),
+        _ => None,
+    }
+}
+
+// Equivalent of `redis-cli --bigkeys --memkeys`: scan the keyspace, measure
+// every key and keep the largest ones per type and overall. Each SCAN page
+// costs two pipelined round-trips (TYPE, then length + MEMORY USAGE).
+pub fn analyze_big_keys(
+    conn: &mut dyn ConnectionLike,
+    options: &BigKeysOptions,
+    cancel: &CancelToken,
+    mut on_progress: impl FnMut(u64, u64),
+) -> RedisResult<BigKeysReport> {
+    let total = keyspace::dbsize(conn);
+    let mut scanner = KeyScanner::new(conn, &options.pattern, keyspace::SCAN_COUNT, None);
+
+    let mut types: BTreeMap<String, TypeStats> = BTreeMap::new();
+    let mut overall = TopN::new(options.top_n);
+    let mut scanned_keys: u64 = 0;
+    let mut total_memory: u64 = 0;
+    let mut cancelled = false;
+
+    while let Some(batch) = scanner.next_batch(conn)? {
+        if cancel.is_cancelled() {
+            cancelled = true;
+            break;
+        }
+        if batch.is_empty() {
+            on_progress(scanner.scanned_estimate(total), total);
+            continue;
+        }
+
+        let mut type_pipe = redis::pipe();
+        for key in &batch {
+            type_pipe.cmd("TYPE").arg(key);
+        }
+        let key_types: Vec<String> = type_pipe.query(conn)?;
+
+        let mut pipe = redis::pipe();
+        let mut command_count = 0;
+        for (key, key_type) in batch.iter().zip(&key_types) {
+            if let Some(length_cmd) = length_command(key_type) {
+                pipe.cmd(length_cmd).arg(key);
+                command_count += 1;
+            }
+            pipe.cmd("MEMORY")
+                .arg("USAGE")
+                .arg(key)
+                .arg("SAMPLES")
+                .arg(options.memory_samples);
+            command_count += 1;
+        }
+        let replies = keyspace::query_pipeline_lenient(conn, &pipe, command_count)?;
+        let mut replies = replies.iter();
+
+        for (key, key_type) in batch.into_iter().zip(key_types) {
+            let elements = if length_command(&key_type).is_some() {
+                replies
+                    .next()
+                    .and_then(keyspace::value_to_i64)
+                    .map(|v| v as u64)
+            } else {
+                None
+            };
+            let memory_bytes = replies
+                .next()
+                .and_then(keyspace::value_to_i64)
+                .map(|v| v as u64);
+
+            // Expired or deleted since SCAN returned it
+            if key_type == "none" {
+                continue;
+            }
+
+            scanned_keys += 1;
+            total_memory += memory_bytes.unwrap_or(0);
+
+            let entry = BigKeyEntry {
+                key: key.to_string_lossy(),
+                key_type: key_type.clone(),
+                memory_bytes,
+                elements,
+            };
+
+            let stats = types.entry(key_type).or_insert_with(|| TypeStats {
+                key_count: 0,
+                total_memory: 0,
+                total_elements: 0,
+                by_memory: TopN::new(options.top_n),
+                by_elements: TopN::new(options.top_n),
+            });
+            stats.key_count += 1;
+            stats.total_memory += memory_bytes.unwrap_or(0);
+            stats.total_elements += elements.unwrap_or(0);
+            if let Some(memory) = memory_bytes {
+                stats.by_memory.offer(memory, &entry);
+                overall.offer(memory, &entry);
+            }
+            if let Some(count) = elements {
+                stats.by_elements.offer(count, &entry);
+            }
+        }
+
+        on_progress(scanner.scanned_estimate(total), total);
+    }
+
+    let mut types: Vec<TypeSummary> = types
+        .into_iter()
+        .map(|(key_type, stats)| TypeSummary {
+            key_type,
+            key_count: stats.key_count,
+            total_memory: stats.total_memory,
+            total_elements: stats.total_elements,
+            avg_memory: stats.total_memory / stats.key_count.max(1),
+            biggest_by_memory: stats.by_memory.into_sorted(),
+            biggest_by_elements: stats.by_elements.into_sorted(),
+        })
+        .collect();
+    types.sort_by_key(|t| Reverse(t.total_memory));
+
+    Ok(BigKeysReport {
+        pattern: options.pattern.clone(),
+        generated_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0),
+        scanned_keys,
+        total_memory,
+        types,
+        top_by_memory: overall.into_sorted(),
+        cancelled,
+    })
+}
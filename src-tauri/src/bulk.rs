@@ -0,0 +1,456 @@
+use crate::export::BinaryString;
+use crate::jobs::{CancelToken, RateLimiter};
+use crate::keyspace::{self, KeyBatches, KeyScanner};
+use redis::{ConnectionLike, RedisResult, ToRedisArgs, Value};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+const DEFAULT_BATCH_SIZE: usize = 500;
+const MAX_ERRORS: usize = 100;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BulkPreview {
+    pub pattern: String,
+    pub estimated: bool, // Counts extrapolated from part of the keyspace
+    pub matched_keys: u64,
+    pub keys_without_ttl: u64,
+    pub sample_keys: Vec<BinaryString>,
+    pub sampled_memory_bytes: Option<u64>, // MEMORY USAGE total of the sample
+    pub estimated_memory_bytes: Option<u64>, // Sample average times matched_keys
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BulkKeyError {
+    pub key: BinaryString,
+    pub message: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct BulkDeleteOptions {
+    pub pattern: String,
+    pub keys: Option<Vec<BinaryString>>, // Explicit keys; the pattern is ignored when set
+    pub batch_size: Option<usize>,
+    pub max_keys_per_second: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BulkDeleteSummary {
+    pub pattern: String,
+    pub command: String, // UNLINK, or DEL on servers older than 4.0
+    pub deleted: u64,
+    pub already_gone: u64, // Expired or deleted by someone else since the scan
+    pub failed: u64,
+    pub errors: Vec<BulkKeyError>, // First MAX_ERRORS failures
+    pub duration_ms: u64,
+    pub cancelled: bool,
+}
+
+// Count the keys matching `pattern` and measure the memory of the first
+// `sample_size` of them. SCAN returns keys in hash table order, so the
+// first keys are a reasonably unbiased sample. Large keyspaces are only
+// scanned up to PREVIEW_SCAN_LIMIT keys and the counts scaled by DBSIZE.
+pub fn preview(
+    conn: &mut dyn ConnectionLike,
+    pattern: &str,
+    sample_size: usize,
+) -> RedisResult<BulkPreview> {
+    let total = keyspace::dbsize(conn);
+    let mut scanner = KeyScanner::new(conn, pattern, keyspace::SCAN_COUNT, None);
+    let mut seen: HashSet<BinaryString> = HashSet::new();
+    let mut sample_keys = Vec::new();
+    let mut keys_without_ttl = 0;
+
+    while let Some(batch) = scanner.next_batch(conn)? {
+        let batch: Vec<BinaryString> = batch
+            .into_iter()
+            .filter(|key| seen.insert(key.clone()))
+            .collect();
+
+        let mut pipe = redis::pipe();
+        for key in &batch {
+            pipe.cmd("PTTL").arg(key);
+        }
+        let pttls = keyspace::query_pipeline_lenient(conn, &pipe, batch.len())?;
+        keys_without_ttl += pttls
+            .iter()
+            .filter(|pttl| keyspace::value_to_i64(pttl) == Some(-1))
+            .count() as u64;
+
+        let room = sample_size.saturating_sub(sample_keys.len());
+        sample_keys.extend(batch.into_iter().take(room));
+
+        if scanner.scanned_estimate(total) >= keyspace::PREVIEW_SCAN_LIMIT {
+            break;
+        }
+    }
+
+    let mut pipe = redis::pipe();
+    for key in &sample_keys {
+        pipe.cmd("MEMORY").arg("USAGE").arg(key);
+    }
+    let replies = keyspace::query_pipeline_lenient(conn, &pipe, sample_keys.len())?;
+    let sizes: Vec<u64> = replies
+        .iter()
+        .filter_map(keyspace::value_to_i64)
+        .map(|bytes| bytes.max(0) as u64)
+        .collect();
+
+    let estimated = !scanner.is_done();
+    let matched_keys = scanner.extrapolate(seen.len() as u64, total);
+    let keys_without_ttl = scanner.extrapolate(keys_without_ttl, total);
+    let sampled_memory_bytes = (!sizes.is_empty()).then(|| sizes.iter().sum::<u64>());
+    let estimated_memory_bytes = sampled_memory_bytes
+        .map(|sum| (sum as f64 / sizes.len() as f64 * matched_keys as f64).round() as u64);
+
+    sample_keys.sort();
+    Ok(BulkPreview {
+        pattern: pattern.to_string(),
+        estimated,
+        matched_keys,
+        keys_without_ttl,
+        sample_keys,
+        sampled_memory_bytes,
+        estimated_memory_bytes,
+    })
+}
+
+// UNLINK frees memory in a background thread; it needs Redis 4.0
+fn delete_command(conn: &mut dyn ConnectionLike) -> &'static str {
+    match keyspace::server_version(conn) {
+        Some(version) if version < (4, 0, 0) => "DEL",
+        _ => "UNLINK",
+    }
+}
+
+// Delete the matching keys with one pipelined UNLINK per key, so keys in
+// different cluster slots never share a command
+pub fn delete_keys(
+    conn: &mut dyn ConnectionLike,
+    options: &BulkDeleteOptions,
+    cancel: &CancelToken,
+    mut on_progress: impl FnMut(u64, u64),
+) -> Result<BulkDeleteSummary, String> {
+    let started = Instant::now();
+    let command = delete_command(conn);
+    let batch_size = options.batch_size.unwrap_or(DEFAULT_BATCH_SIZE).max(1);
+    let mut limiter = RateLimiter::new(options.max_keys_per_second);
+    let mut batches = KeyBatches::new(conn, &options.pattern, options.keys.clone());
+    let total = batches.total();
+
+    let mut summary = BulkDeleteSummary {
+        pattern: options.pattern.clone(),
+        command: command.to_string(),
+        deleted: 0,
+        already_gone: 0,
+        failed: 0,
+        errors: Vec::new(),
+        duration_ms: 0,
+        cancelled: false,
+    };
+
+    'batches: while let Some(batch) = batches.next_batch(conn).map_err(|e| e.to_string())? {
+        // Small chunks keep the rate limit smooth and bound each round-trip
+        let chunk_size = options
+            .max_keys_per_second
+            .map_or(batch_size, |rate| (rate as usize).clamp(1, batch_size));
+
+        for chunk in batch.chunks(chunk_size) {
+            if cancel.is_cancelled() {
+                summary.cancelled = true;
+                break 'batches;
+            }
+
+            let mut pipe = redis::pipe();
+            for key in chunk {
+                pipe.cmd(command).arg(key);
+            }
+            let replies = keyspace::query_pipeline_lenient(conn, &pipe, chunk.len())
+                .map_err(|e| e.to_string())?;
+
+            for (key, reply) in chunk.iter().zip(&replies) {
+                match reply {
+                    Value::Int(0) => summary.already_gone += 1,
+                    Value::Int(_) => summary.deleted += 1,
+                    other => {
+                        let message = match other.clone().extract_error() {
+                            // Nothing else in this job can succeed either
+                            Err(e) if e.code() == Some("READONLY") => return Err(e.to_string()),
+                            Err(e) => e.to_string(),
+                            Ok(_) => format!("Unexpected reply: {:?}", other),
+                        };
+                        summary.failed += 1;
+                        if summary.errors.len() < MAX_ERRORS {
+                            summary.errors.push(BulkKeyError {
+                                key: key.clone(),
+                                message,
+                            });
+                        }
+                    }
+                }
+            }
+
+            limiter.wait(chunk.len(), cancel);
+        }
+
+        on_progress(batches.processed(), total);
+    }
+
+    summary.duration_ms = started.elapsed().as_millis() as u64;
+    Ok(summary)
+}
+
+// TTL changes
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TtlOperation {
+    Expire,   // Seconds from now
+    Pexpire,  // Milliseconds from now
+    Expireat, // Unix time in seconds
+    Persist,
+}
+
+// EXPIRE flags from Redis 7. A key without a TTL counts as an infinite TTL
+// for GT and LT, as on the server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TtlCondition {
+    Nx, // Only keys without a TTL
+    Xx, // Only keys with a TTL
+    Gt, // Only when the new TTL is greater than the current one
+    Lt, // Only when the new TTL is less than the current one
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct TtlChange {
+    pub operation: TtlOperation,
+    #[serde(default)]
+    pub value: i64, // Ignored for Persist
+    pub condition: Option<TtlCondition>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TtlOutcome {
+    Updated,
+    Unchanged, // Condition not met, or PERSIST on a key without a TTL
+    Gone,
+}
+
+fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+impl TtlChange {
+    // A zero or past expiry makes the server delete the key, which is never
+    // what a TTL edit means
+    pub fn validate(&self) -> Result<(), String> {
+        match self.operation {
+            TtlOperation::Persist if self.condition.is_some() => {
+                Err("PERSIST does not take NX/XX/GT/LT".to_string())
+            }
+            TtlOperation::Persist => Ok(()),
+            TtlOperation::Expireat if self.value.saturating_mul(1000) <= now_ms() => {
+                Err("The expiry time is in the past".to_string())
+            }
+            _ if self.value <= 0 => Err("The TTL must be positive".to_string()),
+            _ => Ok(()),
+        }
+    }
+
+    fn new_ttl_ms(&self) -> i64 {
+        match self.operation {
+            TtlOperation::Expire => self.value.saturating_mul(1000),
+            TtlOperation::Pexpire => self.value,
+            TtlOperation::Expireat => self.value.saturating_mul(1000).saturating_sub(now_ms()),
+            TtlOperation::Persist => -1,
+        }
+    }
+
+    fn command(&self, key: &impl ToRedisArgs, native_condition: bool) -> redis::Cmd {
+        let name = match self.operation {
+            TtlOperation::Expire => "EXPIRE",
+            TtlOperation::Pexpire => "PEXPIRE",
+            TtlOperation::Expireat => "EXPIREAT",
+            TtlOperation::Persist => return redis::cmd("PERSIST").arg(key).clone(),
+        };
+        let mut cmd = redis::cmd(name);
+        cmd.arg(key).arg(self.value);
+        if let (Some(condition), true) = (self.condition, native_condition) {
+            cmd.arg(match condition {
+                TtlCondition::Nx => "NX",
+                TtlCondition::Xx => "XX",
+                TtlCondition::Gt => "GT",
+                TtlCondition::Lt => "LT",
+            });
+        }
+        cmd
+    }
+
+    // Client-side check for servers without the flags; `pttl` is -1 for a
+    // key without a TTL
+    fn condition_met(&self, pttl: i64) -> bool {
+        let new_ttl = self.new_ttl_ms();
+        match (self.condition, pttl) {
+            (None, _) => true,
+            (Some(TtlCondition::Nx), pttl) => pttl == -1,
+            (Some(TtlCondition::Xx), pttl) => pttl != -1,
+            (Some(TtlCondition::Gt), -1) => false,
+            (Some(TtlCondition::Lt), -1) => true,
+            (Some(TtlCondition::Gt), pttl) => new_ttl > pttl,
+            (Some(TtlCondition::Lt), pttl) => new_ttl < pttl,
+        }
+    }
+}
+
+// NX/XX/GT/LT need Redis 7; older servers get a PTTL check first, which is
+// not atomic with the change
+pub fn native_ttl_conditions(conn: &mut dyn ConnectionLike) -> bool {
+    keyspace::server_version(conn).is_some_and(|version| version >= (7, 0, 0))
+}
+
+// Apply `change` to a chunk of keys in one or two pipelined round-trips.
+// Each key gets an EXISTS before the change so "condition not met" can be
+// told apart from "key gone" (both reply 0).
+pub fn apply_ttl<K: ToRedisArgs>(
+    conn: &mut dyn ConnectionLike,
+    keys: &[K],
+    change: &TtlChange,
+    native_condition: bool,
+) -> RedisResult<Vec<Result<TtlOutcome, redis::RedisError>>> {
+    let mut eligible = vec![true; keys.len()];
+    if change.condition.is_some() && !native_condition {
+        let mut pipe = redis::pipe();
+        for key in keys {
+            pipe.cmd("PTTL").arg(key);
+        }
+        let pttls = keyspace::query_pipeline_lenient(conn, &pipe, keys.len())?;
+        for (eligible, pttl) in eligible.iter_mut().zip(&pttls) {
+            *eligible = match keyspace::value_to_i64(pttl) {
+                Some(-2) | None => true, // Reported as gone or failed below
+                Some(pttl) => change.condition_met(pttl),
+            };
+        }
+    }
+
+    let mut pipe = redis::pipe();
+    let mut commands = 0;
+    for (key, eligible) in keys.iter().zip(&eligible) {
+        pipe.cmd("EXISTS").arg(key);
+        commands += 1;
+        if *eligible {
+            pipe.add_command(change.command(key, native_condition));
+            commands += 1;
+        }
+    }
+    let mut replies = keyspace::query_pipeline_lenient(conn, &pipe, commands)?.into_iter();
+
+    let mut outcomes = Vec::with_capacity(keys.len());
+    for eligible in eligible {
+        let exists = replies.next().unwrap_or(Value::Nil);
+        let reply = if eligible { replies.next() } else { None };
+        let outcome = match reply.map(|reply| reply.extract_error()) {
+            Some(Err(e)) => Err(e),
+            _ if keyspace::value_to_i64(&exists) == Some(0) => Ok(TtlOutcome::Gone),
+            Some(Ok(Value::Int(1))) => Ok(TtlOutcome::Updated),
+            _ => Ok(TtlOutcome::Unchanged),
+        };
+        outcomes.push(outcome);
+    }
+    Ok(outcomes)
+}
+
+#[derive(Debug, Clone)]
+pub struct BulkTtlOptions {
+    pub pattern: String,
+    pub keys: Option<Vec<BinaryString>>,
+    pub change: TtlChange,
+    pub batch_size: Option<usize>,
+    pub max_keys_per_second: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BulkTtlSummary {
+    pub pattern: String,
+    pub change: TtlChange,
+    pub emulated_condition: bool, // Checked with PTTL on a pre-7.0 server
+    pub updated: u64,
+    pub unchanged: u64,
+    pub already_gone: u64,
+    pub failed: u64,
+    pub errors: Vec<BulkKeyError>, // First MAX_ERRORS failures
+    pub duration_ms: u64,
+    pub cancelled: bool,
+}
+
+pub fn set_ttls(
+    conn: &mut dyn ConnectionLike,
+    options: &BulkTtlOptions,
+    cancel: &CancelToken,
+    mut on_progress: impl FnMut(u64, u64),
+) -> Result<BulkTtlSummary, String> {
+    options.change.validate()?;
+
+    let started = Instant::now();
+    let native_condition = native_ttl_conditions(conn);
+    let batch_size = options.batch_size.unwrap_or(DEFAULT_BATCH_SIZE).max(1);
+    let mut limiter = RateLimiter::new(options.max_keys_per_second);
+    let mut batches = KeyBatches::new(conn, &options.pattern, options.keys.clone());
+    let total = batches.total();
+
+    let mut summary = BulkTtlSummary {
+        pattern: options.pattern.clone(),
+        change: options.change,
+        emulated_condition: options.change.condition.is_some() && !native_condition,
+        updated: 0,
+        unchanged: 0,
+        already_gone: 0,
+        failed: 0,
+        errors: Vec::new(),
+        duration_ms: 0,
+        cancelled: false,
+    };
+
+    'batches: while let Some(batch) = batches.next_batch(conn).map_err(|e| e.to_string())? {
+        let chunk_size = options
+            .max_keys_per_second
+            .map_or(batch_size, |rate| (rate as usize).clamp(1, batch_size));
+
+        for chunk in batch.chunks(chunk_size) {
+            if cancel.is_cancelled() {
+                summary.cancelled = true;
+                break 'batches;
+            }
+
+            let outcomes = apply_ttl(conn, chunk, &options.change, native_condition)
+                .map_err(|e| e.to_string())?;
+            for (key, outcome) in chunk.iter().zip(outcomes) {
+                match outcome {
+                    Ok(TtlOutcome::Updated) => summary.updated += 1,
+                    Ok(TtlOutcome::Unchanged) => summary.unchanged += 1,
+                    Ok(TtlOutcome::Gone) => summary.already_gone += 1,
+                    Err(e) if e.code() == Some("READONLY") => return Err(e.to_string()),
+                    Err(e) => {
+                        summary.failed += 1;
+                        if summary.errors.len() < MAX_ERRORS {
+                            summary.errors.push(BulkKeyError {
+                                key: key.clone(),
+                                message: e.to_string(),
+                            });
+                        }
+                    }
+                }
+            }
+
+            limiter.wait(chunk.len(), cancel);
+        }
+
+        on_progress(batches.processed(), total);
+    }
+
+    summary.duration_ms = started.elapsed().as_millis() as u64;
+    Ok(summary)
+}
@@ -1,9 +1,32 @@
+use crate::access::{self, AccessError};
+use crate::aof::{self, AofExportFormat, AofExportSummary, AofFilter, AofReport, InspectOptions};
+use crate::audit::{AuditEntry, AuditExportFormat, AuditLog, AuditQuery};
+use crate::backup::{self, BackupManifest, BackupSummary, RestoreOptions, RestoreReport};
+use crate::bigkeys::{self, BigKeysOptions, BigKeysReport};
+use crate::bulk::{
+    self, BulkDeleteOptions, BulkDeleteSummary, BulkPreview, BulkTtlOptions, BulkTtlSummary,
+    TtlChange, TtlCondition, TtlOperation, TtlOutcome,
+};
 use crate::connection_store::{ConnectionStore, PasswordStore, StoredConnection};
-use crate::redis_client::{ConnectionConfig, ConnectionStatus, RedisConnectionManager};
-use redis::Commands;
+use crate::diff::{self, DiffOptions, DiffSummary, KeyDifference, KeyElementDiff};
+use crate::expiry::{self, ExpiryOptions, ExpiryReport};
+use crate::export::{self, BinaryString, ExportOptions, ExportSummary};
+use crate::hotkeys::{self, HotKeysReport};
+use crate::import::{self, ConflictPolicy, ImportFormat, ImportOptions, ImportReport};
+use crate::jobs::{self, JobEvent, JobInfo, JobRegistry, ProgressThrottle};
+use crate::keyops::{self, KeyOperationResult};
+use crate::keyspace::{self, DbKeyspace};
+use crate::migrate::{self, MigrateEndpoint, MigrationMode, MigrationOptions, MigrationReport};
+use crate::namespace::{self, NamespaceLevel, NamespaceOptions, NamespaceTree};
+use crate::rdb::RdbSnapshot;
+use crate::redis_client::{ConnectionConfig, ConnectionStatus, RedisConn, RedisConnectionManager};
+use crate::rename::{self, RenameOptions, RenamePreview, RenameReport, RenameRule};
+use crate::undo::{self, UndoJournal, UndoJournalInfo, UndoResult};
+use redis::{Commands, ConnectionLike};
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
-use std::sync::Mutex;
+use std::collections::{BTreeMap, HashSet};
+use std::sync::{Arc, Mutex};
+use tauri::ipc::Channel;
 use tauri::State;
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -17,6 +40,10 @@ pub struct AppState {
     pub redis_manager: Mutex<RedisConnectionManager>,
     pub connection_store: Mutex<ConnectionStore>,
     pub password_store: PasswordStore,
+    pub jobs: JobRegistry,
+    pub audit: AuditLog,
+    pub undo: UndoJournal,
+    pub namespace_trees: Arc<Mutex<std::collections::HashMap<String, NamespaceTree>>>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -38,11 +65,42 @@ pub struct RedisValue {
 
 #[tauri::command]
 pub async fn connect_to_redis(
-    config: ConnectionConfig,
+    mut config: ConnectionConfig,
     state: State<'_, AppState>,
 ) -> Result<ConnectionStatus, String> {
+    // Guardrails saved with the connection apply even if the caller omits
+    // them; a caller's policy can only add restrictions
+    let stored = state
+        .connection_store
+        .lock()
+        .unwrap()
+        .load_connections()
+        .ok()
+        .and_then(|connections| connections.into_iter().find(|c| c.id == config.id));
+    if let Some(stored) = stored {
+        config.read_only |= stored.read_only;
+        config.policy = match (stored.policy, config.policy.take()) {
+            (Some(stored), Some(caller)) => Some(stored.restrict(caller)),
+            (stored, caller) => stored.or(caller),
+        };
+        if config.undo_journal.is_none() {
+            config.undo_journal = stored.undo_journal;
+        }
+    }
+
+    if let Some(policy) = &config.policy {
+        policy.validate(config.undo_journal.is_some())?;
+    }
+
+    state.audit.set_connection_name(&config.id, &config.name);
+    let undo_journal = config.undo_journal;
+
     let manager = state.redis_manager.lock().unwrap();
-    manager.connect(config).map_err(|e| e.to_string())
+    let status = manager.connect(config).map_err(|e| e.to_string())?;
+    if status.connected {
+        state.undo.configure(&status.id, undo_journal);
+    }
+    Ok(status)
 }
 
 #[tauri::command]
@@ -50,6 +108,7 @@ pub async fn disconnect_from_redis(
     connection_id: String,
     state: State<'_, AppState>,
 ) -> Result<bool, String> {
+    state.undo.remove(&connection_id);
     let manager = state.redis_manager.lock().unwrap();
     Ok(manager.disconnect(&connection_id))
 }
@@ -70,25 +129,32 @@ pub async fn get_keys(
     // Avoid `KEYS` which can block Redis on large datasets.
     // We keep the existing frontend contract (return a Vec<String>) but implement it via SCAN.
     // Removed MAX_KEYS limit - now fetches all matching keys
-    const SCAN_COUNT: usize = 1000;
+    let type_filter = key_type_filter.filter(|t| t != "all");
+
+    // Let the server filter by type (Redis 6.0+) instead of a TYPE call per key
+    let server_side_filter = type_filter.is_some()
+        && keyspace::supports_scan_type(manager.server_version(&connection_id, &mut conn));
 
     let mut cursor: u64 = 0;
     let mut seen: HashSet<String> = HashSet::new();
 
     loop {
-        let (next_cursor, batch): (u64, Vec<String>) = redis::cmd("SCAN")
-            .arg(cursor)
-            .arg("MATCH")
-            .arg(&pattern)
-            .arg("COUNT")
-            .arg(SCAN_COUNT)
-            .query(&mut conn)
-            .map_err(|e| e.to_string())?;
+        let (next_cursor, mut batch) = keyspace::scan_page(
+            &mut conn,
+            cursor,
+            &pattern,
+            keyspace::SCAN_COUNT,
+            type_filter.as_deref().filter(|_| server_side_filter),
+        )
+        .map_err(|e| e.to_string())?;
 
-        for key in batch {
-            seen.insert(key);
+        if let (Some(filter_type), false) = (&type_filter, server_side_filter) {
+            batch = keyspace::filter_by_type(&mut conn, batch, filter_type)
+                .map_err(|e| e.to_string())?;
         }
 
+        seen.extend(batch);
+
         if next_cursor == 0 {
             break;
         }
@@ -97,20 +163,6 @@ pub async fn get_keys(
 
     let mut keys: Vec<String> = seen.into_iter().collect();
 
-    // Filter by type if specified
-    if let Some(filter_type) = key_type_filter {
-        if filter_type != "all" {
-            let mut filtered_keys = Vec::new();
-            for key in &keys {
-                let key_type: String = conn.key_type(key).map_err(|e| e.to_string())?;
-                if key_type == filter_type {
-                    filtered_keys.push(key.clone());
-                }
-            }
-            keys = filtered_keys;
-        }
-    }
-
     keys.sort();
     Ok(keys)
 }
@@ -121,6 +173,7 @@ pub async fn scan_keys(
     pattern: String,
     cursor: u64,
     count: usize,
+    key_type_filter: Option<String>,
     state: State<'_, AppState>,
 ) -> Result<ScanResult, String> {
     let manager = state.redis_manager.lock().unwrap();
@@ -129,21 +182,1152 @@ pub async fn scan_keys(
         .get_connection(&connection_id)
         .ok_or("Connection not found")?;
 
-    // Execute SCAN command with provided cursor
-    let (next_cursor, batch): (u64, Vec<String>) = redis::cmd("SCAN")
-        .arg(cursor)
-        .arg("MATCH")
-        .arg(&pattern)
-        .arg("COUNT")
-        .arg(count)
-        .query(&mut conn)
-        .map_err(|e| e.to_string())?;
+    let type_filter = key_type_filter.filter(|t| t != "all");
+    let server_side_filter = type_filter.is_some()
+        && keyspace::supports_scan_type(manager.server_version(&connection_id, &mut conn));
+
+    // Execute SCAN command with provided cursor
+    let (next_cursor, mut batch) = keyspace::scan_page(
+        &mut conn,
+        cursor,
+        &pattern,
+        count,
+        type_filter.as_deref().filter(|_| server_side_filter),
+    )
+    .map_err(|e| e.to_string())?;
+
+    if let (Some(filter_type), false) = (&type_filter, server_side_filter) {
+        batch =
+            keyspace::filter_by_type(&mut conn, batch, filter_type).map_err(|e| e.to_string())?;
+    }
+
+    Ok(ScanResult {
+        keys: batch,
+        cursor: next_cursor,
+        has_more: next_cursor != 0,
+    })
+}
+
+// Streaming key scans
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", content = "data", rename_all = "snake_case")]
+pub enum ScanEvent {
+    Started {
+        job_id: String,
+        estimated_total: u64,
+    },
+    Keys {
+        keys: Vec<String>, // May repeat a key sent earlier in the scan
+        scanned: u64,
+        matched: u64, // Keys sent so far, repeats included
+        estimated_total: u64,
+    },
+    Finished {
+        scanned: u64,
+        matched: u64,
+        cancelled: bool,
+    },
+    Error {
+        message: String,
+    },
+}
+
+// Keys buffered before a chunk is sent over the channel
+const SCAN_CHUNK_SIZE: usize = 1000;
+
+// Starts a background SCAN and streams matching keys in chunks. Returns the
+// job id, which can be passed to cancel_job.
+#[tauri::command]
+pub async fn start_key_scan(
+    connection_id: String,
+    pattern: String,
+    key_type_filter: Option<String>,
+    count: Option<usize>,
+    on_event: Channel<ScanEvent>,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let mut conn = {
+        let manager = state.redis_manager.lock().unwrap();
+        manager
+            .get_connection(&connection_id)
+            .ok_or("Connection not found")?
+    };
+
+    let count = count.unwrap_or(keyspace::SCAN_COUNT).max(1);
+    let type_filter = key_type_filter.filter(|t| t != "all");
+    let job_id = jobs::spawn(
+        &state.jobs,
+        "scan",
+        &connection_id,
+        {
+            let on_event = on_event.clone();
+            move |job_id, cancel| {
+                let estimated_total = keyspace::dbsize(&mut conn);
+                let mut scanner =
+                    keyspace::KeyScanner::new(&mut conn, &pattern, count, type_filter.as_deref());
+
+                let _ = on_event.send(ScanEvent::Started {
+                    job_id: job_id.to_string(),
+                    estimated_total,
+                });
+
+                let mut matched: u64 = 0;
+                let mut chunk: Vec<String> = Vec::new();
+                let mut throttle = ProgressThrottle::new();
+
+                let result: redis::RedisResult<()> = loop {
+                    if cancel.is_cancelled() {
+                        break Ok(());
+                    }
+
+                    let batch = match scanner.next_batch(&mut conn) {
+                        Ok(Some(batch)) => batch,
+                        Ok(None) => break Ok(()),
+                        Err(e) => break Err(e),
+                    };
+
+                    // SCAN may return a key more than once while the table rehashes;
+                    // the browser drops repeats, so no set of every key is kept here.
+                    // The browser works on text keys, so others are shown lossily.
+                    matched += batch.len() as u64;
+                    chunk.extend(batch.iter().map(BinaryString::to_string_lossy));
+
+                    let done = scanner.is_done();
+                    if done || chunk.len() >= SCAN_CHUNK_SIZE || throttle.ready() {
+                        let _ = on_event.send(ScanEvent::Keys {
+                            keys: std::mem::take(&mut chunk),
+                            scanned: scanner.scanned_estimate(estimated_total),
+                            matched,
+                            estimated_total,
+                        });
+                    }
+                };
+
+                let _ = match result {
+                    Ok(()) => on_event.send(ScanEvent::Finished {
+                        scanned: scanner.scanned_estimate(estimated_total),
+                        matched,
+                        cancelled: cancel.is_cancelled(),
+                    }),
+                    Err(e) => on_event.send(ScanEvent::Error {
+                        message: e.to_string(),
+                    }),
+                };
+            }
+        },
+        move |message| {
+            let _ = on_event.send(ScanEvent::Error { message });
+        },
+    );
+
+    Ok(job_id)
+}
+
+#[tauri::command]
+pub async fn cancel_job(job_id: String, state: State<'_, AppState>) -> Result<bool, String> {
+    Ok(state.jobs.cancel(&job_id))
+}
+
+#[tauri::command]
+pub async fn list_jobs(state: State<'_, AppState>) -> Result<Vec<JobInfo>, String> {
+    Ok(state.jobs.list())
+}
+
+// Keyspace analysis
+
+// Builds a prefix tree in the background; once finished, nodes are fetched
+// lazily with get_namespace_level using the returned job id
+#[tauri::command]
+pub async fn start_namespace_analysis(
+    connection_id: String,
+    pattern: Option<String>,
+    delimiter: Option<String>,
+    memory_sample_rate: Option<f64>,
+    max_depth: Option<usize>,
+    on_event: Channel<JobEvent<Option<NamespaceLevel>>>,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let mut conn = {
+        let manager = state.redis_manager.lock().unwrap();
+        manager
+            .get_connection(&connection_id)
+            .ok_or("Connection not found")?
+    };
+
+    let options = NamespaceOptions {
+        pattern: pattern.unwrap_or_else(|| "*".to_string()),
+        delimiter: delimiter
+            .filter(|d| !d.is_empty())
+            .unwrap_or_else(|| ":".to_string()),
+        memory_sample_rate: memory_sample_rate.unwrap_or(0.1),
+        max_depth: max_depth.unwrap_or(8),
+    };
+
+    let trees = state.namespace_trees.clone();
+
+    Ok(jobs::spawn_job(
+        &state.jobs,
+        "namespace",
+        &connection_id,
+        on_event,
+        move |cancel, reporter| {
+            reporter.start(keyspace::dbsize(&mut conn));
+            let tree =
+                namespace::build_namespace_tree(&mut conn, &options, cancel, |processed, total| {
+                    reporter.progress(processed, total)
+                })
+                .map_err(|e| e.to_string())?;
+
+            let root = tree.level(&[], Some(100));
+            trees
+                .lock()
+                .unwrap()
+                .insert(reporter.job_id().to_string(), tree);
+            Ok(root)
+        },
+    ))
+}
+
+#[tauri::command]
+pub async fn get_namespace_level(
+    analysis_id: String,
+    path: Vec<String>,
+    limit: Option<usize>,
+    state: State<'_, AppState>,
+) -> Result<NamespaceLevel, String> {
+    let trees = state.namespace_trees.lock().unwrap();
+    let tree = trees.get(&analysis_id).ok_or("Analysis not found")?;
+
+    tree.level(&path, limit)
+        .ok_or_else(|| format!("Namespace not found: {}", path.join(&tree.delimiter)))
+}
+
+#[tauri::command]
+pub async fn drop_namespace_analysis(
+    analysis_id: String,
+    state: State<'_, AppState>,
+) -> Result<bool, String> {
+    let mut trees = state.namespace_trees.lock().unwrap();
+    Ok(trees.remove(&analysis_id).is_some())
+}
+
+#[tauri::command]
+pub async fn start_bigkeys_analysis(
+    connection_id: String,
+    pattern: Option<String>,
+    top_n: Option<usize>,
+    memory_samples: Option<usize>,
+    on_event: Channel<JobEvent<BigKeysReport>>,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let mut conn = {
+        let manager = state.redis_manager.lock().unwrap();
+        manager
+            .get_connection(&connection_id)
+            .ok_or("Connection not found")?
+    };
+
+    let options = BigKeysOptions {
+        pattern: pattern.unwrap_or_else(|| "*".to_string()),
+        top_n: top_n.unwrap_or(10),
+        memory_samples: memory_samples.unwrap_or(5),
+    };
+
+    Ok(jobs::spawn_job(
+        &state.jobs,
+        "bigkeys",
+        &connection_id,
+        on_event,
+        move |cancel, reporter| {
+            reporter.start(keyspace::dbsize(&mut conn));
+            bigkeys::analyze_big_keys(&mut conn, &options, cancel, |processed, total| {
+                reporter.progress(processed, total)
+            })
+            .map_err(|e| e.to_string())
+        },
+    ))
+}
+
+// TTL histogram, keys without an expiry and per-prefix TTL coverage
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn start_expiry_analysis(
+    connection_id: String,
+    pattern: Option<String>,
+    expiring_within_minutes: Option<u64>,
+    expiring_limit: Option<usize>,
+    delimiter: Option<String>,
+    prefix_depth: Option<usize>,
+    max_prefixes: Option<usize>,
+    on_event: Channel<JobEvent<ExpiryReport>>,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let mut conn = {
+        let manager = state.redis_manager.lock().unwrap();
+        manager
+            .get_connection(&connection_id)
+            .ok_or("Connection not found")?
+    };
+
+    let options = ExpiryOptions {
+        pattern: pattern.unwrap_or_else(|| "*".to_string()),
+        expiring_within_minutes: expiring_within_minutes.unwrap_or(60),
+        expiring_limit: expiring_limit.unwrap_or(100),
+        delimiter: delimiter.unwrap_or_else(|| ":".to_string()),
+        prefix_depth: prefix_depth.unwrap_or(1),
+        max_prefixes: max_prefixes.unwrap_or(100),
+        memory_samples: 5,
+    };
+
+    Ok(jobs::spawn_job(
+        &state.jobs,
+        "expiry",
+        &connection_id,
+        on_event,
+        move |cancel, reporter| {
+            reporter.start(keyspace::dbsize(&mut conn));
+            expiry::analyze_expiry(&mut conn, &options, cancel, |processed, total| {
+                reporter.progress(processed, total)
+            })
+            .map_err(|e| e.to_string())
+        },
+    ))
+}
+
+// Rank keys by LFU access frequency, or by idle time when the server uses a
+// non-LFU eviction policy. Fails up front if the policy rules out the mode.
+#[tauri::command]
+pub async fn start_hotkeys_analysis(
+    connection_id: String,
+    pattern: Option<String>,
+    mode: Option<String>, // "auto", "frequency" or "idle"
+    top_n: Option<usize>,
+    on_event: Channel<JobEvent<HotKeysReport>>,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let mut conn = {
+        let manager = state.redis_manager.lock().unwrap();
+        manager
+            .get_connection(&connection_id)
+            .ok_or("Connection not found")?
+    };
+
+    let policy = hotkeys::maxmemory_policy(&mut conn)?;
+    let mode = hotkeys::resolve_mode(&policy, mode.as_deref())?;
+    let pattern = pattern.unwrap_or_else(|| "*".to_string());
+    let top_n = top_n.unwrap_or(50);
+
+    Ok(jobs::spawn_job(
+        &state.jobs,
+        "hotkeys",
+        &connection_id,
+        on_event,
+        move |cancel, reporter| {
+            reporter.start(keyspace::dbsize(&mut conn));
+            hotkeys::analyze_hot_keys(
+                &mut conn,
+                &pattern,
+                mode,
+                policy,
+                top_n,
+                cancel,
+                |processed, total| reporter.progress(processed, total),
+            )
+            .map_err(|e| e.to_string())
+        },
+    ))
+}
+
+// Write an analysis report (big keys, TTL, hot keys, ...) to a JSON file
+#[tauri::command]
+pub async fn save_analysis_report(path: String, report: serde_json::Value) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(&report)
+        .map_err(|e| format!("Failed to serialize report: {}", e))?;
+
+    std::fs::write(&path, json).map_err(|e| format!("Failed to write report: {}", e))?;
+
+    Ok(())
+}
+
+// Export keys matching `pattern` (or an explicit key list) to an NDJSON file
+// with type, TTL and binary-safe values
+#[tauri::command]
+pub async fn start_key_export(
+    connection_id: String,
+    output_path: String,
+    pattern: Option<String>,
+    keys: Option<Vec<BinaryString>>,
+    on_event: Channel<JobEvent<ExportSummary>>,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let mut conn = {
+        let manager = state.redis_manager.lock().unwrap();
+        manager
+            .get_connection(&connection_id)
+            .ok_or("Connection not found")?
+    };
+
+    let options = ExportOptions {
+        pattern: pattern.unwrap_or_else(|| "*".to_string()),
+        keys,
+    };
+
+    Ok(jobs::spawn_job(
+        &state.jobs,
+        "export",
+        &connection_id,
+        on_event,
+        move |cancel, reporter| {
+            reporter.start(match &options.keys {
+                Some(keys) => keys.len() as u64,
+                None => keyspace::dbsize(&mut conn),
+            });
+            export::export_keys(
+                &mut conn,
+                &options,
+                &output_path,
+                cancel,
+                |processed, total| reporter.progress(processed, total),
+            )
+        },
+    ))
+}
+
+// Import keys from an NDJSON export or a flat CSV file. With `dry_run` the
+// conflict policy is resolved and reported but nothing is written.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn start_key_import(
+    connection_id: String,
+    input_path: String,
+    format: Option<ImportFormat>,
+    conflict: Option<ConflictPolicy>,
+    rename_suffix: Option<String>,
+    dry_run: Option<bool>,
+    confirmation_token: Option<String>, // Approved with confirm_command
+    on_event: Channel<JobEvent<ImportReport>>,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let mut conn = {
+        let manager = state.redis_manager.lock().unwrap();
+        manager
+            .get_writable_connection(&connection_id)?
+            .with_confirmation(confirmation_token)
+    };
+
+    let options = ImportOptions {
+        format: format.unwrap_or_else(|| ImportFormat::from_path(&input_path)),
+        conflict: conflict.unwrap_or(ConflictPolicy::Skip),
+        rename_suffix: rename_suffix.unwrap_or_else(|| ":imported".to_string()),
+        dry_run: dry_run.unwrap_or(false),
+    };
+
+    let audit = state.audit.clone();
+    let audit_id = connection_id.clone();
+
+    Ok(jobs::spawn_job(
+        &state.jobs,
+        "import",
+        &connection_id,
+        on_event,
+        move |cancel, reporter| {
+            reporter.start(std::fs::metadata(&input_path).map(|m| m.len()).unwrap_or(0));
+            let result = import::import_keys(
+                &mut conn,
+                &input_path,
+                &options,
+                cancel,
+                |processed, total| reporter.progress(processed, total),
+            );
+
+            if !options.dry_run {
+                let args = [input_path.clone()];
+                audit.record(&audit_id, conn.get_db(), "import", None, &args, &result);
+            }
+            result
+        },
+    ))
+}
+
+// Back up keys with DUMP into a local archive. Unlike the NDJSON export this
+// keeps exact encodings and module types.
+#[tauri::command]
+pub async fn start_key_backup(
+    connection_id: String,
+    output_path: String,
+    pattern: Option<String>,
+    keys: Option<Vec<BinaryString>>,
+    on_event: Channel<JobEvent<BackupSummary>>,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let (mut conn, db) = {
+        let manager = state.redis_manager.lock().unwrap();
+        let conn = manager
+            .get_connection(&connection_id)
+            .ok_or("Connection not found")?;
+        (conn, manager.database(&connection_id))
+    };
+    let pattern = pattern.unwrap_or_else(|| "*".to_string());
+
+    Ok(jobs::spawn_job(
+        &state.jobs,
+        "backup",
+        &connection_id,
+        on_event,
+        move |cancel, reporter| {
+            reporter.start(match &keys {
+                Some(keys) => keys.len() as u64,
+                None => keyspace::dbsize(&mut conn),
+            });
+            backup::backup_keys(
+                &mut conn,
+                &pattern,
+                keys,
+                db,
+                &output_path,
+                cancel,
+                |processed, total| reporter.progress(processed, total),
+            )
+        },
+    ))
+}
+
+#[tauri::command]
+pub async fn read_backup_manifest(path: String) -> Result<BackupManifest, String> {
+    backup::read_manifest(&path)
+}
+
+// Restore a backup archive with RESTORE ... ABSTTL. Fails up front if the
+// server cannot load the archive's RDB version.
+#[tauri::command]
+pub async fn start_backup_restore(
+    connection_id: String,
+    input_path: String,
+    replace: Option<bool>,
+    pattern: Option<String>,
+    confirmation_token: Option<String>, // Approved with confirm_command
+    on_event: Channel<JobEvent<RestoreReport>>,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let mut conn = {
+        let manager = state.redis_manager.lock().unwrap();
+        manager
+            .get_writable_connection(&connection_id)?
+            .with_confirmation(confirmation_token)
+    };
+
+    let manifest = backup::read_manifest(&input_path)?;
+    backup::check_compatibility(&mut conn, &manifest)?;
+
+    let options = RestoreOptions {
+        replace: replace.unwrap_or(true),
+        pattern,
+    };
+
+    let audit = state.audit.clone();
+    let audit_id = connection_id.clone();
+
+    Ok(jobs::spawn_job(
+        &state.jobs,
+        "restore",
+        &connection_id,
+        on_event,
+        move |cancel, reporter| {
+            reporter.start(manifest.key_count);
+            let result = backup::restore_keys(
+                &mut conn,
+                &input_path,
+                &options,
+                cancel,
+                |processed, total| reporter.progress(processed, total),
+            );
+
+            let args = [input_path.clone()];
+            let pattern = options.pattern.as_deref();
+            audit.record(&audit_id, conn.get_db(), "restore", pattern, &args, &result);
+            result
+        },
+    ))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MigrationRequest {
+    pub source_db: Option<u32>, // Defaults to each connection's current db
+    pub target_db: Option<u32>,
+    pub pattern: Option<String>,
+    pub keys: Option<Vec<BinaryString>>,
+    pub mode: Option<MigrationMode>,
+    // Target address as seen from the source server, for MIGRATE. Defaults to
+    // the saved target connection's host and port.
+    pub migrate_host: Option<String>,
+    pub migrate_port: Option<u16>,
+    pub conflict: Option<ConflictPolicy>,
+    pub rename_suffix: Option<String>,
+    pub delete_source: Option<bool>,
+    pub max_keys_per_second: Option<u32>,
+}
+
+// Copy (or move) keys between two open connections. DUMP/RESTORE goes
+// through this app's connections, so SSH tunnels on either side just work.
+#[tauri::command]
+pub async fn start_key_migration(
+    source_connection_id: String,
+    target_connection_id: String,
+    request: MigrationRequest,
+    confirmation_token: Option<String>, // Approved with confirm_command
+    on_event: Channel<JobEvent<MigrationReport>>,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let (mut source, mut target, source_db, target_db) = {
+        let manager = state.redis_manager.lock().unwrap();
+        // MIGRATE and deleting migrated keys both write to the source
+        let source_writes = request.delete_source.unwrap_or(false)
+            || matches!(request.mode, Some(MigrationMode::Migrate));
+        let source = if source_writes {
+            manager.get_writable_connection(&source_connection_id)
+        } else {
+            manager
+                .get_connection(&source_connection_id)
+                .ok_or(AccessError::NotFound)
+        }
+        .map_err(|e| format!("Source: {}", e))?;
+        let target = manager
+            .get_writable_connection(&target_connection_id)
+            .map_err(|e| format!("Target: {}", e))?;
+        // A token is only known to the connection that issued it
+        let source = source.with_confirmation(confirmation_token.clone());
+        let target = target.with_confirmation(confirmation_token);
+        (
+            source,
+            target,
+            request
+                .source_db
+                .or_else(|| manager.database(&source_connection_id)),
+            request
+                .target_db
+                .or_else(|| manager.database(&target_connection_id)),
+        )
+    };
+    let target_db = target_db.unwrap_or(0);
+
+    if source_connection_id == target_connection_id && source_db == Some(target_db) {
+        return Err("Source and target are the same database".to_string());
+    }
+    if let Some(db) = request.source_db {
+        redis::cmd("SELECT")
+            .arg(db)
+            .query::<()>(&mut source)
+            .map_err(|e| e.to_string())?;
+    }
+    redis::cmd("SELECT")
+        .arg(target_db)
+        .query::<()>(&mut target)
+        .map_err(|e| e.to_string())?;
+
+    let mode = request.mode.unwrap_or(MigrationMode::DumpRestore);
+    let conflict = request.conflict.unwrap_or(ConflictPolicy::Skip);
+    let endpoint = match mode {
+        MigrationMode::DumpRestore => {
+            migrate::check_compatibility(&mut source, &mut target)?;
+            None
+        }
+        MigrationMode::Migrate => {
+            if conflict == ConflictPolicy::Rename {
+                return Err("MIGRATE cannot rename keys; use DUMP/RESTORE mode".to_string());
+            }
+            let stored = state
+                .connection_store
+                .lock()
+                .unwrap()
+                .load_connections()
+                .map_err(|e| e.to_string())?
+                .into_iter()
+                .find(|c| c.id == target_connection_id);
+            let host = request
+                .migrate_host
+                .or_else(|| stored.as_ref().map(|c| c.host.clone()))
+                .ok_or("Target host is required for MIGRATE")?;
+            let port = request
+                .migrate_port
+                .or_else(|| stored.as_ref().map(|c| c.port))
+                .ok_or("Target port is required for MIGRATE")?;
+            Some(MigrateEndpoint {
+                host,
+                port,
+                username: stored.and_then(|c| c.username),
+                password: state
+                    .password_store
+                    .get_password(&target_connection_id)
+                    .map_err(|e| format!("Failed to get password: {}", e))?,
+            })
+        }
+    };
+
+    let options = MigrationOptions {
+        pattern: request.pattern.unwrap_or_else(|| "*".to_string()),
+        keys: request.keys,
+        mode,
+        endpoint,
+        target_db,
+        conflict,
+        rename_suffix: request
+            .rename_suffix
+            .unwrap_or_else(|| ":migrated".to_string()),
+        delete_source: request.delete_source.unwrap_or(false),
+        max_keys_per_second: request.max_keys_per_second,
+        timeout_ms: 5000,
+    };
+
+    let audit = state.audit.clone();
+
+    Ok(jobs::spawn_job(
+        &state.jobs,
+        "migrate",
+        &source_connection_id.clone(),
+        on_event,
+        move |cancel, reporter| {
+            reporter.start(match &options.keys {
+                Some(keys) => keys.len() as u64,
+                None => keyspace::dbsize(&mut source),
+            });
+            let result = migrate::migrate_keys(
+                &mut source,
+                &mut target,
+                &options,
+                cancel,
+                |processed, total| reporter.progress(processed, total),
+            );
+
+            let args = [format!("from {}", source_connection_id)];
+            let pattern = options.keys.is_none().then_some(options.pattern.as_str());
+            audit.record(
+                &target_connection_id,
+                target.get_db(),
+                "migrate",
+                pattern,
+                &args,
+                &result,
+            );
+            result
+        },
+    ))
+}
+
+// Database diff
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DbDiffRequest {
+    pub a_db: Option<u32>, // Defaults to each connection's current db
+    pub b_db: Option<u32>,
+    pub pattern: Option<String>,
+    pub compare_values: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", content = "data", rename_all = "snake_case")]
+pub enum DiffEvent {
+    Started {
+        job_id: String,
+        total: u64,
+    },
+    Differences {
+        differences: Vec<KeyDifference>,
+        summary: DiffSummary, // Running totals
+    },
+    Progress {
+        processed: u64,
+        total: u64,
+    },
+    Finished {
+        summary: DiffSummary,
+        cancelled: bool,
+    },
+    Error {
+        message: String,
+    },
+}
+
+fn select_db(conn: &mut RedisConn, db: Option<u32>) -> Result<(), String> {
+    match db {
+        Some(db) => redis::cmd("SELECT")
+            .arg(db)
+            .query::<()>(conn)
+            .map_err(|e| e.to_string()),
+        None => Ok(()),
+    }
+}
+
+// Compares the keys matching a pattern in two connection/db pairs and
+// streams the differences as they are found
+#[tauri::command]
+pub async fn start_db_diff(
+    a_connection_id: String,
+    b_connection_id: String,
+    request: DbDiffRequest,
+    on_event: Channel<DiffEvent>,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let (mut a, mut b, a_db, b_db) = {
+        let manager = state.redis_manager.lock().unwrap();
+        let a = manager
+            .get_connection(&a_connection_id)
+            .ok_or("Connection A not found")?;
+        let b = manager
+            .get_connection(&b_connection_id)
+            .ok_or("Connection B not found")?;
+        (
+            a,
+            b,
+            request.a_db.or_else(|| manager.database(&a_connection_id)),
+            request.b_db.or_else(|| manager.database(&b_connection_id)),
+        )
+    };
+
+    if a_connection_id == b_connection_id && a_db == b_db {
+        return Err("Both sides are the same database".to_string());
+    }
+    select_db(&mut a, request.a_db)?;
+    select_db(&mut b, request.b_db)?;
+
+    let options = DiffOptions {
+        pattern: request.pattern.unwrap_or_else(|| "*".to_string()),
+        compare_values: request.compare_values.unwrap_or(true),
+    };
+
+    let job_id = jobs::spawn(
+        &state.jobs,
+        "diff",
+        &a_connection_id,
+        {
+            let on_event = on_event.clone();
+            move |job_id, cancel| {
+                let total = keyspace::dbsize(&mut a) + keyspace::dbsize(&mut b);
+                let _ = on_event.send(DiffEvent::Started {
+                    job_id: job_id.to_string(),
+                    total,
+                });
+
+                let mut throttle = ProgressThrottle::new();
+                let result = diff::diff_databases(
+                    &mut a,
+                    &mut b,
+                    &options,
+                    cancel,
+                    |processed, total| {
+                        if throttle.ready() {
+                            let _ = on_event.send(DiffEvent::Progress { processed, total });
+                        }
+                    },
+                    |differences, summary| {
+                        let _ = on_event.send(DiffEvent::Differences {
+                            differences,
+                            summary: summary.clone(),
+                        });
+                    },
+                );
+
+                let _ = match result {
+                    Ok(summary) => {
+                        let cancelled = summary.cancelled;
+                        on_event.send(DiffEvent::Finished { summary, cancelled })
+                    }
+                    Err(message) => on_event.send(DiffEvent::Error { message }),
+                };
+            }
+        },
+        move |message| {
+            let _ = on_event.send(DiffEvent::Error { message });
+        },
+    );
+
+    Ok(job_id)
+}
+
+// Type-aware diff of two keys, possibly on different connections or dbs.
+// Also used for the keys reported by a database diff.
+#[tauri::command]
+pub async fn diff_keys(
+    a_connection_id: String,
+    a_key: BinaryString,
+    b_connection_id: String,
+    b_key: BinaryString,
+    a_db: Option<u32>,
+    b_db: Option<u32>,
+    state: State<'_, AppState>,
+) -> Result<KeyElementDiff, String> {
+    let (mut a, mut b) = {
+        let manager = state.redis_manager.lock().unwrap();
+        (
+            manager
+                .get_connection(&a_connection_id)
+                .ok_or("Connection A not found")?,
+            manager
+                .get_connection(&b_connection_id)
+                .ok_or("Connection B not found")?,
+        )
+    };
+    select_db(&mut a, a_db)?;
+    select_db(&mut b, b_db)?;
+
+    diff::diff_keys(&mut a, &a_key, &mut b, &b_key)
+}
+
+// Bulk operations
+
+// Explicit key lists as audit log arguments; the log is text
+fn audit_keys(keys: Option<&[BinaryString]>) -> Vec<String> {
+    keys.unwrap_or_default()
+        .iter()
+        .map(BinaryString::to_string_lossy)
+        .collect()
+}
+
+// Sample keys shown in a bulk preview
+const BULK_PREVIEW_SAMPLE: usize = 100;
+
+// Match count, sample keys and estimated memory for a pattern, shown before
+// a bulk operation is confirmed
+#[tauri::command]
+pub async fn preview_bulk_keys(
+    connection_id: String,
+    pattern: String,
+    sample_size: Option<usize>,
+    state: State<'_, AppState>,
+) -> Result<BulkPreview, String> {
+    let mut conn = {
+        let manager = state.redis_manager.lock().unwrap();
+        manager
+            .get_connection(&connection_id)
+            .ok_or("Connection not found")?
+    };
+
+    bulk::preview(
+        &mut conn,
+        &pattern,
+        sample_size.unwrap_or(BULK_PREVIEW_SAMPLE),
+    )
+    .map_err(|e| e.to_string())
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BulkDeleteRequest {
+    pub pattern: Option<String>,
+    pub keys: Option<Vec<BinaryString>>,
+    pub batch_size: Option<usize>,
+    pub max_keys_per_second: Option<u32>,
+}
+
+#[tauri::command]
+pub async fn start_bulk_delete(
+    connection_id: String,
+    request: BulkDeleteRequest,
+    confirmation_token: Option<String>, // Approved with confirm_command
+    on_event: Channel<JobEvent<BulkDeleteSummary>>,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let mut conn = {
+        let manager = state.redis_manager.lock().unwrap();
+        manager
+            .get_writable_connection(&connection_id)?
+            .with_confirmation(confirmation_token)
+    };
+
+    let options = BulkDeleteOptions {
+        pattern: request.pattern.unwrap_or_else(|| "*".to_string()),
+        keys: request.keys,
+        batch_size: request.batch_size,
+        max_keys_per_second: request.max_keys_per_second,
+    };
+
+    let audit = state.audit.clone();
+    let audit_id = connection_id.clone();
+
+    Ok(jobs::spawn_job(
+        &state.jobs,
+        "bulk-delete",
+        &connection_id,
+        on_event,
+        move |cancel, reporter| {
+            reporter.start(match &options.keys {
+                Some(keys) => keys.len() as u64,
+                None => keyspace::dbsize(&mut conn),
+            });
+            let result = bulk::delete_keys(&mut conn, &options, cancel, |processed, total| {
+                reporter.progress(processed, total)
+            });
+
+            let args = audit_keys(options.keys.as_deref());
+            let pattern = options.keys.is_none().then_some(options.pattern.as_str());
+            audit.record(
+                &audit_id,
+                conn.get_db(),
+                "bulk-delete",
+                pattern,
+                &args,
+                &result,
+            );
+            result
+        },
+    ))
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BulkTtlRequest {
+    pub pattern: Option<String>,
+    pub keys: Option<Vec<BinaryString>>,
+    pub change: TtlChange,
+    pub batch_size: Option<usize>,
+    pub max_keys_per_second: Option<u32>,
+}
+
+#[tauri::command]
+pub async fn start_bulk_ttl(
+    connection_id: String,
+    request: BulkTtlRequest,
+    confirmation_token: Option<String>, // Approved with confirm_command
+    on_event: Channel<JobEvent<BulkTtlSummary>>,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    request.change.validate()?;
+
+    let mut conn = {
+        let manager = state.redis_manager.lock().unwrap();
+        manager
+            .get_writable_connection(&connection_id)?
+            .with_confirmation(confirmation_token)
+    };
+
+    let options = BulkTtlOptions {
+        pattern: request.pattern.unwrap_or_else(|| "*".to_string()),
+        keys: request.keys,
+        change: request.change,
+        batch_size: request.batch_size,
+        max_keys_per_second: request.max_keys_per_second,
+    };
+
+    let audit = state.audit.clone();
+    let audit_id = connection_id.clone();
+
+    Ok(jobs::spawn_job(
+        &state.jobs,
+        "bulk-ttl",
+        &connection_id,
+        on_event,
+        move |cancel, reporter| {
+            reporter.start(match &options.keys {
+                Some(keys) => keys.len() as u64,
+                None => keyspace::dbsize(&mut conn),
+            });
+            let result = bulk::set_ttls(&mut conn, &options, cancel, |processed, total| {
+                reporter.progress(processed, total)
+            });
+
+            let mut args = vec![serde_json::to_string(&options.change).unwrap_or_default()];
+            args.extend(audit_keys(options.keys.as_deref()));
+            let pattern = options.keys.is_none().then_some(options.pattern.as_str());
+            audit.record(
+                &audit_id,
+                conn.get_db(),
+                "bulk-ttl",
+                pattern,
+                &args,
+                &result,
+            );
+            result
+        },
+    ))
+}
+
+// Old -> new names (first `limit`), collisions and cross-slot moves for a
+// bulk rename
+#[tauri::command]
+pub async fn preview_bulk_rename(
+    connection_id: String,
+    rule: RenameRule,
+    pattern: Option<String>,
+    limit: Option<usize>,
+    state: State<'_, AppState>,
+) -> Result<RenamePreview, String> {
+    let mut conn = {
+        let manager = state.redis_manager.lock().unwrap();
+        manager
+            .get_connection(&connection_id)
+            .ok_or("Connection not found")?
+    };
+
+    let pattern = pattern.unwrap_or_else(|| rule.scan_pattern());
+    rename::preview(
+        &mut conn,
+        &rule,
+        &pattern,
+        limit.unwrap_or(BULK_PREVIEW_SAMPLE),
+    )
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BulkRenameRequest {
+    pub rule: RenameRule,
+    pub pattern: Option<String>, // Defaults to the keys the rule can match
+    pub overwrite: Option<bool>,
+    pub batch_size: Option<usize>,
+    pub max_keys_per_second: Option<u32>,
+}
+
+#[tauri::command]
+pub async fn start_bulk_rename(
+    connection_id: String,
+    request: BulkRenameRequest,
+    confirmation_token: Option<String>, // Approved with confirm_command
+    on_event: Channel<JobEvent<RenameReport>>,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    request.rule.compile()?;
+
+    let mut conn = {
+        let manager = state.redis_manager.lock().unwrap();
+        manager
+            .get_writable_connection(&connection_id)?
+            .with_confirmation(confirmation_token)
+    };
+
+    let options = RenameOptions {
+        pattern: request
+            .pattern
+            .unwrap_or_else(|| request.rule.scan_pattern()),
+        rule: request.rule,
+        overwrite: request.overwrite.unwrap_or(false),
+        batch_size: request.batch_size,
+        max_keys_per_second: request.max_keys_per_second,
+    };
+
+    let audit = state.audit.clone();
+    let audit_id = connection_id.clone();
+
+    Ok(jobs::spawn_job(
+        &state.jobs,
+        "bulk-rename",
+        &connection_id,
+        on_event,
+        move |cancel, reporter| {
+            reporter.start(keyspace::dbsize(&mut conn));
+            let result = rename::rename_keys(&mut conn, &options, cancel, |processed, total| {
+                reporter.progress(processed, total)
+            });
 
-    Ok(ScanResult {
-        keys: batch,
-        cursor: next_cursor,
-        has_more: next_cursor != 0,
-    })
+            let args = [serde_json::to_string(&options.rule).unwrap_or_default()];
+            let pattern = Some(options.pattern.as_str());
+            audit.record(
+                &audit_id,
+                conn.get_db(),
+                "bulk-rename",
+                pattern,
+                &args,
+                &result,
+            );
+            result
+        },
+    ))
 }
 
 #[tauri::command]
@@ -214,6 +1398,22 @@ pub async fn get_key_info(
     })
 }
 
+// Batched replacement for calling get_key_info once per key in the key list
+#[tauri::command]
+pub async fn get_keys_metadata(
+    connection_id: String,
+    keys: Vec<String>,
+    state: State<'_, AppState>,
+) -> Result<Vec<RedisKey>, String> {
+    let manager = state.redis_manager.lock().unwrap();
+
+    let mut conn = manager
+        .get_connection(&connection_id)
+        .ok_or("Connection not found")?;
+
+    keyspace::fetch_key_metadata(&mut conn, &keys).map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub async fn get_value(
     connection_id: String,
@@ -230,8 +1430,29 @@ pub async fn get_value(
 
     let value = match key_type.as_str() {
         "string" => {
-            let val: String = conn.get(&key).map_err(|e| e.to_string())?;
-            val
+            // Read raw bytes: HyperLogLogs and bitmaps are binary strings and
+            // would fail UTF-8 decoding.
+            let raw: Vec<u8> = conn.get(&key).map_err(|e| e.to_string())?;
+
+            if let Some(encoding) = hll_encoding(&raw) {
+                let cardinality: u64 = redis::cmd("PFCOUNT")
+                    .arg(&key)
+                    .query(&mut conn)
+                    .map_err(|e| e.to_string())?;
+
+                let info = HyperLogLogInfo {
+                    cardinality,
+                    encoding: encoding.to_string(),
+                    size_bytes: raw.len(),
+                };
+
+                return Ok(RedisValue {
+                    value: serde_json::to_string_pretty(&info).unwrap(),
+                    key_type: "hyperloglog".to_string(),
+                });
+            }
+
+            String::from_utf8_lossy(&raw).to_string()
         }
         "list" => {
             let val: Vec<String> = conn.lrange(&key, 0, -1).map_err(|e| e.to_string())?;
@@ -275,83 +1496,367 @@ pub async fn set_value(
     connection_id: String,
     key: String,
     value: String,
+    confirmation_token: Option<String>, // Approved with confirm_command
     state: State<'_, AppState>,
 ) -> Result<(), String> {
     let manager = state.redis_manager.lock().unwrap();
 
     let mut conn = manager
-        .get_connection(&connection_id)
-        .ok_or("Connection not found")?;
-
-    conn.set::<_, _, ()>(&key, value)
-        .map_err(|e| e.to_string())?;
-
-    Ok(())
+        .get_writable_connection(&connection_id)?
+        .with_confirmation(confirmation_token);
+
+    state.undo.run(
+        &state.audit,
+        &connection_id,
+        &mut conn,
+        redis::cmd("SET").arg(&key).arg(&value),
+    )
 }
 
 #[tauri::command]
 pub async fn delete_key(
     connection_id: String,
     key: String,
+    confirmation_token: Option<String>, // Approved with confirm_command
     state: State<'_, AppState>,
 ) -> Result<(), String> {
     let manager = state.redis_manager.lock().unwrap();
 
     let mut conn = manager
-        .get_connection(&connection_id)
-        .ok_or("Connection not found")?;
+        .get_writable_connection(&connection_id)?
+        .with_confirmation(confirmation_token);
+
+    state.undo.run(
+        &state.audit,
+        &connection_id,
+        &mut conn,
+        redis::cmd("DEL").arg(&key),
+    )
+}
 
-    conn.del::<_, ()>(&key).map_err(|e| e.to_string())?;
+// Key names are binary-safe: plain strings, or {"base64": ...} for names
+// that are not valid UTF-8
 
-    Ok(())
+#[tauri::command]
+pub async fn rename_key(
+    connection_id: String,
+    key: BinaryString,
+    new_key: BinaryString,
+    replace: Option<bool>,
+    confirmation_token: Option<String>, // Approved with confirm_command
+    state: State<'_, AppState>,
+) -> Result<KeyOperationResult, String> {
+    let mut conn = {
+        let manager = state.redis_manager.lock().unwrap();
+        manager
+            .get_writable_connection(&connection_id)?
+            .with_confirmation(confirmation_token)
+    };
+
+    let args = [String::from_utf8_lossy(&new_key.0).into_owned()];
+    let source = String::from_utf8_lossy(&key.0).into_owned();
+    let result = keyops::rename(&mut conn, key, new_key, replace.unwrap_or(false));
+    let command = if replace == Some(true) {
+        "RENAME"
+    } else {
+        "RENAMENX"
+    };
+    state.audit.record(
+        &connection_id,
+        conn.get_db(),
+        command,
+        Some(&source),
+        &args,
+        &result,
+    );
+    result
+}
+
+#[tauri::command]
+pub async fn copy_key(
+    connection_id: String,
+    key: BinaryString,
+    new_key: BinaryString,
+    destination_db: Option<u32>,
+    replace: Option<bool>,
+    confirmation_token: Option<String>, // Approved with confirm_command
+    state: State<'_, AppState>,
+) -> Result<KeyOperationResult, String> {
+    let mut conn = {
+        let manager = state.redis_manager.lock().unwrap();
+        manager
+            .get_writable_connection(&connection_id)?
+            .with_confirmation(confirmation_token)
+    };
+
+    let mut args = vec![String::from_utf8_lossy(&new_key.0).into_owned()];
+    args.extend(destination_db.map(|db| format!("DB {}", db)));
+    let source = String::from_utf8_lossy(&key.0).into_owned();
+    let result = keyops::copy(
+        &mut conn,
+        key,
+        new_key,
+        destination_db,
+        replace.unwrap_or(false),
+    );
+    state.audit.record(
+        &connection_id,
+        conn.get_db(),
+        "COPY",
+        Some(&source),
+        &args,
+        &result,
+    );
+    result
+}
+
+#[tauri::command]
+pub async fn move_key(
+    connection_id: String,
+    key: BinaryString,
+    destination_db: u32,
+    confirmation_token: Option<String>, // Approved with confirm_command
+    state: State<'_, AppState>,
+) -> Result<KeyOperationResult, String> {
+    let mut conn = {
+        let manager = state.redis_manager.lock().unwrap();
+        manager
+            .get_writable_connection(&connection_id)?
+            .with_confirmation(confirmation_token)
+    };
+
+    let source = String::from_utf8_lossy(&key.0).into_owned();
+    let result = keyops::move_to_db(&mut conn, key, destination_db);
+    let args = [destination_db.to_string()];
+    state.audit.record(
+        &connection_id,
+        conn.get_db(),
+        "MOVE",
+        Some(&source),
+        &args,
+        &result,
+    );
+    result
 }
 
+// Without an operation a positive `ttl` sets EXPIRE in seconds and anything
+// else removes the TTL. Returns whether the TTL changed.
 #[tauri::command]
 pub async fn set_ttl(
     connection_id: String,
     key: String,
     ttl: i64,
+    operation: Option<TtlOperation>,
+    condition: Option<TtlCondition>,
+    confirmation_token: Option<String>, // Approved with confirm_command
     state: State<'_, AppState>,
-) -> Result<(), String> {
-    let manager = state.redis_manager.lock().unwrap();
-
-    let mut conn = manager
-        .get_connection(&connection_id)
-        .ok_or("Connection not found")?;
+) -> Result<bool, String> {
+    let mut conn = {
+        let manager = state.redis_manager.lock().unwrap();
+        manager
+            .get_writable_connection(&connection_id)?
+            .with_confirmation(confirmation_token)
+    };
 
-    if ttl > 0 {
-        conn.expire::<_, ()>(&key, ttl).map_err(|e| e.to_string())?;
-    } else {
-        conn.persist::<_, ()>(&key).map_err(|e| e.to_string())?;
-    }
+    let change = TtlChange {
+        operation: operation.unwrap_or(if ttl > 0 {
+            TtlOperation::Expire
+        } else {
+            TtlOperation::Persist
+        }),
+        value: ttl,
+        condition,
+    };
+    change.validate()?;
+
+    let command = format!("{:?}", change.operation).to_uppercase();
+    let undo = state
+        .undo
+        .capture(&connection_id, &mut conn, &command, key.as_bytes());
+
+    let native_condition = condition.is_some() && bulk::native_ttl_conditions(&mut conn);
+    let result = bulk::apply_ttl(
+        &mut conn,
+        std::slice::from_ref(&key),
+        &change,
+        native_condition,
+    )
+    .and_then(|mut outcomes| outcomes.remove(0))
+    .map_err(|e| e.to_string())
+    .and_then(|outcome| match outcome {
+        TtlOutcome::Gone => Err("Key not found".to_string()),
+        outcome => Ok(outcome == TtlOutcome::Updated),
+    });
 
-    Ok(())
+    let mut args = vec![change.value.to_string()];
+    args.extend(condition.map(|c| format!("{:?}", c).to_uppercase()));
+    state.audit.record(
+        &connection_id,
+        conn.get_db(),
+        &command,
+        Some(&key),
+        &args,
+        &result,
+    );
+    state.undo.commit(&connection_id, undo, &result);
+    result
 }
 
 #[tauri::command]
 pub async fn execute_command(
     connection_id: String,
     command: String,
+    confirmation_token: Option<String>, // Approved with confirm_command
     state: State<'_, AppState>,
 ) -> Result<String, String> {
     let manager = state.redis_manager.lock().unwrap();
 
     let mut conn = manager
         .get_connection(&connection_id)
-        .ok_or("Connection not found")?;
+        .ok_or("Connection not found")?
+        .with_confirmation(confirmation_token);
 
     let parts: Vec<&str> = command.trim().split_whitespace().collect();
     if parts.is_empty() {
         return Err("Empty command".to_string());
     }
 
-    let result: redis::RedisResult<redis::Value> =
-        redis::cmd(parts[0]).arg(&parts[1..]).query(&mut conn);
+    // Only commands that may write are audited. When COMMAND INFO is
+    // unavailable the command is audited anyway.
+    let audit = if manager.is_read_only(&connection_id) {
+        access::check_read_only_command(&mut conn, &parts)?;
+        None
+    } else {
+        match access::command_info(&mut conn, parts[0], parts.get(1).copied()) {
+            Ok(Some(info)) if !info.may_modify() => None,
+            Ok(Some(info)) => Some(info.first_key),
+            Ok(None) => None,
+            Err(_) => Some(1),
+        }
+    };
 
-    match result {
-        Ok(value) => Ok(format!("{:?}", value)),
-        Err(e) => Err(e.to_string()),
+    let result = redis::cmd(parts[0])
+        .arg(&parts[1..])
+        .query::<redis::Value>(&mut conn)
+        .map(|value| format!("{:?}", value))
+        .map_err(|e| e.to_string());
+
+    if let Some(first_key) = audit {
+        let key = parts.get(first_key).filter(|_| first_key > 0).copied();
+        let args: Vec<String> = (1..parts.len())
+            .filter(|&i| i != first_key)
+            .map(|i| parts[i].to_string())
+            .collect();
+        let command = parts[0].to_uppercase();
+        state
+            .audit
+            .record(&connection_id, conn.get_db(), &command, key, &args, &result);
+    }
+
+    result
+}
+
+// Approve the token from a "CONFIRM: <token> ..." error after the user has
+// confirmed; the original command can then be re-submitted once with
+// `confirmation_token` set to it. Jobs report the error through their events
+// and are restarted with the token; it covers the rest of the job.
+#[tauri::command]
+pub async fn confirm_command(
+    connection_id: String,
+    token: String,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let manager = state.redis_manager.lock().unwrap();
+    Ok(manager.confirm_command(&connection_id, &token)?)
+}
+
+// Audit log of mutating calls, newest first
+#[tauri::command]
+pub async fn query_audit_log(
+    query: Option<AuditQuery>,
+    state: State<'_, AppState>,
+) -> Result<Vec<AuditEntry>, String> {
+    state.audit.query(&query.unwrap_or_default())
+}
+
+// Returns the number of entries written
+#[tauri::command]
+pub async fn export_audit_log(
+    output_path: String,
+    query: Option<AuditQuery>,
+    format: Option<AuditExportFormat>,
+    state: State<'_, AppState>,
+) -> Result<usize, String> {
+    let format = format.unwrap_or_else(|| AuditExportFormat::from_path(&output_path));
+    state
+        .audit
+        .export(&query.unwrap_or_default(), &output_path, format)
+}
+
+// Enable or disable the undo journal for this session. Saved connections
+// enable it through `undo_journal`; either way the history is lost when the
+// connection is closed.
+#[tauri::command]
+pub async fn set_undo_journal(
+    connection_id: String,
+    enabled: bool,
+    max_entries: Option<usize>,
+    state: State<'_, AppState>,
+) -> Result<UndoJournalInfo, String> {
+    let max_entries = enabled.then(|| max_entries.unwrap_or(undo::DEFAULT_UNDO_ENTRIES));
+    let policy = state
+        .redis_manager
+        .lock()
+        .unwrap()
+        .command_policy(&connection_id);
+    if let Some(policy) = policy {
+        policy.validate(enabled)?;
+    }
+    state.undo.configure(&connection_id, max_entries);
+    Ok(state.undo.info(&connection_id))
+}
+
+#[tauri::command]
+pub async fn get_undo_journal(
+    connection_id: String,
+    state: State<'_, AppState>,
+) -> Result<UndoJournalInfo, String> {
+    Ok(state.undo.info(&connection_id))
+}
+
+// Restore the keys touched by the last `count` journaled operations (default
+// 1), newest first
+#[tauri::command]
+pub async fn undo_operations(
+    connection_id: String,
+    count: Option<usize>,
+    confirmation_token: Option<String>, // Approved with confirm_command
+    state: State<'_, AppState>,
+) -> Result<Vec<UndoResult>, String> {
+    let manager = state.redis_manager.lock().unwrap();
+
+    let mut conn = manager
+        .get_writable_connection(&connection_id)?
+        .with_confirmation(confirmation_token);
+
+    let results = state
+        .undo
+        .undo(&connection_id, &mut conn, count.unwrap_or(1))?;
+    for result in &results {
+        let outcome = match &result.message {
+            Some(message) => Err(message.clone()),
+            None => Ok(()),
+        };
+        state.audit.record(
+            &connection_id,
+            result.db as i64,
+            "UNDO",
+            Some(&result.key.to_string_lossy()),
+            std::slice::from_ref(&result.operation),
+            &outcome,
+        );
     }
+    Ok(results)
 }
 
 // Connection Management Commands
@@ -361,6 +1866,10 @@ pub async fn save_connection(
     connection: ConnectionConfig,
     state: State<'_, AppState>,
 ) -> Result<(), String> {
+    if let Some(policy) = &connection.policy {
+        policy.validate(connection.undo_journal.is_some())?;
+    }
+
     let store = state.connection_store.lock().unwrap();
 
     // Save password to keychain if provided
@@ -416,6 +1925,9 @@ pub async fn save_connection(
         database: connection.database,
         use_tls: connection.use_tls,
         ssh_tunnel: stored_ssh_tunnel,
+        read_only: connection.read_only,
+        policy: connection.policy,
+        undo_journal: connection.undo_journal,
     };
 
     store
@@ -491,6 +2003,148 @@ pub async fn test_connection(config: ConnectionConfig) -> Result<ConnectionStatu
     manager.connect(config).map_err(|e| e.to_string())
 }
 
+// RDB snapshot (offline) connections
+
+#[derive(Debug, Serialize)]
+pub struct RdbDatabaseSummary {
+    pub db: u32,
+    pub keys: usize,
+    pub expires: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RdbFileInfo {
+    pub connection_id: String,
+    pub path: String,
+    pub rdb_version: u32,
+    pub redis_version: Option<String>,
+    pub created_at_ms: Option<i64>,
+    pub file_size: usize,
+    pub database: u32,
+    pub databases: Vec<RdbDatabaseSummary>,
+    pub skipped_modules: BTreeMap<String, u64>, // Module type -> keys whose payload was skipped
+    pub functions: u64,
+    pub aux: BTreeMap<String, String>,
+}
+
+// Parse a dump.rdb file and register it as a read-only connection. Every
+// read command works against it as if it were a server; writes are rejected.
+#[tauri::command]
+pub async fn open_rdb_file(
+    path: String,
+    database: Option<u32>,
+    state: State<'_, AppState>,
+) -> Result<RdbFileInfo, String> {
+    let snapshot = RdbSnapshot::open(&path)?;
+
+    // Default to the first database that holds keys
+    let database = database.unwrap_or_else(|| {
+        snapshot
+            .databases
+            .iter()
+            .find(|(_, db)| db.len() > 0)
+            .map(|(index, _)| *index)
+            .unwrap_or(0)
+    });
+
+    let info = RdbFileInfo {
+        connection_id: format!("rdb:{}", path),
+        path: path.clone(),
+        rdb_version: snapshot.version,
+        redis_version: snapshot.redis_version().map(|v| v.to_string()),
+        created_at_ms: snapshot.created_at_ms(),
+        file_size: snapshot.file_size,
+        database,
+        databases: snapshot
+            .databases
+            .iter()
+            .map(|(db, data)| RdbDatabaseSummary {
+                db: *db,
+                keys: data.len(),
+                expires: data.expires,
+            })
+            .collect(),
+        skipped_modules: snapshot.skipped_modules.clone(),
+        functions: snapshot.functions,
+        aux: snapshot.aux.clone(),
+    };
+
+    let manager = state.redis_manager.lock().unwrap();
+    manager.open_snapshot(&info.connection_id, Arc::new(snapshot), database);
+
+    Ok(info)
+}
+
+// AOF inspection (offline). Jobs are keyed by the AOF path since there is no
+// connection behind them.
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn start_aof_inspection(
+    path: String,
+    filter: Option<AofFilter>,
+    bucket_seconds: Option<i64>,
+    skip: Option<usize>,
+    limit: Option<usize>,
+    on_event: Channel<JobEvent<AofReport>>,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    // Resolve up front so a bad path or manifest fails before a job starts
+    let total = aof::parts_size(&aof::resolve_parts(&path)?);
+    let options = InspectOptions {
+        filter: filter.unwrap_or_default(),
+        bucket_seconds: bucket_seconds.unwrap_or(60),
+        skip: skip.unwrap_or(0),
+        limit: limit.unwrap_or(1000),
+    };
+
+    Ok(jobs::spawn_job(
+        &state.jobs,
+        "aof-inspect",
+        &path.clone(),
+        on_event,
+        move |cancel, reporter| {
+            reporter.start(total);
+            aof::inspect(&path, &options, cancel, |processed, total| {
+                reporter.progress(processed, total)
+            })
+        },
+    ))
+}
+
+// Write the commands matching `filter` to `output_path` as a replayable script
+#[tauri::command]
+pub async fn start_aof_export(
+    path: String,
+    filter: Option<AofFilter>,
+    output_path: String,
+    format: Option<AofExportFormat>,
+    on_event: Channel<JobEvent<AofExportSummary>>,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let total = aof::parts_size(&aof::resolve_parts(&path)?);
+    let filter = filter.unwrap_or_default();
+    let format = format.unwrap_or(AofExportFormat::Resp);
+
+    Ok(jobs::spawn_job(
+        &state.jobs,
+        "aof-export",
+        &path.clone(),
+        on_event,
+        move |cancel, reporter| {
+            reporter.start(total);
+            aof::export(
+                &path,
+                &filter,
+                &output_path,
+                format,
+                cancel,
+                |processed, total| reporter.progress(processed, total),
+            )
+        },
+    ))
+}
+
 #[tauri::command]
 pub async fn get_key_memory_usage(
     connection_id: String,
@@ -558,12 +2212,8 @@ pub async fn get_list_range(
         .get_connection(&connection_id)
         .ok_or("Connection not found")?;
 
-    let total_count: usize = conn.llen(&key).map_err(|e| e.to_string())?;
-    let end = start + count as i64 - 1;
-
-    let items: Vec<String> = conn
-        .lrange(&key, start as isize, end as isize)
-        .map_err(|e| e.to_string())?;
+    let (items, total_count) =
+        keyspace::list_page(&mut conn, key.as_bytes(), start, count).map_err(|e| e.to_string())?;
 
     Ok(PaginatedListResult {
         items,
@@ -593,12 +2243,7 @@ pub async fn get_set_members(
         .get_connection(&connection_id)
         .ok_or("Connection not found")?;
 
-    let (next_cursor, members): (u64, Vec<String>) = redis::cmd("SSCAN")
-        .arg(&key)
-        .arg(cursor)
-        .arg("COUNT")
-        .arg(count)
-        .query(&mut conn)
+    let (next_cursor, members) = keyspace::set_scan_page(&mut conn, key.as_bytes(), cursor, count)
         .map_err(|e| e.to_string())?;
 
     Ok(PaginatedSetResult {
@@ -629,12 +2274,8 @@ pub async fn get_zset_range(
         .get_connection(&connection_id)
         .ok_or("Connection not found")?;
 
-    let total_count: usize = conn.zcard(&key).map_err(|e| e.to_string())?;
-    let end = start + count as i64 - 1;
-
-    let items: Vec<(String, f64)> = conn
-        .zrange_withscores(&key, start as isize, end as isize)
-        .map_err(|e| e.to_string())?;
+    let (items, total_count) =
+        keyspace::zset_page(&mut conn, key.as_bytes(), start, count).map_err(|e| e.to_string())?;
 
     Ok(PaginatedZSetResult {
         items,
@@ -648,6 +2289,14 @@ pub struct PaginatedHashResult {
     pub fields: std::collections::HashMap<String, String>,
     pub cursor: u64,
     pub has_more: bool,
+    // Only populated when requested (Redis 7.4+ hash field expiration)
+    pub field_ttls: Option<std::collections::HashMap<String, HashFieldTtl>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HashFieldTtl {
+    pub ttl: i64,                   // Seconds, -1 if the field has no expiry
+    pub expires_at_ms: Option<i64>, // Unix time in milliseconds
 }
 
 #[tauri::command]
@@ -656,6 +2305,7 @@ pub async fn get_hash_fields(
     key: String,
     cursor: u64,
     count: usize,
+    include_ttl: Option<bool>,
     state: State<'_, AppState>,
 ) -> Result<PaginatedHashResult, String> {
     let manager = state.redis_manager.lock().unwrap();
@@ -664,56 +2314,75 @@ pub async fn get_hash_fields(
         .get_connection(&connection_id)
         .ok_or("Connection not found")?;
 
-    // HSCAN returns cursor and array of [field, value, field, value, ...]
-    let result: redis::Value = redis::cmd("HSCAN")
-        .arg(&key)
-        .arg(cursor)
-        .arg("COUNT")
-        .arg(count)
-        .query(&mut conn)
-        .map_err(|e| e.to_string())?;
-
-    match result {
-        redis::Value::Array(ref bulk) if bulk.len() == 2 => {
-            let next_cursor = match &bulk[0] {
-                redis::Value::BulkString(ref bytes) => {
-                    String::from_utf8_lossy(bytes).parse::<u64>().unwrap_or(0)
-                }
-                _ => 0,
-            };
+    // Read raw bytes so a binary field or value does not fail the whole page
+    let (next_cursor, pairs) =
+        keyspace::hash_scan_page::<Vec<u8>>(&mut conn, key.as_bytes(), cursor, count)
+            .map_err(|e| e.to_string())?;
 
-            let mut fields = std::collections::HashMap::new();
+    let fields: std::collections::HashMap<String, String> = pairs
+        .into_iter()
+        .map(|(field, value)| {
+            (
+                String::from_utf8_lossy(&field).to_string(),
+                String::from_utf8_lossy(&value).to_string(),
+            )
+        })
+        .collect();
+
+    let field_ttls = if include_ttl.unwrap_or(false) && !fields.is_empty() {
+        Some(get_hash_field_ttls(&mut conn, &key, fields.keys())?)
+    } else {
+        None
+    };
 
-            if let redis::Value::Array(ref items) = bulk[1] {
-                let mut i = 0;
-                while i < items.len() {
-                    if i + 1 < items.len() {
-                        let field = match &items[i] {
-                            redis::Value::BulkString(ref bytes) => {
-                                String::from_utf8_lossy(bytes).to_string()
-                            }
-                            _ => continue,
-                        };
-                        let value = match &items[i + 1] {
-                            redis::Value::BulkString(ref bytes) => {
-                                String::from_utf8_lossy(bytes).to_string()
-                            }
-                            _ => continue,
-                        };
-                        fields.insert(field, value);
-                    }
-                    i += 2;
-                }
-            }
+    Ok(PaginatedHashResult {
+        fields,
+        cursor: next_cursor,
+        has_more: next_cursor != 0,
+        field_ttls,
+    })
+}
 
-            Ok(PaginatedHashResult {
-                fields,
-                cursor: next_cursor,
-                has_more: next_cursor != 0,
-            })
-        }
-        _ => Err("Unexpected response format from HSCAN".to_string()),
-    }
+// Fetch HTTL and HPEXPIRETIME for a page of fields in a single round-trip
+fn get_hash_field_ttls<'a>(
+    conn: &mut dyn redis::ConnectionLike,
+    key: &str,
+    fields: impl Iterator<Item = &'a String>,
+) -> Result<std::collections::HashMap<String, HashFieldTtl>, String> {
+    let fields: Vec<&String> = fields.collect();
+
+    let mut pipe = redis::pipe();
+    pipe.cmd("HTTL")
+        .arg(key)
+        .arg("FIELDS")
+        .arg(fields.len())
+        .arg(&fields)
+        .cmd("HPEXPIRETIME")
+        .arg(key)
+        .arg("FIELDS")
+        .arg(fields.len())
+        .arg(&fields);
+
+    let (ttls, expire_times): (Vec<i64>, Vec<i64>) = pipe.query(conn).map_err(|e| {
+        format!(
+            "Failed to read hash field TTLs (requires Redis 7.4+): {}",
+            e
+        )
+    })?;
+
+    Ok(fields
+        .into_iter()
+        .zip(ttls.into_iter().zip(expire_times))
+        .map(|(field, (ttl, expires_at))| {
+            (
+                field.clone(),
+                HashFieldTtl {
+                    ttl,
+                    expires_at_ms: (expires_at >= 0).then_some(expires_at),
+                },
+            )
+        })
+        .collect())
 }
 
 // Collection editing commands
@@ -725,37 +2394,248 @@ pub async fn hash_set_field(
     key: String,
     field: String,
     value: String,
+    confirmation_token: Option<String>, // Approved with confirm_command
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let manager = state.redis_manager.lock().unwrap();
+
+    let mut conn = manager
+        .get_writable_connection(&connection_id)?
+        .with_confirmation(confirmation_token);
+
+    state.undo.run(
+        &state.audit,
+        &connection_id,
+        &mut conn,
+        redis::cmd("HSET").arg(&key).arg(&field).arg(&value),
+    )
+}
+
+#[tauri::command]
+pub async fn hash_delete_field(
+    connection_id: String,
+    key: String,
+    field: String,
+    confirmation_token: Option<String>, // Approved with confirm_command
     state: State<'_, AppState>,
 ) -> Result<(), String> {
     let manager = state.redis_manager.lock().unwrap();
 
-    let mut conn = manager
-        .get_connection(&connection_id)
-        .ok_or("Connection not found")?;
+    let mut conn = manager
+        .get_writable_connection(&connection_id)?
+        .with_confirmation(confirmation_token);
+
+    state.undo.run(
+        &state.audit,
+        &connection_id,
+        &mut conn,
+        redis::cmd("HDEL").arg(&key).arg(&field),
+    )
+}
+
+// Hash field expiration (Redis 7.4+)
+
+#[derive(Debug, Deserialize)]
+pub struct HashFieldExpiry {
+    pub mode: String, // "EX", "PX", "EXAT", "PXAT", "PERSIST" (HGETEX) or "KEEPTTL" (HSETEX)
+    pub value: Option<i64>,
+}
+
+fn push_hash_field_expiry(
+    cmd: &mut redis::Cmd,
+    expiry: &HashFieldExpiry,
+    allowed: &[&str],
+) -> Result<(), String> {
+    let mode = expiry.mode.to_uppercase();
+    if !allowed.contains(&mode.as_str()) {
+        return Err(format!(
+            "Invalid expiry mode: {} (must be one of {})",
+            expiry.mode,
+            allowed.join(", ")
+        ));
+    }
+
+    match mode.as_str() {
+        "PERSIST" | "KEEPTTL" => {
+            cmd.arg(mode);
+        }
+        _ => {
+            let value = expiry
+                .value
+                .filter(|v| *v > 0)
+                .ok_or_else(|| format!("{} requires a positive value", mode))?;
+            cmd.arg(mode).arg(value);
+        }
+    }
+
+    Ok(())
+}
+
+fn validate_hash_fields(fields: &[String]) -> Result<(), String> {
+    if fields.is_empty() {
+        return Err("No fields given".to_string());
+    }
+    Ok(())
+}
+
+// Returns one code per field: -2 no such field, 0 condition not met,
+// 1 expiration set, 2 field deleted (expiry in the past)
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn hash_expire_fields(
+    connection_id: String,
+    key: String,
+    fields: Vec<String>,
+    ttl: i64,
+    unit: String,                       // "seconds" or "milliseconds"
+    condition: Option<String>,          // "NX", "XX", "GT" or "LT"
+    confirmation_token: Option<String>, // Approved with confirm_command
+    state: State<'_, AppState>,
+) -> Result<Vec<i64>, String> {
+    let manager = state.redis_manager.lock().unwrap();
+
+    let mut conn = manager
+        .get_writable_connection(&connection_id)?
+        .with_confirmation(confirmation_token);
+
+    validate_hash_fields(&fields)?;
+
+    let mut cmd = match unit.as_str() {
+        "seconds" => redis::cmd("HEXPIRE"),
+        "milliseconds" => redis::cmd("HPEXPIRE"),
+        _ => return Err("Invalid unit: must be 'seconds' or 'milliseconds'".to_string()),
+    };
+    cmd.arg(&key).arg(ttl);
+
+    if let Some(condition) = condition {
+        let condition = condition.to_uppercase();
+        if !matches!(condition.as_str(), "NX" | "XX" | "GT" | "LT") {
+            return Err("Invalid condition: must be 'NX', 'XX', 'GT' or 'LT'".to_string());
+        }
+        cmd.arg(condition);
+    }
+
+    cmd.arg("FIELDS").arg(fields.len()).arg(&fields);
+
+    state
+        .undo
+        .run(&state.audit, &connection_id, &mut conn, &cmd)
+}
+
+// Returns one code per field: -2 no such field, -1 no expiration, 1 persisted
+#[tauri::command]
+pub async fn hash_persist_fields(
+    connection_id: String,
+    key: String,
+    fields: Vec<String>,
+    confirmation_token: Option<String>, // Approved with confirm_command
+    state: State<'_, AppState>,
+) -> Result<Vec<i64>, String> {
+    let manager = state.redis_manager.lock().unwrap();
+
+    let mut conn = manager
+        .get_writable_connection(&connection_id)?
+        .with_confirmation(confirmation_token);
+
+    validate_hash_fields(&fields)?;
+
+    state.audit.run(
+        &connection_id,
+        &mut conn,
+        redis::cmd("HPERSIST")
+            .arg(&key)
+            .arg("FIELDS")
+            .arg(fields.len())
+            .arg(&fields),
+    )
+}
+
+#[tauri::command]
+pub async fn hash_get_fields_ex(
+    connection_id: String,
+    key: String,
+    fields: Vec<String>,
+    expiry: Option<HashFieldExpiry>,
+    confirmation_token: Option<String>, // Approved with confirm_command
+    state: State<'_, AppState>,
+) -> Result<Vec<Option<String>>, String> {
+    let manager = state.redis_manager.lock().unwrap();
+
+    // HGETEX only writes when it changes field TTLs
+    let mut conn = if expiry.is_some() {
+        manager
+            .get_writable_connection(&connection_id)?
+            .with_confirmation(confirmation_token)
+    } else {
+        manager
+            .get_connection(&connection_id)
+            .ok_or("Connection not found")?
+    };
+
+    validate_hash_fields(&fields)?;
 
-    conn.hset::<_, _, _, ()>(&key, &field, &value)
-        .map_err(|e| e.to_string())?;
+    let mut cmd = redis::cmd("HGETEX");
+    cmd.arg(&key);
 
-    Ok(())
+    if let Some(ref expiry) = expiry {
+        push_hash_field_expiry(&mut cmd, expiry, &["EX", "PX", "EXAT", "PXAT", "PERSIST"])?;
+    }
+
+    cmd.arg("FIELDS").arg(fields.len()).arg(&fields);
+
+    if expiry.is_some() {
+        state.audit.run(&connection_id, &mut conn, &cmd)
+    } else {
+        cmd.query(&mut conn).map_err(|e| e.to_string())
+    }
 }
 
+// Returns false when the FNX/FXX condition prevented the write
 #[tauri::command]
-pub async fn hash_delete_field(
+pub async fn hash_set_fields_ex(
     connection_id: String,
     key: String,
-    field: String,
+    fields: std::collections::HashMap<String, String>,
+    condition: Option<String>, // "FNX" or "FXX"
+    expiry: Option<HashFieldExpiry>,
+    confirmation_token: Option<String>, // Approved with confirm_command
     state: State<'_, AppState>,
-) -> Result<(), String> {
+) -> Result<bool, String> {
     let manager = state.redis_manager.lock().unwrap();
 
     let mut conn = manager
-        .get_connection(&connection_id)
-        .ok_or("Connection not found")?;
+        .get_writable_connection(&connection_id)?
+        .with_confirmation(confirmation_token);
 
-    conn.hdel::<_, _, ()>(&key, &field)
-        .map_err(|e| e.to_string())?;
+    if fields.is_empty() {
+        return Err("No fields given".to_string());
+    }
 
-    Ok(())
+    let mut cmd = redis::cmd("HSETEX");
+    cmd.arg(&key);
+
+    if let Some(condition) = condition {
+        let condition = condition.to_uppercase();
+        if !matches!(condition.as_str(), "FNX" | "FXX") {
+            return Err("Invalid condition: must be 'FNX' or 'FXX'".to_string());
+        }
+        cmd.arg(condition);
+    }
+
+    if let Some(ref expiry) = expiry {
+        push_hash_field_expiry(&mut cmd, expiry, &["EX", "PX", "EXAT", "PXAT", "KEEPTTL"])?;
+    }
+
+    cmd.arg("FIELDS").arg(fields.len());
+    for (field, value) in fields.iter() {
+        cmd.arg(field).arg(value);
+    }
+
+    let written: i64 = state
+        .undo
+        .run(&state.audit, &connection_id, &mut conn, &cmd)?;
+
+    Ok(written == 1)
 }
 
 // List operations
@@ -764,52 +2644,55 @@ pub async fn list_push(
     connection_id: String,
     key: String,
     value: String,
-    side: String, // "left" or "right"
+    side: String,                       // "left" or "right"
+    confirmation_token: Option<String>, // Approved with confirm_command
     state: State<'_, AppState>,
 ) -> Result<(), String> {
     let manager = state.redis_manager.lock().unwrap();
 
     let mut conn = manager
-        .get_connection(&connection_id)
-        .ok_or("Connection not found")?;
+        .get_writable_connection(&connection_id)?
+        .with_confirmation(confirmation_token);
 
-    match side.as_str() {
-        "left" => conn
-            .lpush::<_, _, ()>(&key, &value)
-            .map_err(|e| e.to_string())?,
-        "right" => conn
-            .rpush::<_, _, ()>(&key, &value)
-            .map_err(|e| e.to_string())?,
+    let command = match side.as_str() {
+        "left" => "LPUSH",
+        "right" => "RPUSH",
         _ => return Err("Invalid side: must be 'left' or 'right'".to_string()),
-    }
+    };
 
-    Ok(())
+    state.audit.run(
+        &connection_id,
+        &mut conn,
+        redis::cmd(command).arg(&key).arg(&value),
+    )
 }
 
 #[tauri::command]
 pub async fn list_pop(
     connection_id: String,
     key: String,
-    side: String, // "left" or "right"
+    side: String,                       // "left" or "right"
+    confirmation_token: Option<String>, // Approved with confirm_command
     state: State<'_, AppState>,
 ) -> Result<Option<String>, String> {
     let manager = state.redis_manager.lock().unwrap();
 
     let mut conn = manager
-        .get_connection(&connection_id)
-        .ok_or("Connection not found")?;
+        .get_writable_connection(&connection_id)?
+        .with_confirmation(confirmation_token);
 
-    let result = match side.as_str() {
-        "left" => conn
-            .lpop::<_, Option<String>>(&key, None)
-            .map_err(|e| e.to_string())?,
-        "right" => conn
-            .rpop::<_, Option<String>>(&key, None)
-            .map_err(|e| e.to_string())?,
+    let command = match side.as_str() {
+        "left" => "LPOP",
+        "right" => "RPOP",
         _ => return Err("Invalid side: must be 'left' or 'right'".to_string()),
     };
 
-    Ok(result)
+    state.undo.run(
+        &state.audit,
+        &connection_id,
+        &mut conn,
+        redis::cmd(command).arg(&key),
+    )
 }
 
 #[tauri::command]
@@ -818,18 +2701,21 @@ pub async fn list_set_index(
     key: String,
     index: i64,
     value: String,
+    confirmation_token: Option<String>, // Approved with confirm_command
     state: State<'_, AppState>,
 ) -> Result<(), String> {
     let manager = state.redis_manager.lock().unwrap();
 
     let mut conn = manager
-        .get_connection(&connection_id)
-        .ok_or("Connection not found")?;
-
-    conn.lset::<_, _, ()>(&key, index as isize, &value)
-        .map_err(|e| e.to_string())?;
-
-    Ok(())
+        .get_writable_connection(&connection_id)?
+        .with_confirmation(confirmation_token);
+
+    state.undo.run(
+        &state.audit,
+        &connection_id,
+        &mut conn,
+        redis::cmd("LSET").arg(&key).arg(index).arg(&value),
+    )
 }
 
 #[tauri::command]
@@ -838,18 +2724,21 @@ pub async fn list_remove(
     key: String,
     count: i64,
     value: String,
+    confirmation_token: Option<String>, // Approved with confirm_command
     state: State<'_, AppState>,
 ) -> Result<(), String> {
     let manager = state.redis_manager.lock().unwrap();
 
     let mut conn = manager
-        .get_connection(&connection_id)
-        .ok_or("Connection not found")?;
-
-    conn.lrem::<_, _, ()>(&key, count as isize, &value)
-        .map_err(|e| e.to_string())?;
-
-    Ok(())
+        .get_writable_connection(&connection_id)?
+        .with_confirmation(confirmation_token);
+
+    state.undo.run(
+        &state.audit,
+        &connection_id,
+        &mut conn,
+        redis::cmd("LREM").arg(&key).arg(count).arg(&value),
+    )
 }
 
 // Set operations
@@ -858,18 +2747,20 @@ pub async fn set_add_member(
     connection_id: String,
     key: String,
     member: String,
+    confirmation_token: Option<String>, // Approved with confirm_command
     state: State<'_, AppState>,
 ) -> Result<(), String> {
     let manager = state.redis_manager.lock().unwrap();
 
     let mut conn = manager
-        .get_connection(&connection_id)
-        .ok_or("Connection not found")?;
-
-    conn.sadd::<_, _, ()>(&key, &member)
-        .map_err(|e| e.to_string())?;
-
-    Ok(())
+        .get_writable_connection(&connection_id)?
+        .with_confirmation(confirmation_token);
+
+    state.audit.run(
+        &connection_id,
+        &mut conn,
+        redis::cmd("SADD").arg(&key).arg(&member),
+    )
 }
 
 #[tauri::command]
@@ -877,18 +2768,21 @@ pub async fn set_remove_member(
     connection_id: String,
     key: String,
     member: String,
+    confirmation_token: Option<String>, // Approved with confirm_command
     state: State<'_, AppState>,
 ) -> Result<(), String> {
     let manager = state.redis_manager.lock().unwrap();
 
     let mut conn = manager
-        .get_connection(&connection_id)
-        .ok_or("Connection not found")?;
-
-    conn.srem::<_, _, ()>(&key, &member)
-        .map_err(|e| e.to_string())?;
-
-    Ok(())
+        .get_writable_connection(&connection_id)?
+        .with_confirmation(confirmation_token);
+
+    state.undo.run(
+        &state.audit,
+        &connection_id,
+        &mut conn,
+        redis::cmd("SREM").arg(&key).arg(&member),
+    )
 }
 
 // ZSet operations
@@ -898,18 +2792,21 @@ pub async fn zset_add_member(
     key: String,
     member: String,
     score: f64,
+    confirmation_token: Option<String>, // Approved with confirm_command
     state: State<'_, AppState>,
 ) -> Result<(), String> {
     let manager = state.redis_manager.lock().unwrap();
 
     let mut conn = manager
-        .get_connection(&connection_id)
-        .ok_or("Connection not found")?;
-
-    conn.zadd::<_, _, _, ()>(&key, &member, score)
-        .map_err(|e| e.to_string())?;
-
-    Ok(())
+        .get_writable_connection(&connection_id)?
+        .with_confirmation(confirmation_token);
+
+    state.undo.run(
+        &state.audit,
+        &connection_id,
+        &mut conn,
+        redis::cmd("ZADD").arg(&key).arg(score).arg(&member),
+    )
 }
 
 #[tauri::command]
@@ -917,18 +2814,21 @@ pub async fn zset_remove_member(
     connection_id: String,
     key: String,
     member: String,
+    confirmation_token: Option<String>, // Approved with confirm_command
     state: State<'_, AppState>,
 ) -> Result<(), String> {
     let manager = state.redis_manager.lock().unwrap();
 
     let mut conn = manager
-        .get_connection(&connection_id)
-        .ok_or("Connection not found")?;
-
-    conn.zrem::<_, _, ()>(&key, &member)
-        .map_err(|e| e.to_string())?;
-
-    Ok(())
+        .get_writable_connection(&connection_id)?
+        .with_confirmation(confirmation_token);
+
+    state.undo.run(
+        &state.audit,
+        &connection_id,
+        &mut conn,
+        redis::cmd("ZREM").arg(&key).arg(&member),
+    )
 }
 
 #[tauri::command]
@@ -937,19 +2837,21 @@ pub async fn zset_increment_score(
     key: String,
     member: String,
     increment: f64,
+    confirmation_token: Option<String>, // Approved with confirm_command
     state: State<'_, AppState>,
 ) -> Result<f64, String> {
     let manager = state.redis_manager.lock().unwrap();
 
     let mut conn = manager
-        .get_connection(&connection_id)
-        .ok_or("Connection not found")?;
-
-    let new_score: f64 = conn
-        .zincr(&key, &member, increment)
-        .map_err(|e| e.to_string())?;
-
-    Ok(new_score)
+        .get_writable_connection(&connection_id)?
+        .with_confirmation(confirmation_token);
+
+    state.undo.run(
+        &state.audit,
+        &connection_id,
+        &mut conn,
+        redis::cmd("ZINCRBY").arg(&key).arg(increment).arg(&member),
+    )
 }
 
 // Stream operations
@@ -970,13 +2872,14 @@ pub async fn stream_add_entry(
     connection_id: String,
     key: String,
     fields: std::collections::HashMap<String, String>,
+    confirmation_token: Option<String>, // Approved with confirm_command
     state: State<'_, AppState>,
 ) -> Result<String, String> {
     let manager = state.redis_manager.lock().unwrap();
 
     let mut conn = manager
-        .get_connection(&connection_id)
-        .ok_or("Connection not found")?;
+        .get_writable_connection(&connection_id)?
+        .with_confirmation(confirmation_token);
 
     // Build XADD command with * for auto-generated ID
     let mut cmd = redis::cmd("XADD");
@@ -987,9 +2890,7 @@ pub async fn stream_add_entry(
         cmd.arg(field).arg(value);
     }
 
-    let entry_id: String = cmd.query(&mut conn).map_err(|e| e.to_string())?;
-
-    Ok(entry_id)
+    state.audit.run(&connection_id, &mut conn, &cmd)
 }
 
 #[tauri::command]
@@ -997,21 +2898,21 @@ pub async fn stream_delete_entry(
     connection_id: String,
     key: String,
     entry_id: String,
+    confirmation_token: Option<String>, // Approved with confirm_command
     state: State<'_, AppState>,
 ) -> Result<(), String> {
     let manager = state.redis_manager.lock().unwrap();
 
     let mut conn = manager
-        .get_connection(&connection_id)
-        .ok_or("Connection not found")?;
-
-    redis::cmd("XDEL")
-        .arg(&key)
-        .arg(&entry_id)
-        .query::<()>(&mut conn)
-        .map_err(|e| e.to_string())?;
-
-    Ok(())
+        .get_writable_connection(&connection_id)?
+        .with_confirmation(confirmation_token);
+
+    state.undo.run(
+        &state.audit,
+        &connection_id,
+        &mut conn,
+        redis::cmd("XDEL").arg(&key).arg(&entry_id),
+    )
 }
 
 #[tauri::command]
@@ -1053,13 +2954,14 @@ pub async fn stream_trim(
     strategy: String, // "MAXLEN" or "MINID"
     threshold: String,
     approximate: bool,
+    confirmation_token: Option<String>, // Approved with confirm_command
     state: State<'_, AppState>,
 ) -> Result<usize, String> {
     let manager = state.redis_manager.lock().unwrap();
 
     let mut conn = manager
-        .get_connection(&connection_id)
-        .ok_or("Connection not found")?;
+        .get_writable_connection(&connection_id)?
+        .with_confirmation(confirmation_token);
 
     let mut cmd = redis::cmd("XTRIM");
     cmd.arg(&key);
@@ -1078,9 +2980,9 @@ pub async fn stream_trim(
 
     cmd.arg(&threshold);
 
-    let removed: usize = cmd.query(&mut conn).map_err(|e| e.to_string())?;
-
-    Ok(removed)
+    state
+        .undo
+        .run(&state.audit, &connection_id, &mut conn, &cmd)
 }
 
 // Helper function to parse XRANGE response
@@ -1138,6 +3040,282 @@ fn parse_stream_entries(value: redis::Value) -> Result<Vec<StreamEntry>, String>
     Ok(entries)
 }
 
+// HyperLogLog and bitmap commands
+
+#[derive(Debug, Serialize)]
+pub struct HyperLogLogInfo {
+    pub cardinality: u64,
+    pub encoding: String, // "dense" or "sparse"
+    pub size_bytes: usize,
+}
+
+// HyperLogLog values start with a 16 byte header: "HYLL", one encoding byte
+// (0 = dense, 1 = sparse), 3 unused bytes and an 8 byte cached cardinality.
+fn hll_encoding(raw: &[u8]) -> Option<&'static str> {
+    if raw.len() < 16 || &raw[..4] != b"HYLL" {
+        return None;
+    }
+
+    match raw[4] {
+        0 => Some("dense"),
+        1 => Some("sparse"),
+        _ => None,
+    }
+}
+
+#[tauri::command]
+pub async fn get_hyperloglog_info(
+    connection_id: String,
+    key: String,
+    state: State<'_, AppState>,
+) -> Result<HyperLogLogInfo, String> {
+    let manager = state.redis_manager.lock().unwrap();
+
+    let mut conn = manager
+        .get_connection(&connection_id)
+        .ok_or("Connection not found")?;
+
+    let raw: Vec<u8> = conn.get(&key).map_err(|e| e.to_string())?;
+    let encoding = hll_encoding(&raw).ok_or("Key is not a HyperLogLog")?;
+
+    let cardinality: u64 = redis::cmd("PFCOUNT")
+        .arg(&key)
+        .query(&mut conn)
+        .map_err(|e| e.to_string())?;
+
+    Ok(HyperLogLogInfo {
+        cardinality,
+        encoding: encoding.to_string(),
+        size_bytes: raw.len(),
+    })
+}
+
+#[derive(Debug, Serialize)]
+pub struct BitmapInfo {
+    pub byte_length: usize,
+    pub bit_length: usize,
+    pub set_bits: u64,
+    pub first_set_bit: Option<i64>,
+    pub first_clear_bit: Option<i64>,
+}
+
+#[tauri::command]
+pub async fn get_bitmap_info(
+    connection_id: String,
+    key: String,
+    state: State<'_, AppState>,
+) -> Result<BitmapInfo, String> {
+    let manager = state.redis_manager.lock().unwrap();
+
+    let mut conn = manager
+        .get_connection(&connection_id)
+        .ok_or("Connection not found")?;
+
+    let byte_length: usize = conn.strlen(&key).map_err(|e| e.to_string())?;
+
+    let set_bits: u64 = redis::cmd("BITCOUNT")
+        .arg(&key)
+        .query(&mut conn)
+        .map_err(|e| e.to_string())?;
+
+    // BITPOS returns -1 when no matching bit exists
+    let first_set: i64 = redis::cmd("BITPOS")
+        .arg(&key)
+        .arg(1)
+        .query(&mut conn)
+        .map_err(|e| e.to_string())?;
+
+    let first_clear: i64 = redis::cmd("BITPOS")
+        .arg(&key)
+        .arg(0)
+        .query(&mut conn)
+        .map_err(|e| e.to_string())?;
+
+    Ok(BitmapInfo {
+        byte_length,
+        bit_length: byte_length * 8,
+        set_bits,
+        first_set_bit: (first_set >= 0).then_some(first_set),
+        first_clear_bit: (first_clear >= 0).then_some(first_clear),
+    })
+}
+
+#[derive(Debug, Serialize)]
+pub struct BitmapRange {
+    pub byte_offset: usize,
+    pub bytes: Vec<u8>,
+    pub bits: String,
+    pub total_bytes: usize,
+    pub has_more: bool,
+}
+
+#[tauri::command]
+pub async fn get_bitmap_range(
+    connection_id: String,
+    key: String,
+    byte_offset: usize,
+    byte_count: usize,
+    state: State<'_, AppState>,
+) -> Result<BitmapRange, String> {
+    let manager = state.redis_manager.lock().unwrap();
+
+    let mut conn = manager
+        .get_connection(&connection_id)
+        .ok_or("Connection not found")?;
+
+    if byte_count == 0 {
+        return Err("byte_count must be greater than 0".to_string());
+    }
+
+    // GETRANGE takes signed offsets; reject ranges past isize::MAX
+    let end = byte_offset
+        .checked_add(byte_count - 1)
+        .filter(|end| isize::try_from(*end).is_ok())
+        .ok_or("byte_offset + byte_count is out of range")?;
+
+    let total_bytes: usize = conn.strlen(&key).map_err(|e| e.to_string())?;
+
+    let bytes: Vec<u8> = conn
+        .getrange(&key, byte_offset as isize, end as isize)
+        .map_err(|e| e.to_string())?;
+
+    // Most significant bit first, matching SETBIT/GETBIT offsets
+    let bits = bytes.iter().map(|b| format!("{:08b}", b)).collect();
+
+    Ok(BitmapRange {
+        byte_offset,
+        bytes,
+        bits,
+        total_bytes,
+        has_more: end + 1 < total_bytes,
+    })
+}
+
+#[tauri::command]
+pub async fn bitmap_set_bit(
+    connection_id: String,
+    key: String,
+    offset: usize,
+    value: bool,
+    confirmation_token: Option<String>, // Approved with confirm_command
+    state: State<'_, AppState>,
+) -> Result<bool, String> {
+    let manager = state.redis_manager.lock().unwrap();
+
+    let mut conn = manager
+        .get_writable_connection(&connection_id)?
+        .with_confirmation(confirmation_token);
+
+    // SETBIT returns the bit's previous value
+    state.undo.run(
+        &state.audit,
+        &connection_id,
+        &mut conn,
+        redis::cmd("SETBIT").arg(&key).arg(offset).arg(value as u8),
+    )
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BitfieldOperation {
+    pub op: String, // "GET", "SET" or "INCRBY"
+    pub signed: bool,
+    pub bits: u8,
+    pub offset: String, // Bit offset, or "#N" to multiply by the type width
+    pub value: Option<i64>,
+    pub overflow: Option<String>, // "WRAP", "SAT" or "FAIL"
+}
+
+#[tauri::command]
+pub async fn bitmap_bitfield(
+    connection_id: String,
+    key: String,
+    operations: Vec<BitfieldOperation>,
+    confirmation_token: Option<String>, // Approved with confirm_command
+    state: State<'_, AppState>,
+) -> Result<Vec<Option<i64>>, String> {
+    let manager = state.redis_manager.lock().unwrap();
+
+    let read_only = operations
+        .iter()
+        .all(|operation| operation.op.eq_ignore_ascii_case("GET"));
+    let mut conn = if read_only {
+        manager
+            .get_connection(&connection_id)
+            .ok_or("Connection not found")?
+    } else {
+        manager
+            .get_writable_connection(&connection_id)?
+            .with_confirmation(confirmation_token)
+    };
+
+    if operations.is_empty() {
+        return Err("No BITFIELD operations given".to_string());
+    }
+
+    let mut cmd = redis::cmd("BITFIELD");
+    cmd.arg(&key);
+
+    for operation in &operations {
+        // Redis supports i1..i64 and u1..u63
+        let max_bits = if operation.signed { 64 } else { 63 };
+        if operation.bits == 0 || operation.bits > max_bits {
+            return Err(format!(
+                "Invalid {} width: {} (must be 1-{})",
+                if operation.signed {
+                    "signed"
+                } else {
+                    "unsigned"
+                },
+                operation.bits,
+                max_bits
+            ));
+        }
+
+        let offset = operation.offset.trim();
+        let offset_digits = offset.strip_prefix('#').unwrap_or(offset);
+        if offset_digits.is_empty() || !offset_digits.chars().all(|c| c.is_ascii_digit()) {
+            return Err(format!("Invalid bit offset: {}", operation.offset));
+        }
+
+        let encoding = format!(
+            "{}{}",
+            if operation.signed { "i" } else { "u" },
+            operation.bits
+        );
+
+        if let Some(ref overflow) = operation.overflow {
+            let overflow = overflow.to_uppercase();
+            if !matches!(overflow.as_str(), "WRAP" | "SAT" | "FAIL") {
+                return Err("Invalid overflow: must be 'WRAP', 'SAT' or 'FAIL'".to_string());
+            }
+            cmd.arg("OVERFLOW").arg(overflow);
+        }
+
+        match operation.op.to_uppercase().as_str() {
+            "GET" => {
+                cmd.arg("GET").arg(&encoding).arg(offset);
+            }
+            "SET" | "INCRBY" => {
+                let value = operation
+                    .value
+                    .ok_or_else(|| format!("{} requires a value", operation.op))?;
+                cmd.arg(operation.op.to_uppercase())
+                    .arg(&encoding)
+                    .arg(offset)
+                    .arg(value);
+            }
+            _ => return Err("Invalid op: must be 'GET', 'SET' or 'INCRBY'".to_string()),
+        }
+    }
+
+    // Each entry is None when OVERFLOW FAIL prevented the operation
+    if read_only {
+        cmd.query(&mut conn).map_err(|e| e.to_string())
+    } else {
+        state.audit.run(&connection_id, &mut conn, &cmd)
+    }
+}
+
 // Monitoring Commands
 
 #[derive(Debug, Serialize)]
@@ -1209,19 +3387,82 @@ pub async fn get_server_info(
     }
 
     // Get total keys from keyspace
-    for line in info.lines() {
-        if line.starts_with("db") {
-            if let Some(keys_part) = line.split(',').next() {
-                if let Some(keys_str) = keys_part.split('=').nth(1) {
-                    if let Ok(keys) = keys_str.parse::<u64>() {
-                        server_info.total_keys += keys;
-                    }
-                }
-            }
+    server_info.total_keys = keyspace::parse_keyspace_info(&info)
+        .iter()
+        .map(|db| db.keys)
+        .sum();
+
+    Ok(server_info)
+}
+
+#[derive(Debug, Serialize)]
+pub struct KeyspaceOverview {
+    pub current_db: Option<u32>,
+    pub database_count: Option<u32>, // CONFIG GET databases; None if CONFIG is disabled
+    pub databases: Vec<DbKeyspace>,  // Every db when the count is known, else the non-empty ones
+}
+
+#[tauri::command]
+pub async fn get_keyspace_overview(
+    connection_id: String,
+    state: State<'_, AppState>,
+) -> Result<KeyspaceOverview, String> {
+    let (mut conn, current_db) = {
+        let manager = state.redis_manager.lock().unwrap();
+        let conn = manager
+            .get_connection(&connection_id)
+            .ok_or("Connection not found")?;
+        (conn, manager.database(&connection_id))
+    };
+
+    let info: String = redis::cmd("INFO")
+        .arg("keyspace")
+        .query(&mut conn)
+        .map_err(|e| e.to_string())?;
+    let mut databases = keyspace::parse_keyspace_info(&info);
+
+    let database_count = redis::cmd("CONFIG")
+        .arg("GET")
+        .arg("databases")
+        .query::<Vec<String>>(&mut conn)
+        .ok()
+        .and_then(|reply| reply.get(1)?.parse::<u32>().ok());
+
+    let wanted = match database_count {
+        Some(count) => (0..count).collect(),
+        None => current_db.into_iter().collect::<Vec<_>>(),
+    };
+    for db in wanted {
+        if !databases.iter().any(|d| d.db == db) {
+            databases.push(DbKeyspace {
+                db,
+                ..Default::default()
+            });
         }
     }
+    databases.sort_by_key(|d| d.db);
 
-    Ok(server_info)
+    Ok(KeyspaceOverview {
+        current_db,
+        database_count,
+        databases,
+    })
+}
+
+// Switch the database an existing connection works on; later commands and
+// jobs use the new db
+#[tauri::command]
+pub async fn select_database(
+    connection_id: String,
+    db: u32,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let manager = state.redis_manager.lock().unwrap();
+    match manager.select_database(&connection_id, db) {
+        Ok(true) => Ok(()),
+        Ok(false) => Err("Connection not found".to_string()),
+        Err(e) => Err(e.to_string()),
+    }
 }
 
 #[derive(Debug, Serialize)]
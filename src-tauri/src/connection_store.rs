@@ -1,3 +1,4 @@
+use crate::access::CommandPolicy;
 use crate::redis_client::SshAuthMethod;
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
@@ -18,6 +19,12 @@ pub struct StoredConnection {
     pub database: u8,
     pub use_tls: bool,
     pub ssh_tunnel: Option<StoredSshTunnelConfig>,
+    #[serde(default)] // Absent in connections saved by older versions
+    pub read_only: bool,
+    #[serde(default)]
+    pub policy: Option<CommandPolicy>,
+    #[serde(default)]
+    pub undo_journal: Option<usize>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -0,0 +1,813 @@
+use crate::export::{self, BinaryString, FieldValue, RecordValue, ScoredMember};
+use crate::jobs::CancelToken;
+use crate::keyspace::{self, KeyBatches};
+use redis::{ConnectionLike, RedisResult, Value};
+use serde::Serialize;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+
+// DUMP payloads end with a 2-byte RDB version and an 8-byte CRC64
+const DUMP_FOOTER_LEN: usize = 10;
+
+// Types `export::read_value` understands
+const READABLE_TYPES: [&str; 6] = ["string", "list", "set", "zset", "hash", "stream"];
+
+// Entries kept per category in an element-level diff
+const MAX_DIFF_ENTRIES: usize = 1000;
+
+// Lines kept in a string diff, and the largest LCS table built for one.
+// Inputs whose changed region is bigger are shown as a full replacement.
+const MAX_DIFF_LINES: usize = 10_000;
+const MAX_LCS_CELLS: usize = 4_000_000;
+
+#[derive(Debug, Clone)]
+pub struct DiffOptions {
+    pub pattern: String,
+    pub compare_values: bool, // Only compare existence and type when false
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffKind {
+    OnlyInA,
+    OnlyInB,
+    TypeMismatch,
+    ValueDiffers,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct KeyDifference {
+    pub key: BinaryString,
+    pub kind: DiffKind,
+    pub type_a: Option<String>,
+    pub type_b: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DiffSummary {
+    pub compared_keys: u64, // Keys present on both sides
+    pub identical_keys: u64,
+    pub only_in_a: u64,
+    pub only_in_b: u64,
+    pub type_mismatches: u64,
+    pub value_differences: u64,
+    pub skipped_keys: u64, // Module types that could not be DUMPed on both sides
+    pub cancelled: bool,
+}
+
+impl DiffSummary {
+    fn record(&mut self, kind: DiffKind) {
+        match kind {
+            DiffKind::OnlyInA => self.only_in_a += 1,
+            DiffKind::OnlyInB => self.only_in_b += 1,
+            DiffKind::TypeMismatch => self.type_mismatches += 1,
+            DiffKind::ValueDiffers => self.value_differences += 1,
+        }
+    }
+}
+
+fn payload_digest(payload: &[u8]) -> u64 {
+    // The footer differs between servers even for identical values
+    let body = &payload[..payload.len().saturating_sub(DUMP_FOOTER_LEN)];
+    let mut hasher = DefaultHasher::new();
+    body.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn dump_digest(value: &Value) -> Option<u64> {
+    match value {
+        Value::BulkString(payload) => Some(payload_digest(payload)),
+        _ => None,
+    }
+}
+
+// Put unordered collections in a canonical order so values read from two
+// servers can be compared with ==
+fn normalize(value: RecordValue) -> RecordValue {
+    match value {
+        RecordValue::Set(mut members) => {
+            members.sort_by(|a, b| a.0.cmp(&b.0));
+            RecordValue::Set(members)
+        }
+        RecordValue::Hash(mut fields) => {
+            fields.sort_by(|a, b| a.field.0.cmp(&b.field.0));
+            RecordValue::Hash(fields)
+        }
+        other => other,
+    }
+}
+
+// Compare two keys of the same type by reading both values. Returns None if
+// either key vanished in the meantime.
+fn values_equal(
+    a: &mut dyn ConnectionLike,
+    b: &mut dyn ConnectionLike,
+    key: &[u8],
+    key_type: &str,
+) -> RedisResult<Option<bool>> {
+    let value_a = export::read_value(a, key, key_type)?;
+    let value_b = export::read_value(b, key, key_type)?;
+    Ok(match (value_a, value_b) {
+        (Some(value_a), Some(value_b)) => Some(normalize(value_a) == normalize(value_b)),
+        _ => None,
+    })
+}
+
+// Classify one batch of keys scanned from A. TYPE and DUMP are pipelined on
+// both sides; equal payload digests mean equal values. Different digests are
+// not conclusive (encodings and hash table order vary between servers), so
+// those keys are confirmed by reading both values.
+fn diff_batch(
+    a: &mut dyn ConnectionLike,
+    b: &mut dyn ConnectionLike,
+    batch: &[BinaryString],
+    compare_values: bool,
+    summary: &mut DiffSummary,
+) -> RedisResult<Vec<KeyDifference>> {
+    let per_key = if compare_values { 2 } else { 1 };
+    let mut pipe = redis::pipe();
+    for key in batch {
+        pipe.cmd("TYPE").arg(key);
+        if compare_values {
+            pipe.cmd("DUMP").arg(key);
+        }
+    }
+    let replies_a = keyspace::query_pipeline_lenient(a, &pipe, batch.len() * per_key)?;
+    let replies_b = keyspace::query_pipeline_lenient(b, &pipe, batch.len() * per_key)?;
+
+    let mut differences = Vec::new();
+    for ((key, ra), rb) in batch
+        .iter()
+        .zip(replies_a.chunks(per_key))
+        .zip(replies_b.chunks(per_key))
+    {
+        let type_a = keyspace::value_to_string(&ra[0]).unwrap_or_default();
+        let type_b = keyspace::value_to_string(&rb[0]).unwrap_or_default();
+        if type_a.is_empty() || type_a == "none" {
+            continue; // Deleted from A since the scan
+        }
+
+        let kind = if type_b.is_empty() || type_b == "none" {
+            Some(DiffKind::OnlyInA)
+        } else if type_a != type_b {
+            Some(DiffKind::TypeMismatch)
+        } else if !compare_values {
+            None
+        } else {
+            // No digest when DUMP is unsupported (RDB snapshots) or the key vanished
+            let digests = (dump_digest(&ra[1]), dump_digest(&rb[1]));
+            if let (Some(digest_a), Some(digest_b)) = digests {
+                if digest_a == digest_b {
+                    summary.compared_keys += 1;
+                    summary.identical_keys += 1;
+                    continue;
+                }
+            }
+            if !READABLE_TYPES.contains(&type_a.as_str()) {
+                // Module types cannot be read back, so only the payloads decide
+                if digests.0.is_none() || digests.1.is_none() {
+                    summary.skipped_keys += 1;
+                    continue;
+                }
+                Some(DiffKind::ValueDiffers)
+            } else {
+                match values_equal(a, b, &key.0, &type_a) {
+                    Ok(Some(true)) => None,
+                    Ok(Some(false)) => Some(DiffKind::ValueDiffers),
+                    Ok(None) => continue,
+                    // Changed type or vanished mid-comparison
+                    Err(e) if e.code().is_some() => continue,
+                    Err(e) => return Err(e),
+                }
+            }
+        };
+
+        if kind != Some(DiffKind::OnlyInA) {
+            summary.compared_keys += 1;
+        }
+        match kind {
+            Some(kind) => {
+                summary.record(kind);
+                differences.push(KeyDifference {
+                    key: key.clone(),
+                    kind,
+                    type_a: Some(type_a),
+                    type_b: (kind != DiffKind::OnlyInA).then_some(type_b),
+                });
+            }
+            None => summary.identical_keys += 1,
+        }
+    }
+
+    Ok(differences)
+}
+
+// Keys scanned from B that do not exist in A. Keys present on both sides
+// were already compared while scanning A.
+fn missing_from_a(
+    a: &mut dyn ConnectionLike,
+    b: &mut dyn ConnectionLike,
+    batch: &[BinaryString],
+    summary: &mut DiffSummary,
+) -> RedisResult<Vec<KeyDifference>> {
+    let mut exists = redis::pipe();
+    let mut types = redis::pipe();
+    for key in batch {
+        exists.cmd("EXISTS").arg(key);
+        types.cmd("TYPE").arg(key);
+    }
+    let in_a = keyspace::query_pipeline_lenient(a, &exists, batch.len())?;
+    let types_b = keyspace::query_pipeline_lenient(b, &types, batch.len())?;
+
+    let mut differences = Vec::new();
+    for ((key, in_a), type_b) in batch.iter().zip(&in_a).zip(&types_b) {
+        let type_b = keyspace::value_to_string(type_b).unwrap_or_default();
+        if keyspace::value_to_i64(in_a) != Some(0) || type_b.is_empty() || type_b == "none" {
+            continue;
+        }
+        summary.record(DiffKind::OnlyInB);
+        differences.push(KeyDifference {
+            key: key.clone(),
+            kind: DiffKind::OnlyInB,
+            type_a: None,
+            type_b: Some(type_b),
+        });
+    }
+
+    Ok(differences)
+}
+
+// Compare the keys matching `options.pattern` in two databases. A is
+// scanned first, then B for keys that only exist there. Differences are
+// passed to `on_differences` one batch at a time as they are found.
+pub fn diff_databases(
+    a: &mut dyn ConnectionLike,
+    b: &mut dyn ConnectionLike,
+    options: &DiffOptions,
+    cancel: &CancelToken,
+    mut on_progress: impl FnMut(u64, u64),
+    mut on_differences: impl FnMut(Vec<KeyDifference>, &DiffSummary),
+) -> Result<DiffSummary, String> {
+    let mut summary = DiffSummary::default();
+    let mut batches_a = KeyBatches::new(a, &options.pattern, None);
+    let mut batches_b = KeyBatches::new(b, &options.pattern, None);
+    let total = batches_a.total() + batches_b.total();
+
+    while let Some(batch) = batches_a.next_batch(a).map_err(|e| e.to_string())? {
+        if cancel.is_cancelled() {
+            summary.cancelled = true;
+            return Ok(summary);
+        }
+        let differences = diff_batch(a, b, &batch, options.compare_values, &mut summary)
+            .map_err(|e| e.to_string())?;
+        if !differences.is_empty() {
+            on_differences(differences, &summary);
+        }
+        on_progress(batches_a.processed(), total);
+    }
+
+    while let Some(batch) = batches_b.next_batch(b).map_err(|e| e.to_string())? {
+        if cancel.is_cancelled() {
+            summary.cancelled = true;
+            return Ok(summary);
+        }
+        let differences = missing_from_a(a, b, &batch, &mut summary).map_err(|e| e.to_string())?;
+        if !differences.is_empty() {
+            on_differences(differences, &summary);
+        }
+        on_progress(batches_a.total() + batches_b.processed(), total);
+    }
+
+    Ok(summary)
+}
+
+// Element-level diffs, computed on demand for one key or a pair of keys
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldChange {
+    pub field: BinaryString,
+    pub a: BinaryString,
+    pub b: BinaryString,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ScoreChange {
+    pub member: BinaryString,
+    pub a: f64,
+    pub b: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ListChange {
+    pub index: u64,
+    pub a: Option<BinaryString>, // None past the end of A
+    pub b: Option<BinaryString>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineOp {
+    Equal,
+    Added,
+    Removed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DiffLine {
+    pub op: LineOp,
+    pub line_a: Option<u64>, // 1-based; None for added lines
+    pub line_b: Option<u64>, // 1-based; None for removed lines
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JsonChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JsonChange {
+    pub path: String, // e.g. $.items[2].name
+    pub kind: JsonChangeKind,
+    pub a: Option<serde_json::Value>, // None when added
+    pub b: Option<serde_json::Value>, // None when removed
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "format", rename_all = "lowercase")]
+pub enum StringDiff {
+    Text {
+        lines: Vec<DiffLine>,
+    },
+    // Both values are JSON objects or arrays
+    Json {
+        changes: Vec<JsonChange>,
+    },
+    // Either value is not valid UTF-8
+    Binary {
+        len_a: u64,
+        len_b: u64,
+        first_difference: Option<u64>, // Byte offset; None when identical
+    },
+}
+
+// "added" is present in B but not A, "removed" the reverse
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum ElementDiff {
+    String(StringDiff),
+    Hash {
+        added: Vec<FieldValue>,
+        removed: Vec<FieldValue>,
+        changed: Vec<FieldChange>,
+    },
+    Set {
+        added: Vec<BinaryString>,
+        removed: Vec<BinaryString>,
+    },
+    Zset {
+        added: Vec<ScoredMember>,
+        removed: Vec<ScoredMember>,
+        changed: Vec<ScoreChange>,
+    },
+    List {
+        len_a: u64,
+        len_b: u64,
+        changed: Vec<ListChange>,
+    },
+}
+
+impl ElementDiff {
+    fn is_empty(&self) -> bool {
+        match self {
+            ElementDiff::String(StringDiff::Text { lines }) => {
+                lines.iter().all(|line| line.op == LineOp::Equal)
+            }
+            ElementDiff::String(StringDiff::Json { changes }) => changes.is_empty(),
+            ElementDiff::String(StringDiff::Binary {
+                first_difference, ..
+            }) => first_difference.is_none(),
+            ElementDiff::Hash {
+                added,
+                removed,
+                changed,
+            } => added.is_empty() && removed.is_empty() && changed.is_empty(),
+            ElementDiff::Set { added, removed } => added.is_empty() && removed.is_empty(),
+            ElementDiff::Zset {
+                added,
+                removed,
+                changed,
+            } => added.is_empty() && removed.is_empty() && changed.is_empty(),
+            ElementDiff::List { changed, .. } => changed.is_empty(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct KeyElementDiff {
+    pub key_a: BinaryString,
+    pub key_b: BinaryString,
+    pub key_type: String,
+    pub identical: bool,
+    pub diff: ElementDiff,
+    pub truncated: bool, // Some category hit MAX_DIFF_ENTRIES
+}
+
+fn push_capped<T>(items: &mut Vec<T>, item: T, truncated: &mut bool) {
+    if items.len() < MAX_DIFF_ENTRIES {
+        items.push(item);
+    } else {
+        *truncated = true;
+    }
+}
+
+// Line diff via an LCS table over the region between the common prefix
+// and suffix, which is usually small for two versions of the same value
+fn diff_lines(a: &str, b: &str, truncated: &mut bool) -> Vec<DiffLine> {
+    let lines_a: Vec<&str> = a.lines().collect();
+    let lines_b: Vec<&str> = b.lines().collect();
+    let prefix = lines_a
+        .iter()
+        .zip(&lines_b)
+        .take_while(|(x, y)| x == y)
+        .count();
+    let suffix = lines_a[prefix..]
+        .iter()
+        .rev()
+        .zip(lines_b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let mid_a = &lines_a[prefix..lines_a.len() - suffix];
+    let mid_b = &lines_b[prefix..lines_b.len() - suffix];
+
+    // (op, index in A, index in B) for the changed region
+    let mut ops: Vec<(LineOp, usize, usize)> = Vec::new();
+    let (n, m) = (mid_a.len(), mid_b.len());
+    if n.saturating_mul(m) <= MAX_LCS_CELLS {
+        // lcs[i][j] = length of the LCS of mid_a[i..] and mid_b[j..]
+        let mut lcs = vec![0u32; (n + 1) * (m + 1)];
+        for i in (0..n).rev() {
+            for j in (0..m).rev() {
+                lcs[i * (m + 1) + j] = if mid_a[i] == mid_b[j] {
+                    lcs[(i + 1) * (m + 1) + j + 1] + 1
+                } else {
+                    lcs[(i + 1) * (m + 1) + j].max(lcs[i * (m + 1) + j + 1])
+                };
+            }
+        }
+        let (mut i, mut j) = (0, 0);
+        while i < n || j < m {
+            if i < n && j < m && mid_a[i] == mid_b[j] {
+                ops.push((LineOp::Equal, i, j));
+                i += 1;
+                j += 1;
+            } else if j == m || (i < n && lcs[(i + 1) * (m + 1) + j] >= lcs[i * (m + 1) + j + 1]) {
+                ops.push((LineOp::Removed, i, j));
+                i += 1;
+            } else {
+                ops.push((LineOp::Added, i, j));
+                j += 1;
+            }
+        }
+    } else {
+        ops.extend((0..n).map(|i| (LineOp::Removed, i, 0)));
+        ops.extend((0..m).map(|j| (LineOp::Added, n, j)));
+    }
+
+    let mut lines = Vec::new();
+    let mut push = |op: LineOp, i: usize, j: usize| {
+        if lines.len() >= MAX_DIFF_LINES {
+            *truncated = true;
+            return;
+        }
+        lines.push(DiffLine {
+            op,
+            line_a: (op != LineOp::Added).then_some(i as u64 + 1),
+            line_b: (op != LineOp::Removed).then_some(j as u64 + 1),
+            text: if op == LineOp::Added {
+                lines_b[j]
+            } else {
+                lines_a[i]
+            }
+            .to_string(),
+        });
+    };
+    for i in 0..prefix {
+        push(LineOp::Equal, i, i);
+    }
+    for (op, i, j) in ops {
+        push(op, prefix + i, prefix + j);
+    }
+    for k in 0..suffix {
+        push(
+            LineOp::Equal,
+            lines_a.len() - suffix + k,
+            lines_b.len() - suffix + k,
+        );
+    }
+    lines
+}
+
+fn json_path(parent: &str, key: &str) -> String {
+    if !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        format!("{}.{}", parent, key)
+    } else {
+        format!("{}[{}]", parent, serde_json::Value::from(key))
+    }
+}
+
+fn diff_json(
+    path: &str,
+    a: &serde_json::Value,
+    b: &serde_json::Value,
+    changes: &mut Vec<JsonChange>,
+    truncated: &mut bool,
+) {
+    use serde_json::Value as Json;
+
+    match (a, b) {
+        (Json::Object(map_a), Json::Object(map_b)) => {
+            for (key, value_a) in map_a {
+                let path = json_path(path, key);
+                match map_b.get(key) {
+                    Some(value_b) => diff_json(&path, value_a, value_b, changes, truncated),
+                    None => push_capped(
+                        changes,
+                        JsonChange {
+                            path,
+                            kind: JsonChangeKind::Removed,
+                            a: Some(value_a.clone()),
+                            b: None,
+                        },
+                        truncated,
+                    ),
+                }
+            }
+            for (key, value_b) in map_b {
+                if !map_a.contains_key(key) {
+                    push_capped(
+                        changes,
+                        JsonChange {
+                            path: json_path(path, key),
+                            kind: JsonChangeKind::Added,
+                            a: None,
+                            b: Some(value_b.clone()),
+                        },
+                        truncated,
+                    );
+                }
+            }
+        }
+        (Json::Array(items_a), Json::Array(items_b)) => {
+            for index in 0..items_a.len().max(items_b.len()) {
+                let path = format!("{}[{}]", path, index);
+                match (items_a.get(index), items_b.get(index)) {
+                    (Some(item_a), Some(item_b)) => {
+                        diff_json(&path, item_a, item_b, changes, truncated)
+                    }
+                    (item_a, item_b) => push_capped(
+                        changes,
+                        JsonChange {
+                            path,
+                            kind: if item_a.is_some() {
+                                JsonChangeKind::Removed
+                            } else {
+                                JsonChangeKind::Added
+                            },
+                            a: item_a.cloned(),
+                            b: item_b.cloned(),
+                        },
+                        truncated,
+                    ),
+                }
+            }
+        }
+        _ if a != b => push_capped(
+            changes,
+            JsonChange {
+                path: path.to_string(),
+                kind: JsonChangeKind::Changed,
+                a: Some(a.clone()),
+                b: Some(b.clone()),
+            },
+            truncated,
+        ),
+        _ => {}
+    }
+}
+
+fn diff_strings(a: &[u8], b: &[u8], truncated: &mut bool) -> StringDiff {
+    let (Ok(text_a), Ok(text_b)) = (std::str::from_utf8(a), std::str::from_utf8(b)) else {
+        let common = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+        return StringDiff::Binary {
+            len_a: a.len() as u64,
+            len_b: b.len() as u64,
+            first_difference: (a != b).then_some(common as u64),
+        };
+    };
+
+    // Scalars such as "42" are valid JSON but read better as text
+    let parse = |text: &str| {
+        serde_json::from_str::<serde_json::Value>(text)
+            .ok()
+            .filter(|v| v.is_object() || v.is_array())
+    };
+    if let (Some(json_a), Some(json_b)) = (parse(text_a), parse(text_b)) {
+        let mut changes = Vec::new();
+        diff_json("$", &json_a, &json_b, &mut changes, truncated);
+        return StringDiff::Json { changes };
+    }
+
+    StringDiff::Text {
+        lines: diff_lines(text_a, text_b, truncated),
+    }
+}
+
+fn diff_values(value_a: RecordValue, value_b: RecordValue) -> Result<(ElementDiff, bool), String> {
+    let mut truncated = false;
+    let diff = match (value_a, value_b) {
+        (RecordValue::String(a), RecordValue::String(b)) => {
+            ElementDiff::String(diff_strings(&a.0, &b.0, &mut truncated))
+        }
+        (RecordValue::Hash(fields_a), RecordValue::Hash(fields_b)) => {
+            let mut b: HashMap<Vec<u8>, BinaryString> =
+                fields_b.into_iter().map(|f| (f.field.0, f.value)).collect();
+            let (mut removed, mut changed) = (Vec::new(), Vec::new());
+            for FieldValue { field, value } in fields_a {
+                match b.remove(&field.0) {
+                    None => push_capped(&mut removed, FieldValue { field, value }, &mut truncated),
+                    Some(value_b) if value_b != value => push_capped(
+                        &mut changed,
+                        FieldChange {
+                            field,
+                            a: value,
+                            b: value_b,
+                        },
+                        &mut truncated,
+                    ),
+                    Some(_) => {}
+                }
+            }
+            let mut b: Vec<_> = b.into_iter().collect();
+            b.sort_by(|x, y| x.0.cmp(&y.0));
+            let mut added = Vec::new();
+            for (field, value) in b {
+                push_capped(
+                    &mut added,
+                    FieldValue {
+                        field: BinaryString(field),
+                        value,
+                    },
+                    &mut truncated,
+                );
+            }
+            ElementDiff::Hash {
+                added,
+                removed,
+                changed,
+            }
+        }
+        (RecordValue::Set(members_a), RecordValue::Set(members_b)) => {
+            let a: HashSet<BinaryString> = members_a.into_iter().collect();
+            let b: HashSet<BinaryString> = members_b.into_iter().collect();
+            let mut sorted_difference = |x: &HashSet<BinaryString>, y: &HashSet<BinaryString>| {
+                let mut members: Vec<&BinaryString> = x.difference(y).collect();
+                members.sort_by(|m, n| m.0.cmp(&n.0));
+                let mut capped = Vec::new();
+                for member in members {
+                    push_capped(&mut capped, member.clone(), &mut truncated);
+                }
+                capped
+            };
+            let added = sorted_difference(&b, &a);
+            let removed = sorted_difference(&a, &b);
+            ElementDiff::Set { added, removed }
+        }
+        (RecordValue::Zset(members_a), RecordValue::Zset(members_b)) => {
+            let mut b: HashMap<Vec<u8>, f64> = members_b
+                .into_iter()
+                .map(|m| (m.member.0, m.score))
+                .collect();
+            let (mut removed, mut changed) = (Vec::new(), Vec::new());
+            for ScoredMember { member, score } in members_a {
+                match b.remove(&member.0) {
+                    None => {
+                        push_capped(&mut removed, ScoredMember { member, score }, &mut truncated)
+                    }
+                    Some(score_b) if score_b != score => push_capped(
+                        &mut changed,
+                        ScoreChange {
+                            member,
+                            a: score,
+                            b: score_b,
+                        },
+                        &mut truncated,
+                    ),
+                    Some(_) => {}
+                }
+            }
+            let mut b: Vec<_> = b.into_iter().collect();
+            b.sort_by(|x, y| x.0.cmp(&y.0));
+            let mut added = Vec::new();
+            for (member, score) in b {
+                push_capped(
+                    &mut added,
+                    ScoredMember {
+                        member: BinaryString(member),
+                        score,
+                    },
+                    &mut truncated,
+                );
+            }
+            ElementDiff::Zset {
+                added,
+                removed,
+                changed,
+            }
+        }
+        (RecordValue::List(items_a), RecordValue::List(items_b)) => {
+            let (len_a, len_b) = (items_a.len(), items_b.len());
+            let mut changed = Vec::new();
+            for index in 0..len_a.max(len_b) {
+                let (item_a, item_b) = (items_a.get(index), items_b.get(index));
+                if item_a != item_b {
+                    push_capped(
+                        &mut changed,
+                        ListChange {
+                            index: index as u64,
+                            a: item_a.cloned(),
+                            b: item_b.cloned(),
+                        },
+                        &mut truncated,
+                    );
+                }
+            }
+            ElementDiff::List {
+                len_a: len_a as u64,
+                len_b: len_b as u64,
+                changed,
+            }
+        }
+        (value_a, _) => {
+            return Err(format!(
+                "Element diff is not supported for {} keys",
+                value_a.type_name()
+            ))
+        }
+    };
+    Ok((diff, truncated))
+}
+
+fn key_type(conn: &mut dyn ConnectionLike, key: &BinaryString) -> Result<String, String> {
+    let key_type: String = redis::cmd("TYPE")
+        .arg(key)
+        .query(conn)
+        .map_err(|e| e.to_string())?;
+    Ok(key_type)
+}
+
+// Compare `key_a` on A with `key_b` on B. Both keys must have the same type.
+pub fn diff_keys(
+    a: &mut dyn ConnectionLike,
+    key_a: &BinaryString,
+    b: &mut dyn ConnectionLike,
+    key_b: &BinaryString,
+) -> Result<KeyElementDiff, String> {
+    let type_a = key_type(a, key_a)?;
+    let type_b = key_type(b, key_b)?;
+    if type_a == "none" {
+        return Err(format!(
+            "Key {} does not exist in A",
+            key_a.to_string_lossy()
+        ));
+    }
+    if type_b == "none" {
+        return Err(format!(
+            "Key {} does not exist in B",
+            key_b.to_string_lossy()
+        ));
+    }
+    if type_a != type_b {
+        return Err(format!("Type mismatch: {} in A, {} in B", type_a, type_b));
+    }
+
+    let value_a = export::read_value(a, &key_a.0, &type_a).map_err(|e| e.to_string())?;
+    let value_b = export::read_value(b, &key_b.0, &type_b).map_err(|e| e.to_string())?;
+    let (Some(value_a), Some(value_b)) = (value_a, value_b) else {
+        return Err(format!("Cannot read {} keys", type_a));
+    };
+
+    let (diff, truncated) = diff_values(value_a, value_b)?;
+    Ok(KeyElementDiff {
+        key_a: key_a.clone(),
+        key_b: key_b.clone(),
+        key_type: type_a,
+        identical: diff.is_empty(),
+        diff,
+        truncated,
+    })
+}
@@ -0,0 +1,255 @@
+use crate::bigkeys::TopN;
+use crate::jobs::CancelToken;
+use crate::keyspace::{self, KeyScanner};
+use redis::{ConnectionLike, RedisResult};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Upper bounds (exclusive, in seconds) of the TTL histogram buckets; keys
+// above the last bound land in a final open-ended bucket
+const BUCKET_BOUNDS: [(u64, &str); 6] = [
+    (60, "< 1 minute"),
+    (3_600, "1 minute - 1 hour"),
+    (86_400, "1 hour - 1 day"),
+    (604_800, "1 day - 7 days"),
+    (2_592_000, "7 days - 30 days"),
+    (31_536_000, "30 days - 1 year"),
+];
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExpiryOptions {
+    pub pattern: String,
+    pub expiring_within_minutes: u64,
+    pub expiring_limit: usize, // Max keys listed in `expiring_soon`
+    pub delimiter: String,
+    pub prefix_depth: usize, // Namespace segments used to group keys
+    pub max_prefixes: usize,
+    pub memory_samples: usize, // MEMORY USAGE ... SAMPLES for nested values (0 = all)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TtlBucket {
+    pub label: String,
+    pub min_seconds: u64,
+    pub max_seconds: Option<u64>, // None for the open-ended last bucket
+    pub key_count: u64,
+    pub memory_bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ExpiringKey {
+    pub key: String,
+    pub ttl_ms: u64,
+    pub memory_bytes: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrefixTtlCoverage {
+    pub prefix: String, // Empty for keys without the delimiter
+    pub key_count: u64,
+    pub keys_with_ttl: u64,
+    pub ttl_coverage: f64,
+    pub memory_bytes: u64,
+    pub memory_without_ttl: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExpiryReport {
+    pub pattern: String,
+    pub generated_at: u64, // Unix time in milliseconds
+    pub scanned_keys: u64,
+    pub total_memory: u64,
+    pub keys_without_ttl: u64,
+    pub memory_without_ttl: u64,
+    pub memory_share_without_ttl: f64, // 0-1
+    pub buckets: Vec<TtlBucket>,
+    pub expiring_within_minutes: u64,
+    pub expiring_soon_count: u64,
+    pub expiring_soon: Vec<ExpiringKey>,  // Soonest first
+    pub prefixes: Vec<PrefixTtlCoverage>, // Most memory without a TTL first
+    pub cancelled: bool,
+}
+
+#[derive(Default)]
+struct PrefixStats {
+    key_count: u64,
+    keys_with_ttl: u64,
+    memory_bytes: u64,
+    memory_without_ttl: u64,
+}
+
+fn empty_buckets() -> Vec<TtlBucket> {
+    let mut buckets = Vec::with_capacity(BUCKET_BOUNDS.len() + 1);
+    let mut min_seconds = 0;
+    for (max_seconds, label) in BUCKET_BOUNDS {
+        buckets.push(TtlBucket {
+            label: label.to_string(),
+            min_seconds,
+            max_seconds: Some(max_seconds),
+            key_count: 0,
+            memory_bytes: 0,
+        });
+        min_seconds = max_seconds;
+    }
+    buckets.push(TtlBucket {
+        label: "> 1 year".to_string(),
+        min_seconds,
+        max_seconds: None,
+        key_count: 0,
+        memory_bytes: 0,
+    });
+    buckets
+}
+
+fn bucket_index(ttl_ms: u64) -> usize {
+    let seconds = ttl_ms / 1000;
+    BUCKET_BOUNDS
+        .iter()
+        .position(|(max, _)| seconds < *max)
+        .unwrap_or(BUCKET_BOUNDS.len())
+}
+
+fn key_prefix(key: &str, delimiter: &str, depth: usize) -> String {
+    if delimiter.is_empty() || depth == 0 {
+        return String::new();
+    }
+    let segments: Vec<&str> = key.split(delimiter).collect();
+    // The last segment is the key's own name, not a namespace
+    let namespaces = segments.len().saturating_sub(1).min(depth);
+    segments[..namespaces].join(delimiter)
+}
+
+// Scan the keyspace reading PTTL and MEMORY USAGE per key, then bucket the
+// TTLs, measure keys without an expiry and group TTL coverage by prefix.
+// Each SCAN page costs one pipelined round-trip.
+pub fn analyze_expiry(
+    conn: &mut dyn ConnectionLike,
+    options: &ExpiryOptions,
+    cancel: &CancelToken,
+    mut on_progress: impl FnMut(u64, u64),
+) -> RedisResult<ExpiryReport> {
+    let total = keyspace::dbsize(conn);
+    let mut scanner = KeyScanner::new(conn, &options.pattern, keyspace::SCAN_COUNT, None);
+    let expiring_window_ms = options.expiring_within_minutes.saturating_mul(60_000);
+
+    let mut buckets = empty_buckets();
+    let mut prefixes: HashMap<String, PrefixStats> = HashMap::new();
+    // TopN keeps the largest scores, so rank by the inverted TTL to keep the
+    // keys expiring soonest
+    let mut expiring = TopN::new(options.expiring_limit);
+    let mut expiring_soon_count: u64 = 0;
+    let mut scanned_keys: u64 = 0;
+    let mut total_memory: u64 = 0;
+    let mut keys_without_ttl: u64 = 0;
+    let mut memory_without_ttl: u64 = 0;
+    let mut cancelled = false;
+
+    while let Some(batch) = scanner.next_batch(conn)? {
+        if cancel.is_cancelled() {
+            cancelled = true;
+            break;
+        }
+
+        let mut pipe = redis::pipe();
+        for key in &batch {
+            pipe.cmd("PTTL").arg(key);
+            pipe.cmd("MEMORY")
+                .arg("USAGE")
+                .arg(key)
+                .arg("SAMPLES")
+                .arg(options.memory_samples);
+        }
+        let replies = keyspace::query_pipeline_lenient(conn, &pipe, batch.len() * 2)?;
+
+        for (key, r) in batch.iter().zip(replies.chunks(2)) {
+            let key = key.to_string_lossy();
+            let pttl = keyspace::value_to_i64(&r[0]).unwrap_or(-2);
+            // Expired or deleted since SCAN returned it
+            if pttl == -2 {
+                continue;
+            }
+            let memory_bytes = keyspace::value_to_i64(&r[1]).map(|m| m as u64);
+            let memory = memory_bytes.unwrap_or(0);
+
+            scanned_keys += 1;
+            total_memory += memory;
+
+            let stats = prefixes
+                .entry(key_prefix(&key, &options.delimiter, options.prefix_depth))
+                .or_default();
+            stats.key_count += 1;
+            stats.memory_bytes += memory;
+
+            if pttl < 0 {
+                keys_without_ttl += 1;
+                memory_without_ttl += memory;
+                stats.memory_without_ttl += memory;
+                continue;
+            }
+
+            let ttl_ms = pttl as u64;
+            stats.keys_with_ttl += 1;
+
+            let bucket = &mut buckets[bucket_index(ttl_ms)];
+            bucket.key_count += 1;
+            bucket.memory_bytes += memory;
+
+            if ttl_ms <= expiring_window_ms {
+                expiring_soon_count += 1;
+                expiring.offer(
+                    u64::MAX - ttl_ms,
+                    &ExpiringKey {
+                        key,
+                        ttl_ms,
+                        memory_bytes,
+                    },
+                );
+            }
+        }
+
+        on_progress(scanner.scanned_estimate(total), total);
+    }
+
+    let mut prefixes: Vec<PrefixTtlCoverage> = prefixes
+        .into_iter()
+        .map(|(prefix, stats)| PrefixTtlCoverage {
+            prefix,
+            key_count: stats.key_count,
+            keys_with_ttl: stats.keys_with_ttl,
+            ttl_coverage: stats.keys_with_ttl as f64 / stats.key_count.max(1) as f64,
+            memory_bytes: stats.memory_bytes,
+            memory_without_ttl: stats.memory_without_ttl,
+        })
+        .collect();
+    prefixes.sort_by(|a, b| {
+        b.memory_without_ttl
+            .cmp(&a.memory_without_ttl)
+            .then(b.key_count.cmp(&a.key_count))
+            .then(a.prefix.cmp(&b.prefix))
+    });
+    prefixes.truncate(options.max_prefixes);
+
+    Ok(ExpiryReport {
+        pattern: options.pattern.clone(),
+        generated_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0),
+        scanned_keys,
+        total_memory,
+        keys_without_ttl,
+        memory_without_ttl,
+        memory_share_without_ttl: if total_memory > 0 {
+            memory_without_ttl as f64 / total_memory as f64
+        } else {
+            0.0
+        },
+        buckets,
+        expiring_within_minutes: options.expiring_within_minutes,
+        expiring_soon_count,
+        expiring_soon: expiring.into_sorted(),
+        prefixes,
+        cancelled,
+    })
+}
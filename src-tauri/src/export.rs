@@ -0,0 +1,490 @@
+use crate::jobs::CancelToken;
+use crate::keyspace::{self, KeyBatches};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use redis::{ConnectionLike, RedisResult, RedisWrite, ToRedisArgs};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::HashSet;
+use std::io::Write;
+
+// Field/value pairs of a stream entry as returned by XRANGE
+type RawPairs = Vec<(Vec<u8>, Vec<u8>)>;
+
+// Elements fetched per LRANGE/ZRANGE/SSCAN/HSCAN/XRANGE call
+const PAGE_SIZE: usize = 1000;
+
+// Binary-safe string. Serialized as plain JSON text when the bytes are valid
+// UTF-8 and as {"base64": "..."} otherwise.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct BinaryString(pub Vec<u8>);
+
+impl BinaryString {
+    // For display only; invalid UTF-8 is replaced
+    pub fn to_string_lossy(&self) -> String {
+        String::from_utf8_lossy(&self.0).into_owned()
+    }
+}
+
+impl ToRedisArgs for BinaryString {
+    fn write_redis_args<W: ?Sized + RedisWrite>(&self, out: &mut W) {
+        out.write_arg(&self.0)
+    }
+}
+
+impl Serialize for BinaryString {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match std::str::from_utf8(&self.0) {
+            Ok(text) => serializer.serialize_str(text),
+            Err(_) => {
+                #[derive(Serialize)]
+                struct Encoded {
+                    base64: String,
+                }
+                Encoded {
+                    base64: BASE64.encode(&self.0),
+                }
+                .serialize(serializer)
+            }
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for BinaryString {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Text(String),
+            Encoded { base64: String },
+        }
+
+        match Repr::deserialize(deserializer)? {
+            Repr::Text(text) => Ok(BinaryString(text.into_bytes())),
+            Repr::Encoded { base64 } => BASE64
+                .decode(base64)
+                .map(BinaryString)
+                .map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+// JSON has no infinity, so +inf/-inf scores are written as strings
+mod score_format {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(score: &f64, serializer: S) -> Result<S::Ok, S::Error> {
+        if score.is_finite() {
+            serializer.serialize_f64(*score)
+        } else if *score > 0.0 {
+            serializer.serialize_str("inf")
+        } else {
+            serializer.serialize_str("-inf")
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Number(f64),
+            Text(String),
+        }
+
+        match Repr::deserialize(deserializer)? {
+            Repr::Number(n) => Ok(n),
+            Repr::Text(text) => match text.to_lowercase().as_str() {
+                "inf" | "+inf" => Ok(f64::INFINITY),
+                "-inf" => Ok(f64::NEG_INFINITY),
+                other => other.parse().map_err(serde::de::Error::custom),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScoredMember {
+    pub member: BinaryString,
+    #[serde(with = "score_format")]
+    pub score: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldValue {
+    pub field: BinaryString,
+    pub value: BinaryString,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StreamRecordEntry {
+    pub id: String,
+    pub fields: Vec<FieldValue>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "lowercase")]
+pub enum RecordValue {
+    String(BinaryString),
+    List(Vec<BinaryString>),
+    Set(Vec<BinaryString>),
+    Zset(Vec<ScoredMember>),
+    Hash(Vec<FieldValue>),
+    Stream(Vec<StreamRecordEntry>),
+}
+
+impl RecordValue {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            RecordValue::String(_) => "string",
+            RecordValue::List(_) => "list",
+            RecordValue::Set(_) => "set",
+            RecordValue::Zset(_) => "zset",
+            RecordValue::Hash(_) => "hash",
+            RecordValue::Stream(_) => "stream",
+        }
+    }
+}
+
+// One NDJSON line: {"key": ..., "type": ..., "value": ..., "ttl_ms": ...}
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyRecord {
+    pub key: BinaryString,
+    #[serde(flatten)]
+    pub value: RecordValue,
+    #[serde(default)]
+    pub ttl_ms: Option<i64>, // Remaining TTL at export time; None if persistent
+}
+
+#[derive(Debug, Clone)]
+pub struct ExportOptions {
+    pub pattern: String,
+    pub keys: Option<Vec<BinaryString>>, // Explicit keys; the pattern is ignored when set
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportSummary {
+    pub output_path: String,
+    pub exported_keys: u64,
+    pub skipped_keys: u64, // Deleted mid-export, changed type, or a module type
+    pub bytes_written: u64,
+    pub cancelled: bool,
+}
+
+// Stream IDs are "<ms>-<seq>"; the smallest ID after `id`, used to page
+// XRANGE without the exclusive "(" syntax that needs Redis 6.2
+fn next_stream_id(id: &str) -> Option<String> {
+    let (ms, seq) = id.split_once('-')?;
+    let ms: u64 = ms.parse().ok()?;
+    let seq: u64 = seq.parse().ok()?;
+    match seq.checked_add(1) {
+        Some(seq) => Some(format!("{}-{}", ms, seq)),
+        None => ms.checked_add(1).map(|ms| format!("{}-0", ms)),
+    }
+}
+
+// Read a whole value with paged commands so large collections never need a
+// single huge reply. Returns None if the key vanished or has a type that
+// cannot be exported.
+pub fn read_value(
+    conn: &mut dyn ConnectionLike,
+    key: &[u8],
+    key_type: &str,
+) -> RedisResult<Option<RecordValue>> {
+    let value = match key_type {
+        "string" => {
+            let value: Option<Vec<u8>> = redis::cmd("GET").arg(key).query(conn)?;
+            match value {
+                Some(value) => RecordValue::String(BinaryString(value)),
+                None => return Ok(None),
+            }
+        }
+        "list" => {
+            let mut items = Vec::new();
+            loop {
+                let (page, total) =
+                    keyspace::list_page::<Vec<u8>>(conn, key, items.len() as i64, PAGE_SIZE)?;
+                let done = page.is_empty() || items.len() + page.len() >= total;
+                items.extend(page.into_iter().map(BinaryString));
+                if done {
+                    break;
+                }
+            }
+            RecordValue::List(items)
+        }
+        "set" => {
+            // SSCAN can return a member more than once
+            let mut seen = HashSet::new();
+            let mut cursor = 0;
+            loop {
+                let (next, page) =
+                    keyspace::set_scan_page::<Vec<u8>>(conn, key, cursor, PAGE_SIZE)?;
+                seen.extend(page);
+                cursor = next;
+                if cursor == 0 {
+                    break;
+                }
+            }
+            RecordValue::Set(seen.into_iter().map(BinaryString).collect())
+        }
+        "zset" => {
+            let mut members = Vec::new();
+            loop {
+                let (page, total) =
+                    keyspace::zset_page::<Vec<u8>>(conn, key, members.len() as i64, PAGE_SIZE)?;
+                let done = page.is_empty() || members.len() + page.len() >= total;
+                members.extend(page.into_iter().map(|(member, score)| ScoredMember {
+                    member: BinaryString(member),
+                    score,
+                }));
+                if done {
+                    break;
+                }
+            }
+            RecordValue::Zset(members)
+        }
+        "hash" => {
+            let mut seen = HashSet::new();
+            let mut fields = Vec::new();
+            let mut cursor = 0;
+            loop {
+                let (next, page) =
+                    keyspace::hash_scan_page::<Vec<u8>>(conn, key, cursor, PAGE_SIZE)?;
+                for (field, value) in page {
+                    if seen.insert(field.clone()) {
+                        fields.push(FieldValue {
+                            field: BinaryString(field),
+                            value: BinaryString(value),
+                        });
+                    }
+                }
+                cursor = next;
+                if cursor == 0 {
+                    break;
+                }
+            }
+            RecordValue::Hash(fields)
+        }
+        "stream" => {
+            let mut entries = Vec::new();
+            let mut start = "-".to_string();
+            loop {
+                let page: Vec<(String, RawPairs)> = redis::cmd("XRANGE")
+                    .arg(key)
+                    .arg(&start)
+                    .arg("+")
+                    .arg("COUNT")
+                    .arg(PAGE_SIZE)
+                    .query(conn)?;
+                let last_id = page.last().map(|(id, _)| id.clone());
+                let full_page = page.len() == PAGE_SIZE;
+
+                entries.extend(page.into_iter().map(|(id, fields)| {
+                    StreamRecordEntry {
+                        id,
+                        fields: fields
+                            .into_iter()
+                            .map(|(field, value)| FieldValue {
+                                field: BinaryString(field),
+                                value: BinaryString(value),
+                            })
+                            .collect(),
+                    }
+                }));
+
+                match last_id.as_deref().and_then(next_stream_id) {
+                    Some(next) if full_page => start = next,
+                    _ => break,
+                }
+            }
+            RecordValue::Stream(entries)
+        }
+        _ => return Ok(None),
+    };
+
+    Ok(Some(value))
+}
+
+// Write one NDJSON record per key to `output_path`. Keys come from SCAN
+// (or the explicit list), with TYPE and PTTL fetched per batch in a single
+// pipelined round-trip before the values are read.
+pub fn export_keys(
+    conn: &mut dyn ConnectionLike,
+    options: &ExportOptions,
+    output_path: &str,
+    cancel: &CancelToken,
+    mut on_progress: impl FnMut(u64, u64),
+) -> Result<ExportSummary, String> {
+    let file = std::fs::File::create(output_path)
+        .map_err(|e| format!("Failed to create {}: {}", output_path, e))?;
+    let mut out = std::io::BufWriter::new(file);
+
+    let mut batches = KeyBatches::new(conn, &options.pattern, options.keys.clone());
+    let total = batches.total();
+
+    let mut summary = ExportSummary {
+        output_path: output_path.to_string(),
+        exported_keys: 0,
+        skipped_keys: 0,
+        bytes_written: 0,
+        cancelled: false,
+    };
+
+    while let Some(batch) = batches.next_batch(conn).map_err(|e| e.to_string())? {
+        if cancel.is_cancelled() {
+            summary.cancelled = true;
+            break;
+        }
+
+        let mut pipe = redis::pipe();
+        for key in &batch {
+            pipe.cmd("TYPE").arg(key);
+            pipe.cmd("PTTL").arg(key);
+        }
+        let replies = keyspace::query_pipeline_lenient(conn, &pipe, batch.len() * 2)
+            .map_err(|e| e.to_string())?;
+
+        for (key, r) in batch.iter().zip(replies.chunks(2)) {
+            if cancel.is_cancelled() {
+                summary.cancelled = true;
+                break;
+            }
+
+            let key_type = keyspace::value_to_string(&r[0]).unwrap_or_default();
+            let pttl = keyspace::value_to_i64(&r[1]).unwrap_or(-2);
+
+            // Server errors (WRONGTYPE after a concurrent change) skip the
+            // key; connection errors abort the export
+            let value = match read_value(conn, &key.0, &key_type) {
+                Ok(value) => value,
+                Err(e) if e.code().is_some() => None,
+                Err(e) => return Err(e.to_string()),
+            };
+            let Some(value) = value else {
+                summary.skipped_keys += 1;
+                continue;
+            };
+
+            let record = KeyRecord {
+                key: key.clone(),
+                value,
+                ttl_ms: (pttl >= 0).then_some(pttl),
+            };
+            let mut line = serde_json::to_vec(&record).map_err(|e| e.to_string())?;
+            line.push(b'\n');
+            out.write_all(&line)
+                .map_err(|e| format!("Failed to write {}: {}", output_path, e))?;
+
+            summary.exported_keys += 1;
+            summary.bytes_written += line.len() as u64;
+        }
+
+        if summary.cancelled {
+            break;
+        }
+
+        on_progress(batches.processed(), total);
+    }
+
+    out.flush()
+        .map_err(|e| format!("Failed to write {}: {}", output_path, e))?;
+
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jobs::JobRegistry;
+    use crate::rdb;
+    use crate::snapshot::SnapshotConnection;
+    use std::sync::Arc;
+
+    // Snapshot holding two string keys, one of them not valid UTF-8
+    fn connection() -> SnapshotConnection {
+        let data = [
+            b"REDIS0011".as_slice(),
+            &[0, 3, 0xff, 0xfe, b'k', 1, b'v'],
+            &[
+                0, 5, b'p', b'l', b'a', b'i', b'n', 4, b't', b'e', b'x', b't',
+            ],
+            &[0xff],
+            &[0; 8],
+        ]
+        .concat();
+        let (snapshot, _) = rdb::parse(&data).unwrap();
+        SnapshotConnection::new(Arc::new(snapshot), 0)
+    }
+
+    fn export(options: &ExportOptions, name: &str) -> (ExportSummary, Vec<String>) {
+        let path = std::env::temp_dir().join(format!(
+            "export-test-{}-{}.ndjson",
+            std::process::id(),
+            name
+        ));
+        let (_, cancel) = JobRegistry::new().register("test", "test");
+        let summary = export_keys(
+            &mut connection(),
+            options,
+            path.to_str().unwrap(),
+            &cancel,
+            |_, _| {},
+        )
+        .unwrap();
+        let output = std::fs::read_to_string(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        (summary, output.lines().map(str::to_string).collect())
+    }
+
+    #[test]
+    fn binary_string_serializes_invalid_utf8_as_base64() {
+        let text = BinaryString(b"plain".to_vec());
+        let binary = BinaryString(vec![0xff, 0xfe, b'k']);
+        assert_eq!(serde_json::to_string(&text).unwrap(), "\"plain\"");
+        assert_eq!(
+            serde_json::to_string(&binary).unwrap(),
+            "{\"base64\":\"//5r\"}"
+        );
+        for value in [text, binary] {
+            let json = serde_json::to_string(&value).unwrap();
+            assert_eq!(serde_json::from_str::<BinaryString>(&json).unwrap(), value);
+        }
+    }
+
+    #[test]
+    fn scanned_invalid_utf8_key_is_exported() {
+        let options = ExportOptions {
+            pattern: "*".to_string(),
+            keys: None,
+        };
+        let (summary, lines) = export(&options, "scan");
+        assert_eq!(summary.exported_keys, 2);
+        assert_eq!(summary.skipped_keys, 0);
+
+        let mut records: Vec<KeyRecord> = lines
+            .iter()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        records.sort_by(|a, b| a.key.cmp(&b.key));
+        assert_eq!(records[0].key, BinaryString(b"plain".to_vec()));
+        assert_eq!(records[1].key, BinaryString(vec![0xff, 0xfe, b'k']));
+        assert!(matches!(
+            &records[1].value,
+            RecordValue::String(value) if value.0 == b"v"
+        ));
+        assert!(lines
+            .iter()
+            .any(|line| line.contains("{\"base64\":\"//5r\"}")));
+    }
+
+    #[test]
+    fn listed_invalid_utf8_key_is_exported() {
+        let options = ExportOptions {
+            pattern: String::new(),
+            keys: Some(vec![BinaryString(vec![0xff, 0xfe, b'k'])]),
+        };
+        let (summary, lines) = export(&options, "list");
+        assert_eq!(summary.exported_keys, 1);
+        let record: KeyRecord = serde_json::from_str(&lines[0]).unwrap();
+        assert_eq!(record.key, BinaryString(vec![0xff, 0xfe, b'k']));
+        assert_eq!(record.ttl_ms, None);
+    }
+}
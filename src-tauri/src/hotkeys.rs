@@ -0,0 +1,153 @@
+use crate::bigkeys::TopN;
+use crate::export::BinaryString;
+use crate::jobs::CancelToken;
+use crate::keyspace::{self, KeyScanner};
+use redis::{ConnectionLike, RedisResult};
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HotKeyMode {
+    Frequency, // OBJECT FREQ, requires an LFU maxmemory-policy
+    Idle,      // OBJECT IDLETIME, requires a non-LFU policy
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HotKeyEntry {
+    pub key: String,
+    pub key_type: String,
+    pub frequency: Option<u64>,    // Logarithmic LFU counter (0-255)
+    pub idle_seconds: Option<u64>, // Seconds since last access
+    pub memory_bytes: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HotKeysReport {
+    pub mode: HotKeyMode,
+    pub maxmemory_policy: String,
+    pub scanned_keys: u64,
+    pub keys: Vec<HotKeyEntry>,
+    pub cancelled: bool,
+}
+
+pub fn maxmemory_policy(conn: &mut dyn ConnectionLike) -> Result<String, String> {
+    let config: Vec<String> = redis::cmd("CONFIG")
+        .arg("GET")
+        .arg("maxmemory-policy")
+        .query(conn)
+        .map_err(|e| {
+            format!(
+                "Cannot read maxmemory-policy (CONFIG GET may be disabled on this server): {}",
+                e
+            )
+        })?;
+
+    config
+        .get(1)
+        .cloned()
+        .ok_or_else(|| "Server did not return maxmemory-policy".to_string())
+}
+
+// Pick the analysis the server's eviction policy allows: LFU policies track
+// access frequency but not idle time, every other policy the reverse
+pub fn resolve_mode(policy: &str, requested: Option<&str>) -> Result<HotKeyMode, String> {
+    let lfu = policy.ends_with("-lfu");
+
+    match requested.unwrap_or("auto") {
+        "auto" => Ok(if lfu {
+            HotKeyMode::Frequency
+        } else {
+            HotKeyMode::Idle
+        }),
+        "frequency" if lfu => Ok(HotKeyMode::Frequency),
+        "frequency" => Err(format!(
+            "Hot key detection needs an LFU eviction policy (allkeys-lfu or volatile-lfu), \
+             but this server uses '{}'. Use idle mode to find the coldest keys instead.",
+            policy
+        )),
+        "idle" if !lfu => Ok(HotKeyMode::Idle),
+        "idle" => Err(format!(
+            "Idle time is not tracked under the '{}' eviction policy. Use frequency mode instead.",
+            policy
+        )),
+        other => Err(format!(
+            "Invalid mode: {} (must be 'auto', 'frequency' or 'idle')",
+            other
+        )),
+    }
+}
+
+// Scan the keyspace reading OBJECT FREQ (or OBJECT IDLETIME) per key, keep
+// the top N, then fetch type and memory usage for the ranked keys only
+pub fn analyze_hot_keys(
+    conn: &mut dyn ConnectionLike,
+    pattern: &str,
+    mode: HotKeyMode,
+    policy: String,
+    top_n: usize,
+    cancel: &CancelToken,
+    mut on_progress: impl FnMut(u64, u64),
+) -> RedisResult<HotKeysReport> {
+    let total = keyspace::dbsize(conn);
+    let mut scanner = KeyScanner::new(conn, pattern, keyspace::SCAN_COUNT, None);
+    let mut top: TopN<BinaryString> = TopN::new(top_n);
+    let mut scanned_keys: u64 = 0;
+    let mut cancelled = false;
+
+    let subcommand = match mode {
+        HotKeyMode::Frequency => "FREQ",
+        HotKeyMode::Idle => "IDLETIME",
+    };
+
+    while let Some(batch) = scanner.next_batch(conn)? {
+        if cancel.is_cancelled() {
+            cancelled = true;
+            break;
+        }
+
+        let mut pipe = redis::pipe();
+        for key in &batch {
+            pipe.cmd("OBJECT").arg(subcommand).arg(key);
+        }
+        // Keys that expire mid-scan reply with an error; skip them
+        let replies = keyspace::query_pipeline_lenient(conn, &pipe, batch.len())?;
+
+        for (key, reply) in batch.iter().zip(&replies) {
+            if let Some(score) = keyspace::value_to_i64(reply) {
+                scanned_keys += 1;
+                top.offer(score.max(0) as u64, key);
+            }
+        }
+
+        on_progress(scanner.scanned_estimate(total), total);
+    }
+
+    let ranked = top.into_sorted_with_scores();
+
+    let mut pipe = redis::pipe();
+    for (_, key) in &ranked {
+        pipe.cmd("TYPE").arg(key);
+        pipe.cmd("MEMORY").arg("USAGE").arg(key);
+    }
+    let replies = keyspace::query_pipeline_lenient(conn, &pipe, ranked.len() * 2)?;
+
+    let keys = ranked
+        .into_iter()
+        .zip(replies.chunks(2))
+        .map(|((score, key), r)| HotKeyEntry {
+            key: key.to_string_lossy(),
+            key_type: keyspace::value_to_string(&r[0]).unwrap_or_else(|| "none".to_string()),
+            frequency: (mode == HotKeyMode::Frequency).then_some(score),
+            idle_seconds: (mode == HotKeyMode::Idle).then_some(score),
+            memory_bytes: keyspace::value_to_i64(&r[1]).map(|m| m as u64),
+        })
+        .collect();
+
+    Ok(HotKeysReport {
+        mode,
+        maxmemory_policy: policy,
+        scanned_keys,
+        keys,
+        cancelled,
+    })
+}
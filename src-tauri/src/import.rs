@@ -0,0 +1,766 @@
+use crate::export::{BinaryString, FieldValue, KeyRecord, RecordValue};
+use crate::jobs::CancelToken;
+use crate::keyspace;
+use redis::{ConnectionLike, Value};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::io::{BufRead, BufReader, Read};
+
+// A batch is written once it holds this many records or elements
+const BATCH_RECORDS: usize = 500;
+const BATCH_ELEMENTS: usize = 10_000;
+// Elements per RPUSH/SADD/ZADD/HSET command
+const WRITE_CHUNK: usize = 1000;
+const MAX_CHANGES: usize = 1000;
+const MAX_ERRORS: usize = 100;
+// Attempts at finding a free name under the rename policy
+const MAX_RENAME_ATTEMPTS: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportFormat {
+    Ndjson, // Export schema, one record per line; a JSON array is also accepted
+    Csv,    // key,value[,ttl_ms] for strings or key,field,value[,ttl_ms] for hashes
+}
+
+impl ImportFormat {
+    pub fn from_path(path: &str) -> Self {
+        if path.to_lowercase().ends_with(".csv") {
+            ImportFormat::Csv
+        } else {
+            ImportFormat::Ndjson
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictPolicy {
+    Skip,
+    Overwrite,
+    Rename, // Write to `<key><suffix>`, then `<key><suffix>:2`, ...
+    Fail,   // Stop at the first existing key; imports check every key up front
+}
+
+#[derive(Debug, Clone)]
+pub struct ImportOptions {
+    pub format: ImportFormat,
+    pub conflict: ConflictPolicy,
+    pub rename_suffix: String,
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportAction {
+    Create,
+    Overwrite,
+    Rename,
+    Skip,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportChange {
+    pub key: String,
+    pub key_type: String,
+    pub action: ImportAction,
+    pub target_key: String, // Differs from `key` only when renamed
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportError {
+    pub line: u64,
+    pub key: Option<String>,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportReport {
+    pub input_path: String,
+    pub dry_run: bool,
+    pub records: u64,
+    pub created: u64,
+    pub overwritten: u64,
+    pub renamed: u64,
+    pub skipped: u64,   // Existing keys under the skip policy, or already expired
+    pub failed: u64,    // Unparseable records and rejected writes
+    pub conflicts: u64, // Records whose key already existed
+    pub changes: Vec<ImportChange>, // First MAX_CHANGES decisions
+    pub errors: Vec<ImportError>, // First MAX_ERRORS errors
+    pub cancelled: bool,
+}
+
+struct SourceRecord {
+    line: u64,
+    record: KeyRecord,
+}
+
+// A parsed record (or parse error) and how far into the file it ends, for
+// progress reporting
+struct SourceItem {
+    position: u64,
+    record: Result<SourceRecord, ImportError>,
+}
+
+type Source = Box<dyn Iterator<Item = SourceItem>>;
+
+fn open_source(path: &str, format: ImportFormat) -> Result<(Source, u64), String> {
+    let file = std::fs::File::open(path).map_err(|e| format!("Failed to open {}: {}", path, e))?;
+    let size = file.metadata().map(|m| m.len()).unwrap_or(0);
+    let mut reader = BufReader::new(file);
+
+    // A JSON array or a CSV file has to be read whole (CSV rows of one hash
+    // may be spread over the file); NDJSON is streamed line by line
+    let first = reader
+        .fill_buf()
+        .map_err(|e| format!("Failed to read {}: {}", path, e))?
+        .iter()
+        .find(|b| !b.is_ascii_whitespace())
+        .copied();
+
+    if format == ImportFormat::Csv || first == Some(b'[') {
+        let mut text = String::new();
+        reader
+            .read_to_string(&mut text)
+            .map_err(|e| format!("Failed to read {}: {}", path, e))?;
+        let records = match format {
+            ImportFormat::Csv => csv_records(&text)?,
+            ImportFormat::Ndjson => serde_json::from_str::<Vec<KeyRecord>>(&text)
+                .map_err(|e| format!("Invalid JSON: {}", e))?
+                .into_iter()
+                .enumerate()
+                .map(|(i, record)| SourceRecord {
+                    line: i as u64 + 1,
+                    record,
+                })
+                .collect(),
+        };
+
+        let count = records.len().max(1) as u64;
+        let source = records
+            .into_iter()
+            .enumerate()
+            .map(move |(i, record)| SourceItem {
+                position: size * (i as u64 + 1) / count,
+                record: Ok(record),
+            });
+        return Ok((Box::new(source), size));
+    }
+
+    let mut line_number: u64 = 0;
+    let mut position: u64 = 0;
+    let mut failed = false;
+    let source = std::iter::from_fn(move || loop {
+        if failed {
+            return None;
+        }
+        let mut line = Vec::new();
+        let read = match reader.read_until(b'\n', &mut line) {
+            Ok(0) => return None,
+            Ok(read) => read,
+            Err(e) => {
+                // Surface the read error once, then stop
+                failed = true;
+                return Some(SourceItem {
+                    position,
+                    record: Err(ImportError {
+                        line: line_number + 1,
+                        key: None,
+                        message: e.to_string(),
+                    }),
+                });
+            }
+        };
+        line_number += 1;
+        position += read as u64;
+
+        if line.iter().all(|b| b.is_ascii_whitespace()) {
+            continue;
+        }
+        return Some(SourceItem {
+            position,
+            record: serde_json::from_slice::<KeyRecord>(&line)
+                .map(|record| SourceRecord {
+                    line: line_number,
+                    record,
+                })
+                .map_err(|e| ImportError {
+                    line: line_number,
+                    key: None,
+                    message: format!("Invalid record: {}", e),
+                }),
+        });
+    });
+
+    Ok((Box::new(source), size))
+}
+
+// Minimal RFC 4180 reader: comma separated, double-quoted fields may hold
+// commas, newlines and "" escapes. Returns each row with its line number.
+fn parse_csv(text: &str) -> Result<Vec<(u64, Vec<String>)>, String> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut line: u64 = 1;
+    let mut row_line: u64 = 1;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match (c, in_quotes) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            ('"', true) => in_quotes = false,
+            ('"', false) if field.is_empty() => in_quotes = true,
+            (',', false) => row.push(std::mem::take(&mut field)),
+            ('\r', false) if chars.peek() == Some(&'\n') => {}
+            ('\n', false) => {
+                row.push(std::mem::take(&mut field));
+                if !(row.len() == 1 && row[0].is_empty()) {
+                    rows.push((row_line, std::mem::take(&mut row)));
+                }
+                row.clear();
+                line += 1;
+                row_line = line;
+            }
+            (c, _) => {
+                if c == '\n' {
+                    line += 1;
+                }
+                field.push(c);
+            }
+        }
+    }
+
+    if in_quotes {
+        return Err(format!(
+            "Unterminated quoted field starting on line {}",
+            row_line
+        ));
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push((row_line, row));
+    }
+    Ok(rows)
+}
+
+// Rows become string records, or hash records when there is a `field`
+// column (rows sharing a key are merged). TTLs come from `ttl_ms` or `ttl`
+// (seconds); for hashes the first row that sets one wins.
+fn csv_records(text: &str) -> Result<Vec<SourceRecord>, String> {
+    let mut rows = parse_csv(text)?.into_iter();
+    let (_, header) = rows.next().ok_or("CSV file is empty")?;
+    let header: Vec<String> = header.iter().map(|h| h.trim().to_lowercase()).collect();
+    let column = |name: &str| header.iter().position(|h| h == name);
+
+    let key_col = column("key").ok_or("CSV header must have a 'key' column")?;
+    let value_col = column("value").ok_or("CSV header must have a 'value' column")?;
+    let field_col = column("field");
+    let ttl_ms_col = column("ttl_ms");
+    let ttl_col = column("ttl");
+
+    let mut records: Vec<SourceRecord> = Vec::new();
+    let mut hashes: HashMap<String, usize> = HashMap::new();
+
+    for (line, row) in rows {
+        let get = |col: Option<usize>| col.and_then(|c| row.get(c)).map(|v| v.as_str());
+        let key = get(Some(key_col)).unwrap_or_default().to_string();
+        let value = get(Some(value_col)).unwrap_or_default();
+        if key.is_empty() {
+            return Err(format!("Missing key on line {}", line));
+        }
+
+        let ttl_ms = match (get(ttl_ms_col), get(ttl_col)) {
+            (Some(ms), _) if !ms.trim().is_empty() => Some(ms.trim().parse::<i64>()),
+            (_, Some(s)) if !s.trim().is_empty() => Some(s.trim().parse::<i64>().map(|s| s * 1000)),
+            _ => None,
+        }
+        .transpose()
+        .map_err(|_| format!("Invalid TTL on line {}", line))?;
+
+        let Some(field_col) = field_col else {
+            records.push(SourceRecord {
+                line,
+                record: KeyRecord {
+                    key: BinaryString(key.into_bytes()),
+                    value: RecordValue::String(BinaryString(value.as_bytes().to_vec())),
+                    ttl_ms,
+                },
+            });
+            continue;
+        };
+
+        let field = FieldValue {
+            field: BinaryString(get(Some(field_col)).unwrap_or_default().as_bytes().to_vec()),
+            value: BinaryString(value.as_bytes().to_vec()),
+        };
+        match hashes.get(&key) {
+            Some(&index) => {
+                let record = &mut records[index].record;
+                record.ttl_ms = record.ttl_ms.or(ttl_ms);
+                if let RecordValue::Hash(fields) = &mut record.value {
+                    fields.push(field);
+                }
+            }
+            None => {
+                hashes.insert(key.clone(), records.len());
+                records.push(SourceRecord {
+                    line,
+                    record: KeyRecord {
+                        key: BinaryString(key.into_bytes()),
+                        value: RecordValue::Hash(vec![field]),
+                        ttl_ms,
+                    },
+                });
+            }
+        }
+    }
+
+    Ok(records)
+}
+
+fn element_count(value: &RecordValue) -> usize {
+    match value {
+        RecordValue::String(_) => 1,
+        RecordValue::List(items) | RecordValue::Set(items) => items.len(),
+        RecordValue::Zset(members) => members.len(),
+        RecordValue::Hash(fields) => fields.len(),
+        RecordValue::Stream(entries) => entries.len(),
+    }
+}
+
+fn format_score(score: f64) -> String {
+    if score == f64::INFINITY {
+        "+inf".to_string()
+    } else if score == f64::NEG_INFINITY {
+        "-inf".to_string()
+    } else {
+        score.to_string()
+    }
+}
+
+// Queue the commands that recreate `value` under `key`; returns how many
+fn push_writes(pipe: &mut redis::Pipeline, key: &[u8], value: &RecordValue) -> usize {
+    let mut commands = 0;
+    match value {
+        RecordValue::String(value) => {
+            pipe.cmd("SET").arg(key).arg(&value.0);
+            commands += 1;
+        }
+        RecordValue::List(items) | RecordValue::Set(items) => {
+            let name = if matches!(value, RecordValue::List(_)) {
+                "RPUSH"
+            } else {
+                "SADD"
+            };
+            for chunk in items.chunks(WRITE_CHUNK) {
+                let cmd = pipe.cmd(name).arg(key);
+                for item in chunk {
+                    cmd.arg(&item.0);
+                }
+                commands += 1;
+            }
+        }
+        RecordValue::Zset(members) => {
+            for chunk in members.chunks(WRITE_CHUNK) {
+                let cmd = pipe.cmd("ZADD").arg(key);
+                for member in chunk {
+                    cmd.arg(format_score(member.score)).arg(&member.member.0);
+                }
+                commands += 1;
+            }
+        }
+        RecordValue::Hash(fields) => {
+            for chunk in fields.chunks(WRITE_CHUNK) {
+                let cmd = pipe.cmd("HSET").arg(key);
+                for field in chunk {
+                    cmd.arg(&field.field.0).arg(&field.value.0);
+                }
+                commands += 1;
+            }
+        }
+        RecordValue::Stream(entries) => {
+            for entry in entries {
+                let cmd = pipe.cmd("XADD").arg(key).arg(&entry.id);
+                for field in &entry.fields {
+                    cmd.arg(&field.field.0).arg(&field.value.0);
+                }
+                commands += 1;
+            }
+        }
+    }
+    commands
+}
+
+fn first_error(replies: &[Value]) -> Option<String> {
+    replies
+        .iter()
+        .find_map(|reply| reply.clone().extract_error().err())
+        .map(|e| e.to_string())
+}
+
+fn display_key(key: &[u8]) -> String {
+    String::from_utf8_lossy(key).to_string()
+}
+
+// EXISTS for each key in one round-trip; keys in `claimed` count as taken
+pub fn existing_keys(
+    conn: &mut dyn ConnectionLike,
+    keys: &[&[u8]],
+    claimed: &HashSet<Vec<u8>>,
+) -> Result<Vec<bool>, String> {
+    let mut pipe = redis::pipe();
+    for key in keys {
+        pipe.cmd("EXISTS").arg(*key);
+    }
+    let replies =
+        keyspace::query_pipeline_lenient(conn, &pipe, keys.len()).map_err(|e| e.to_string())?;
+    Ok(keys
+        .iter()
+        .zip(replies)
+        .map(|(key, r)| claimed.contains(*key) || keyspace::value_to_i64(&r) == Some(1))
+        .collect())
+}
+
+// First free name among `<key><suffix>`, `<key><suffix>:2`, ... for the
+// rename conflict policy
+pub fn rename_target(
+    conn: &mut dyn ConnectionLike,
+    key: &[u8],
+    suffix: &str,
+    claimed: &HashSet<Vec<u8>>,
+) -> Result<Option<Vec<u8>>, String> {
+    let candidates: Vec<Vec<u8>> = (1..=MAX_RENAME_ATTEMPTS)
+        .map(|n| {
+            let mut candidate = key.to_vec();
+            candidate.extend_from_slice(suffix.as_bytes());
+            if n > 1 {
+                candidate.extend_from_slice(format!(":{}", n).as_bytes());
+            }
+            candidate
+        })
+        .collect();
+    let refs: Vec<&[u8]> = candidates.iter().map(|c| c.as_slice()).collect();
+    let taken = existing_keys(conn, &refs, claimed)?;
+    Ok(candidates
+        .into_iter()
+        .zip(taken)
+        .find(|(_, taken)| !taken)
+        .map(|(candidate, _)| candidate))
+}
+
+struct Importer<'a> {
+    conn: &'a mut dyn ConnectionLike,
+    options: &'a ImportOptions,
+    report: ImportReport,
+    // Keys written (or planned, in a dry run) by this import, so duplicate
+    // records and rename targets see them as taken
+    claimed: HashSet<Vec<u8>>,
+}
+
+impl Importer<'_> {
+    fn push_error(&mut self, error: ImportError) {
+        self.report.failed += 1;
+        if self.report.errors.len() < MAX_ERRORS {
+            self.report.errors.push(error);
+        }
+    }
+
+    fn exists(&mut self, keys: &[&[u8]]) -> Result<Vec<bool>, String> {
+        existing_keys(self.conn, keys, &self.claimed)
+    }
+
+    fn rename_target(&mut self, key: &[u8]) -> Result<Option<Vec<u8>>, String> {
+        rename_target(self.conn, key, &self.options.rename_suffix, &self.claimed)
+    }
+
+    // Resolve the conflict policy for a batch, then write it with one
+    // MULTI/EXEC block per record in a single pipelined round-trip. Returns
+    // false if the import has to stop.
+    fn flush(&mut self, batch: Vec<SourceRecord>) -> Result<bool, String> {
+        let keys: Vec<&[u8]> = batch.iter().map(|r| r.record.key.0.as_slice()).collect();
+        let existing = self.exists(&keys)?;
+
+        let mut planned = Vec::with_capacity(batch.len());
+        for (source, exists) in batch.into_iter().zip(existing) {
+            let key = source.record.key.0.clone();
+            let action = if !exists {
+                ImportAction::Create
+            } else {
+                self.report.conflicts += 1;
+                match self.options.conflict {
+                    ConflictPolicy::Skip => ImportAction::Skip,
+                    ConflictPolicy::Overwrite => ImportAction::Overwrite,
+                    ConflictPolicy::Rename => ImportAction::Rename,
+                    ConflictPolicy::Fail => {
+                        self.push_error(ImportError {
+                            line: source.line,
+                            key: Some(display_key(&key)),
+                            message: "Key already exists".to_string(),
+                        });
+                        if self.options.dry_run {
+                            continue;
+                        }
+                        return Ok(false);
+                    }
+                }
+            };
+
+            let target = match action {
+                ImportAction::Rename => match self.rename_target(&key)? {
+                    Some(target) => target,
+                    None => {
+                        self.push_error(ImportError {
+                            line: source.line,
+                            key: Some(display_key(&key)),
+                            message: "No free name found to rename to".to_string(),
+                        });
+                        continue;
+                    }
+                },
+                _ => key.clone(),
+            };
+
+            if self.report.changes.len() < MAX_CHANGES {
+                self.report.changes.push(ImportChange {
+                    key: display_key(&key),
+                    key_type: source.record.value.type_name().to_string(),
+                    action,
+                    target_key: display_key(&target),
+                });
+            }
+            if action == ImportAction::Skip {
+                self.report.skipped += 1;
+                continue;
+            }
+            self.claimed.insert(target.clone());
+            planned.push((source, action, target));
+        }
+
+        if self.options.dry_run {
+            for (_, action, _) in &planned {
+                self.count(*action);
+            }
+            return Ok(true);
+        }
+
+        let mut pipe = redis::pipe();
+        let mut spans = Vec::with_capacity(planned.len());
+        let mut total_commands = 0;
+        for (source, action, target) in &planned {
+            pipe.cmd("MULTI");
+            let mut commands = 2;
+            if *action == ImportAction::Overwrite {
+                pipe.cmd("DEL").arg(target);
+                commands += 1;
+            }
+            commands += push_writes(&mut pipe, target, &source.record.value);
+            if let Some(ttl_ms) = source.record.ttl_ms {
+                pipe.cmd("PEXPIRE").arg(target).arg(ttl_ms);
+                commands += 1;
+            }
+            pipe.cmd("EXEC");
+            spans.push(commands);
+            total_commands += commands;
+        }
+
+        let replies = keyspace::query_pipeline_lenient(self.conn, &pipe, total_commands)
+            .map_err(|e| e.to_string())?;
+
+        let mut offset = 0;
+        for ((source, action, target), commands) in planned.into_iter().zip(spans) {
+            match first_error(&replies[offset..offset + commands]) {
+                Some(message) => {
+                    self.claimed.remove(&target);
+                    self.push_error(ImportError {
+                        line: source.line,
+                        key: Some(display_key(&source.record.key.0)),
+                        message,
+                    });
+                }
+                None => self.count(action),
+            }
+            offset += commands;
+        }
+
+        Ok(true)
+    }
+
+    fn count(&mut self, action: ImportAction) {
+        match action {
+            ImportAction::Create => self.report.created += 1,
+            ImportAction::Overwrite => self.report.overwritten += 1,
+            ImportAction::Rename => self.report.renamed += 1,
+            ImportAction::Skip => self.report.skipped += 1,
+        }
+    }
+}
+
+// Reject records that cannot be written as-is. Redis has no empty
+// collections, and a non-positive TTL means the key has already expired.
+fn validate(record: &SourceRecord) -> Result<bool, String> {
+    if record.record.ttl_ms.is_some_and(|ttl| ttl <= 0) {
+        return Ok(false);
+    }
+    if element_count(&record.record.value) == 0 {
+        return Err(format!(
+            "Empty {} cannot be stored",
+            record.record.value.type_name()
+        ));
+    }
+    Ok(true)
+}
+
+// Under the fail policy, check every key before anything is written so a
+// conflict never leaves a partial import behind
+fn precheck_conflicts(
+    conn: &mut dyn ConnectionLike,
+    path: &str,
+    format: ImportFormat,
+    cancel: &CancelToken,
+) -> Result<(), String> {
+    let (source, _) = open_source(path, format)?;
+    let mut seen: HashSet<Vec<u8>> = HashSet::new();
+    let mut conflicts: Vec<String> = Vec::new();
+    let mut conflict_count: u64 = 0;
+
+    let mut keys: Vec<Vec<u8>> = Vec::new();
+    let mut check = |keys: &mut Vec<Vec<u8>>| -> Result<(), String> {
+        let mut pipe = redis::pipe();
+        for key in keys.iter() {
+            pipe.cmd("EXISTS").arg(key);
+        }
+        let replies =
+            keyspace::query_pipeline_lenient(conn, &pipe, keys.len()).map_err(|e| e.to_string())?;
+        for (key, r) in keys.drain(..).zip(replies) {
+            if keyspace::value_to_i64(&r) == Some(1) {
+                conflict_count += 1;
+                if conflicts.len() < 5 {
+                    conflicts.push(display_key(&key));
+                }
+            }
+        }
+        Ok(())
+    };
+
+    for item in source {
+        if cancel.is_cancelled() {
+            return Ok(());
+        }
+        let Ok(source) = item.record else {
+            continue;
+        };
+        let key = source.record.key.0;
+        // A key repeated in the file conflicts with itself
+        if !seen.insert(key.clone()) {
+            return Err(format!(
+                "Key '{}' appears more than once (line {})",
+                display_key(&key),
+                source.line
+            ));
+        }
+        keys.push(key);
+        if keys.len() >= BATCH_RECORDS {
+            check(&mut keys)?;
+        }
+    }
+    check(&mut keys)?;
+
+    if conflict_count > 0 {
+        return Err(format!(
+            "{} key(s) already exist, e.g. {}. Nothing was imported.",
+            conflict_count,
+            conflicts.join(", ")
+        ));
+    }
+    Ok(())
+}
+
+pub fn import_keys(
+    conn: &mut dyn ConnectionLike,
+    input_path: &str,
+    options: &ImportOptions,
+    cancel: &CancelToken,
+    mut on_progress: impl FnMut(u64, u64),
+) -> Result<ImportReport, String> {
+    if options.conflict == ConflictPolicy::Fail && !options.dry_run {
+        precheck_conflicts(conn, input_path, options.format, cancel)?;
+    }
+
+    let (source, total) = open_source(input_path, options.format)?;
+    let mut importer = Importer {
+        conn,
+        options,
+        report: ImportReport {
+            input_path: input_path.to_string(),
+            dry_run: options.dry_run,
+            records: 0,
+            created: 0,
+            overwritten: 0,
+            renamed: 0,
+            skipped: 0,
+            failed: 0,
+            conflicts: 0,
+            changes: Vec::new(),
+            errors: Vec::new(),
+            cancelled: false,
+        },
+        claimed: HashSet::new(),
+    };
+
+    let mut batch = Vec::new();
+    let mut batch_elements = 0;
+    let mut position = 0;
+
+    for item in source {
+        if cancel.is_cancelled() {
+            importer.report.cancelled = true;
+            break;
+        }
+        position = item.position.min(total);
+        importer.report.records += 1;
+
+        let record = match item.record {
+            Ok(record) => record,
+            Err(error) => {
+                importer.push_error(error);
+                continue;
+            }
+        };
+        match validate(&record) {
+            Ok(true) => {}
+            Ok(false) => {
+                importer.report.skipped += 1;
+                continue;
+            }
+            Err(message) => {
+                importer.push_error(ImportError {
+                    line: record.line,
+                    key: Some(display_key(&record.record.key.0)),
+                    message,
+                });
+                continue;
+            }
+        }
+
+        batch_elements += element_count(&record.record.value);
+        batch.push(record);
+        if batch.len() >= BATCH_RECORDS || batch_elements >= BATCH_ELEMENTS {
+            batch_elements = 0;
+            if !importer.flush(std::mem::take(&mut batch))? {
+                return Ok(importer.report);
+            }
+            on_progress(position, total);
+        }
+    }
+
+    if !importer.report.cancelled && !batch.is_empty() {
+        importer.flush(batch)?;
+    }
+    on_progress(position, total);
+
+    Ok(importer.report)
+}
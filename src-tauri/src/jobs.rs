@@ -0,0 +1,287 @@
+use serde::Serialize;
+use std::any::Any;
+use std::collections::HashMap;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::ipc::Channel;
+
+// Minimum delay between progress events sent over a job's channel
+pub const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JobInfo {
+    pub id: String,
+    pub kind: String,
+    pub connection_id: String,
+    pub started_at: u64, // Unix time in milliseconds
+}
+
+struct JobEntry {
+    info: JobInfo,
+    cancelled: Arc<AtomicBool>,
+}
+
+// Cheap to clone; shared between the command handlers and the worker threads
+// that run long operations (scans, analysis, bulk edits)
+#[derive(Clone, Default)]
+pub struct JobRegistry {
+    jobs: Arc<Mutex<HashMap<String, JobEntry>>>,
+    next_id: Arc<AtomicU64>,
+}
+
+#[derive(Clone)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+impl JobRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(&self, kind: &str, connection_id: &str) -> (String, CancelToken) {
+        let started_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        let id = format!(
+            "{}-{}-{}",
+            kind,
+            started_at,
+            self.next_id.fetch_add(1, Ordering::Relaxed)
+        );
+
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let mut jobs = self.jobs.lock().unwrap();
+        jobs.insert(
+            id.clone(),
+            JobEntry {
+                info: JobInfo {
+                    id: id.clone(),
+                    kind: kind.to_string(),
+                    connection_id: connection_id.to_string(),
+                    started_at,
+                },
+                cancelled: cancelled.clone(),
+            },
+        );
+
+        (id, CancelToken(cancelled))
+    }
+
+    pub fn cancel(&self, job_id: &str) -> bool {
+        let jobs = self.jobs.lock().unwrap();
+        match jobs.get(job_id) {
+            Some(job) => {
+                job.cancelled.store(true, Ordering::Relaxed);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn finish(&self, job_id: &str) {
+        let mut jobs = self.jobs.lock().unwrap();
+        jobs.remove(job_id);
+    }
+
+    pub fn list(&self) -> Vec<JobInfo> {
+        let jobs = self.jobs.lock().unwrap();
+        let mut list: Vec<JobInfo> = jobs.values().map(|j| j.info.clone()).collect();
+        list.sort_by_key(|j| j.started_at);
+        list
+    }
+}
+
+// Events streamed over a job's channel; `T` is the job's final result
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", content = "data", rename_all = "snake_case")]
+pub enum JobEvent<T> {
+    Started { job_id: String, total: u64 },
+    Progress { processed: u64, total: u64 },
+    Finished { result: T, cancelled: bool },
+    Error { message: String },
+}
+
+// Unregisters a job when its worker thread ends, including by panic
+struct FinishGuard {
+    registry: JobRegistry,
+    job_id: String,
+}
+
+impl Drop for FinishGuard {
+    fn drop(&mut self) {
+        self.registry.finish(&self.job_id);
+    }
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown error".to_string())
+}
+
+// Register a job and run `work` on a worker thread. If `work` panics, the
+// message is passed to `on_panic` so the caller's channel still gets an
+// error event. Returns the job id.
+pub fn spawn<F, P>(
+    registry: &JobRegistry,
+    kind: &str,
+    connection_id: &str,
+    work: F,
+    on_panic: P,
+) -> String
+where
+    F: FnOnce(&str, &CancelToken) + Send + 'static,
+    P: FnOnce(String) + Send + 'static,
+{
+    let (job_id, cancel) = registry.register(kind, connection_id);
+    let guard = FinishGuard {
+        registry: registry.clone(),
+        job_id: job_id.clone(),
+    };
+
+    std::thread::spawn(move || {
+        let result = panic::catch_unwind(AssertUnwindSafe(|| work(&guard.job_id, &cancel)));
+        if let Err(payload) = result {
+            on_panic(format!("Job failed: {}", panic_message(payload.as_ref())));
+        }
+        drop(guard);
+    });
+
+    job_id
+}
+
+// Passed to the work closure of spawn_job to announce the job and report
+// throttled progress
+pub struct JobReporter<T> {
+    job_id: String,
+    on_event: Channel<JobEvent<T>>,
+    throttle: ProgressThrottle,
+}
+
+impl<T: Serialize + Send + 'static> JobReporter<T> {
+    pub fn job_id(&self) -> &str {
+        &self.job_id
+    }
+
+    pub fn start(&self, total: u64) {
+        let _ = self.on_event.send(JobEvent::Started {
+            job_id: self.job_id.clone(),
+            total,
+        });
+    }
+
+    pub fn progress(&mut self, processed: u64, total: u64) {
+        if self.throttle.ready() {
+            let _ = self.on_event.send(JobEvent::Progress { processed, total });
+        }
+    }
+}
+
+// Run a job that streams JobEvents: `work` calls `start`, reports progress
+// and returns the result sent with Finished, or the message sent with Error
+pub fn spawn_job<T, F>(
+    registry: &JobRegistry,
+    kind: &str,
+    connection_id: &str,
+    on_event: Channel<JobEvent<T>>,
+    work: F,
+) -> String
+where
+    T: Serialize + Send + 'static,
+    F: FnOnce(&CancelToken, &mut JobReporter<T>) -> Result<T, String> + Send + 'static,
+{
+    let on_error = on_event.clone();
+    spawn(
+        registry,
+        kind,
+        connection_id,
+        move |job_id, cancel| {
+            let mut reporter = JobReporter {
+                job_id: job_id.to_string(),
+                on_event,
+                throttle: ProgressThrottle::new(),
+            };
+            let _ = match work(cancel, &mut reporter) {
+                Ok(result) => reporter.on_event.send(JobEvent::Finished {
+                    result,
+                    cancelled: cancel.is_cancelled(),
+                }),
+                Err(message) => reporter.on_event.send(JobEvent::Error { message }),
+            };
+        },
+        move |message| {
+            let _ = on_error.send(JobEvent::Error { message });
+        },
+    )
+}
+
+// Rate limiter for progress events
+pub struct ProgressThrottle {
+    last: Instant,
+}
+
+impl Default for ProgressThrottle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProgressThrottle {
+    pub fn new() -> Self {
+        Self {
+            last: Instant::now(),
+        }
+    }
+
+    pub fn ready(&mut self) -> bool {
+        if self.last.elapsed() >= PROGRESS_INTERVAL {
+            self.last = Instant::now();
+            true
+        } else {
+            false
+        }
+    }
+}
+
+// Sleeps just long enough to keep the average rate of a bulk job under
+// `keys_per_second`; no-op without a limit
+pub struct RateLimiter {
+    keys_per_second: Option<u32>,
+    started: Instant,
+    keys: u64,
+}
+
+impl RateLimiter {
+    pub fn new(keys_per_second: Option<u32>) -> Self {
+        Self {
+            keys_per_second,
+            started: Instant::now(),
+            keys: 0,
+        }
+    }
+
+    pub fn wait(&mut self, keys: usize, cancel: &CancelToken) {
+        self.keys += keys as u64;
+        let Some(rate) = self.keys_per_second.filter(|r| *r > 0) else {
+            return;
+        };
+        let due = Duration::from_secs_f64(self.keys as f64 / rate as f64);
+        // Sleep in short steps so cancellation stays responsive
+        while let Some(remaining) = due.checked_sub(self.started.elapsed()) {
+            if cancel.is_cancelled() || remaining.is_zero() {
+                break;
+            }
+            std::thread::sleep(remaining.min(Duration::from_millis(100)));
+        }
+    }
+}
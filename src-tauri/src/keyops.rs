@@ -0,0 +1,210 @@
+use crate::export::BinaryString;
+use crate::keyspace;
+use redis::{ConnectionLike, RedisResult, Value};
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyOperation {
+    Rename,
+    Copy,
+    Move,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct KeyOperationResult {
+    pub operation: KeyOperation,
+    pub source: BinaryString,
+    pub destination: BinaryString,
+    pub source_db: u32,
+    pub destination_db: u32,
+    pub applied: bool,
+    pub replaced: bool,          // The destination existed and was overwritten
+    pub ttl_ms: Option<i64>,     // TTL carried over; None if persistent
+    pub message: Option<String>, // Why the operation was not applied
+}
+
+// Run `f` with `db` selected, then switch back to the connection's database
+pub fn in_db<T>(
+    conn: &mut dyn ConnectionLike,
+    db: u32,
+    f: impl FnOnce(&mut dyn ConnectionLike) -> RedisResult<T>,
+) -> RedisResult<T> {
+    let current = conn.get_db() as u32;
+    if db == current {
+        return f(conn);
+    }
+    redis::cmd("SELECT").arg(db).query::<()>(conn)?;
+    let result = f(conn);
+    redis::cmd("SELECT").arg(current).query::<()>(conn)?;
+    result
+}
+
+fn exists(conn: &mut dyn ConnectionLike, key: &[u8]) -> RedisResult<bool> {
+    redis::cmd("EXISTS").arg(key).query(conn)
+}
+
+struct Plan {
+    result: KeyOperationResult,
+    destination_exists: bool,
+}
+
+// Validate the keys and look up the source TTL and the destination
+fn plan(
+    conn: &mut dyn ConnectionLike,
+    operation: KeyOperation,
+    source: BinaryString,
+    destination: BinaryString,
+    destination_db: Option<u32>,
+) -> Result<Plan, String> {
+    if source.0.is_empty() || destination.0.is_empty() {
+        return Err("Key names cannot be empty".to_string());
+    }
+    let source_db = conn.get_db() as u32;
+    let destination_db = destination_db.unwrap_or(source_db);
+    if source == destination && source_db == destination_db {
+        return Err("Source and destination are the same key".to_string());
+    }
+
+    let pttl: i64 = redis::cmd("PTTL")
+        .arg(&source.0)
+        .query(conn)
+        .map_err(|e| e.to_string())?;
+    if pttl == -2 {
+        return Err("Key not found".to_string());
+    }
+    let destination_exists = in_db(conn, destination_db, |conn| exists(conn, &destination.0))
+        .map_err(|e| e.to_string())?;
+
+    Ok(Plan {
+        result: KeyOperationResult {
+            operation,
+            source,
+            destination,
+            source_db,
+            destination_db,
+            applied: false,
+            replaced: false,
+            ttl_ms: (pttl >= 0).then_some(pttl),
+            message: None,
+        },
+        destination_exists,
+    })
+}
+
+fn finish(mut plan: Plan, applied: bool, replace: bool) -> KeyOperationResult {
+    plan.result.applied = applied;
+    plan.result.replaced = applied && replace && plan.destination_exists;
+    if !applied {
+        plan.result.message = Some("The destination key already exists".to_string());
+    }
+    plan.result
+}
+
+// RENAME keeps the TTL; RENAMENX is used unless `replace` is set so a
+// destination created after the check is still not overwritten
+pub fn rename(
+    conn: &mut dyn ConnectionLike,
+    source: BinaryString,
+    destination: BinaryString,
+    replace: bool,
+) -> Result<KeyOperationResult, String> {
+    let plan = plan(conn, KeyOperation::Rename, source, destination, None)?;
+    if plan.destination_exists && !replace {
+        return Ok(finish(plan, false, replace));
+    }
+
+    let command = if replace { "RENAME" } else { "RENAMENX" };
+    let reply: Value = redis::cmd(command)
+        .arg(&plan.result.source.0)
+        .arg(&plan.result.destination.0)
+        .query(conn)
+        .map_err(|e| e.to_string())?;
+    let applied = reply != Value::Int(0);
+    Ok(finish(plan, applied, replace))
+}
+
+// COPY needs Redis 6.2; older servers get DUMP + RESTORE with the source's
+// remaining TTL
+pub fn copy(
+    conn: &mut dyn ConnectionLike,
+    source: BinaryString,
+    destination: BinaryString,
+    destination_db: Option<u32>,
+    replace: bool,
+) -> Result<KeyOperationResult, String> {
+    let plan = plan(
+        conn,
+        KeyOperation::Copy,
+        source,
+        destination,
+        destination_db,
+    )?;
+    if plan.destination_exists && !replace {
+        return Ok(finish(plan, false, replace));
+    }
+
+    let native = keyspace::server_version(conn).is_none_or(|version| version >= (6, 2, 0));
+    let applied = if native {
+        let mut cmd = redis::cmd("COPY");
+        cmd.arg(&plan.result.source.0)
+            .arg(&plan.result.destination.0);
+        if plan.result.destination_db != plan.result.source_db {
+            cmd.arg("DB").arg(plan.result.destination_db);
+        }
+        if replace {
+            cmd.arg("REPLACE");
+        }
+        let copied: i64 = cmd.query(conn).map_err(|e| e.to_string())?;
+        copied == 1
+    } else {
+        let payload: Option<Vec<u8>> = redis::cmd("DUMP")
+            .arg(&plan.result.source.0)
+            .query(conn)
+            .map_err(|e| e.to_string())?;
+        let payload = payload.ok_or("Key not found")?;
+        let ttl_ms = plan.result.ttl_ms.unwrap_or(0);
+        let restored = in_db(conn, plan.result.destination_db, |conn| {
+            let mut cmd = redis::cmd("RESTORE");
+            cmd.arg(&plan.result.destination.0).arg(ttl_ms).arg(payload);
+            if replace {
+                cmd.arg("REPLACE");
+            }
+            cmd.query::<()>(conn)
+        });
+        match restored {
+            Ok(()) => true,
+            Err(e) if e.code() == Some("BUSYKEY") => false,
+            Err(e) => return Err(e.to_string()),
+        }
+    };
+    Ok(finish(plan, applied, replace))
+}
+
+// MOVE keeps the key name and TTL and never overwrites the destination
+pub fn move_to_db(
+    conn: &mut dyn ConnectionLike,
+    key: BinaryString,
+    destination_db: u32,
+) -> Result<KeyOperationResult, String> {
+    if destination_db == conn.get_db() as u32 {
+        return Err("The key is already in that database".to_string());
+    }
+    let plan = plan(
+        conn,
+        KeyOperation::Move,
+        key.clone(),
+        key,
+        Some(destination_db),
+    )?;
+    if plan.destination_exists {
+        return Ok(finish(plan, false, false));
+    }
+
+    let moved: i64 = redis::cmd("MOVE")
+        .arg(&plan.result.source.0)
+        .arg(destination_db)
+        .query(conn)
+        .map_err(|e| e.to_string())?;
+    Ok(finish(plan, moved == 1, false))
+}
@@ -0,0 +1,481 @@
+use crate::commands::RedisKey;
+use crate::export::BinaryString;
+use redis::{ConnectionLike, FromRedisValue, RedisResult, ToRedisArgs, Value};
+use serde::Serialize;
+
+// Keys scanned per SCAN call when walking the whole keyspace
+pub const SCAN_COUNT: usize = 1000;
+
+// Keys a preview scans before it stops and extrapolates from DBSIZE
+pub const PREVIEW_SCAN_LIMIT: u64 = 100_000;
+
+// (major, minor, patch)
+pub type ServerVersion = (u32, u32, u32);
+
+// Parse "redis_version:x.y.z" from INFO server
+pub fn server_version(conn: &mut dyn ConnectionLike) -> Option<ServerVersion> {
+    let info: String = redis::cmd("INFO").arg("server").query(conn).ok()?;
+
+    let version = info
+        .lines()
+        .find_map(|line| line.strip_prefix("redis_version:"))?
+        .trim();
+
+    let mut parts = version.split('.').map(|p| p.parse::<u32>().unwrap_or(0));
+    Some((
+        parts.next().unwrap_or(0),
+        parts.next().unwrap_or(0),
+        parts.next().unwrap_or(0),
+    ))
+}
+
+// SCAN ... TYPE was added in Redis 6.0
+pub fn supports_scan_type(version: Option<ServerVersion>) -> bool {
+    version.is_some_and(|v| v >= (6, 0, 0))
+}
+
+// Keys are generic so the browser can read text while jobs read raw bytes
+pub fn scan_page<T: FromRedisValue>(
+    conn: &mut dyn ConnectionLike,
+    cursor: u64,
+    pattern: &str,
+    count: usize,
+    key_type: Option<&str>,
+) -> RedisResult<(u64, Vec<T>)> {
+    let mut cmd = redis::cmd("SCAN");
+    cmd.arg(cursor)
+        .arg("MATCH")
+        .arg(pattern)
+        .arg("COUNT")
+        .arg(count);
+
+    if let Some(key_type) = key_type {
+        cmd.arg("TYPE").arg(key_type);
+    }
+
+    cmd.query(conn)
+}
+
+// Walks the keyspace one SCAN page at a time, applying the type filter on
+// the server when supported. Used by background jobs that need every
+// matching key; SCAN may return a key more than once during a rehash. Keys
+// are read as raw bytes so one that is not UTF-8 does not abort the walk.
+pub struct KeyScanner {
+    pattern: String,
+    count: usize,
+    type_filter: Option<String>,
+    server_side_filter: bool,
+    cursor: u64,
+    calls: u64,
+    done: bool,
+}
+
+impl KeyScanner {
+    pub fn new(
+        conn: &mut dyn ConnectionLike,
+        pattern: &str,
+        count: usize,
+        key_type: Option<&str>,
+    ) -> Self {
+        let type_filter = key_type.filter(|t| *t != "all").map(|t| t.to_string());
+        let server_side_filter = type_filter.is_some() && supports_scan_type(server_version(conn));
+
+        Self {
+            pattern: pattern.to_string(),
+            count: count.max(1),
+            type_filter,
+            server_side_filter,
+            cursor: 0,
+            calls: 0,
+            done: false,
+        }
+    }
+
+    // Returns None once the cursor has wrapped back to 0
+    pub fn next_batch(
+        &mut self,
+        conn: &mut dyn ConnectionLike,
+    ) -> RedisResult<Option<Vec<BinaryString>>> {
+        if self.done {
+            return Ok(None);
+        }
+
+        let server_type = self
+            .type_filter
+            .as_deref()
+            .filter(|_| self.server_side_filter);
+        let (next_cursor, mut batch) =
+            scan_page::<Vec<u8>>(conn, self.cursor, &self.pattern, self.count, server_type)?;
+
+        if let (Some(filter_type), false) = (&self.type_filter, self.server_side_filter) {
+            batch = filter_by_type(conn, batch, filter_type)?;
+        }
+
+        self.calls += 1;
+        self.cursor = next_cursor;
+        self.done = next_cursor == 0;
+
+        Ok(Some(batch.into_iter().map(BinaryString).collect()))
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    // Scale a count taken over the keys scanned so far to the whole DBSIZE
+    // estimate; exact once the scan has finished
+    pub fn extrapolate(&self, count: u64, total: u64) -> u64 {
+        let scanned = self.scanned_estimate(total);
+        if self.done || total == 0 || scanned == 0 {
+            count
+        } else {
+            (count as f64 * total as f64 / scanned as f64).round() as u64
+        }
+    }
+
+    // SCAN does not report how many keys it examined; estimate it from the
+    // number of calls times COUNT, capped at the DBSIZE estimate
+    pub fn scanned_estimate(&self, total: u64) -> u64 {
+        let scanned = self.calls * self.count as u64;
+        if total == 0 {
+            scanned
+        } else if self.done {
+            total
+        } else {
+            scanned.min(total)
+        }
+    }
+}
+
+// Key source for jobs that take either a SCAN pattern or an explicit list
+// of keys. Both are walked in batches of SCAN_COUNT.
+pub enum KeyBatches {
+    Scan {
+        scanner: KeyScanner,
+        total: u64,
+    },
+    List {
+        keys: Vec<BinaryString>,
+        offset: usize,
+    },
+}
+
+impl KeyBatches {
+    pub fn new(
+        conn: &mut dyn ConnectionLike,
+        pattern: &str,
+        keys: Option<Vec<BinaryString>>,
+    ) -> Self {
+        match keys {
+            Some(keys) => KeyBatches::List { keys, offset: 0 },
+            None => KeyBatches::Scan {
+                total: dbsize(conn),
+                scanner: KeyScanner::new(conn, pattern, SCAN_COUNT, None),
+            },
+        }
+    }
+
+    // DBSIZE estimate when scanning, exact for a key list
+    pub fn total(&self) -> u64 {
+        match self {
+            KeyBatches::Scan { total, .. } => *total,
+            KeyBatches::List { keys, .. } => keys.len() as u64,
+        }
+    }
+
+    pub fn processed(&self) -> u64 {
+        match self {
+            KeyBatches::Scan { scanner, total } => scanner.scanned_estimate(*total),
+            KeyBatches::List { offset, .. } => *offset as u64,
+        }
+    }
+
+    pub fn next_batch(
+        &mut self,
+        conn: &mut dyn ConnectionLike,
+    ) -> RedisResult<Option<Vec<BinaryString>>> {
+        match self {
+            KeyBatches::Scan { scanner, .. } => scanner.next_batch(conn),
+            KeyBatches::List { keys, offset } => {
+                if *offset >= keys.len() {
+                    return Ok(None);
+                }
+                let end = (*offset + SCAN_COUNT).min(keys.len());
+                let batch = keys[*offset..end].to_vec();
+                *offset = end;
+                Ok(Some(batch))
+            }
+        }
+    }
+}
+
+// Escape glob metacharacters so a literal prefix can be used in MATCH
+pub fn glob_escape(literal: &str) -> String {
+    let mut escaped = String::with_capacity(literal.len());
+    for c in literal.chars() {
+        if matches!(c, '*' | '?' | '[' | ']' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+// One "dbN:keys=..,expires=..,avg_ttl=..[,subexpiry=..]" line of INFO keyspace
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DbKeyspace {
+    pub db: u32,
+    pub keys: u64,
+    pub expires: u64,
+    pub avg_ttl_ms: u64,
+    pub subexpiry: Option<u64>, // Keys with hash field TTLs (Redis 7.4+)
+}
+
+pub fn parse_keyspace_info(info: &str) -> Vec<DbKeyspace> {
+    info.lines()
+        .filter_map(|line| {
+            let (name, fields) = line.trim().split_once(':')?;
+            let mut db = DbKeyspace {
+                db: name.strip_prefix("db")?.parse().ok()?,
+                ..Default::default()
+            };
+            for field in fields.split(',') {
+                let Some((field, value)) = field.split_once('=') else {
+                    continue;
+                };
+                let value = value.parse().unwrap_or(0);
+                match field {
+                    "keys" => db.keys = value,
+                    "expires" => db.expires = value,
+                    "avg_ttl" => db.avg_ttl_ms = value,
+                    "subexpiry" => db.subexpiry = Some(value),
+                    _ => {}
+                }
+            }
+            Some(db)
+        })
+        .collect()
+}
+
+// CRC16/XMODEM, as used by Redis Cluster to map keys to slots
+fn crc16(data: &[u8]) -> u16 {
+    let mut crc: u16 = 0;
+    for byte in data {
+        crc ^= (*byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+// Cluster slot of a key; only the {hash tag} is hashed when present
+pub fn key_slot(key: &[u8]) -> u16 {
+    let tagged = key.iter().position(|b| *b == b'{').and_then(|open| {
+        let rest = &key[open + 1..];
+        rest.iter()
+            .position(|b| *b == b'}')
+            .filter(|close| *close > 0)
+            .map(|close| &rest[..close])
+    });
+    crc16(tagged.unwrap_or(key)) % 16384
+}
+
+pub fn cluster_enabled(conn: &mut dyn ConnectionLike) -> bool {
+    let info: String = redis::cmd("INFO")
+        .arg("cluster")
+        .query(conn)
+        .unwrap_or_default();
+    info.lines().any(|line| line.trim() == "cluster_enabled:1")
+}
+
+pub fn dbsize(conn: &mut dyn ConnectionLike) -> u64 {
+    redis::cmd("DBSIZE").query(conn).unwrap_or(0)
+}
+
+// Client-side type filter for servers without SCAN TYPE: one pipelined TYPE
+// round-trip per batch instead of one round-trip per key
+pub fn filter_by_type<T: ToRedisArgs>(
+    conn: &mut dyn ConnectionLike,
+    keys: Vec<T>,
+    key_type: &str,
+) -> RedisResult<Vec<T>> {
+    if keys.is_empty() {
+        return Ok(keys);
+    }
+
+    let mut pipe = redis::pipe();
+    for key in &keys {
+        pipe.cmd("TYPE").arg(key);
+    }
+    let types: Vec<String> = pipe.query(conn)?;
+
+    Ok(keys
+        .into_iter()
+        .zip(types)
+        .filter(|(_, t)| t == key_type)
+        .map(|(key, _)| key)
+        .collect())
+}
+
+// Run a pipeline and return every reply, keeping per-command errors as
+// `Value::ServerError` instead of failing the whole batch
+pub fn query_pipeline_lenient(
+    conn: &mut dyn ConnectionLike,
+    pipe: &redis::Pipeline,
+    command_count: usize,
+) -> RedisResult<Vec<Value>> {
+    if command_count == 0 {
+        return Ok(Vec::new());
+    }
+    conn.req_packed_commands(&pipe.get_packed_pipeline(), 0, command_count)
+}
+
+pub fn value_to_i64(value: &Value) -> Option<i64> {
+    match value {
+        Value::Int(i) => Some(*i),
+        Value::BulkString(bytes) => String::from_utf8_lossy(bytes).parse().ok(),
+        _ => None,
+    }
+}
+
+pub fn value_to_string(value: &Value) -> Option<String> {
+    match value {
+        Value::SimpleString(s) => Some(s.clone()),
+        Value::BulkString(bytes) => Some(String::from_utf8_lossy(bytes).to_string()),
+        _ => None,
+    }
+}
+
+// Commands sent per key by the first `fetch_key_metadata` round-trip
+const METADATA_COMMANDS_PER_KEY: usize = 5;
+
+// Element count command for a collection type
+fn length_command(key_type: &str) -> Option<&'static str> {
+    match key_type {
+        "list" => Some("LLEN"),
+        "set" => Some("SCARD"),
+        "zset" => Some("ZCARD"),
+        "hash" => Some("HLEN"),
+        "stream" => Some("XLEN"),
+        _ => None,
+    }
+}
+
+// Type, TTL, memory usage, encoding, refcount and element count for a page
+// of keys in two pipelined round-trips: the per-key metadata first, then one
+// length command per collection chosen by its type
+pub fn fetch_key_metadata(
+    conn: &mut dyn ConnectionLike,
+    keys: &[String],
+) -> RedisResult<Vec<RedisKey>> {
+    let mut pipe = redis::pipe();
+    for key in keys {
+        pipe.cmd("TYPE").arg(key);
+        pipe.cmd("TTL").arg(key);
+        pipe.cmd("MEMORY").arg("USAGE").arg(key);
+        pipe.cmd("OBJECT").arg("ENCODING").arg(key);
+        pipe.cmd("OBJECT").arg("REFCOUNT").arg(key);
+    }
+    let replies = query_pipeline_lenient(conn, &pipe, keys.len() * METADATA_COMMANDS_PER_KEY)?;
+
+    let mut metadata: Vec<RedisKey> = keys
+        .iter()
+        .zip(replies.chunks(METADATA_COMMANDS_PER_KEY))
+        .map(|(key, r)| RedisKey {
+            name: key.clone(),
+            key_type: value_to_string(&r[0]).unwrap_or_else(|| "none".to_string()),
+            ttl: value_to_i64(&r[1]).unwrap_or(-2),
+            memory_usage: value_to_i64(&r[2]).map(|v| v as usize),
+            encoding: value_to_string(&r[3]),
+            refcount: value_to_i64(&r[4]).map(|v| v as usize),
+            size: None,
+        })
+        .collect();
+
+    let mut pipe = redis::pipe();
+    let mut collections = Vec::new();
+    for (i, key) in metadata.iter().enumerate() {
+        if let Some(command) = length_command(&key.key_type) {
+            pipe.cmd(command).arg(&key.name);
+            collections.push(i);
+        }
+    }
+    // A key whose type changed in between gets a WRONGTYPE reply and no size
+    let lengths = query_pipeline_lenient(conn, &pipe, collections.len())?;
+    for (i, length) in collections.into_iter().zip(&lengths) {
+        metadata[i].size = value_to_i64(length).map(|v| v as usize);
+    }
+
+    Ok(metadata)
+}
+
+// Paged collection reads, shared by the value views and the export job.
+// Elements are generic so callers can read text or raw bytes.
+
+// LRANGE page plus the list length
+pub fn list_page<T: FromRedisValue>(
+    conn: &mut dyn ConnectionLike,
+    key: &[u8],
+    start: i64,
+    count: usize,
+) -> RedisResult<(Vec<T>, usize)> {
+    let total: usize = redis::cmd("LLEN").arg(key).query(conn)?;
+    let items = redis::cmd("LRANGE")
+        .arg(key)
+        .arg(start)
+        .arg(start + count as i64 - 1)
+        .query(conn)?;
+    Ok((items, total))
+}
+
+// ZRANGE ... WITHSCORES page plus the sorted set cardinality
+pub fn zset_page<T: FromRedisValue>(
+    conn: &mut dyn ConnectionLike,
+    key: &[u8],
+    start: i64,
+    count: usize,
+) -> RedisResult<(Vec<(T, f64)>, usize)> {
+    let total: usize = redis::cmd("ZCARD").arg(key).query(conn)?;
+    let items = redis::cmd("ZRANGE")
+        .arg(key)
+        .arg(start)
+        .arg(start + count as i64 - 1)
+        .arg("WITHSCORES")
+        .query(conn)?;
+    Ok((items, total))
+}
+
+// One SSCAN call; returns the next cursor (0 when done)
+pub fn set_scan_page<T: FromRedisValue>(
+    conn: &mut dyn ConnectionLike,
+    key: &[u8],
+    cursor: u64,
+    count: usize,
+) -> RedisResult<(u64, Vec<T>)> {
+    redis::cmd("SSCAN")
+        .arg(key)
+        .arg(cursor)
+        .arg("COUNT")
+        .arg(count)
+        .query(conn)
+}
+
+// One HSCAN call; returns the next cursor (0 when done) and field/value pairs
+pub fn hash_scan_page<T: FromRedisValue>(
+    conn: &mut dyn ConnectionLike,
+    key: &[u8],
+    cursor: u64,
+    count: usize,
+) -> RedisResult<(u64, Vec<(T, T)>)> {
+    redis::cmd("HSCAN")
+        .arg(key)
+        .arg(cursor)
+        .arg("COUNT")
+        .arg(count)
+        .query(conn)
+}
@@ -1,13 +1,37 @@
+mod access;
+mod aof;
+mod audit;
+mod backup;
+mod bigkeys;
+mod bulk;
 mod commands;
 mod connection_store;
+mod diff;
+mod expiry;
+mod export;
+mod hotkeys;
+mod import;
+mod jobs;
+mod keyops;
+mod keyspace;
+mod migrate;
+mod namespace;
+mod rdb;
 mod redis_client;
+mod rename;
+mod snapshot;
 mod ssh_tunnel;
+mod undo;
 
+use audit::AuditLog;
 use commands::AppState;
 use connection_store::{ConnectionStore, PasswordStore};
+use jobs::JobRegistry;
 use redis_client::RedisConnectionManager;
-use std::sync::Mutex;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 use tauri::Manager;
+use undo::UndoJournal;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
@@ -17,11 +41,16 @@ pub fn run() {
             let connection_store =
                 ConnectionStore::new(&app.handle()).expect("Failed to initialize connection store");
             let password_store = PasswordStore::new();
+            let audit = AuditLog::new(&app.handle()).expect("Failed to initialize audit log");
 
             app.manage(AppState {
                 redis_manager: Mutex::new(RedisConnectionManager::new()),
                 connection_store: Mutex::new(connection_store),
                 password_store,
+                jobs: JobRegistry::new(),
+                audit,
+                undo: UndoJournal::new(),
+                namespace_trees: Arc::new(Mutex::new(HashMap::new())),
             });
 
             Ok(())
@@ -31,12 +60,45 @@ pub fn run() {
             commands::disconnect_from_redis,
             commands::get_keys,
             commands::scan_keys,
+            commands::start_key_scan,
+            commands::cancel_job,
+            commands::list_jobs,
+            commands::start_namespace_analysis,
+            commands::get_namespace_level,
+            commands::drop_namespace_analysis,
+            commands::start_bigkeys_analysis,
+            commands::start_expiry_analysis,
+            commands::start_hotkeys_analysis,
+            commands::save_analysis_report,
+            commands::start_key_export,
+            commands::start_key_import,
+            commands::start_key_backup,
+            commands::read_backup_manifest,
+            commands::start_backup_restore,
+            commands::start_key_migration,
+            commands::start_db_diff,
+            commands::diff_keys,
+            commands::preview_bulk_keys,
+            commands::start_bulk_delete,
+            commands::start_bulk_ttl,
+            commands::preview_bulk_rename,
+            commands::start_bulk_rename,
             commands::get_key_info,
+            commands::get_keys_metadata,
             commands::get_value,
             commands::set_value,
             commands::delete_key,
             commands::set_ttl,
+            commands::rename_key,
+            commands::copy_key,
+            commands::move_key,
             commands::execute_command,
+            commands::confirm_command,
+            commands::query_audit_log,
+            commands::export_audit_log,
+            commands::set_undo_journal,
+            commands::get_undo_journal,
+            commands::undo_operations,
             commands::save_connection,
             commands::load_connections,
             commands::delete_saved_connection,
@@ -44,6 +106,9 @@ pub fn run() {
             commands::get_ssh_password,
             commands::get_ssh_passphrase,
             commands::test_connection,
+            commands::open_rdb_file,
+            commands::start_aof_inspection,
+            commands::start_aof_export,
             commands::get_key_memory_usage,
             commands::get_list_range,
             commands::get_set_members,
@@ -51,6 +116,10 @@ pub fn run() {
             commands::get_hash_fields,
             commands::hash_set_field,
             commands::hash_delete_field,
+            commands::hash_expire_fields,
+            commands::hash_persist_fields,
+            commands::hash_get_fields_ex,
+            commands::hash_set_fields_ex,
             commands::list_push,
             commands::list_pop,
             commands::list_set_index,
@@ -64,7 +133,14 @@ pub fn run() {
             commands::stream_delete_entry,
             commands::stream_get_range,
             commands::stream_trim,
+            commands::get_hyperloglog_info,
+            commands::get_bitmap_info,
+            commands::get_bitmap_range,
+            commands::bitmap_set_bit,
+            commands::bitmap_bitfield,
             commands::get_server_info,
+            commands::get_keyspace_overview,
+            commands::select_database,
             commands::get_client_list,
             commands::get_slow_log,
             commands::get_command_stats,
@@ -0,0 +1,463 @@
+use crate::backup;
+use crate::export::BinaryString;
+use crate::import::{self, ConflictPolicy};
+use crate::jobs::{CancelToken, RateLimiter};
+use crate::keyspace::{self, KeyBatches};
+use redis::{ConnectionLike, Value};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::time::Instant;
+
+// Keys moved per round-trip (and per MIGRATE call)
+const CHUNK_SIZE: usize = 100;
+const MAX_RESULTS: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MigrationMode {
+    DumpRestore, // Through this app's connections, so SSH tunnels work on both sides
+    Migrate,     // Server to server; the target must be reachable from the source
+}
+
+// Target address and credentials as seen from the source server (MIGRATE)
+#[derive(Debug, Clone)]
+pub struct MigrateEndpoint {
+    pub host: String,
+    pub port: u16,
+    pub username: Option<String>,
+    pub password: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct MigrationOptions {
+    pub pattern: String,
+    pub keys: Option<Vec<BinaryString>>,
+    pub mode: MigrationMode,
+    pub endpoint: Option<MigrateEndpoint>, // Required in Migrate mode
+    pub target_db: u32,
+    pub conflict: ConflictPolicy,
+    pub rename_suffix: String,
+    pub delete_source: bool,
+    pub max_keys_per_second: Option<u32>,
+    pub timeout_ms: u64, // MIGRATE timeout
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MigrationOutcome {
+    Migrated,
+    Renamed,
+    Overwritten,
+    Skipped,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MigrationKeyResult {
+    pub key: BinaryString,
+    pub target_key: BinaryString,
+    pub outcome: MigrationOutcome,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MigrationReport {
+    pub mode: MigrationMode,
+    pub scanned: u64,
+    pub migrated: u64, // Includes renamed and overwritten keys
+    pub renamed: u64,
+    pub overwritten: u64,
+    pub skipped_existing: u64,
+    pub vanished: u64, // Deleted from the source before they could be copied
+    pub failed: u64,
+    pub deleted_from_source: u64,
+    pub duration_ms: u64,
+    pub results: Vec<MigrationKeyResult>, // First MAX_RESULTS keys
+    pub aborted: Option<String>,          // Set when the fail policy hit an existing key
+    pub cancelled: bool,
+}
+
+impl MigrationReport {
+    fn record(
+        &mut self,
+        key: &[u8],
+        target_key: &[u8],
+        outcome: MigrationOutcome,
+        error: Option<String>,
+    ) {
+        match outcome {
+            MigrationOutcome::Migrated => self.migrated += 1,
+            MigrationOutcome::Renamed => {
+                self.migrated += 1;
+                self.renamed += 1;
+            }
+            MigrationOutcome::Overwritten => {
+                self.migrated += 1;
+                self.overwritten += 1;
+            }
+            MigrationOutcome::Skipped => self.skipped_existing += 1,
+            MigrationOutcome::Failed => self.failed += 1,
+        }
+        if self.results.len() < MAX_RESULTS {
+            self.results.push(MigrationKeyResult {
+                key: BinaryString(key.to_vec()),
+                target_key: BinaryString(target_key.to_vec()),
+                outcome,
+                error,
+            });
+        }
+    }
+}
+// Refuse before copying anything if the target cannot load the source's
+// DUMP payloads (DUMP/RESTORE mode only; MIGRATE reports it per call)
+pub fn check_compatibility(
+    source: &mut dyn ConnectionLike,
+    target: &mut dyn ConnectionLike,
+) -> Result<(), String> {
+    if let (Some(source_version), Some(target_version)) = (
+        backup::server_rdb_version(source),
+        backup::server_rdb_version(target),
+    ) {
+        if source_version > target_version {
+            return Err(format!(
+                "The source writes RDB version {} but the target only supports up to {}",
+                source_version, target_version
+            ));
+        }
+    }
+    Ok(())
+}
+
+struct Planned {
+    key: BinaryString,
+    target_key: Vec<u8>,
+    outcome: MigrationOutcome,
+}
+
+// Resolve the conflict policy for one chunk against the target. Returns None
+// if the fail policy hit an existing key.
+fn plan_chunk(
+    target: &mut dyn ConnectionLike,
+    chunk: &[BinaryString],
+    options: &MigrationOptions,
+    claimed: &mut HashSet<Vec<u8>>,
+    report: &mut MigrationReport,
+) -> Result<Option<Vec<Planned>>, String> {
+    let keys: Vec<&[u8]> = chunk.iter().map(|k| k.0.as_slice()).collect();
+    let existing = import::existing_keys(target, &keys, claimed)?;
+
+    let mut planned = Vec::with_capacity(chunk.len());
+    for (key, exists) in chunk.iter().zip(existing) {
+        let (target_key, outcome) = match (exists, options.conflict) {
+            (false, _) => (key.0.clone(), MigrationOutcome::Migrated),
+            (true, ConflictPolicy::Skip) => {
+                report.record(&key.0, &key.0, MigrationOutcome::Skipped, None);
+                continue;
+            }
+            (true, ConflictPolicy::Overwrite) => (key.0.clone(), MigrationOutcome::Overwritten),
+            (true, ConflictPolicy::Rename) => {
+                match import::rename_target(target, &key.0, &options.rename_suffix, claimed)? {
+                    Some(renamed) => (renamed, MigrationOutcome::Renamed),
+                    None => {
+                        let error = Some("No free name found to rename to".to_string());
+                        report.record(&key.0, &key.0, MigrationOutcome::Failed, error);
+                        continue;
+                    }
+                }
+            }
+            (true, ConflictPolicy::Fail) => {
+                report.aborted = Some(format!(
+                    "Key '{}' already exists on the target",
+                    key.to_string_lossy()
+                ));
+                return Ok(None);
+            }
+        };
+        claimed.insert(target_key.clone());
+        planned.push(Planned {
+            key: key.clone(),
+            target_key,
+            outcome,
+        });
+    }
+    Ok(Some(planned))
+}
+
+// DUMP + PTTL from the source, RESTORE on the target; returns the keys that
+// made it so they can be removed from the source
+fn copy_chunk(
+    source: &mut dyn ConnectionLike,
+    target: &mut dyn ConnectionLike,
+    planned: Vec<Planned>,
+    report: &mut MigrationReport,
+) -> Result<Vec<BinaryString>, String> {
+    let mut pipe = redis::pipe();
+    for p in &planned {
+        pipe.cmd("DUMP").arg(&p.key);
+        pipe.cmd("PTTL").arg(&p.key);
+    }
+    let dumps = keyspace::query_pipeline_lenient(source, &pipe, planned.len() * 2)
+        .map_err(|e| e.to_string())?;
+
+    let mut pipe = redis::pipe();
+    let mut restoring = Vec::with_capacity(planned.len());
+    for (p, r) in planned.into_iter().zip(dumps.chunks(2)) {
+        let Value::BulkString(payload) = &r[0] else {
+            report.vanished += 1;
+            continue;
+        };
+        let ttl = keyspace::value_to_i64(&r[1])
+            .filter(|t| *t > 0)
+            .unwrap_or(0);
+        let cmd = pipe.cmd("RESTORE").arg(&p.target_key).arg(ttl).arg(payload);
+        if p.outcome == MigrationOutcome::Overwritten {
+            cmd.arg("REPLACE");
+        }
+        restoring.push(p);
+    }
+    let replies = keyspace::query_pipeline_lenient(target, &pipe, restoring.len())
+        .map_err(|e| e.to_string())?;
+
+    let mut copied = Vec::with_capacity(restoring.len());
+    for (p, reply) in restoring.into_iter().zip(replies) {
+        match reply.extract_error() {
+            Ok(_) => {
+                report.record(&p.key.0, &p.target_key, p.outcome, None);
+                copied.push(p.key);
+            }
+            Err(e) => report.record(
+                &p.key.0,
+                &p.target_key,
+                MigrationOutcome::Failed,
+                Some(e.to_string()),
+            ),
+        }
+    }
+    Ok(copied)
+}
+
+// EXISTS for each key in one pipelined round-trip
+fn keys_exist<'a>(
+    conn: &mut dyn ConnectionLike,
+    keys: impl Iterator<Item = &'a [u8]>,
+) -> Result<Vec<bool>, String> {
+    let mut pipe = redis::pipe();
+    let mut count = 0;
+    for key in keys {
+        pipe.cmd("EXISTS").arg(key);
+        count += 1;
+    }
+    let replies =
+        keyspace::query_pipeline_lenient(conn, &pipe, count).map_err(|e| e.to_string())?;
+    Ok(replies
+        .iter()
+        .map(|r| keyspace::value_to_i64(r).unwrap_or(0) > 0)
+        .collect())
+}
+
+// One MIGRATE call for the whole chunk. MIGRATE removes the source keys
+// itself unless COPY is given.
+fn migrate_chunk(
+    source: &mut dyn ConnectionLike,
+    target: &mut dyn ConnectionLike,
+    endpoint: &MigrateEndpoint,
+    options: &MigrationOptions,
+    planned: Vec<Planned>,
+    report: &mut MigrationReport,
+) -> Result<(), String> {
+    // MIGRATE silently skips missing keys, so count them up front rather
+    // than reporting them as migrated
+    let exists = keys_exist(source, planned.iter().map(|p| p.key.0.as_slice()))?;
+    let before = planned.len();
+    let planned: Vec<Planned> = planned
+        .into_iter()
+        .zip(exists)
+        .filter_map(|(p, exists)| exists.then_some(p))
+        .collect();
+    report.vanished += (before - planned.len()) as u64;
+    if planned.is_empty() {
+        return Ok(());
+    }
+
+    let mut cmd = redis::cmd("MIGRATE");
+    cmd.arg(&endpoint.host)
+        .arg(endpoint.port)
+        .arg("")
+        .arg(options.target_db)
+        .arg(options.timeout_ms);
+    if !options.delete_source {
+        cmd.arg("COPY");
+    }
+    if planned
+        .iter()
+        .any(|p| p.outcome == MigrationOutcome::Overwritten)
+    {
+        cmd.arg("REPLACE");
+    }
+    match (&endpoint.username, &endpoint.password) {
+        (Some(username), Some(password)) => {
+            cmd.arg("AUTH2").arg(username).arg(password);
+        }
+        (None, Some(password)) => {
+            cmd.arg("AUTH").arg(password);
+        }
+        _ => {}
+    }
+    cmd.arg("KEYS");
+    for p in &planned {
+        cmd.arg(&p.key);
+    }
+
+    let reply: Value = match cmd.query(source) {
+        Ok(reply) => reply,
+        Err(e) if e.code().is_some() => {
+            return record_partial_migration(
+                source,
+                target,
+                options,
+                planned,
+                e.to_string(),
+                report,
+            )
+        }
+        Err(e) => return Err(e.to_string()),
+    };
+
+    // NOKEY means none of the keys existed any more
+    if matches!(&reply, Value::SimpleString(s) if s == "NOKEY") {
+        report.vanished += planned.len() as u64;
+        return Ok(());
+    }
+    if options.delete_source {
+        report.deleted_from_source += planned.len() as u64;
+    }
+    for p in planned {
+        report.record(&p.key.0, &p.target_key, p.outcome, None);
+    }
+    Ok(())
+}
+
+// A failed MIGRATE may still have moved the keys sent before the error, so
+// each key's outcome is read back from the servers
+fn record_partial_migration(
+    source: &mut dyn ConnectionLike,
+    target: &mut dyn ConnectionLike,
+    options: &MigrationOptions,
+    planned: Vec<Planned>,
+    error: String,
+    report: &mut MigrationReport,
+) -> Result<(), String> {
+    let on_target = keys_exist(target, planned.iter().map(|p| p.target_key.as_slice()))?;
+    let on_source = if options.delete_source {
+        keys_exist(source, planned.iter().map(|p| p.key.0.as_slice()))?
+    } else {
+        vec![true; planned.len()]
+    };
+
+    for ((p, on_target), on_source) in planned.into_iter().zip(on_target).zip(on_source) {
+        let migrated = if options.delete_source {
+            !on_source && on_target
+        } else {
+            // An overwritten key was on the target before, so its presence
+            // proves nothing
+            on_target && p.outcome != MigrationOutcome::Overwritten
+        };
+        if migrated {
+            if options.delete_source {
+                report.deleted_from_source += 1;
+            }
+            report.record(&p.key.0, &p.target_key, p.outcome, None);
+        } else if !on_source {
+            report.vanished += 1;
+        } else {
+            report.record(
+                &p.key.0,
+                &p.target_key,
+                MigrationOutcome::Failed,
+                Some(error.clone()),
+            );
+        }
+    }
+    Ok(())
+}
+
+pub fn migrate_keys(
+    source: &mut dyn ConnectionLike,
+    target: &mut dyn ConnectionLike,
+    options: &MigrationOptions,
+    cancel: &CancelToken,
+    mut on_progress: impl FnMut(u64, u64),
+) -> Result<MigrationReport, String> {
+    let started = Instant::now();
+    let mut report = MigrationReport {
+        mode: options.mode,
+        scanned: 0,
+        migrated: 0,
+        renamed: 0,
+        overwritten: 0,
+        skipped_existing: 0,
+        vanished: 0,
+        failed: 0,
+        deleted_from_source: 0,
+        duration_ms: 0,
+        results: Vec::new(),
+        aborted: None,
+        cancelled: false,
+    };
+    let mut limiter = RateLimiter::new(options.max_keys_per_second);
+    let mut claimed = HashSet::new();
+    let mut batches = KeyBatches::new(source, &options.pattern, options.keys.clone());
+    let total = batches.total();
+
+    'batches: while let Some(batch) = batches.next_batch(source).map_err(|e| e.to_string())? {
+        // Small chunks keep the rate limit smooth and bound each round-trip
+        let chunk_size = options
+            .max_keys_per_second
+            .map_or(CHUNK_SIZE, |rate| (rate as usize).clamp(1, CHUNK_SIZE));
+
+        for chunk in batch.chunks(chunk_size) {
+            if cancel.is_cancelled() {
+                report.cancelled = true;
+                break 'batches;
+            }
+            report.scanned += chunk.len() as u64;
+
+            let Some(planned) = plan_chunk(target, chunk, options, &mut claimed, &mut report)?
+            else {
+                break 'batches;
+            };
+
+            match (options.mode, &options.endpoint) {
+                (MigrationMode::Migrate, Some(endpoint)) => {
+                    migrate_chunk(source, target, endpoint, options, planned, &mut report)?
+                }
+                (MigrationMode::Migrate, None) => {
+                    return Err("MIGRATE needs the target address as seen from the source".into())
+                }
+                (MigrationMode::DumpRestore, _) => {
+                    let copied = copy_chunk(source, target, planned, &mut report)?;
+                    if options.delete_source && !copied.is_empty() {
+                        let mut pipe = redis::pipe();
+                        for key in &copied {
+                            pipe.cmd("UNLINK").arg(key);
+                        }
+                        let replies = keyspace::query_pipeline_lenient(source, &pipe, copied.len())
+                            .map_err(|e| e.to_string())?;
+                        report.deleted_from_source += replies
+                            .iter()
+                            .filter_map(keyspace::value_to_i64)
+                            .sum::<i64>()
+                            as u64;
+                    }
+                }
+            }
+
+            limiter.wait(chunk.len(), cancel);
+        }
+
+        on_progress(batches.processed(), total);
+    }
+
+    report.duration_ms = started.elapsed().as_millis() as u64;
+    Ok(report)
+}
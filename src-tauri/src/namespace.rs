@@ -0,0 +1,243 @@
+use crate::jobs::CancelToken;
+use crate::keyspace::{self, KeyScanner};
+use redis::{ConnectionLike, RedisResult};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct NamespaceOptions {
+    pub pattern: String,
+    pub delimiter: String,
+    pub memory_sample_rate: f64, // Fraction of keys measured with MEMORY USAGE (0-1]
+    pub max_depth: usize,
+}
+
+#[derive(Debug, Default)]
+pub struct NamespaceNode {
+    key_count: u64,
+    direct_keys: u64, // Keys whose name ends at this level
+    types: BTreeMap<String, u64>,
+    keys_with_ttl: u64,
+    sampled_keys: u64,
+    sampled_memory: u64,
+    children: HashMap<String, NamespaceNode>,
+}
+
+pub struct NamespaceTree {
+    pub root: NamespaceNode,
+    pub delimiter: String,
+    pub cancelled: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NamespaceNodeSummary {
+    pub name: String,
+    pub path: Vec<String>,
+    pub pattern: String, // SCAN pattern matching every key under this node
+    pub key_count: u64,
+    pub direct_keys: u64,
+    pub types: BTreeMap<String, u64>,
+    pub keys_with_ttl: u64,
+    pub ttl_coverage: f64,
+    pub sampled_keys: u64,
+    pub avg_memory: Option<u64>,
+    pub estimated_memory: Option<u64>,
+    pub child_count: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NamespaceLevel {
+    pub node: NamespaceNodeSummary,
+    pub children: Vec<NamespaceNodeSummary>,
+    pub cancelled: bool,
+}
+
+struct KeySample<'a> {
+    name: &'a str,
+    key_type: String,
+    has_ttl: bool,
+    memory: Option<u64>,
+}
+
+impl NamespaceNode {
+    fn record(&mut self, sample: &KeySample) {
+        self.key_count += 1;
+        *self.types.entry(sample.key_type.clone()).or_insert(0) += 1;
+        if sample.has_ttl {
+            self.keys_with_ttl += 1;
+        }
+        if let Some(memory) = sample.memory {
+            self.sampled_keys += 1;
+            self.sampled_memory += memory;
+        }
+    }
+
+    fn summarize(&self, name: &str, path: &[String], delimiter: &str) -> NamespaceNodeSummary {
+        let avg_memory = (self.sampled_keys > 0).then(|| self.sampled_memory / self.sampled_keys);
+
+        let pattern = if path.is_empty() {
+            "*".to_string()
+        } else {
+            let prefix: Vec<String> = path.iter().map(|p| keyspace::glob_escape(p)).collect();
+            format!(
+                "{}{}*",
+                prefix.join(delimiter),
+                keyspace::glob_escape(delimiter)
+            )
+        };
+
+        NamespaceNodeSummary {
+            name: name.to_string(),
+            path: path.to_vec(),
+            pattern,
+            key_count: self.key_count,
+            direct_keys: self.direct_keys,
+            types: self.types.clone(),
+            keys_with_ttl: self.keys_with_ttl,
+            ttl_coverage: if self.key_count > 0 {
+                self.keys_with_ttl as f64 / self.key_count as f64
+            } else {
+                0.0
+            },
+            sampled_keys: self.sampled_keys,
+            avg_memory,
+            estimated_memory: avg_memory.map(|avg| avg * self.key_count),
+            child_count: self.children.len(),
+        }
+    }
+}
+
+impl NamespaceTree {
+    fn insert(&mut self, sample: &KeySample, max_depth: usize) {
+        let mut segments: Vec<&str> = sample.name.split(self.delimiter.as_str()).collect();
+        // The last segment is the key's own name, not a namespace
+        segments.pop();
+
+        let mut node = &mut self.root;
+        node.record(sample);
+        for segment in segments.into_iter().take(max_depth) {
+            node = node.children.entry(segment.to_string()).or_default();
+            node.record(sample);
+        }
+        node.direct_keys += 1;
+    }
+
+    // Summary of the node at `path` plus its children, largest first
+    pub fn level(&self, path: &[String], limit: Option<usize>) -> Option<NamespaceLevel> {
+        let mut node = &self.root;
+        for segment in path {
+            node = node.children.get(segment)?;
+        }
+
+        let name = path.last().cloned().unwrap_or_default();
+        let mut children: Vec<NamespaceNodeSummary> = node
+            .children
+            .iter()
+            .map(|(child_name, child)| {
+                let mut child_path = path.to_vec();
+                child_path.push(child_name.clone());
+                child.summarize(child_name, &child_path, &self.delimiter)
+            })
+            .collect();
+        children.sort_by(|a, b| b.key_count.cmp(&a.key_count).then(a.name.cmp(&b.name)));
+        if let Some(limit) = limit {
+            children.truncate(limit);
+        }
+
+        Some(NamespaceLevel {
+            node: node.summarize(&name, path, &self.delimiter),
+            children,
+            cancelled: self.cancelled,
+        })
+    }
+}
+
+// Scan the keyspace and aggregate key counts, types, TTL coverage and
+// sampled memory usage per prefix. Each SCAN page costs one pipelined
+// round-trip for TYPE/PTTL (and MEMORY USAGE on sampled keys).
+pub fn build_namespace_tree(
+    conn: &mut dyn ConnectionLike,
+    options: &NamespaceOptions,
+    cancel: &CancelToken,
+    mut on_progress: impl FnMut(u64, u64),
+) -> RedisResult<NamespaceTree> {
+    let total = keyspace::dbsize(conn);
+    let sample_every = if options.memory_sample_rate > 0.0 {
+        (1.0 / options.memory_sample_rate.min(1.0)).round().max(1.0) as u64
+    } else {
+        0 // Memory sampling disabled
+    };
+
+    let mut tree = NamespaceTree {
+        root: NamespaceNode::default(),
+        delimiter: options.delimiter.clone(),
+        cancelled: false,
+    };
+    let mut scanner = KeyScanner::new(conn, &options.pattern, keyspace::SCAN_COUNT, None);
+    let mut key_index: u64 = 0;
+
+    while let Some(batch) = scanner.next_batch(conn)? {
+        if cancel.is_cancelled() {
+            tree.cancelled = true;
+            break;
+        }
+
+        let mut pipe = redis::pipe();
+        let mut command_count = 0;
+        let mut sampled = Vec::with_capacity(batch.len());
+        for key in &batch {
+            let sample = sample_every > 0 && key_index.is_multiple_of(sample_every);
+            key_index += 1;
+
+            pipe.cmd("TYPE").arg(key);
+            pipe.cmd("PTTL").arg(key);
+            command_count += 2;
+            if sample {
+                pipe.cmd("MEMORY").arg("USAGE").arg(key);
+                command_count += 1;
+            }
+            sampled.push(sample);
+        }
+
+        let replies = keyspace::query_pipeline_lenient(conn, &pipe, command_count)?;
+        let mut replies = replies.iter();
+
+        for (key, sample) in batch.iter().zip(sampled) {
+            let key_type = replies
+                .next()
+                .and_then(keyspace::value_to_string)
+                .unwrap_or_else(|| "none".to_string());
+            let pttl = replies
+                .next()
+                .and_then(keyspace::value_to_i64)
+                .unwrap_or(-2);
+            let memory = if sample {
+                replies
+                    .next()
+                    .and_then(keyspace::value_to_i64)
+                    .map(|m| m as u64)
+            } else {
+                None
+            };
+
+            // Skip keys that expired between SCAN and TYPE
+            if key_type == "none" {
+                continue;
+            }
+
+            tree.insert(
+                &KeySample {
+                    name: &key.to_string_lossy(),
+                    key_type,
+                    has_ttl: pttl >= 0,
+                    memory,
+                },
+                options.max_depth,
+            );
+        }
+
+        on_progress(scanner.scanned_estimate(total), total);
+    }
+
+    Ok(tree)
+}
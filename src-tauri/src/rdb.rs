@@ -0,0 +1,1707 @@
+use std::collections::BTreeMap;
+
+// Oldest and newest RDB format versions the parser understands
+pub const MIN_RDB_VERSION: u32 = 8;
+pub const MAX_RDB_VERSION: u32 = 12;
+
+// Value types
+const TYPE_STRING: u8 = 0;
+const TYPE_LIST: u8 = 1;
+const TYPE_SET: u8 = 2;
+const TYPE_ZSET: u8 = 3;
+const TYPE_HASH: u8 = 4;
+const TYPE_ZSET_2: u8 = 5;
+const TYPE_MODULE_PRE_GA: u8 = 6;
+const TYPE_MODULE_2: u8 = 7;
+const TYPE_HASH_ZIPMAP: u8 = 9;
+const TYPE_LIST_ZIPLIST: u8 = 10;
+const TYPE_SET_INTSET: u8 = 11;
+const TYPE_ZSET_ZIPLIST: u8 = 12;
+const TYPE_HASH_ZIPLIST: u8 = 13;
+const TYPE_LIST_QUICKLIST: u8 = 14;
+const TYPE_STREAM_LISTPACKS: u8 = 15;
+const TYPE_HASH_LISTPACK: u8 = 16;
+const TYPE_ZSET_LISTPACK: u8 = 17;
+const TYPE_LIST_QUICKLIST_2: u8 = 18;
+const TYPE_STREAM_LISTPACKS_2: u8 = 19;
+const TYPE_SET_LISTPACK: u8 = 20;
+const TYPE_STREAM_LISTPACKS_3: u8 = 21;
+const TYPE_HASH_METADATA_PRE_GA: u8 = 22;
+const TYPE_HASH_LISTPACK_EX_PRE_GA: u8 = 23;
+const TYPE_HASH_METADATA: u8 = 24;
+const TYPE_HASH_LISTPACK_EX: u8 = 25;
+
+// Opcodes
+const OPCODE_SLOT_INFO: u8 = 244;
+const OPCODE_FUNCTION2: u8 = 245;
+const OPCODE_FUNCTION_PRE_GA: u8 = 246;
+const OPCODE_MODULE_AUX: u8 = 247;
+const OPCODE_IDLE: u8 = 248;
+const OPCODE_FREQ: u8 = 249;
+const OPCODE_AUX: u8 = 250;
+const OPCODE_RESIZEDB: u8 = 251;
+const OPCODE_EXPIRETIME_MS: u8 = 252;
+const OPCODE_EXPIRETIME: u8 = 253;
+const OPCODE_SELECTDB: u8 = 254;
+const OPCODE_EOF: u8 = 255;
+
+// Module value opcodes
+const MODULE_OPCODE_EOF: u64 = 0;
+const MODULE_OPCODE_SINT: u64 = 1;
+const MODULE_OPCODE_UINT: u64 = 2;
+const MODULE_OPCODE_FLOAT: u64 = 3;
+const MODULE_OPCODE_DOUBLE: u64 = 4;
+const MODULE_OPCODE_STRING: u64 = 5;
+
+// Quicklist 2 node containers
+const QUICKLIST_NODE_PLAIN: u64 = 1;
+const QUICKLIST_NODE_PACKED: u64 = 2;
+
+// Stream listpack entry flags
+const STREAM_ITEM_FLAG_DELETED: i64 = 1;
+const STREAM_ITEM_FLAG_SAMEFIELDS: i64 = 2;
+
+const MODULE_NAME_CHARSET: &[u8] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+pub type StreamId = (u64, u64);
+
+type FieldValuePairs = Vec<(Vec<u8>, Vec<u8>)>;
+
+#[derive(Debug, Clone)]
+pub struct HashField {
+    pub field: Vec<u8>,
+    pub value: Vec<u8>,
+    pub expires_at_ms: Option<i64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct StreamEntry {
+    pub id: StreamId,
+    pub fields: FieldValuePairs,
+}
+
+#[derive(Debug, Clone)]
+pub struct RdbStream {
+    pub entries: Vec<StreamEntry>, // Ordered by ID
+    pub length: u64,
+    pub last_id: StreamId,
+    pub groups: u64,
+}
+
+#[derive(Debug, Clone)]
+pub enum RdbValue {
+    String(Vec<u8>),
+    List(Vec<Vec<u8>>),
+    Set(Vec<Vec<u8>>),
+    SortedSet(Vec<(Vec<u8>, f64)>), // Ordered by score, then member
+    Hash(Vec<HashField>),
+    Stream(RdbStream),
+    Module { type_name: String }, // Payload skipped, only the type is kept
+}
+
+impl RdbValue {
+    // Name reported by TYPE
+    pub fn type_name(&self) -> &str {
+        match self {
+            RdbValue::String(_) => "string",
+            RdbValue::List(_) => "list",
+            RdbValue::Set(_) => "set",
+            RdbValue::SortedSet(_) => "zset",
+            RdbValue::Hash(_) => "hash",
+            RdbValue::Stream(_) => "stream",
+            RdbValue::Module { type_name } => type_name,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RdbEntry {
+    pub key: Vec<u8>,
+    pub value: RdbValue,
+    pub encoding: &'static str, // As reported by OBJECT ENCODING
+    pub expires_at_ms: Option<i64>,
+    pub idle_seconds: Option<u64>,
+    pub lfu_freq: Option<u8>,
+    pub serialized_size: usize, // Bytes used by the key and value in the file
+}
+
+#[derive(Debug, Default)]
+pub struct RdbDatabase {
+    entries: Vec<RdbEntry>, // Sorted by key once loading finishes
+    pub expires: u64,
+}
+
+impl RdbDatabase {
+    pub fn entries(&self) -> &[RdbEntry] {
+        &self.entries
+    }
+
+    pub fn get(&self, key: &[u8]) -> Option<&RdbEntry> {
+        self.entries
+            .binary_search_by(|e| e.key.as_slice().cmp(key))
+            .ok()
+            .map(|i| &self.entries[i])
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+}
+
+#[derive(Debug)]
+pub struct RdbSnapshot {
+    pub path: String,
+    pub version: u32,
+    pub aux: BTreeMap<String, String>,
+    pub databases: BTreeMap<u32, RdbDatabase>,
+    pub skipped_modules: BTreeMap<String, u64>, // Module type name -> keys skipped
+    pub functions: u64,
+    pub file_size: usize,
+}
+
+impl RdbSnapshot {
+    pub fn open(path: &str) -> Result<Self, String> {
+        let data = std::fs::read(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+        let mut snapshot = parse(&data)?.0;
+        snapshot.path = path.to_string();
+        Ok(snapshot)
+    }
+
+    // Unix time in milliseconds at which the snapshot was written, from the
+    // `ctime` aux field
+    pub fn created_at_ms(&self) -> Option<i64> {
+        self.aux
+            .get("ctime")
+            .and_then(|t| t.parse::<i64>().ok())
+            .map(|t| t * 1000)
+    }
+
+    pub fn redis_version(&self) -> Option<&str> {
+        self.aux.get("redis-ver").map(|v| v.as_str())
+    }
+}
+
+// Check the "REDISxxxx" magic without parsing the rest of the file
+pub fn rdb_version(data: &[u8]) -> Option<u32> {
+    if data.len() < 9 || &data[..5] != b"REDIS" {
+        return None;
+    }
+    std::str::from_utf8(&data[5..9]).ok()?.parse().ok()
+}
+
+// Parse a complete RDB payload. Returns the snapshot and the number of bytes
+// consumed, which lets callers find where an AOF's RDB preamble ends.
+pub fn parse(data: &[u8]) -> Result<(RdbSnapshot, usize), String> {
+    let mut reader = Reader::open(data)?;
+    let version = reader.version;
+    let mut snapshot = RdbSnapshot {
+        path: String::new(),
+        version,
+        aux: BTreeMap::new(),
+        databases: BTreeMap::new(),
+        skipped_modules: BTreeMap::new(),
+        functions: 0,
+        file_size: data.len(),
+    };
+
+    reader.records(
+        |key, value| {
+            snapshot.aux.insert(
+                String::from_utf8_lossy(&key).to_string(),
+                String::from_utf8_lossy(&value).to_string(),
+            );
+        },
+        || snapshot.functions += 1,
+        |reader, header| {
+            let key = reader.string()?;
+            let (value, encoding) = reader.value(header.value_type)?;
+
+            if let RdbValue::Module { type_name } = &value {
+                *snapshot
+                    .skipped_modules
+                    .entry(type_name.clone())
+                    .or_insert(0) += 1;
+            }
+
+            let database = snapshot.databases.entry(header.db).or_default();
+            if header.expires_at_ms.is_some() {
+                database.expires += 1;
+            }
+            database.entries.push(RdbEntry {
+                key,
+                value,
+                encoding,
+                expires_at_ms: header.expires_at_ms,
+                idle_seconds: header.idle_seconds,
+                lfu_freq: header.lfu_freq,
+                serialized_size: reader.pos - header.start,
+            });
+            Ok(())
+        },
+    )?;
+
+    for database in snapshot.databases.values_mut() {
+        database.entries.sort_by(|a, b| a.key.cmp(&b.key));
+    }
+
+    Ok((snapshot, reader.pos))
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct RdbSummary {
+    pub version: u32,
+    pub keys: u64,
+    pub bytes: usize, // Bytes up to and including the checksum
+}
+
+// Walk an RDB payload without decoding values, to count keys and find where
+// it ends. Much cheaper than `parse` for an AOF's RDB preamble.
+pub fn scan(data: &[u8]) -> Result<RdbSummary, String> {
+    let mut reader = Reader::open(data)?;
+    let mut keys: u64 = 0;
+    reader.records(
+        |_, _| {},
+        || {},
+        |reader, header| {
+            reader.skip_string()?;
+            reader.skip_value(header.value_type)?;
+            keys += 1;
+            Ok(())
+        },
+    )?;
+
+    Ok(RdbSummary {
+        version: reader.version,
+        keys,
+        bytes: reader.pos,
+    })
+}
+
+// Position and metadata of a key record; the reader is left at its key
+struct KeyHeader {
+    db: u32,
+    value_type: u8,
+    start: usize,
+    expires_at_ms: Option<i64>,
+    idle_seconds: Option<u64>,
+    lfu_freq: Option<u8>,
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    version: u32,
+}
+
+impl<'a> Reader<'a> {
+    fn open(data: &'a [u8]) -> Result<Self, String> {
+        let version = rdb_version(data).ok_or("Not an RDB file (missing REDIS header)")?;
+        if !(MIN_RDB_VERSION..=MAX_RDB_VERSION).contains(&version) {
+            return Err(format!(
+                "Unsupported RDB version {} (supported: {}-{})",
+                version, MIN_RDB_VERSION, MAX_RDB_VERSION
+            ));
+        }
+        Ok(Reader {
+            data,
+            pos: 9,
+            version,
+        })
+    }
+
+    // Read every record up to the EOF opcode. `on_key` must consume the key
+    // and its value.
+    fn records(
+        &mut self,
+        mut on_aux: impl FnMut(Vec<u8>, Vec<u8>),
+        mut on_function: impl FnMut(),
+        mut on_key: impl FnMut(&mut Self, KeyHeader) -> Result<(), String>,
+    ) -> Result<(), String> {
+        let mut db: u32 = 0;
+        let mut expires_at_ms: Option<i64> = None;
+        let mut idle_seconds: Option<u64> = None;
+        let mut lfu_freq: Option<u8> = None;
+
+        loop {
+            let opcode = self.u8()?;
+            match opcode {
+                OPCODE_EOF => {
+                    // CRC64 checksum follows; it is not verified
+                    if self.remaining() >= 8 {
+                        self.pos += 8;
+                    }
+                    return Ok(());
+                }
+                OPCODE_SELECTDB => db = self.length()? as u32,
+                OPCODE_RESIZEDB => {
+                    self.length()?; // Keys
+                    self.length()?; // Expires
+                }
+                OPCODE_EXPIRETIME => expires_at_ms = Some(self.u32_le()? as i64 * 1000),
+                OPCODE_EXPIRETIME_MS => expires_at_ms = Some(self.u64_le()? as i64),
+                OPCODE_IDLE => idle_seconds = Some(self.length()?),
+                OPCODE_FREQ => lfu_freq = Some(self.u8()?),
+                OPCODE_AUX => {
+                    let key = self.string()?;
+                    let value = self.string()?;
+                    on_aux(key, value);
+                }
+                OPCODE_MODULE_AUX => {
+                    self.length()?; // Module id
+                    let when_opcode = self.length()?;
+                    if when_opcode != MODULE_OPCODE_UINT {
+                        return Err(self.error("Invalid module aux header"));
+                    }
+                    self.length()?; // When
+                    self.skip_module_value()?;
+                }
+                OPCODE_FUNCTION_PRE_GA => {
+                    self.skip_string()?; // Name
+                    self.skip_string()?; // Engine
+                    if self.length()? != 0 {
+                        self.skip_string()?; // Description
+                    }
+                    self.skip_string()?; // Code
+                    on_function();
+                }
+                OPCODE_FUNCTION2 => {
+                    self.skip_string()?;
+                    on_function();
+                }
+                OPCODE_SLOT_INFO => {
+                    self.length()?; // Slot id
+                    self.length()?; // Slot size
+                    self.length()?; // Expires slot size
+                }
+                value_type => {
+                    let header = KeyHeader {
+                        db,
+                        value_type,
+                        start: self.pos - 1,
+                        expires_at_ms: expires_at_ms.take(),
+                        idle_seconds: idle_seconds.take(),
+                        lfu_freq: lfu_freq.take(),
+                    };
+                    on_key(self, header)?;
+                }
+            }
+        }
+    }
+
+    fn error(&self, message: &str) -> String {
+        format!("{} at offset {} (RDB v{})", message, self.pos, self.version)
+    }
+
+    fn remaining(&self) -> usize {
+        self.data.len() - self.pos
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8], String> {
+        if self.remaining() < n {
+            return Err(self.error("Unexpected end of file"));
+        }
+        let slice = &self.data[self.pos..self.pos + n];
+        self.pos += n;
+        Ok(slice)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32_le(&mut self) -> Result<u32, String> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64_le(&mut self) -> Result<u64, String> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn f64_le(&mut self) -> Result<f64, String> {
+        Ok(f64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    // Length encoding; the flag is set for the special string encodings
+    fn length_with_flag(&mut self) -> Result<(u64, bool), String> {
+        let first = self.u8()?;
+        match first >> 6 {
+            0 => Ok(((first & 0x3f) as u64, false)),
+            1 => {
+                let second = self.u8()?;
+                Ok(((((first & 0x3f) as u64) << 8) | second as u64, false))
+            }
+            2 => match first {
+                0x80 => Ok((
+                    u32::from_be_bytes(self.take(4)?.try_into().unwrap()) as u64,
+                    false,
+                )),
+                0x81 => Ok((u64::from_be_bytes(self.take(8)?.try_into().unwrap()), false)),
+                _ => Err(self.error("Invalid length encoding")),
+            },
+            _ => Ok(((first & 0x3f) as u64, true)),
+        }
+    }
+
+    fn length(&mut self) -> Result<u64, String> {
+        match self.length_with_flag()? {
+            (len, false) => Ok(len),
+            (_, true) => Err(self.error("Unexpected encoded string")),
+        }
+    }
+
+    fn string(&mut self) -> Result<Vec<u8>, String> {
+        let (len, encoded) = self.length_with_flag()?;
+        if !encoded {
+            return Ok(self.take(len as usize)?.to_vec());
+        }
+
+        match len {
+            0 => Ok((self.u8()? as i8).to_string().into_bytes()),
+            1 => Ok(i16::from_le_bytes(self.take(2)?.try_into().unwrap())
+                .to_string()
+                .into_bytes()),
+            2 => Ok(i32::from_le_bytes(self.take(4)?.try_into().unwrap())
+                .to_string()
+                .into_bytes()),
+            3 => {
+                let compressed_len = self.length()? as usize;
+                let original_len = self.length()? as usize;
+                let compressed = self.take(compressed_len)?;
+                lzf_decompress(compressed, original_len)
+                    .ok_or_else(|| self.error("Corrupt LZF compressed string"))
+            }
+            _ => Err(self.error("Unknown string encoding")),
+        }
+    }
+
+    // Skip a string without decompressing it
+    fn skip_string(&mut self) -> Result<(), String> {
+        let len = match self.length_with_flag()? {
+            (len, false) => len,
+            (0, true) => 1,
+            (1, true) => 2,
+            (2, true) => 4,
+            (3, true) => {
+                let compressed_len = self.length()?;
+                self.length()?; // Original length
+                compressed_len
+            }
+            _ => return Err(self.error("Unknown string encoding")),
+        };
+        self.take(len as usize)?;
+        Ok(())
+    }
+
+    // ZSET (v1) scores are stored as length-prefixed ASCII
+    fn string_double(&mut self) -> Result<f64, String> {
+        match self.u8()? {
+            253 => Ok(f64::NAN),
+            254 => Ok(f64::INFINITY),
+            255 => Ok(f64::NEG_INFINITY),
+            len => {
+                let text = self.take(len as usize)?;
+                std::str::from_utf8(text)
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .ok_or_else(|| self.error("Invalid score"))
+            }
+        }
+    }
+
+    fn value(&mut self, value_type: u8) -> Result<(RdbValue, &'static str), String> {
+        match value_type {
+            TYPE_STRING => {
+                let value = self.string()?;
+                let encoding = string_encoding(&value);
+                Ok((RdbValue::String(value), encoding))
+            }
+            TYPE_LIST => {
+                let len = self.length()?;
+                let items = (0..len).map(|_| self.string()).collect::<Result<_, _>>()?;
+                Ok((RdbValue::List(items), "quicklist"))
+            }
+            TYPE_SET => {
+                let len = self.length()?;
+                let members = (0..len).map(|_| self.string()).collect::<Result<_, _>>()?;
+                Ok((RdbValue::Set(members), "hashtable"))
+            }
+            TYPE_ZSET | TYPE_ZSET_2 => {
+                let len = self.length()?;
+                let mut members = Vec::with_capacity(len.min(1 << 20) as usize);
+                for _ in 0..len {
+                    let member = self.string()?;
+                    let score = if value_type == TYPE_ZSET_2 {
+                        self.f64_le()?
+                    } else {
+                        self.string_double()?
+                    };
+                    members.push((member, score));
+                }
+                Ok((sorted_set(members), "skiplist"))
+            }
+            TYPE_HASH => {
+                let len = self.length()?;
+                let mut fields = Vec::with_capacity(len.min(1 << 20) as usize);
+                for _ in 0..len {
+                    fields.push(HashField {
+                        field: self.string()?,
+                        value: self.string()?,
+                        expires_at_ms: None,
+                    });
+                }
+                Ok((RdbValue::Hash(fields), "hashtable"))
+            }
+            TYPE_HASH_METADATA | TYPE_HASH_METADATA_PRE_GA => {
+                // GA format stores field TTLs relative to the smallest one
+                // (plus one, so 0 still means "no TTL")
+                let min_expire = if value_type == TYPE_HASH_METADATA {
+                    Some(self.u64_le()?.min(i64::MAX as u64) as i64)
+                } else {
+                    None
+                };
+                let len = self.length()?;
+                let mut fields = Vec::with_capacity(len.min(1 << 20) as usize);
+                for _ in 0..len {
+                    let ttl = self.length()? as i64;
+                    let expires_at_ms = match (ttl, min_expire) {
+                        (0, _) => None,
+                        (ttl, Some(min)) => Some(
+                            ttl.checked_add(min - 1)
+                                .ok_or_else(|| self.error("Invalid hash field TTL"))?,
+                        ),
+                        (ttl, None) => Some(ttl),
+                    };
+                    fields.push(HashField {
+                        field: self.string()?,
+                        value: self.string()?,
+                        expires_at_ms,
+                    });
+                }
+                Ok((RdbValue::Hash(fields), "hashtable"))
+            }
+            TYPE_HASH_LISTPACK_EX | TYPE_HASH_LISTPACK_EX_PRE_GA => {
+                if value_type == TYPE_HASH_LISTPACK_EX {
+                    self.u64_le()?; // Smallest field TTL
+                }
+                let blob = self.string()?;
+                let items = self.listpack(&blob)?;
+                let fields = items
+                    .chunks(3)
+                    .filter(|c| c.len() == 3)
+                    .map(|c| {
+                        let ttl = parse_i64(&c[2]).unwrap_or(0);
+                        HashField {
+                            field: c[0].clone(),
+                            value: c[1].clone(),
+                            expires_at_ms: (ttl > 0).then_some(ttl),
+                        }
+                    })
+                    .collect();
+                Ok((RdbValue::Hash(fields), "listpackex"))
+            }
+            TYPE_HASH_ZIPMAP => {
+                let blob = self.string()?;
+                let fields = self
+                    .zipmap(&blob)?
+                    .into_iter()
+                    .map(|(field, value)| HashField {
+                        field,
+                        value,
+                        expires_at_ms: None,
+                    })
+                    .collect();
+                Ok((RdbValue::Hash(fields), "ziplist"))
+            }
+            TYPE_LIST_ZIPLIST => {
+                let blob = self.string()?;
+                Ok((RdbValue::List(self.ziplist(&blob)?), "ziplist"))
+            }
+            TYPE_SET_INTSET => {
+                let blob = self.string()?;
+                Ok((RdbValue::Set(self.intset(&blob)?), "intset"))
+            }
+            TYPE_SET_LISTPACK => {
+                let blob = self.string()?;
+                Ok((RdbValue::Set(self.listpack(&blob)?), "listpack"))
+            }
+            TYPE_ZSET_ZIPLIST | TYPE_ZSET_LISTPACK => {
+                let blob = self.string()?;
+                let (items, encoding) = if value_type == TYPE_ZSET_ZIPLIST {
+                    (self.ziplist(&blob)?, "ziplist")
+                } else {
+                    (self.listpack(&blob)?, "listpack")
+                };
+                let members = items
+                    .chunks(2)
+                    .filter(|c| c.len() == 2)
+                    .map(|c| (c[0].clone(), parse_f64(&c[1]).unwrap_or(0.0)))
+                    .collect();
+                Ok((sorted_set(members), encoding))
+            }
+            TYPE_HASH_ZIPLIST | TYPE_HASH_LISTPACK => {
+                let blob = self.string()?;
+                let (items, encoding) = if value_type == TYPE_HASH_ZIPLIST {
+                    (self.ziplist(&blob)?, "ziplist")
+                } else {
+                    (self.listpack(&blob)?, "listpack")
+                };
+                let fields = items
+                    .chunks(2)
+                    .filter(|c| c.len() == 2)
+                    .map(|c| HashField {
+                        field: c[0].clone(),
+                        value: c[1].clone(),
+                        expires_at_ms: None,
+                    })
+                    .collect();
+                Ok((RdbValue::Hash(fields), encoding))
+            }
+            TYPE_LIST_QUICKLIST => {
+                let nodes = self.length()?;
+                let mut items = Vec::new();
+                for _ in 0..nodes {
+                    let blob = self.string()?;
+                    items.extend(self.ziplist(&blob)?);
+                }
+                Ok((RdbValue::List(items), "quicklist"))
+            }
+            TYPE_LIST_QUICKLIST_2 => {
+                let nodes = self.length()?;
+                let mut items = Vec::new();
+                for _ in 0..nodes {
+                    let container = self.length()?;
+                    let blob = self.string()?;
+                    match container {
+                        QUICKLIST_NODE_PLAIN => items.push(blob),
+                        QUICKLIST_NODE_PACKED => items.extend(self.listpack(&blob)?),
+                        _ => return Err(self.error("Unknown quicklist container")),
+                    }
+                }
+                // Small lists are converted back to a listpack on load
+                let encoding = if nodes <= 1 { "listpack" } else { "quicklist" };
+                Ok((RdbValue::List(items), encoding))
+            }
+            TYPE_STREAM_LISTPACKS | TYPE_STREAM_LISTPACKS_2 | TYPE_STREAM_LISTPACKS_3 => {
+                Ok((RdbValue::Stream(self.stream(value_type)?), "stream"))
+            }
+            TYPE_MODULE_2 => {
+                let module_id = self.length()?;
+                self.skip_module_value()?;
+                Ok((
+                    RdbValue::Module {
+                        type_name: module_type_name(module_id),
+                    },
+                    "module",
+                ))
+            }
+            TYPE_MODULE_PRE_GA => Err(self
+                .error("Module values in the pre-GA format cannot be skipped without the module")),
+            other => Err(self.error(&format!("Unknown value type {}", other))),
+        }
+    }
+
+    // Skip a value without decoding it. Compact encodings are a single
+    // string, so only the container types need walking.
+    fn skip_value(&mut self, value_type: u8) -> Result<(), String> {
+        match value_type {
+            TYPE_STRING | TYPE_HASH_ZIPMAP | TYPE_LIST_ZIPLIST | TYPE_SET_INTSET
+            | TYPE_SET_LISTPACK | TYPE_ZSET_ZIPLIST | TYPE_ZSET_LISTPACK | TYPE_HASH_ZIPLIST
+            | TYPE_HASH_LISTPACK => self.skip_string(),
+            TYPE_LIST | TYPE_SET | TYPE_LIST_QUICKLIST => {
+                for _ in 0..self.length()? {
+                    self.skip_string()?;
+                }
+                Ok(())
+            }
+            TYPE_ZSET | TYPE_ZSET_2 => {
+                for _ in 0..self.length()? {
+                    self.skip_string()?;
+                    if value_type == TYPE_ZSET_2 {
+                        self.take(8)?;
+                    } else {
+                        self.string_double()?;
+                    }
+                }
+                Ok(())
+            }
+            TYPE_HASH => {
+                for _ in 0..self.length()? {
+                    self.skip_string()?;
+                    self.skip_string()?;
+                }
+                Ok(())
+            }
+            TYPE_HASH_METADATA | TYPE_HASH_METADATA_PRE_GA => {
+                if value_type == TYPE_HASH_METADATA {
+                    self.take(8)?; // Smallest field TTL
+                }
+                for _ in 0..self.length()? {
+                    self.length()?; // TTL
+                    self.skip_string()?;
+                    self.skip_string()?;
+                }
+                Ok(())
+            }
+            TYPE_HASH_LISTPACK_EX | TYPE_HASH_LISTPACK_EX_PRE_GA => {
+                if value_type == TYPE_HASH_LISTPACK_EX {
+                    self.take(8)?; // Smallest field TTL
+                }
+                self.skip_string()
+            }
+            TYPE_LIST_QUICKLIST_2 => {
+                for _ in 0..self.length()? {
+                    self.length()?; // Container
+                    self.skip_string()?;
+                }
+                Ok(())
+            }
+            TYPE_STREAM_LISTPACKS | TYPE_STREAM_LISTPACKS_2 | TYPE_STREAM_LISTPACKS_3 => {
+                for _ in 0..self.length()? {
+                    self.skip_string()?; // Master id
+                    self.skip_string()?; // Listpack
+                }
+                self.stream_metadata(value_type)?;
+                Ok(())
+            }
+            TYPE_MODULE_2 => {
+                self.length()?; // Module id
+                self.skip_module_value()
+            }
+            // Unsupported types fail the same way they do when decoding
+            other => self.value(other).map(drop),
+        }
+    }
+
+    // Module values are self-describing since MODULE_2: a sequence of typed
+    // fields terminated by an EOF opcode
+    fn skip_module_value(&mut self) -> Result<(), String> {
+        loop {
+            match self.length()? {
+                MODULE_OPCODE_EOF => return Ok(()),
+                MODULE_OPCODE_SINT | MODULE_OPCODE_UINT => {
+                    self.length()?;
+                }
+                MODULE_OPCODE_FLOAT => {
+                    self.take(4)?;
+                }
+                MODULE_OPCODE_DOUBLE => {
+                    self.take(8)?;
+                }
+                MODULE_OPCODE_STRING => {
+                    self.skip_string()?;
+                }
+                _ => return Err(self.error("Unknown module opcode")),
+            }
+        }
+    }
+
+    fn stream(&mut self, value_type: u8) -> Result<RdbStream, String> {
+        let mut entries = Vec::new();
+
+        let nodes = self.length()?;
+        for _ in 0..nodes {
+            let master_key = self.string()?;
+            let blob = self.string()?;
+            if master_key.len() != 16 {
+                return Err(self.error("Invalid stream node key"));
+            }
+            let master_id = (
+                u64::from_be_bytes(master_key[..8].try_into().unwrap()),
+                u64::from_be_bytes(master_key[8..].try_into().unwrap()),
+            );
+            let items = self.listpack(&blob)?;
+            entries.extend(
+                stream_node_entries(master_id, &items)
+                    .ok_or_else(|| self.error("Corrupt stream listpack"))?,
+            );
+        }
+
+        let (length, last_id, groups) = self.stream_metadata(value_type)?;
+        Ok(RdbStream {
+            entries,
+            length,
+            last_id,
+            groups,
+        })
+    }
+
+    // Length, last ID and number of consumer groups, which follow the nodes
+    fn stream_metadata(&mut self, value_type: u8) -> Result<(u64, StreamId, u64), String> {
+        let length = self.length()?;
+        let last_id = (self.length()?, self.length()?);
+        if value_type >= TYPE_STREAM_LISTPACKS_2 {
+            self.length()?; // First id ms
+            self.length()?; // First id seq
+            self.length()?; // Max deleted id ms
+            self.length()?; // Max deleted id seq
+            self.length()?; // Entries added
+        }
+
+        let groups = self.length()?;
+        for _ in 0..groups {
+            self.string()?; // Name
+            self.length()?; // Last id ms
+            self.length()?; // Last id seq
+            if value_type >= TYPE_STREAM_LISTPACKS_2 {
+                self.length()?; // Entries read
+            }
+
+            let pending = self.length()?;
+            for _ in 0..pending {
+                self.take(16)?; // Id
+                self.take(8)?; // Delivery time
+                self.length()?; // Delivery count
+            }
+
+            let consumers = self.length()?;
+            for _ in 0..consumers {
+                self.string()?; // Name
+                self.take(8)?; // Seen time
+                if value_type >= TYPE_STREAM_LISTPACKS_3 {
+                    self.take(8)?; // Active time
+                }
+                let consumer_pending = self.length()?;
+                let ids = usize::try_from(consumer_pending)
+                    .ok()
+                    .and_then(|n| n.checked_mul(16))
+                    .ok_or_else(|| self.error("Invalid consumer PEL length"))?;
+                self.take(ids)?;
+            }
+        }
+
+        Ok((length, last_id, groups))
+    }
+
+    fn ziplist(&self, blob: &[u8]) -> Result<Vec<Vec<u8>>, String> {
+        decode_ziplist(blob).ok_or_else(|| self.error("Corrupt ziplist"))
+    }
+
+    fn listpack(&self, blob: &[u8]) -> Result<Vec<Vec<u8>>, String> {
+        decode_listpack(blob).ok_or_else(|| self.error("Corrupt listpack"))
+    }
+
+    fn intset(&self, blob: &[u8]) -> Result<Vec<Vec<u8>>, String> {
+        decode_intset(blob).ok_or_else(|| self.error("Corrupt intset"))
+    }
+
+    fn zipmap(&self, blob: &[u8]) -> Result<FieldValuePairs, String> {
+        decode_zipmap(blob).ok_or_else(|| self.error("Corrupt zipmap"))
+    }
+}
+
+fn string_encoding(value: &[u8]) -> &'static str {
+    if value.len() <= 20 && parse_i64(value).is_some() {
+        "int"
+    } else if value.len() <= 44 {
+        "embstr"
+    } else {
+        "raw"
+    }
+}
+
+fn sorted_set(mut members: Vec<(Vec<u8>, f64)>) -> RdbValue {
+    members.sort_by(|a, b| a.1.total_cmp(&b.1).then_with(|| a.0.cmp(&b.0)));
+    RdbValue::SortedSet(members)
+}
+
+fn parse_i64(bytes: &[u8]) -> Option<i64> {
+    std::str::from_utf8(bytes).ok()?.parse().ok()
+}
+
+fn parse_f64(bytes: &[u8]) -> Option<f64> {
+    std::str::from_utf8(bytes).ok()?.parse().ok()
+}
+
+// Module type names are packed into the top 54 bits of the module id as
+// nine 6-bit characters; the low 10 bits hold the encoding version
+fn module_type_name(module_id: u64) -> String {
+    (0..9)
+        .map(|i| MODULE_NAME_CHARSET[((module_id >> (64 - 6 * (i + 1))) & 63) as usize] as char)
+        .collect()
+}
+
+pub fn lzf_decompress(input: &[u8], expected_len: usize) -> Option<Vec<u8>> {
+    // The length comes from the file; don't trust it for the allocation
+    let mut output = Vec::with_capacity(expected_len.min(1 << 20));
+    let mut i = 0;
+
+    while i < input.len() {
+        let ctrl = input[i] as usize;
+        i += 1;
+
+        if ctrl < 32 {
+            // Literal run of ctrl + 1 bytes
+            let run = ctrl + 1;
+            output.extend_from_slice(input.get(i..i + run)?);
+            i += run;
+        } else {
+            // Back reference
+            let mut len = ctrl >> 5;
+            if len == 7 {
+                len += *input.get(i)? as usize;
+                i += 1;
+            }
+            let offset = ((ctrl & 0x1f) << 8) + *input.get(i)? as usize + 1;
+            i += 1;
+
+            let start = output.len().checked_sub(offset)?;
+            // The reference may overlap the bytes being written
+            for j in 0..len + 2 {
+                output.push(output[start + j]);
+            }
+        }
+        if output.len() > expected_len {
+            return None;
+        }
+    }
+
+    (output.len() == expected_len).then_some(output)
+}
+
+fn decode_ziplist(blob: &[u8]) -> Option<Vec<Vec<u8>>> {
+    let count = u16::from_le_bytes(blob.get(8..10)?.try_into().ok()?) as usize;
+    let mut items = Vec::with_capacity(count);
+    let mut i = 10;
+
+    loop {
+        let first = *blob.get(i)?;
+        if first == 0xff {
+            break;
+        }
+        // Previous entry length: 1 byte, or 0xFE followed by 4 bytes
+        i += if first == 0xfe { 5 } else { 1 };
+
+        let encoding = *blob.get(i)?;
+        i += 1;
+        let item = match encoding >> 6 {
+            0 => {
+                let len = (encoding & 0x3f) as usize;
+                let item = blob.get(i..i + len)?.to_vec();
+                i += len;
+                item
+            }
+            1 => {
+                let len = (((encoding & 0x3f) as usize) << 8) | *blob.get(i)? as usize;
+                i += 1;
+                let item = blob.get(i..i + len)?.to_vec();
+                i += len;
+                item
+            }
+            2 => {
+                let len = u32::from_be_bytes(blob.get(i..i + 4)?.try_into().ok()?) as usize;
+                i += 4;
+                let item = blob.get(i..i + len)?.to_vec();
+                i += len;
+                item
+            }
+            _ => {
+                let (value, size) = match encoding {
+                    0xc0 => (
+                        i16::from_le_bytes(blob.get(i..i + 2)?.try_into().ok()?) as i64,
+                        2,
+                    ),
+                    0xd0 => (
+                        i32::from_le_bytes(blob.get(i..i + 4)?.try_into().ok()?) as i64,
+                        4,
+                    ),
+                    0xe0 => (i64::from_le_bytes(blob.get(i..i + 8)?.try_into().ok()?), 8),
+                    0xf0 => {
+                        let b = blob.get(i..i + 3)?;
+                        // Sign-extend 24 bits
+                        let v = i32::from_le_bytes([0, b[0], b[1], b[2]]) >> 8;
+                        (v as i64, 3)
+                    }
+                    0xfe => (*blob.get(i)? as i8 as i64, 1),
+                    0xf1..=0xfd => ((encoding & 0x0f) as i64 - 1, 0),
+                    _ => return None,
+                };
+                i += size;
+                value.to_string().into_bytes()
+            }
+        };
+        items.push(item);
+    }
+
+    Some(items)
+}
+
+fn decode_listpack(blob: &[u8]) -> Option<Vec<Vec<u8>>> {
+    let mut items = Vec::new();
+    let mut i = 6; // Total bytes (4) + element count (2)
+
+    loop {
+        let encoding = *blob.get(i)?;
+        if encoding == 0xff {
+            break;
+        }
+        let start = i;
+        i += 1;
+
+        let item = if encoding & 0x80 == 0 {
+            // 7-bit unsigned integer
+            (encoding & 0x7f).to_string().into_bytes()
+        } else if encoding & 0xc0 == 0x80 {
+            // 6-bit length string
+            let len = (encoding & 0x3f) as usize;
+            let item = blob.get(i..i + len)?.to_vec();
+            i += len;
+            item
+        } else if encoding & 0xe0 == 0xc0 {
+            // 13-bit signed integer
+            let raw = (((encoding & 0x1f) as i64) << 8) | *blob.get(i)? as i64;
+            i += 1;
+            let value = if raw >= 1 << 12 { raw - (1 << 13) } else { raw };
+            value.to_string().into_bytes()
+        } else if encoding & 0xf0 == 0xe0 {
+            // 12-bit length string
+            let len = (((encoding & 0x0f) as usize) << 8) | *blob.get(i)? as usize;
+            i += 1;
+            let item = blob.get(i..i + len)?.to_vec();
+            i += len;
+            item
+        } else {
+            match encoding {
+                0xf0 => {
+                    let len = u32::from_le_bytes(blob.get(i..i + 4)?.try_into().ok()?) as usize;
+                    i += 4;
+                    let item = blob.get(i..i + len)?.to_vec();
+                    i += len;
+                    item
+                }
+                0xf1 => {
+                    let v = i16::from_le_bytes(blob.get(i..i + 2)?.try_into().ok()?);
+                    i += 2;
+                    v.to_string().into_bytes()
+                }
+                0xf2 => {
+                    let b = blob.get(i..i + 3)?;
+                    i += 3;
+                    (i32::from_le_bytes([0, b[0], b[1], b[2]]) >> 8)
+                        .to_string()
+                        .into_bytes()
+                }
+                0xf3 => {
+                    let v = i32::from_le_bytes(blob.get(i..i + 4)?.try_into().ok()?);
+                    i += 4;
+                    v.to_string().into_bytes()
+                }
+                0xf4 => {
+                    let v = i64::from_le_bytes(blob.get(i..i + 8)?.try_into().ok()?);
+                    i += 8;
+                    v.to_string().into_bytes()
+                }
+                _ => return None,
+            }
+        };
+
+        // Skip the backlen, which grows with the size of the entry
+        let entry_len = i - start;
+        i += match entry_len {
+            0..=127 => 1,
+            128..=16382 => 2,
+            16383..=2097150 => 3,
+            2097151..=268435454 => 4,
+            _ => 5,
+        };
+        items.push(item);
+    }
+
+    Some(items)
+}
+
+fn decode_intset(blob: &[u8]) -> Option<Vec<Vec<u8>>> {
+    let width = u32::from_le_bytes(blob.get(0..4)?.try_into().ok()?) as usize;
+    if !matches!(width, 2 | 4 | 8) {
+        return None;
+    }
+    let count = u32::from_le_bytes(blob.get(4..8)?.try_into().ok()?) as usize;
+    let body = blob.get(8..8usize.checked_add(width.checked_mul(count)?)?)?;
+
+    body.chunks(width)
+        .map(|c| {
+            let value = match width {
+                2 => i16::from_le_bytes(c.try_into().ok()?) as i64,
+                4 => i32::from_le_bytes(c.try_into().ok()?) as i64,
+                8 => i64::from_le_bytes(c.try_into().ok()?),
+                _ => return None,
+            };
+            Some(value.to_string().into_bytes())
+        })
+        .collect()
+}
+
+// Pre-RDB-v4 hash encoding; still accepted by current servers
+fn decode_zipmap(blob: &[u8]) -> Option<FieldValuePairs> {
+    fn read_len(blob: &[u8], i: &mut usize) -> Option<Option<usize>> {
+        let first = *blob.get(*i)?;
+        *i += 1;
+        match first {
+            0xff => Some(None),
+            254 => {
+                let len = u32::from_le_bytes(blob.get(*i..*i + 4)?.try_into().ok()?);
+                *i += 4;
+                Some(Some(len as usize))
+            }
+            len => Some(Some(len as usize)),
+        }
+    }
+
+    let mut pairs = Vec::new();
+    let mut i = 1; // Skip the length byte
+    while let Some(field_len) = read_len(blob, &mut i)? {
+        let field = blob.get(i..i + field_len)?.to_vec();
+        i += field_len;
+        let value_len = read_len(blob, &mut i)??;
+        let free = *blob.get(i)? as usize;
+        i += 1;
+        let value = blob.get(i..i + value_len)?.to_vec();
+        i += value_len + free;
+        pairs.push((field, value));
+    }
+    Some(pairs)
+}
+
+// A stream node is a listpack holding a master entry (the field names shared
+// by most entries) followed by entries stored as deltas from the node's ID
+fn stream_node_entries(master_id: StreamId, items: &[Vec<u8>]) -> Option<Vec<StreamEntry>> {
+    let int = |i: usize| -> Option<i64> { parse_i64(items.get(i)?) };
+    // Counts must fit in the listpack, which also rules out negative values
+    let count_at =
+        |i: usize| -> Option<usize> { usize::try_from(int(i)?).ok().filter(|n| *n <= items.len()) };
+
+    let mut i = 0;
+    let count = count_at(i)?;
+    let deleted = count_at(i + 1)?;
+    let master_field_count = count_at(i + 2)?;
+    i += 3;
+    let master_fields: Vec<&Vec<u8>> = items.get(i..i + master_field_count)?.iter().collect();
+    i += master_field_count + 1; // Master entry terminator
+
+    let mut entries = Vec::with_capacity(count);
+    for _ in 0..count + deleted {
+        let flags = int(i)?;
+        let id = (
+            master_id.0.wrapping_add(int(i + 1)? as u64),
+            master_id.1.wrapping_add(int(i + 2)? as u64),
+        );
+        i += 3;
+
+        let fields: FieldValuePairs = if flags & STREAM_ITEM_FLAG_SAMEFIELDS != 0 {
+            let values = items.get(i..i + master_field_count)?;
+            i += master_field_count;
+            master_fields
+                .iter()
+                .zip(values)
+                .map(|(f, v)| ((*f).clone(), v.clone()))
+                .collect()
+        } else {
+            let field_count = count_at(i)?;
+            i += 1;
+            let pairs = items.get(i..i + field_count * 2)?;
+            i += field_count * 2;
+            pairs
+                .chunks(2)
+                .map(|c| (c[0].clone(), c[1].clone()))
+                .collect()
+        };
+        i += 1; // lp-count
+
+        if flags & STREAM_ITEM_FLAG_DELETED == 0 {
+            entries.push(StreamEntry { id, fields });
+        }
+    }
+
+    Some(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // RDB length encoding
+    fn len(n: usize) -> Vec<u8> {
+        match n {
+            0..=63 => vec![n as u8],
+            64..=16383 => vec![0x40 | (n >> 8) as u8, n as u8],
+            _ => [vec![0x80], (n as u32).to_be_bytes().to_vec()].concat(),
+        }
+    }
+
+    fn string(bytes: &[u8]) -> Vec<u8> {
+        [len(bytes.len()), bytes.to_vec()].concat()
+    }
+
+    fn rdb(version: u32, body: &[u8]) -> Vec<u8> {
+        [
+            format!("REDIS{:04}", version).into_bytes(),
+            body.to_vec(),
+            vec![OPCODE_EOF],
+            vec![0; 8], // Checksum
+        ]
+        .concat()
+    }
+
+    // Listpack with every item stored as a string
+    fn listpack(items: &[&[u8]]) -> Vec<u8> {
+        let mut body = Vec::new();
+        for item in items {
+            let entry = if item.len() < 64 {
+                [vec![0x80 | item.len() as u8], item.to_vec()].concat()
+            } else {
+                let n = item.len();
+                [vec![0xe0 | (n >> 8) as u8, n as u8], item.to_vec()].concat()
+            };
+            let backlen = if entry.len() < 128 { 1 } else { 2 };
+            body.extend_from_slice(&entry);
+            body.extend(std::iter::repeat_n(0, backlen));
+        }
+        let total = 6 + body.len() + 1;
+        [
+            (total as u32).to_le_bytes().to_vec(),
+            (items.len() as u16).to_le_bytes().to_vec(),
+            body,
+            vec![0xff],
+        ]
+        .concat()
+    }
+
+    fn single_key(value_type: u8, value: &[u8]) -> Result<RdbEntry, String> {
+        let body = [vec![value_type], string(b"key"), value.to_vec()].concat();
+        let (snapshot, _) = parse(&rdb(11, &body))?;
+        Ok(snapshot.databases[&0].entries()[0].clone())
+    }
+
+    fn strings(items: &[&str]) -> Vec<Vec<u8>> {
+        items.iter().map(|s| s.as_bytes().to_vec()).collect()
+    }
+
+    #[test]
+    fn lzf_literals_and_back_references() {
+        assert_eq!(lzf_decompress(b"\x02abc", 3).unwrap(), b"abc");
+        // "abc" then an overlapping 6-byte reference 3 bytes back
+        assert_eq!(lzf_decompress(b"\x02abc\x80\x02", 9).unwrap(), b"abcabcabc");
+        // Long reference: 7 + 3 extra + 2 = 12 bytes
+        assert_eq!(
+            lzf_decompress(b"\x00a\xe0\x03\x00", 13).unwrap(),
+            vec![b'a'; 13]
+        );
+    }
+
+    #[test]
+    fn lzf_rejects_corrupt_input() {
+        assert!(lzf_decompress(b"\x02abc", 4).is_none()); // Length mismatch
+        assert!(lzf_decompress(b"\x05abc", 6).is_none()); // Truncated literal
+        assert!(lzf_decompress(b"\x00a\x20\x05", 4).is_none()); // Reference before start
+        assert!(lzf_decompress(b"\x00a\xe0", 13).is_none()); // Truncated reference
+        assert!(lzf_decompress(b"\x00a\xe0\xff\x00", 2).is_none()); // Longer than declared
+    }
+
+    #[test]
+    fn listpack_encodings() {
+        let mut blob = vec![0; 6];
+        blob.extend_from_slice(&[0x05, 1]); // 7-bit uint
+        blob.extend_from_slice(&[0x83, b'f', b'o', b'o', 4]); // 6-bit string
+        blob.extend_from_slice(&[0xdf, 0x9c, 2]); // 13-bit int -100
+        blob.extend_from_slice(&[0xf1, 0x39, 0x30, 3]); // 16-bit int
+        blob.extend_from_slice(&[0xf2, 0xff, 0xff, 0xff, 4]); // 24-bit int
+        blob.push(0xf3);
+        blob.extend_from_slice(&(-70000i32).to_le_bytes());
+        blob.push(5);
+        blob.push(0xf4);
+        blob.extend_from_slice(&i64::MIN.to_le_bytes());
+        blob.push(9);
+        blob.extend_from_slice(&[0xe0, 100]); // 12-bit string
+        blob.extend_from_slice(&[b'x'; 100]);
+        blob.push(102);
+        blob.push(0xff);
+
+        let items = decode_listpack(&blob).unwrap();
+        let long = "x".repeat(100);
+        let min = i64::MIN.to_string();
+        assert_eq!(
+            items,
+            strings(&["5", "foo", "-100", "12345", "-1", "-70000", &min, &long])
+        );
+    }
+
+    #[test]
+    fn listpack_rejects_corrupt_input() {
+        let blob = listpack(&[b"a", b"b"]);
+        assert!(decode_listpack(&blob[..blob.len() - 1]).is_none()); // No terminator
+        assert!(decode_listpack(&[0, 0, 0, 0, 0, 0, 0xf5, 0xff]).is_none()); // Bad encoding
+        assert!(decode_listpack(&[0, 0, 0, 0, 0, 0, 0x85, b'a']).is_none()); // Short string
+        assert!(decode_listpack(&[0, 0]).is_none());
+    }
+
+    #[test]
+    fn ziplist_encodings() {
+        let mut blob = vec![0; 8];
+        blob.extend_from_slice(&6u16.to_le_bytes());
+        blob.extend_from_slice(&[0x00, 0x02, b'a', b'b']); // 6-bit string
+        blob.extend_from_slice(&[0x04, 0xc0, 0x39, 0x30]); // int16
+        blob.extend_from_slice(&[0x04, 0xf3]); // Immediate 2
+        blob.extend_from_slice(&[0x02, 0xf0, 0xff, 0xff, 0xff]); // int24 -1
+        blob.extend_from_slice(&[0x05, 0xfe, 0x80]); // int8 -128
+                                                     // 0xFE previous length prefix, then a 14-bit length string
+        blob.extend_from_slice(&[0xfe, 3, 0, 0, 0, 0x40, 70]);
+        blob.extend_from_slice(&[b'y'; 70]);
+        blob.push(0xff);
+
+        let items = decode_ziplist(&blob).unwrap();
+        let long = "y".repeat(70);
+        assert_eq!(items, strings(&["ab", "12345", "2", "-1", "-128", &long]));
+    }
+
+    #[test]
+    fn ziplist_rejects_corrupt_input() {
+        let mut blob = vec![0; 10];
+        blob.extend_from_slice(&[0x00, 0xc1, 0xff]); // Unknown int encoding
+        assert!(decode_ziplist(&blob).is_none());
+
+        let mut blob = vec![0; 10];
+        blob.extend_from_slice(&[0x00, 0x05, b'a', b'b']); // Short string
+        assert!(decode_ziplist(&blob).is_none());
+        assert!(decode_ziplist(&[0; 4]).is_none());
+    }
+
+    fn intset(width: u32, values: &[i64]) -> Vec<u8> {
+        let mut blob = [width.to_le_bytes(), (values.len() as u32).to_le_bytes()].concat();
+        for v in values {
+            blob.extend_from_slice(&v.to_le_bytes()[..width as usize]);
+        }
+        blob
+    }
+
+    #[test]
+    fn intset_widths() {
+        assert_eq!(
+            decode_intset(&intset(2, &[-2, 1, 300])).unwrap(),
+            strings(&["-2", "1", "300"])
+        );
+        assert_eq!(
+            decode_intset(&intset(4, &[-70000, 70000])).unwrap(),
+            strings(&["-70000", "70000"])
+        );
+        let max = i64::MAX.to_string();
+        assert_eq!(
+            decode_intset(&intset(8, &[i64::MAX])).unwrap(),
+            strings(&[&max])
+        );
+    }
+
+    #[test]
+    fn intset_rejects_corrupt_input() {
+        // Width 0 used to panic in chunks()
+        assert!(decode_intset(&[0, 0, 0, 0, 1, 0, 0, 0]).is_none());
+        assert!(decode_intset(&[3, 0, 0, 0, 1, 0, 0, 0, 1, 2, 3]).is_none());
+        assert!(decode_intset(&[8, 0, 0, 0, 0xff, 0xff, 0xff, 0xff]).is_none());
+        let blob = intset(4, &[1, 2]);
+        assert!(decode_intset(&blob[..blob.len() - 1]).is_none());
+
+        let entry = single_key(TYPE_SET_INTSET, &string(&[0, 0, 0, 0, 1, 0, 0, 0]));
+        assert!(entry.unwrap_err().contains("Corrupt intset"));
+    }
+
+    #[test]
+    fn zipmap_pairs() {
+        let mut blob = vec![2];
+        blob.extend_from_slice(&[1, b'a', 3, 2, b'x', b'y', b'z', 0, 0]); // Two free bytes
+        blob.extend_from_slice(&[2, b'b', b'c', 0, 0]);
+        blob.push(0xff);
+        assert_eq!(
+            decode_zipmap(&blob).unwrap(),
+            vec![
+                (b"a".to_vec(), b"xyz".to_vec()),
+                (b"bc".to_vec(), Vec::new())
+            ]
+        );
+        assert!(decode_zipmap(&blob[..blob.len() - 1]).is_none());
+        assert!(decode_zipmap(&[1, 1, b'a', 0xff]).is_none()); // Field without a value
+    }
+
+    #[test]
+    fn string_encodings() {
+        let body = [
+            vec![OPCODE_AUX],
+            string(b"redis-ver"),
+            string(b"7.2.4"),
+            vec![OPCODE_SELECTDB, 2, OPCODE_RESIZEDB, 3, 1],
+            vec![OPCODE_EXPIRETIME_MS],
+            1_700_000_000_000i64.to_le_bytes().to_vec(),
+            vec![TYPE_STRING],
+            string(b"a"),
+            string(b"plain"),
+            vec![TYPE_STRING],
+            string(b"b"),
+            vec![0xc1, 0x39, 0x30], // int16
+            vec![TYPE_STRING],
+            string(b"c"),
+            vec![0xc3, 6, 9, 0x02, b'a', b'b', b'c', 0x80, 0x02], // LZF
+        ]
+        .concat();
+        let (snapshot, consumed) = parse(&rdb(11, &body)).unwrap();
+        let db = &snapshot.databases[&2];
+
+        assert_eq!(consumed, 9 + body.len() + 9);
+        assert_eq!(snapshot.redis_version(), Some("7.2.4"));
+        assert_eq!(db.len(), 3);
+        assert_eq!(db.expires, 1);
+        let a = db.get(b"a").unwrap();
+        assert_eq!(a.expires_at_ms, Some(1_700_000_000_000));
+        assert!(matches!(&a.value, RdbValue::String(v) if v == b"plain"));
+        let b = db.get(b"b").unwrap();
+        assert!(matches!(&b.value, RdbValue::String(v) if v == b"12345"));
+        assert_eq!(b.encoding, "int");
+        let c = db.get(b"c").unwrap();
+        assert!(matches!(&c.value, RdbValue::String(v) if v == b"abcabcabc"));
+    }
+
+    #[test]
+    fn quicklists() {
+        let v1 = {
+            let mut blob = vec![0; 8];
+            blob.extend_from_slice(&2u16.to_le_bytes());
+            blob.extend_from_slice(&[0x00, 0x01, b'a', 0x03, 0xf4, 0xff]);
+            [len(1), string(&blob)].concat()
+        };
+        let entry = single_key(TYPE_LIST_QUICKLIST, &v1).unwrap();
+        assert!(matches!(&entry.value, RdbValue::List(v) if *v == strings(&["a", "3"])));
+
+        let v2 = [
+            len(2),
+            len(QUICKLIST_NODE_PACKED as usize),
+            string(&listpack(&[b"x", b"y"])),
+            len(QUICKLIST_NODE_PLAIN as usize),
+            string(b"big"),
+        ]
+        .concat();
+        let entry = single_key(TYPE_LIST_QUICKLIST_2, &v2).unwrap();
+        assert!(matches!(&entry.value, RdbValue::List(v) if *v == strings(&["x", "y", "big"])));
+        assert_eq!(entry.encoding, "quicklist");
+
+        let bad = [len(1), len(3), string(b"x")].concat();
+        let err = single_key(TYPE_LIST_QUICKLIST_2, &bad).unwrap_err();
+        assert!(err.contains("Unknown quicklist container"));
+    }
+
+    // Node with master fields ["f"]: one SAMEFIELDS entry, one entry with
+    // its own fields and one deleted entry
+    fn stream_node() -> Vec<u8> {
+        listpack(&[
+            b"2", b"1", b"1", b"f", b"0", // count, deleted, master fields, terminator
+            b"2", b"0", b"0", b"v1", b"4", // SAMEFIELDS at +0-0
+            b"0", b"5", b"1", b"1", b"g", b"x", b"6", // Own fields at +5-1
+            b"3", b"9", b"0", b"v3", b"4", // Deleted
+        ])
+    }
+
+    fn stream_value(consumer_pending: &[u8]) -> Vec<u8> {
+        let mut master_key = 100u64.to_be_bytes().to_vec();
+        master_key.extend_from_slice(&0u64.to_be_bytes());
+        [
+            len(1),
+            string(&master_key),
+            string(&stream_node()),
+            len(2),     // Length
+            len(105),   // Last id ms
+            len(1),     // Last id seq
+            vec![0; 5], // First id, max deleted id, entries added
+            len(1),     // Groups
+            string(b"group"),
+            len(0),
+            len(0),
+            len(2), // Entries read
+            len(1), // Group PEL
+            vec![0; 24],
+            len(1),
+            len(1), // Consumers
+            string(b"alice"),
+            vec![0; 16], // Seen and active time
+            consumer_pending.to_vec(),
+        ]
+        .concat()
+    }
+
+    #[test]
+    fn stream_entries_and_groups() {
+        let value = [stream_value(&len(1)), vec![0; 16]].concat();
+        let entry = single_key(TYPE_STREAM_LISTPACKS_3, &value).unwrap();
+        let RdbValue::Stream(stream) = entry.value else {
+            panic!("expected a stream");
+        };
+        assert_eq!(stream.length, 2);
+        assert_eq!(stream.last_id, (105, 1));
+        assert_eq!(stream.groups, 1);
+        assert_eq!(stream.entries.len(), 2);
+        assert_eq!(stream.entries[0].id, (100, 0));
+        assert_eq!(
+            stream.entries[0].fields,
+            vec![(b"f".to_vec(), b"v1".to_vec())]
+        );
+        assert_eq!(stream.entries[1].id, (105, 1));
+        assert_eq!(
+            stream.entries[1].fields,
+            vec![(b"g".to_vec(), b"x".to_vec())]
+        );
+    }
+
+    #[test]
+    fn stream_rejects_corrupt_input() {
+        // A consumer PEL length whose byte size overflows used to panic
+        let huge = [vec![0x81], u64::MAX.to_be_bytes().to_vec()].concat();
+        let err = single_key(TYPE_STREAM_LISTPACKS_3, &stream_value(&huge)).unwrap_err();
+        assert!(err.contains("Invalid consumer PEL length"));
+
+        let items = |raw: &[&str]| strings(raw);
+        assert!(stream_node_entries((0, 0), &items(&["-1", "0", "0", "0"])).is_none());
+        assert!(stream_node_entries((0, 0), &items(&["1", "0", "99", "0"])).is_none());
+        assert!(
+            stream_node_entries((0, 0), &items(&["1", "0", "0", "0", "0", "0", "0"])).is_none()
+        );
+        let huge_fields = items(&["1", "0", "0", "0", "0", "0", "0", "9223372036854775807"]);
+        assert!(stream_node_entries((0, 0), &huge_fields).is_none());
+    }
+
+    // Module id for a 9-character type name and encoding version
+    fn module_id(name: &[u8; 9], encver: u64) -> u64 {
+        let mut id = 0u64;
+        for c in name {
+            let index = MODULE_NAME_CHARSET.iter().position(|x| x == c).unwrap() as u64;
+            id = (id << 6) | index;
+        }
+        (id << 10) | encver
+    }
+
+    #[test]
+    fn module_values_are_skipped() {
+        let id = module_id(b"ReJSON-RL", 3);
+        assert_eq!(module_type_name(id), "ReJSON-RL");
+
+        let value = [
+            vec![0x81],
+            id.to_be_bytes().to_vec(),
+            len(MODULE_OPCODE_SINT as usize),
+            len(7),
+            len(MODULE_OPCODE_FLOAT as usize),
+            vec![0; 4],
+            len(MODULE_OPCODE_DOUBLE as usize),
+            vec![0; 8],
+            len(MODULE_OPCODE_STRING as usize),
+            string(b"{\"a\":1}"),
+            len(MODULE_OPCODE_EOF as usize),
+        ]
+        .concat();
+        let body = [vec![TYPE_MODULE_2], string(b"doc"), value.clone()].concat();
+        let (snapshot, _) = parse(&rdb(11, &body)).unwrap();
+        assert_eq!(snapshot.skipped_modules.get("ReJSON-RL"), Some(&1));
+        assert_eq!(
+            snapshot.databases[&0].entries()[0].value.type_name(),
+            "ReJSON-RL"
+        );
+
+        // Unknown opcode
+        let mut bad = value;
+        let eof = bad.len() - 1;
+        bad[eof] = 9;
+        let err = single_key(TYPE_MODULE_2, &bad).unwrap_err();
+        assert!(err.contains("Unknown module opcode"));
+    }
+
+    #[test]
+    fn rejects_bad_headers() {
+        assert!(parse(b"NOTREDIS0011").is_err());
+        assert!(parse(&rdb(7, &[]))
+            .unwrap_err()
+            .contains("Unsupported RDB version"));
+        assert!(parse(&rdb(8, &[])).is_ok());
+        assert!(parse(&rdb(MAX_RDB_VERSION + 1, &[])).is_err());
+    }
+
+    // One key of most value types, followed by the EOF opcode
+    fn mixed_body() -> Vec<u8> {
+        let module = [
+            vec![0x81],
+            module_id(b"ReJSON-RL", 3).to_be_bytes().to_vec(),
+            len(MODULE_OPCODE_STRING as usize),
+            vec![0xc3, 6, 9, 0x02, b'a', b'b', b'c', 0x80, 0x02],
+            len(MODULE_OPCODE_EOF as usize),
+        ]
+        .concat();
+        [
+            vec![OPCODE_AUX],
+            string(b"ctime"),
+            vec![0xc2],
+            1_700_000_000i32.to_le_bytes().to_vec(),
+            vec![TYPE_STRING],
+            string(b"s"),
+            vec![0xc3, 6, 9, 0x02, b'a', b'b', b'c', 0x80, 0x02],
+            vec![TYPE_SET_INTSET],
+            string(b"i"),
+            string(&intset(2, &[1, 2])),
+            vec![TYPE_HASH],
+            string(b"h"),
+            len(1),
+            string(b"f"),
+            string(b"v"),
+            vec![TYPE_ZSET_2],
+            string(b"z"),
+            len(1),
+            string(b"m"),
+            1.5f64.to_le_bytes().to_vec(),
+            vec![TYPE_LIST_QUICKLIST_2],
+            string(b"l"),
+            len(1),
+            len(QUICKLIST_NODE_PACKED as usize),
+            string(&listpack(&[b"x"])),
+            vec![OPCODE_SELECTDB, 1],
+            vec![TYPE_STREAM_LISTPACKS_3],
+            string(b"st"),
+            stream_value(&len(1)),
+            vec![0; 16],
+            vec![TYPE_MODULE_2],
+            string(b"doc"),
+            module,
+        ]
+        .concat()
+    }
+
+    #[test]
+    fn scan_agrees_with_parse() {
+        let data = rdb(11, &mixed_body());
+        let (snapshot, consumed) = parse(&data).unwrap();
+        let keys: usize = snapshot.databases.values().map(|d| d.len()).sum();
+        assert_eq!(keys, 7);
+
+        // Trailing AOF commands are not part of the preamble
+        let aof = [data.clone(), b"*1\r\n$4\r\nPING\r\n".to_vec()].concat();
+        let summary = scan(&aof).unwrap();
+        assert_eq!(summary.version, 11);
+        assert_eq!(summary.keys, 7);
+        assert_eq!(summary.bytes, consumed);
+        assert_eq!(summary.bytes, data.len());
+
+        let v8 = rdb(8, &[vec![TYPE_STRING], string(b"k"), string(b"v")].concat());
+        assert_eq!(scan(&v8).unwrap().keys, 1);
+    }
+
+    #[test]
+    fn truncated_files_fail_without_panicking() {
+        let body = mixed_body();
+        let data = rdb(11, &body);
+        assert!(parse(&data).is_ok());
+        // Everything before the EOF opcode is required
+        for end in 0..9 + body.len() {
+            assert!(
+                parse(&data[..end]).is_err(),
+                "prefix of {} bytes parsed",
+                end
+            );
+            assert!(
+                scan(&data[..end]).is_err(),
+                "prefix of {} bytes scanned",
+                end
+            );
+        }
+    }
+}
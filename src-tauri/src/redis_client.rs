@@ -1,7 +1,11 @@
+use crate::access::{self, AccessError, CommandGuard, CommandPolicy, GuardedConnection};
+use crate::keyspace::{self, ServerVersion};
+use crate::rdb::RdbSnapshot;
+use crate::snapshot::SnapshotConnection;
 use crate::ssh_tunnel::SshTunnel;
-use redis::{Client, Connection, RedisResult};
+use redis::{Client, Connection, ConnectionLike, RedisResult, Value};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::{mpsc, Arc, Mutex};
 use std::time::Duration;
 
@@ -38,18 +42,98 @@ pub struct ConnectionConfig {
     pub database: u8,
     pub use_tls: bool,
     pub ssh_tunnel: Option<SshTunnelConfig>,
+    #[serde(default)]
+    pub read_only: bool, // Reject writes in the backend
+    #[serde(default)]
+    pub policy: Option<CommandPolicy>,
+    #[serde(default)]
+    pub undo_journal: Option<usize>, // Undo entries kept per connection; None disables it
 }
 
 #[derive(Debug, Clone, Serialize)]
 pub struct ConnectionStatus {
     pub id: String,
     pub connected: bool,
+    pub read_only: bool, // Configured read-only, or connected to a replica
     pub error: Option<String>,
 }
 
+// A live server connection, or a read-only RDB file opened from disk.
+// Live connections with a command policy are Guarded.
+pub enum RedisConn {
+    Live(Connection),
+    Guarded(GuardedConnection),
+    Snapshot(SnapshotConnection),
+}
+
+impl RedisConn {
+    pub fn is_snapshot(&self) -> bool {
+        matches!(self, RedisConn::Snapshot(_))
+    }
+
+    // Redeem an approved confirmation token; only guarded connections use it
+    pub fn with_confirmation(self, token: Option<String>) -> Self {
+        match self {
+            RedisConn::Guarded(conn) => RedisConn::Guarded(conn.with_confirmation(token)),
+            conn => conn,
+        }
+    }
+}
+
+impl ConnectionLike for RedisConn {
+    fn req_packed_command(&mut self, cmd: &[u8]) -> RedisResult<Value> {
+        match self {
+            RedisConn::Live(conn) => conn.req_packed_command(cmd),
+            RedisConn::Guarded(conn) => conn.req_packed_command(cmd),
+            RedisConn::Snapshot(conn) => conn.req_packed_command(cmd),
+        }
+    }
+
+    fn req_packed_commands(
+        &mut self,
+        cmd: &[u8],
+        offset: usize,
+        count: usize,
+    ) -> RedisResult<Vec<Value>> {
+        match self {
+            RedisConn::Live(conn) => conn.req_packed_commands(cmd, offset, count),
+            RedisConn::Guarded(conn) => conn.req_packed_commands(cmd, offset, count),
+            RedisConn::Snapshot(conn) => conn.req_packed_commands(cmd, offset, count),
+        }
+    }
+
+    fn get_db(&self) -> i64 {
+        match self {
+            RedisConn::Live(conn) => conn.get_db(),
+            RedisConn::Guarded(conn) => conn.get_db(),
+            RedisConn::Snapshot(conn) => conn.get_db(),
+        }
+    }
+
+    fn check_connection(&mut self) -> bool {
+        match self {
+            RedisConn::Live(conn) => conn.check_connection(),
+            RedisConn::Guarded(conn) => conn.check_connection(),
+            RedisConn::Snapshot(conn) => conn.check_connection(),
+        }
+    }
+
+    fn is_open(&self) -> bool {
+        match self {
+            RedisConn::Live(conn) => conn.is_open(),
+            RedisConn::Guarded(conn) => conn.is_open(),
+            RedisConn::Snapshot(conn) => conn.is_open(),
+        }
+    }
+}
+
 pub struct RedisConnectionManager {
     connections: Arc<Mutex<HashMap<String, Client>>>,
     ssh_tunnels: Arc<Mutex<HashMap<String, SshTunnel>>>,
+    snapshots: Arc<Mutex<HashMap<String, SnapshotConnection>>>,
+    read_only: Arc<Mutex<HashSet<String>>>,
+    guards: Arc<Mutex<HashMap<String, Arc<CommandGuard>>>>,
+    server_versions: Arc<Mutex<HashMap<String, ServerVersion>>>,
 }
 
 impl RedisConnectionManager {
@@ -57,6 +141,10 @@ impl RedisConnectionManager {
         Self {
             connections: Arc::new(Mutex::new(HashMap::new())),
             ssh_tunnels: Arc::new(Mutex::new(HashMap::new())),
+            snapshots: Arc::new(Mutex::new(HashMap::new())),
+            read_only: Arc::new(Mutex::new(HashSet::new())),
+            guards: Arc::new(Mutex::new(HashMap::new())),
+            server_versions: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 
@@ -88,6 +176,7 @@ impl RedisConnectionManager {
             return Ok(ConnectionStatus {
                 id: config.id,
                 connected: false,
+                read_only: false,
                 error: Some(format!("SSH tunnel error: {}", e)),
             });
         }
@@ -117,6 +206,7 @@ impl RedisConnectionManager {
                         return Ok(ConnectionStatus {
                             id: config.id,
                             connected: false,
+                            read_only: false,
                             error: Some(e.to_string()),
                         });
                     }
@@ -126,6 +216,7 @@ impl RedisConnectionManager {
                         return Ok(ConnectionStatus {
                             id: config.id,
                             connected: false,
+                            read_only: false,
                             error: Some(format!(
                                 "Redis connection timed out after {:?}",
                                 REDIS_HANDSHAKE_TIMEOUT
@@ -138,12 +229,32 @@ impl RedisConnectionManager {
                 redis::cmd("PING").query::<String>(&mut conn)?;
                 eprintln!("Redis: PING successful in {:?}", start.elapsed());
 
+                let read_only = config.read_only || access::is_replica(&mut conn);
+                let mut read_only_ids = self.read_only.lock().unwrap();
+                if read_only {
+                    read_only_ids.insert(config.id.clone());
+                } else {
+                    read_only_ids.remove(&config.id);
+                }
+
+                let mut guards = self.guards.lock().unwrap();
+                match config.policy.filter(|policy| !policy.is_empty()) {
+                    Some(policy) => {
+                        guards.insert(config.id.clone(), Arc::new(CommandGuard::new(policy)));
+                    }
+                    None => {
+                        guards.remove(&config.id);
+                    }
+                }
+
+                self.server_versions.lock().unwrap().remove(&config.id);
                 let mut connections = self.connections.lock().unwrap();
                 connections.insert(config.id.clone(), client);
 
                 Ok(ConnectionStatus {
                     id: config.id,
                     connected: true,
+                    read_only,
                     error: None,
                 })
             }
@@ -155,6 +266,7 @@ impl RedisConnectionManager {
                 Ok(ConnectionStatus {
                     id: config.id,
                     connected: false,
+                    read_only: false,
                     error: Some(e.to_string()),
                 })
             }
@@ -165,18 +277,125 @@ impl RedisConnectionManager {
         let mut connections = self.connections.lock().unwrap();
         let mut tunnels = self.ssh_tunnels.lock().unwrap();
 
+        let mut snapshots = self.snapshots.lock().unwrap();
+
         // Remove both connection and tunnel (if exists)
         let conn_removed = connections.remove(connection_id).is_some();
         tunnels.remove(connection_id);
+        let snapshot_removed = snapshots.remove(connection_id).is_some();
+        self.read_only.lock().unwrap().remove(connection_id);
+        self.guards.lock().unwrap().remove(connection_id);
+        self.server_versions.lock().unwrap().remove(connection_id);
 
-        conn_removed
+        conn_removed || snapshot_removed
     }
 
-    pub fn get_connection(&self, connection_id: &str) -> Option<Connection> {
+    // Register a parsed RDB file as a read-only connection
+    pub fn open_snapshot(&self, connection_id: &str, snapshot: Arc<RdbSnapshot>, database: u32) {
+        let mut snapshots = self.snapshots.lock().unwrap();
+        snapshots.insert(
+            connection_id.to_string(),
+            SnapshotConnection::new(snapshot, database),
+        );
+    }
+
+    pub fn get_connection(&self, connection_id: &str) -> Option<RedisConn> {
+        if let Some(snapshot) = self.snapshots.lock().unwrap().get(connection_id) {
+            return Some(RedisConn::Snapshot(snapshot.clone()));
+        }
+
+        let conn = {
+            let connections = self.connections.lock().unwrap();
+            connections.get(connection_id)?.get_connection().ok()?
+        };
+        match self.guards.lock().unwrap().get(connection_id) {
+            Some(guard) => {
+                Some(RedisConn::Guarded(GuardedConnection::new(conn, guard.clone())))
+            }
+            None => Some(RedisConn::Live(conn)),
+        }
+    }
+
+    // Server version from INFO, fetched once per connection
+    pub fn server_version(
+        &self,
+        connection_id: &str,
+        conn: &mut dyn ConnectionLike,
+    ) -> Option<ServerVersion> {
+        if let Some(version) = self.server_versions.lock().unwrap().get(connection_id) {
+            return Some(*version);
+        }
+        let version = keyspace::server_version(conn)?;
+        self.server_versions
+            .lock()
+            .unwrap()
+            .insert(connection_id.to_string(), version);
+        Some(version)
+    }
+
+    pub fn is_read_only(&self, connection_id: &str) -> bool {
+        self.read_only.lock().unwrap().contains(connection_id)
+    }
+
+    // Connection for commands that modify data; fails on read-only connections
+    pub fn get_writable_connection(&self, connection_id: &str) -> Result<RedisConn, AccessError> {
+        if self.is_read_only(connection_id) {
+            return Err(AccessError::ReadOnly);
+        }
+        self.get_connection(connection_id).ok_or(AccessError::NotFound)
+    }
+
+    // The policy applied to a live connection, if it has one
+    pub fn command_policy(&self, connection_id: &str) -> Option<CommandPolicy> {
+        let guards = self.guards.lock().unwrap();
+        guards.get(connection_id).map(|guard| guard.policy().clone())
+    }
+
+    // Approve a confirmation token so the confirmed command can be re-submitted
+    pub fn confirm_command(&self, connection_id: &str, token: &str) -> Result<String, AccessError> {
+        let guard = self.guards.lock().unwrap().get(connection_id).cloned();
+        guard.ok_or(AccessError::InvalidToken)?.confirm(token)
+    }
+
+    // Database index the connection was opened on
+    pub fn database(&self, connection_id: &str) -> Option<u32> {
+        if let Some(snapshot) = self.snapshots.lock().unwrap().get(connection_id) {
+            return Some(snapshot.db());
+        }
+
         let connections = self.connections.lock().unwrap();
         connections
             .get(connection_id)
-            .and_then(|client| client.get_connection().ok())
+            .map(|client| client.get_connection_info().redis.db as u32)
+    }
+
+    // Point future connections at another database without reconnecting
+    // the SSH tunnel. The handshake SELECTs the db, so an out-of-range index
+    // fails here and the previous client is kept. Returns false for an
+    // unknown connection.
+    pub fn select_database(&self, connection_id: &str, db: u32) -> RedisResult<bool> {
+        if let Some(snapshot) = self.snapshots.lock().unwrap().get_mut(connection_id) {
+            *snapshot = snapshot.with_db(db);
+            return Ok(true);
+        }
+
+        let info = self
+            .connections
+            .lock()
+            .unwrap()
+            .get(connection_id)
+            .map(|client| client.get_connection_info().clone());
+        let Some(mut info) = info else {
+            return Ok(false);
+        };
+
+        info.redis.db = db as i64;
+        let client = Client::open(info)?;
+        client.get_connection_with_timeout(REDIS_CONNECT_TIMEOUT)?;
+
+        let mut connections = self.connections.lock().unwrap();
+        connections.insert(connection_id.to_string(), client);
+        Ok(true)
     }
 
     fn build_connection_string(&self, config: &ConnectionConfig) -> String {
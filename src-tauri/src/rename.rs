@@ -0,0 +1,459 @@
+use crate::export::BinaryString;
+use crate::jobs::{CancelToken, RateLimiter};
+use crate::keyspace::{self, KeyBatches, KeyScanner};
+use redis::{ConnectionLike, RedisResult, Value};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::time::Instant;
+
+const DEFAULT_BATCH_SIZE: usize = 500;
+const MAX_RESULTS: usize = 1000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "lowercase")]
+pub enum RenameRule {
+    Prefix {
+        from: String,
+        to: String,
+    },
+    Suffix {
+        from: String,
+        to: String,
+    },
+    // `replacement` may refer to captures as $1 or ${name}
+    Regex {
+        pattern: String,
+        replacement: String,
+    },
+}
+
+pub enum CompiledRule {
+    Prefix {
+        from: String,
+        to: String,
+    },
+    Suffix {
+        from: String,
+        to: String,
+    },
+    Regex {
+        regex: regex::bytes::Regex,
+        replacement: String,
+    },
+}
+
+impl RenameRule {
+    pub fn compile(&self) -> Result<CompiledRule, String> {
+        match self {
+            RenameRule::Prefix { from, .. } | RenameRule::Suffix { from, .. }
+                if from.is_empty() =>
+            {
+                Err("The text to replace cannot be empty".to_string())
+            }
+            RenameRule::Prefix { from, to } => Ok(CompiledRule::Prefix {
+                from: from.clone(),
+                to: to.clone(),
+            }),
+            RenameRule::Suffix { from, to } => Ok(CompiledRule::Suffix {
+                from: from.clone(),
+                to: to.clone(),
+            }),
+            RenameRule::Regex {
+                pattern,
+                replacement,
+            } => Ok(CompiledRule::Regex {
+                regex: regex::bytes::Regex::new(pattern)
+                    .map_err(|e| format!("Invalid regex: {}", e))?,
+                replacement: replacement.clone(),
+            }),
+        }
+    }
+
+    // SCAN pattern covering every key the rule can apply to
+    pub fn scan_pattern(&self) -> String {
+        match self {
+            RenameRule::Prefix { from, .. } => format!("{}*", keyspace::glob_escape(from)),
+            RenameRule::Suffix { from, .. } => format!("*{}", keyspace::glob_escape(from)),
+            RenameRule::Regex { .. } => "*".to_string(),
+        }
+    }
+}
+
+impl CompiledRule {
+    // New name for `key`, or None if the rule does not apply or would not
+    // change it. Works on bytes so keys that are not UTF-8 keep their bytes.
+    pub fn rewrite(&self, key: &[u8]) -> Option<Vec<u8>> {
+        let renamed = match self {
+            CompiledRule::Prefix { from, to } => key
+                .strip_prefix(from.as_bytes())
+                .map(|rest| [to.as_bytes(), rest].concat()),
+            CompiledRule::Suffix { from, to } => key
+                .strip_suffix(from.as_bytes())
+                .map(|rest| [rest, to.as_bytes()].concat()),
+            CompiledRule::Regex { regex, replacement } => regex
+                .is_match(key)
+                .then(|| regex.replace(key, replacement.as_bytes()).into_owned()),
+        }?;
+        (!renamed.is_empty() && renamed != key).then_some(renamed)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RenamePair {
+    pub from: BinaryString,
+    pub to: BinaryString,
+    pub target_exists: bool,
+    pub cross_slot: bool, // Moved with DUMP/RESTORE on a cluster
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RenamePreview {
+    pub pattern: String,
+    pub cluster: bool,
+    pub estimated: bool, // Counts extrapolated from part of the keyspace
+    pub matched_keys: u64,
+    pub collisions: u64,        // Targets that already exist
+    pub duplicate_targets: u64, // Targets produced by more than one key
+    pub cross_slot: u64,
+    pub renames: Vec<RenamePair>, // First `limit` pairs
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RenameOutcome {
+    Renamed,
+    Overwritten,
+    Collision, // Target exists; left untouched
+    Gone,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RenameKeyResult {
+    pub from: BinaryString,
+    pub to: BinaryString,
+    pub outcome: RenameOutcome,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct RenameOptions {
+    pub rule: RenameRule,
+    pub pattern: String,
+    pub overwrite: bool, // RENAME instead of RENAMENX
+    pub batch_size: Option<usize>,
+    pub max_keys_per_second: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RenameReport {
+    pub pattern: String,
+    pub cluster: bool,
+    pub renamed: u64,
+    pub overwritten: u64,
+    pub moved_across_slots: u64,
+    pub collisions: u64,
+    pub already_gone: u64,
+    pub failed: u64,
+    pub results: Vec<RenameKeyResult>, // First MAX_RESULTS keys that were not renamed cleanly
+    pub duration_ms: u64,
+    pub cancelled: bool,
+}
+
+// A read-only server fails every key the same way, so stop at the first
+fn check_writable(replies: &[Value]) -> RedisResult<()> {
+    for reply in replies {
+        if let Err(e) = reply.clone().extract_error() {
+            if e.code() == Some("READONLY") {
+                return Err(e);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn crosses_slot(cluster: bool, from: &BinaryString, to: &BinaryString) -> bool {
+    cluster && keyspace::key_slot(&from.0) != keyspace::key_slot(&to.0)
+}
+
+// Rename pairs for one scanned batch. Keys produced by earlier batches are
+// skipped so a rule whose output still matches the pattern (svc: -> svc:v2:)
+// never renames a key twice.
+fn plan(
+    rule: &CompiledRule,
+    batch: Vec<BinaryString>,
+    produced: &HashSet<BinaryString>,
+) -> Vec<(BinaryString, BinaryString)> {
+    batch
+        .into_iter()
+        .filter(|key| !produced.contains(key))
+        .filter_map(|key| rule.rewrite(&key.0).map(|to| (key, BinaryString(to))))
+        .collect()
+}
+
+// Large keyspaces are only scanned up to PREVIEW_SCAN_LIMIT keys, with the
+// counts scaled by DBSIZE
+pub fn preview(
+    conn: &mut dyn ConnectionLike,
+    rule: &RenameRule,
+    pattern: &str,
+    limit: usize,
+) -> Result<RenamePreview, String> {
+    let compiled = rule.compile()?;
+    let cluster = keyspace::cluster_enabled(conn);
+    let total = keyspace::dbsize(conn);
+    let mut scanner = KeyScanner::new(conn, pattern, keyspace::SCAN_COUNT, None);
+    let mut seen = HashSet::new();
+    let mut targets = HashSet::new();
+
+    let mut preview = RenamePreview {
+        pattern: pattern.to_string(),
+        cluster,
+        estimated: false,
+        matched_keys: 0,
+        collisions: 0,
+        duplicate_targets: 0,
+        cross_slot: 0,
+        renames: Vec::new(),
+    };
+
+    while let Some(batch) = scanner.next_batch(conn).map_err(|e| e.to_string())? {
+        let batch = batch
+            .into_iter()
+            .filter(|key| seen.insert(key.clone()))
+            .collect();
+        let pairs = plan(&compiled, batch, &HashSet::new());
+
+        let mut pipe = redis::pipe();
+        for (_, to) in &pairs {
+            pipe.cmd("EXISTS").arg(to);
+        }
+        let exists = keyspace::query_pipeline_lenient(conn, &pipe, pairs.len())
+            .map_err(|e| e.to_string())?;
+
+        for ((from, to), exists) in pairs.into_iter().zip(&exists) {
+            let target_exists = keyspace::value_to_i64(exists) == Some(1);
+            let cross_slot = crosses_slot(cluster, &from, &to);
+            preview.matched_keys += 1;
+            preview.collisions += target_exists as u64;
+            preview.cross_slot += cross_slot as u64;
+            if !targets.insert(to.clone()) {
+                preview.duplicate_targets += 1;
+            }
+            if preview.renames.len() < limit {
+                preview.renames.push(RenamePair {
+                    from,
+                    to,
+                    target_exists,
+                    cross_slot,
+                });
+            }
+        }
+
+        if scanner.scanned_estimate(total) >= keyspace::PREVIEW_SCAN_LIMIT {
+            break;
+        }
+    }
+
+    preview.estimated = !scanner.is_done();
+    preview.matched_keys = scanner.extrapolate(preview.matched_keys, total);
+    preview.collisions = scanner.extrapolate(preview.collisions, total);
+    preview.duplicate_targets = scanner.extrapolate(preview.duplicate_targets, total);
+    preview.cross_slot = scanner.extrapolate(preview.cross_slot, total);
+    Ok(preview)
+}
+
+// RENAME/RENAMENX for pairs in the same slot (or on a standalone server).
+// RENAMENX replies 0 when the target exists; a vanished source is an
+// "ERR no such key" error.
+fn rename_same_slot(
+    conn: &mut dyn ConnectionLike,
+    pairs: &[&(BinaryString, BinaryString)],
+    overwrite: bool,
+) -> RedisResult<Vec<(RenameOutcome, Option<String>)>> {
+    let mut pipe = redis::pipe();
+    for (from, to) in pairs {
+        if overwrite {
+            pipe.cmd("EXISTS").arg(to);
+            pipe.cmd("RENAME").arg(from).arg(to);
+        } else {
+            pipe.cmd("RENAMENX").arg(from).arg(to);
+        }
+    }
+    let per_pair = if overwrite { 2 } else { 1 };
+    let replies = keyspace::query_pipeline_lenient(conn, &pipe, pairs.len() * per_pair)?;
+    check_writable(&replies)?;
+
+    Ok(replies
+        .chunks(per_pair)
+        .map(|r| {
+            let reply = r[per_pair - 1].clone();
+            match reply.extract_error() {
+                Err(e) if e.to_string().contains("no such key") => (RenameOutcome::Gone, None),
+                Err(e) => (RenameOutcome::Failed, Some(e.to_string())),
+                Ok(Value::Int(0)) => (RenameOutcome::Collision, None),
+                Ok(_) if overwrite && keyspace::value_to_i64(&r[0]) == Some(1) => {
+                    (RenameOutcome::Overwritten, None)
+                }
+                Ok(_) => (RenameOutcome::Renamed, None),
+            }
+        })
+        .collect())
+}
+
+// Cluster nodes reject RENAME across slots, so those keys are copied with
+// DUMP/RESTORE and the source is removed once the copy succeeded. This is
+// not atomic: a write to the source in between is lost. RESTORE fails with
+// MOVED when another node serves the target slot.
+fn rename_cross_slot(
+    conn: &mut dyn ConnectionLike,
+    pairs: &[&(BinaryString, BinaryString)],
+    overwrite: bool,
+) -> RedisResult<Vec<(RenameOutcome, Option<String>)>> {
+    let mut pipe = redis::pipe();
+    for (from, to) in pairs {
+        pipe.cmd("DUMP").arg(from);
+        pipe.cmd("PTTL").arg(from);
+        pipe.cmd("EXISTS").arg(to);
+    }
+    let replies = keyspace::query_pipeline_lenient(conn, &pipe, pairs.len() * 3)?;
+
+    let mut outcomes = vec![(RenameOutcome::Gone, None); pairs.len()];
+    let mut restore = redis::pipe();
+    let mut restored = Vec::new();
+    for (i, ((_, to), r)) in pairs.iter().zip(replies.chunks(3)).enumerate() {
+        let Value::BulkString(payload) = &r[0] else {
+            continue; // Source gone
+        };
+        let target_exists = keyspace::value_to_i64(&r[2]) == Some(1);
+        if target_exists && !overwrite {
+            outcomes[i] = (RenameOutcome::Collision, None);
+            continue;
+        }
+        let pttl = keyspace::value_to_i64(&r[1]).unwrap_or(-1).max(0);
+        restore
+            .cmd("RESTORE")
+            .arg(to)
+            .arg(pttl)
+            .arg(payload.as_slice());
+        if overwrite {
+            restore.arg("REPLACE");
+        }
+        restored.push((i, target_exists));
+    }
+
+    let replies = keyspace::query_pipeline_lenient(conn, &restore, restored.len())?;
+    check_writable(&replies)?;
+    let mut unlink = redis::pipe();
+    let mut unlinked = 0;
+    for ((i, target_exists), reply) in restored.into_iter().zip(replies) {
+        outcomes[i] = match reply.extract_error() {
+            Err(e) if e.code() == Some("BUSYKEY") => (RenameOutcome::Collision, None),
+            Err(e) => (RenameOutcome::Failed, Some(e.to_string())),
+            Ok(_) => {
+                unlink.cmd("UNLINK").arg(&pairs[i].0);
+                unlinked += 1;
+                if target_exists {
+                    (RenameOutcome::Overwritten, None)
+                } else {
+                    (RenameOutcome::Renamed, None)
+                }
+            }
+        };
+    }
+    keyspace::query_pipeline_lenient(conn, &unlink, unlinked)?;
+
+    Ok(outcomes)
+}
+
+pub fn rename_keys(
+    conn: &mut dyn ConnectionLike,
+    options: &RenameOptions,
+    cancel: &CancelToken,
+    mut on_progress: impl FnMut(u64, u64),
+) -> Result<RenameReport, String> {
+    let compiled = options.rule.compile()?;
+    let started = Instant::now();
+    let cluster = keyspace::cluster_enabled(conn);
+    let batch_size = options.batch_size.unwrap_or(DEFAULT_BATCH_SIZE).max(1);
+    let mut limiter = RateLimiter::new(options.max_keys_per_second);
+    let mut produced = HashSet::new();
+    let mut batches = KeyBatches::new(conn, &options.pattern, None);
+    let total = batches.total();
+
+    let mut report = RenameReport {
+        pattern: options.pattern.clone(),
+        cluster,
+        renamed: 0,
+        overwritten: 0,
+        moved_across_slots: 0,
+        collisions: 0,
+        already_gone: 0,
+        failed: 0,
+        results: Vec::new(),
+        duration_ms: 0,
+        cancelled: false,
+    };
+
+    'batches: while let Some(batch) = batches.next_batch(conn).map_err(|e| e.to_string())? {
+        let pairs = plan(&compiled, batch, &produced);
+        let chunk_size = options
+            .max_keys_per_second
+            .map_or(batch_size, |rate| (rate as usize).clamp(1, batch_size));
+
+        for chunk in pairs.chunks(chunk_size) {
+            if cancel.is_cancelled() {
+                report.cancelled = true;
+                break 'batches;
+            }
+
+            let (cross, same): (Vec<_>, Vec<_>) = chunk
+                .iter()
+                .partition(|(from, to)| crosses_slot(cluster, from, to));
+            let same_outcomes =
+                rename_same_slot(conn, &same, options.overwrite).map_err(|e| e.to_string())?;
+            let cross_outcomes =
+                rename_cross_slot(conn, &cross, options.overwrite).map_err(|e| e.to_string())?;
+
+            let results = same
+                .iter()
+                .zip(same_outcomes)
+                .map(|(pair, outcome)| (pair, outcome, false))
+                .chain(
+                    cross
+                        .iter()
+                        .zip(cross_outcomes)
+                        .map(|(pair, outcome)| (pair, outcome, true)),
+                );
+            for ((from, to), (outcome, error), cross_slot) in results {
+                match outcome {
+                    RenameOutcome::Renamed => report.renamed += 1,
+                    RenameOutcome::Overwritten => report.overwritten += 1,
+                    RenameOutcome::Collision => report.collisions += 1,
+                    RenameOutcome::Gone => report.already_gone += 1,
+                    RenameOutcome::Failed => report.failed += 1,
+                }
+                let moved = matches!(outcome, RenameOutcome::Renamed | RenameOutcome::Overwritten);
+                if moved {
+                    produced.insert(to.clone());
+                    report.moved_across_slots += cross_slot as u64;
+                }
+                if outcome != RenameOutcome::Renamed && report.results.len() < MAX_RESULTS {
+                    report.results.push(RenameKeyResult {
+                        from: from.clone(),
+                        to: to.clone(),
+                        outcome,
+                        error,
+                    });
+                }
+            }
+
+            limiter.wait(chunk.len(), cancel);
+        }
+
+        on_progress(batches.processed(), total);
+    }
+
+    report.duration_ms = started.elapsed().as_millis() as u64;
+    Ok(report)
+}
@@ -0,0 +1,1084 @@
+use crate::rdb::{HashField, RdbDatabase, RdbEntry, RdbSnapshot, RdbValue, StreamEntry, StreamId};
+use redis::{ConnectionLike, ErrorKind, RedisResult, Value};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Commands rejected with a READONLY error instead of "unknown command"
+const WRITE_COMMANDS: &[&str] = &[
+    "APPEND",
+    "BITFIELD",
+    "BLMOVE",
+    "BLPOP",
+    "BRPOP",
+    "BZPOPMAX",
+    "BZPOPMIN",
+    "COPY",
+    "DECR",
+    "DECRBY",
+    "DEL",
+    "EXPIRE",
+    "EXPIREAT",
+    "FLUSHALL",
+    "FLUSHDB",
+    "GETDEL",
+    "GETEX",
+    "GETSET",
+    "HDEL",
+    "HEXPIRE",
+    "HGETDEL",
+    "HGETEX",
+    "HINCRBY",
+    "HINCRBYFLOAT",
+    "HMSET",
+    "HPERSIST",
+    "HPEXPIRE",
+    "HPEXPIREAT",
+    "HSET",
+    "HSETEX",
+    "HSETNX",
+    "INCR",
+    "INCRBY",
+    "INCRBYFLOAT",
+    "LINSERT",
+    "LMOVE",
+    "LPOP",
+    "LPUSH",
+    "LPUSHX",
+    "LREM",
+    "LSET",
+    "LTRIM",
+    "MIGRATE",
+    "MOVE",
+    "MSET",
+    "MSETNX",
+    "PERSIST",
+    "PEXPIRE",
+    "PEXPIREAT",
+    "PFADD",
+    "PFMERGE",
+    "PSETEX",
+    "RENAME",
+    "RENAMENX",
+    "RESTORE",
+    "RPOP",
+    "RPUSH",
+    "RPUSHX",
+    "SADD",
+    "SET",
+    "SETBIT",
+    "SETEX",
+    "SETNX",
+    "SETRANGE",
+    "SINTERSTORE",
+    "SMOVE",
+    "SPOP",
+    "SREM",
+    "SUNIONSTORE",
+    "SWAPDB",
+    "UNLINK",
+    "XADD",
+    "XDEL",
+    "XTRIM",
+    "ZADD",
+    "ZINCRBY",
+    "ZPOPMAX",
+    "ZPOPMIN",
+    "ZREM",
+    "ZREMRANGEBYRANK",
+    "ZREMRANGEBYSCORE",
+];
+
+const HLL_REGISTERS: usize = 16384;
+const HLL_Q: usize = 50; // 64 - log2(registers)
+
+// Read-only stand-in for a server connection, answering the subset of
+// commands the key browser, value viewers and analyzers use from a parsed
+// RDB file. TTLs are reported relative to when the snapshot was written.
+#[derive(Clone)]
+pub struct SnapshotConnection {
+    snapshot: Arc<RdbSnapshot>,
+    db: u32,
+    empty: Arc<RdbDatabase>,
+}
+
+// Errors are full RESP error lines ("CODE message") so they reach callers
+// as regular server errors
+type Reply = Result<Value, String>;
+
+fn error(message: &str) -> String {
+    format!("ERR {}", message)
+}
+
+fn wrong_type() -> String {
+    "WRONGTYPE Operation against a key holding the wrong kind of value".to_string()
+}
+
+fn syntax_error() -> String {
+    error("syntax error")
+}
+
+fn error_value(line: String) -> Value {
+    let line = line.replace(['\r', '\n'], " ");
+    redis::parse_redis_value(format!("-{}\r\n", line).as_bytes()).unwrap_or(Value::Nil)
+}
+
+fn bulk(bytes: &[u8]) -> Value {
+    Value::BulkString(bytes.to_vec())
+}
+
+fn bulk_str(text: &str) -> Value {
+    Value::BulkString(text.as_bytes().to_vec())
+}
+
+fn format_score(score: f64) -> String {
+    if score.is_infinite() {
+        if score > 0.0 { "inf" } else { "-inf" }.to_string()
+    } else {
+        score.to_string()
+    }
+}
+
+fn arg_str(args: &[Vec<u8>], i: usize) -> Result<String, String> {
+    args.get(i)
+        .map(|a| String::from_utf8_lossy(a).to_string())
+        .ok_or_else(syntax_error)
+}
+
+fn arg_i64(args: &[Vec<u8>], i: usize) -> Result<i64, String> {
+    arg_str(args, i)?
+        .parse()
+        .map_err(|_| error("value is not an integer or out of range"))
+}
+
+// Clamp a Redis-style inclusive index range (negative = from the end)
+fn index_range(start: i64, stop: i64, len: usize) -> Option<(usize, usize)> {
+    let len = len as i64;
+    let start = if start < 0 {
+        (len + start).max(0)
+    } else {
+        start
+    };
+    let stop = if stop < 0 {
+        len + stop
+    } else {
+        stop.min(len - 1)
+    };
+    (start <= stop && start < len).then_some((start as usize, stop as usize))
+}
+
+// Redis glob matching (`*`, `?`, `[a-z]`, `[^x]`, `\` escapes) on raw bytes
+pub fn glob_match(pattern: &[u8], text: &[u8]) -> bool {
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        if p < pattern.len() {
+            match pattern[p] {
+                b'*' => {
+                    backtrack = Some((p, t));
+                    p += 1;
+                    continue;
+                }
+                b'?' => {
+                    p += 1;
+                    t += 1;
+                    continue;
+                }
+                b'[' => {
+                    if let Some((matched, next)) = match_class(pattern, p + 1, text[t]) {
+                        if matched {
+                            p = next;
+                            t += 1;
+                            continue;
+                        }
+                    }
+                }
+                b'\\' if p + 1 < pattern.len() && pattern[p + 1] == text[t] => {
+                    p += 2;
+                    t += 1;
+                    continue;
+                }
+                b'\\' if p + 1 < pattern.len() => {}
+                c if c == text[t] => {
+                    p += 1;
+                    t += 1;
+                    continue;
+                }
+                _ => {}
+            }
+        }
+
+        // Mismatch: let the last `*` absorb one more character
+        match backtrack {
+            Some((star_p, star_t)) => {
+                backtrack = Some((star_p, star_t + 1));
+                p = star_p + 1;
+                t = star_t + 1;
+            }
+            None => return false,
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == b'*')
+}
+
+// Returns whether `c` matches the class starting after `[`, and the index
+// just past the closing `]`
+fn match_class(pattern: &[u8], mut p: usize, c: u8) -> Option<(bool, usize)> {
+    let negate = pattern.get(p) == Some(&b'^');
+    if negate {
+        p += 1;
+    }
+
+    let mut matched = false;
+    loop {
+        match *pattern.get(p)? {
+            b']' => break,
+            b'\\' => {
+                p += 1;
+                matched |= *pattern.get(p)? == c;
+            }
+            start
+                if pattern.get(p + 1) == Some(&b'-')
+                    && pattern.get(p + 2).is_some_and(|e| *e != b']') =>
+            {
+                let end = pattern[p + 2];
+                let (lo, hi) = if start <= end {
+                    (start, end)
+                } else {
+                    (end, start)
+                };
+                matched |= (lo..=hi).contains(&c);
+                p += 2;
+            }
+            other => matched |= other == c,
+        }
+        p += 1;
+    }
+
+    Some((matched != negate, p + 1))
+}
+
+impl SnapshotConnection {
+    pub fn new(snapshot: Arc<RdbSnapshot>, db: u32) -> Self {
+        Self {
+            snapshot,
+            db,
+            empty: Arc::new(RdbDatabase::default()),
+        }
+    }
+
+    pub fn snapshot(&self) -> &RdbSnapshot {
+        &self.snapshot
+    }
+
+    pub fn db(&self) -> u32 {
+        self.db
+    }
+
+    // The same snapshot viewed through another database
+    pub fn with_db(&self, db: u32) -> Self {
+        Self {
+            snapshot: self.snapshot.clone(),
+            db,
+            empty: self.empty.clone(),
+        }
+    }
+
+    fn database(&self) -> &RdbDatabase {
+        self.snapshot.databases.get(&self.db).unwrap_or(&self.empty)
+    }
+
+    fn entry(&self, key: &[u8]) -> Option<&RdbEntry> {
+        self.database().get(key)
+    }
+
+    // "Now" for TTL purposes is the moment the snapshot was written
+    fn now_ms(&self) -> i64 {
+        self.snapshot.created_at_ms().unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_millis() as i64)
+                .unwrap_or(0)
+        })
+    }
+
+    fn pttl(&self, entry: &RdbEntry) -> i64 {
+        match entry.expires_at_ms {
+            Some(at) => (at - self.now_ms()).max(0),
+            None => -1,
+        }
+    }
+
+    fn execute(&mut self, args: &[Vec<u8>]) -> Reply {
+        let name = arg_str(args, 0)?.to_uppercase();
+        let args = &args[1..];
+
+        match name.as_str() {
+            "PING" => Ok(Value::SimpleString("PONG".to_string())),
+            "ECHO" => Ok(bulk(args.first().ok_or_else(syntax_error)?)),
+            "SELECT" => {
+                self.db = arg_i64(args, 0)? as u32;
+                Ok(Value::Okay)
+            }
+            "DBSIZE" => Ok(Value::Int(self.database().len() as i64)),
+            "INFO" => Ok(bulk_str(&self.info())),
+            "CONFIG" => self.config(args),
+            "EXISTS" => Ok(Value::Int(
+                args.iter().filter(|k| self.entry(k).is_some()).count() as i64,
+            )),
+            "TYPE" => Ok(Value::SimpleString(
+                args.first()
+                    .and_then(|k| self.entry(k))
+                    .map(|e| e.value.type_name().to_string())
+                    .unwrap_or_else(|| "none".to_string()),
+            )),
+            "TTL" | "PTTL" | "EXPIRETIME" | "PEXPIRETIME" => self.ttl(&name, args),
+            "OBJECT" => self.object(args),
+            "MEMORY" => self.memory(args),
+            "DEBUG" => self.debug(args),
+            "SCAN" => self.scan(args),
+            "KEYS" => {
+                let pattern = args.first().ok_or_else(syntax_error)?;
+                Ok(Value::Array(
+                    self.database()
+                        .entries()
+                        .iter()
+                        .filter(|e| glob_match(pattern, &e.key))
+                        .map(|e| bulk(&e.key))
+                        .collect(),
+                ))
+            }
+            "RANDOMKEY" => Ok(self
+                .database()
+                .entries()
+                .first()
+                .map(|e| bulk(&e.key))
+                .unwrap_or(Value::Nil)),
+            "GET" | "STRLEN" | "GETRANGE" | "SUBSTR" | "GETBIT" | "BITCOUNT" | "BITPOS"
+            | "PFCOUNT" => self.string_command(&name, args),
+            "MGET" => Ok(Value::Array(
+                args.iter()
+                    .map(|k| match self.entry(k).map(|e| &e.value) {
+                        Some(RdbValue::String(s)) => bulk(s),
+                        _ => Value::Nil,
+                    })
+                    .collect(),
+            )),
+            "LLEN" | "LRANGE" | "LINDEX" => self.list_command(&name, args),
+            "SCARD" | "SMEMBERS" | "SISMEMBER" | "SSCAN" => self.set_command(&name, args),
+            "ZCARD" | "ZRANGE" | "ZREVRANGE" | "ZSCORE" | "ZSCAN" => self.zset_command(&name, args),
+            "HLEN" | "HGET" | "HGETALL" | "HKEYS" | "HVALS" | "HEXISTS" | "HSCAN" | "HTTL"
+            | "HPTTL" | "HEXPIRETIME" | "HPEXPIRETIME" => self.hash_command(&name, args),
+            "XLEN" | "XRANGE" | "XREVRANGE" => self.stream_command(&name, args),
+            _ if WRITE_COMMANDS.contains(&name.as_str()) => {
+                Err("READONLY You can't write against a read only RDB snapshot".to_string())
+            }
+            _ => Err(error(&format!(
+                "unknown command '{}' (not supported for RDB snapshots)",
+                name
+            ))),
+        }
+    }
+
+    fn info(&self) -> String {
+        let snapshot = &self.snapshot;
+        let mut info = String::from("# Server\r\n");
+        info.push_str(&format!(
+            "redis_version:{}\r\n",
+            snapshot.redis_version().unwrap_or("0.0.0")
+        ));
+        info.push_str(&format!("rdb_version:{}\r\n", snapshot.version));
+        info.push_str(&format!("rdb_file:{}\r\n", snapshot.path));
+        info.push_str("uptime_in_seconds:0\r\n");
+        info.push_str("\r\n# Memory\r\n");
+        let used_memory: u64 = snapshot
+            .aux
+            .get("used-mem")
+            .and_then(|m| m.parse().ok())
+            .unwrap_or(0);
+        info.push_str(&format!("used_memory:{}\r\n", used_memory));
+        info.push_str(&format!("used_memory_human:{}B\r\n", used_memory));
+        info.push_str("\r\n# Keyspace\r\n");
+        for (db, database) in &snapshot.databases {
+            if database.len() > 0 {
+                info.push_str(&format!(
+                    "db{}:keys={},expires={},avg_ttl=0\r\n",
+                    db,
+                    database.len(),
+                    database.expires
+                ));
+            }
+        }
+        info
+    }
+
+    fn config(&self, args: &[Vec<u8>]) -> Reply {
+        if !arg_str(args, 0)?.eq_ignore_ascii_case("GET") {
+            return Err(error("CONFIG SET is not supported for RDB snapshots"));
+        }
+        let parameter = arg_str(args, 1)?;
+        if parameter != "maxmemory-policy" {
+            return Ok(Value::Array(vec![]));
+        }
+
+        // The policy itself is not saved; infer it from the access metadata
+        let entries = self.database().entries();
+        let policy = if entries.iter().any(|e| e.lfu_freq.is_some()) {
+            "allkeys-lfu"
+        } else if entries.iter().any(|e| e.idle_seconds.is_some()) {
+            "allkeys-lru"
+        } else {
+            "noeviction"
+        };
+        Ok(Value::Array(vec![bulk_str(&parameter), bulk_str(policy)]))
+    }
+
+    fn ttl(&self, name: &str, args: &[Vec<u8>]) -> Reply {
+        let key = args.first().ok_or_else(syntax_error)?;
+        let Some(entry) = self.entry(key) else {
+            return Ok(Value::Int(-2));
+        };
+
+        let value = match (name, entry.expires_at_ms) {
+            (_, None) => -1,
+            ("TTL", Some(_)) => (self.pttl(entry) + 500) / 1000,
+            ("PTTL", Some(_)) => self.pttl(entry),
+            ("EXPIRETIME", Some(at)) => at / 1000,
+            (_, Some(at)) => at,
+        };
+        Ok(Value::Int(value))
+    }
+
+    fn object(&self, args: &[Vec<u8>]) -> Reply {
+        let subcommand = arg_str(args, 0)?.to_uppercase();
+        let key = args.get(1).ok_or_else(syntax_error)?;
+        let Some(entry) = self.entry(key) else {
+            return Ok(Value::Nil);
+        };
+
+        match subcommand.as_str() {
+            "ENCODING" => Ok(bulk_str(entry.encoding)),
+            "REFCOUNT" => Ok(Value::Int(1)),
+            "FREQ" => entry
+                .lfu_freq
+                .map(|f| Value::Int(f as i64))
+                .ok_or_else(|| error("no LFU information for this key in the snapshot")),
+            "IDLETIME" => entry
+                .idle_seconds
+                .map(|s| Value::Int(s as i64))
+                .ok_or_else(|| error("no LRU information for this key in the snapshot")),
+            _ => Err(syntax_error()),
+        }
+    }
+
+    // Memory usage cannot be measured without a server; report the size the
+    // key takes in the file, which tracks real usage closely for most types
+    fn memory(&self, args: &[Vec<u8>]) -> Reply {
+        if !arg_str(args, 0)?.eq_ignore_ascii_case("USAGE") {
+            return Err(syntax_error());
+        }
+        let key = args.get(1).ok_or_else(syntax_error)?;
+        Ok(self
+            .entry(key)
+            .map(|e| Value::Int(e.serialized_size as i64))
+            .unwrap_or(Value::Nil))
+    }
+
+    fn debug(&self, args: &[Vec<u8>]) -> Reply {
+        if !arg_str(args, 0)?.eq_ignore_ascii_case("OBJECT") {
+            return Err(syntax_error());
+        }
+        let key = args.get(1).ok_or_else(syntax_error)?;
+        let entry = self.entry(key).ok_or_else(|| error("no such key"))?;
+        Ok(Value::SimpleString(format!(
+            "Value at:0x0 refcount:1 encoding:{} serializedlength:{} lru_seconds_idle:{}",
+            entry.encoding,
+            entry.serialized_size,
+            entry.idle_seconds.unwrap_or(0)
+        )))
+    }
+
+    // Cursors are positions in the sorted key list, so they stay valid for
+    // the lifetime of the snapshot
+    fn scan(&self, args: &[Vec<u8>]) -> Reply {
+        let cursor = arg_i64(args, 0)?.max(0) as usize;
+        let mut pattern: Option<&[u8]> = None;
+        let mut count = 10;
+        let mut type_filter: Option<String> = None;
+
+        let mut i = 1;
+        while i < args.len() {
+            match arg_str(args, i)?.to_uppercase().as_str() {
+                "MATCH" => pattern = Some(args.get(i + 1).ok_or_else(syntax_error)?),
+                "COUNT" => count = arg_i64(args, i + 1)?.max(1) as usize,
+                "TYPE" => type_filter = Some(arg_str(args, i + 1)?),
+                _ => return Err(syntax_error()),
+            }
+            i += 2;
+        }
+
+        let entries = self.database().entries();
+        let end = (cursor + count).min(entries.len());
+        let keys = entries
+            .get(cursor..end)
+            .unwrap_or(&[])
+            .iter()
+            .filter(|e| pattern.is_none_or(|p| glob_match(p, &e.key)))
+            .filter(|e| {
+                type_filter
+                    .as_deref()
+                    .is_none_or(|t| e.value.type_name().eq_ignore_ascii_case(t))
+            })
+            .map(|e| bulk(&e.key))
+            .collect();
+
+        let next = if end >= entries.len() { 0 } else { end };
+        Ok(Value::Array(vec![
+            bulk_str(&next.to_string()),
+            Value::Array(keys),
+        ]))
+    }
+
+    fn string_command(&self, name: &str, args: &[Vec<u8>]) -> Reply {
+        let key = args.first().ok_or_else(syntax_error)?;
+        let value = match self.entry(key).map(|e| &e.value) {
+            Some(RdbValue::String(value)) => value.as_slice(),
+            Some(_) => return Err(wrong_type()),
+            None if name == "GET" => return Ok(Value::Nil),
+            None => &[],
+        };
+
+        match name {
+            "GET" => Ok(bulk(value)),
+            "STRLEN" => Ok(Value::Int(value.len() as i64)),
+            "GETRANGE" | "SUBSTR" => {
+                let range = index_range(arg_i64(args, 1)?, arg_i64(args, 2)?, value.len());
+                Ok(bulk(range.map(|(s, e)| &value[s..=e]).unwrap_or(&[])))
+            }
+            "GETBIT" => {
+                let offset = arg_i64(args, 1)?.max(0) as usize;
+                let bit = value
+                    .get(offset / 8)
+                    .map(|b| (b >> (7 - offset % 8)) & 1)
+                    .unwrap_or(0);
+                Ok(Value::Int(bit as i64))
+            }
+            "BITCOUNT" => {
+                let bytes = match (args.get(1), args.get(2)) {
+                    (Some(_), Some(_)) => {
+                        index_range(arg_i64(args, 1)?, arg_i64(args, 2)?, value.len())
+                            .map(|(s, e)| &value[s..=e])
+                            .unwrap_or(&[])
+                    }
+                    _ => value,
+                };
+                Ok(Value::Int(
+                    bytes.iter().map(|b| b.count_ones() as i64).sum(),
+                ))
+            }
+            "BITPOS" => {
+                let target = arg_i64(args, 1)? != 0;
+                let start = args.get(2).map(|_| arg_i64(args, 2)).transpose()?;
+                let end = args.get(3).map(|_| arg_i64(args, 3)).transpose()?;
+                Ok(Value::Int(bitpos(value, target, start, end)))
+            }
+            _ => Ok(Value::Int(
+                hll_count(value).ok_or("WRONGTYPE Key is not a valid HyperLogLog string value.")?
+                    as i64,
+            )),
+        }
+    }
+
+    fn list_command(&self, name: &str, args: &[Vec<u8>]) -> Reply {
+        let key = args.first().ok_or_else(syntax_error)?;
+        let items: &[Vec<u8>] = match self.entry(key).map(|e| &e.value) {
+            Some(RdbValue::List(items)) => items,
+            Some(_) => return Err(wrong_type()),
+            None => &[],
+        };
+
+        match name {
+            "LLEN" => Ok(Value::Int(items.len() as i64)),
+            "LINDEX" => {
+                let index = arg_i64(args, 1)?;
+                Ok(index_range(index, index, items.len())
+                    .map(|(i, _)| bulk(&items[i]))
+                    .unwrap_or(Value::Nil))
+            }
+            _ => {
+                let range = index_range(arg_i64(args, 1)?, arg_i64(args, 2)?, items.len());
+                Ok(Value::Array(
+                    range
+                        .map(|(s, e)| items[s..=e].iter().map(|i| bulk(i)).collect())
+                        .unwrap_or_default(),
+                ))
+            }
+        }
+    }
+
+    fn set_command(&self, name: &str, args: &[Vec<u8>]) -> Reply {
+        let key = args.first().ok_or_else(syntax_error)?;
+        let members: &[Vec<u8>] = match self.entry(key).map(|e| &e.value) {
+            Some(RdbValue::Set(members)) => members,
+            Some(_) => return Err(wrong_type()),
+            None => &[],
+        };
+
+        match name {
+            "SCARD" => Ok(Value::Int(members.len() as i64)),
+            "SMEMBERS" => Ok(Value::Array(members.iter().map(|m| bulk(m)).collect())),
+            "SISMEMBER" => {
+                let member = args.get(1).ok_or_else(syntax_error)?;
+                Ok(Value::Int(members.contains(member) as i64))
+            }
+            _ => collection_scan(args, members, |m| vec![bulk(m)], |m| m),
+        }
+    }
+
+    fn zset_command(&self, name: &str, args: &[Vec<u8>]) -> Reply {
+        let key = args.first().ok_or_else(syntax_error)?;
+        let members: &[(Vec<u8>, f64)] = match self.entry(key).map(|e| &e.value) {
+            Some(RdbValue::SortedSet(members)) => members,
+            Some(_) => return Err(wrong_type()),
+            None => &[],
+        };
+
+        match name {
+            "ZCARD" => Ok(Value::Int(members.len() as i64)),
+            "ZSCORE" => {
+                let member = args.get(1).ok_or_else(syntax_error)?;
+                Ok(members
+                    .iter()
+                    .find(|(m, _)| m == member)
+                    .map(|(_, s)| bulk_str(&format_score(*s)))
+                    .unwrap_or(Value::Nil))
+            }
+            "ZSCAN" => collection_scan(
+                args,
+                members,
+                |(m, s)| vec![bulk(m), bulk_str(&format_score(*s))],
+                |(m, _)| m,
+            ),
+            _ => {
+                let mut with_scores = false;
+                let mut reverse = name == "ZREVRANGE";
+                for option in args.iter().skip(3) {
+                    match String::from_utf8_lossy(option).to_uppercase().as_str() {
+                        "WITHSCORES" => with_scores = true,
+                        "REV" => reverse = true,
+                        _ => {
+                            return Err(error("only index ranges are supported for RDB snapshots"))
+                        }
+                    }
+                }
+
+                let ordered: Vec<&(Vec<u8>, f64)> = if reverse {
+                    members.iter().rev().collect()
+                } else {
+                    members.iter().collect()
+                };
+                let range = index_range(arg_i64(args, 1)?, arg_i64(args, 2)?, ordered.len());
+
+                let mut reply = Vec::new();
+                if let Some((start, end)) = range {
+                    for (member, score) in &ordered[start..=end] {
+                        reply.push(bulk(member));
+                        if with_scores {
+                            reply.push(bulk_str(&format_score(*score)));
+                        }
+                    }
+                }
+                Ok(Value::Array(reply))
+            }
+        }
+    }
+
+    fn hash_command(&self, name: &str, args: &[Vec<u8>]) -> Reply {
+        let key = args.first().ok_or_else(syntax_error)?;
+        let fields: &[HashField] = match self.entry(key).map(|e| &e.value) {
+            Some(RdbValue::Hash(fields)) => fields,
+            Some(_) => return Err(wrong_type()),
+            None => &[],
+        };
+        let find = |field: &[u8]| fields.iter().find(|f| f.field == field);
+
+        match name {
+            "HLEN" => Ok(Value::Int(fields.len() as i64)),
+            "HGET" => Ok(find(args.get(1).ok_or_else(syntax_error)?)
+                .map(|f| bulk(&f.value))
+                .unwrap_or(Value::Nil)),
+            "HEXISTS" => Ok(Value::Int(
+                find(args.get(1).ok_or_else(syntax_error)?).is_some() as i64,
+            )),
+            "HGETALL" => Ok(Value::Array(
+                fields
+                    .iter()
+                    .flat_map(|f| [bulk(&f.field), bulk(&f.value)])
+                    .collect(),
+            )),
+            "HKEYS" => Ok(Value::Array(
+                fields.iter().map(|f| bulk(&f.field)).collect(),
+            )),
+            "HVALS" => Ok(Value::Array(
+                fields.iter().map(|f| bulk(&f.value)).collect(),
+            )),
+            "HSCAN" => collection_scan(
+                args,
+                fields,
+                |f| vec![bulk(&f.field), bulk(&f.value)],
+                |f| &f.field,
+            ),
+            _ => {
+                // HTTL key FIELDS numfields field [field ...]
+                if !arg_str(args, 1)?.eq_ignore_ascii_case("FIELDS") {
+                    return Err(syntax_error());
+                }
+                let count = arg_i64(args, 2)?.max(0) as usize;
+                let requested = args.get(3..3 + count).ok_or_else(syntax_error)?;
+                let now = self.now_ms();
+
+                Ok(Value::Array(
+                    requested
+                        .iter()
+                        .map(|field| {
+                            let value = match find(field) {
+                                None => -2,
+                                Some(HashField {
+                                    expires_at_ms: None,
+                                    ..
+                                }) => -1,
+                                Some(HashField {
+                                    expires_at_ms: Some(at),
+                                    ..
+                                }) => match name {
+                                    "HTTL" => ((at - now).max(0) + 500) / 1000,
+                                    "HPTTL" => (at - now).max(0),
+                                    "HEXPIRETIME" => at / 1000,
+                                    _ => *at,
+                                },
+                            };
+                            Value::Int(value)
+                        })
+                        .collect(),
+                ))
+            }
+        }
+    }
+
+    fn stream_command(&self, name: &str, args: &[Vec<u8>]) -> Reply {
+        let key = args.first().ok_or_else(syntax_error)?;
+        let stream = match self.entry(key).map(|e| &e.value) {
+            Some(RdbValue::Stream(stream)) => Some(stream),
+            Some(_) => return Err(wrong_type()),
+            None => None,
+        };
+
+        if name == "XLEN" {
+            return Ok(Value::Int(stream.map(|s| s.length as i64).unwrap_or(0)));
+        }
+
+        // XRANGE key start end / XREVRANGE key end start
+        let (low, high) = if name == "XRANGE" {
+            (arg_str(args, 1)?, arg_str(args, 2)?)
+        } else {
+            (arg_str(args, 2)?, arg_str(args, 1)?)
+        };
+        let low = parse_stream_bound(&low, false).ok_or_else(|| error("Invalid stream ID"))?;
+        let high = parse_stream_bound(&high, true).ok_or_else(|| error("Invalid stream ID"))?;
+        let count = match arg_str(args, 3) {
+            Ok(option) if option.eq_ignore_ascii_case("COUNT") => arg_i64(args, 4)?.max(0) as usize,
+            Ok(_) => return Err(syntax_error()),
+            Err(_) => usize::MAX,
+        };
+
+        let entries: &[StreamEntry] = stream.map(|s| s.entries.as_slice()).unwrap_or(&[]);
+        let in_range = |e: &&StreamEntry| e.id >= low && e.id <= high;
+        let to_value = |e: &StreamEntry| {
+            Value::Array(vec![
+                bulk_str(&format!("{}-{}", e.id.0, e.id.1)),
+                Value::Array(
+                    e.fields
+                        .iter()
+                        .flat_map(|(f, v)| [bulk(f), bulk(v)])
+                        .collect(),
+                ),
+            ])
+        };
+
+        let reply: Vec<Value> = if name == "XRANGE" {
+            entries
+                .iter()
+                .filter(in_range)
+                .take(count)
+                .map(to_value)
+                .collect()
+        } else {
+            entries
+                .iter()
+                .rev()
+                .filter(in_range)
+                .take(count)
+                .map(to_value)
+                .collect()
+        };
+        Ok(Value::Array(reply))
+    }
+}
+
+// SSCAN/ZSCAN/HSCAN over an in-memory collection; the cursor is an index
+fn collection_scan<T>(
+    args: &[Vec<u8>],
+    items: &[T],
+    to_values: impl Fn(&T) -> Vec<Value>,
+    name_of: impl Fn(&T) -> &[u8],
+) -> Reply {
+    let cursor = arg_i64(args, 1)?.max(0) as usize;
+    let mut pattern: Option<&[u8]> = None;
+    let mut count = 10;
+
+    let mut i = 2;
+    while i < args.len() {
+        match arg_str(args, i)?.to_uppercase().as_str() {
+            "MATCH" => pattern = Some(args.get(i + 1).ok_or_else(syntax_error)?),
+            "COUNT" => count = arg_i64(args, i + 1)?.max(1) as usize,
+            _ => return Err(syntax_error()),
+        }
+        i += 2;
+    }
+
+    let end = (cursor + count).min(items.len());
+    let values = items
+        .get(cursor..end)
+        .unwrap_or(&[])
+        .iter()
+        .filter(|item| pattern.is_none_or(|p| glob_match(p, name_of(item))))
+        .flat_map(&to_values)
+        .collect();
+
+    let next = if end >= items.len() { 0 } else { end };
+    Ok(Value::Array(vec![
+        bulk_str(&next.to_string()),
+        Value::Array(values),
+    ]))
+}
+
+fn parse_stream_bound(id: &str, upper: bool) -> Option<StreamId> {
+    match id {
+        "-" => Some((0, 0)),
+        "+" => Some((u64::MAX, u64::MAX)),
+        _ => {
+            let id = id.strip_prefix('(').unwrap_or(id);
+            match id.split_once('-') {
+                Some((ms, seq)) => Some((ms.parse().ok()?, seq.parse().ok()?)),
+                None => Some((id.parse().ok()?, if upper { u64::MAX } else { 0 })),
+            }
+        }
+    }
+}
+
+fn bitpos(value: &[u8], target: bool, start: Option<i64>, end: Option<i64>) -> i64 {
+    let explicit_end = end.is_some();
+    let Some((start, end)) = index_range(start.unwrap_or(0), end.unwrap_or(-1), value.len()) else {
+        return if target || explicit_end { -1 } else { 0 };
+    };
+
+    for (i, byte) in value[start..=end].iter().enumerate() {
+        for bit in 0..8 {
+            if ((byte >> (7 - bit)) & 1 == 1) == target {
+                return ((start + i) * 8 + bit) as i64;
+            }
+        }
+    }
+
+    // Looking for a clear bit past the end of the string finds the padding
+    if !target && !explicit_end {
+        (value.len() * 8) as i64
+    } else {
+        -1
+    }
+}
+
+// HyperLogLog cardinality, using the cached value when it is still valid
+// and otherwise the same estimator as PFCOUNT
+fn hll_count(raw: &[u8]) -> Option<u64> {
+    if raw.len() < 16 || &raw[..4] != b"HYLL" {
+        return None;
+    }
+    if raw[15] & 0x80 == 0 {
+        return Some(u64::from_le_bytes(raw[8..16].try_into().ok()?));
+    }
+
+    let mut histogram = [0u64; 64];
+    let registers = &raw[16..];
+    match raw[4] {
+        0 => {
+            // Dense: 16384 little-endian 6-bit registers
+            for i in 0..HLL_REGISTERS {
+                let bit = i * 6;
+                let (byte, shift) = (bit / 8, bit % 8);
+                let low = *registers.get(byte)? as u16;
+                let high = *registers.get(byte + 1).unwrap_or(&0) as u16;
+                let value = (((high << 8) | low) >> shift) & 63;
+                histogram[value as usize] += 1;
+            }
+        }
+        1 => {
+            // Sparse: run-length opcodes ZERO, XZERO and VAL
+            let mut i = 0;
+            while i < registers.len() {
+                let op = registers[i];
+                if op & 0xc0 == 0 {
+                    histogram[0] += (op & 0x3f) as u64 + 1;
+                    i += 1;
+                } else if op & 0xc0 == 0x40 {
+                    let len = ((((op & 0x3f) as u64) << 8) | *registers.get(i + 1)? as u64) + 1;
+                    histogram[0] += len;
+                    i += 2;
+                } else {
+                    let value = ((op >> 2) & 0x1f) as usize + 1;
+                    histogram[value] += (op & 0x03) as u64 + 1;
+                    i += 1;
+                }
+            }
+        }
+        _ => return None,
+    }
+
+    let m = HLL_REGISTERS as f64;
+    let mut z = m * hll_tau((m - histogram[HLL_Q + 1] as f64) / m);
+    for j in (1..=HLL_Q).rev() {
+        z += histogram[j] as f64;
+        z *= 0.5;
+    }
+    z += m * hll_sigma(histogram[0] as f64 / m);
+
+    const ALPHA_INF: f64 = 0.721_347_520_444_481_7;
+    Some((ALPHA_INF * m * m / z).round() as u64)
+}
+
+fn hll_sigma(mut x: f64) -> f64 {
+    if x == 1.0 {
+        return f64::INFINITY;
+    }
+    let mut y = 1.0;
+    let mut z = x;
+    loop {
+        x *= x;
+        let previous = z;
+        z += x * y;
+        y += y;
+        if previous == z {
+            return z;
+        }
+    }
+}
+
+fn hll_tau(mut x: f64) -> f64 {
+    if x == 0.0 || x == 1.0 {
+        return 0.0;
+    }
+    let mut y = 1.0;
+    let mut z = 1.0 - x;
+    loop {
+        x = x.sqrt();
+        let previous = z;
+        y *= 0.5;
+        z -= (1.0 - x).powi(2) * y;
+        if previous == z {
+            return z / 3.0;
+        }
+    }
+}
+
+// Split a packed RESP request (as produced by `Cmd`/`Pipeline`) into the
+// argument lists of its commands
+fn parse_packed_commands(mut data: &[u8]) -> RedisResult<Vec<Vec<Vec<u8>>>> {
+    fn line<'a>(data: &mut &'a [u8]) -> Option<&'a [u8]> {
+        let end = data.windows(2).position(|w| w == b"\r\n")?;
+        let line = &data[..end];
+        *data = &data[end + 2..];
+        Some(line)
+    }
+    fn number(line: &[u8]) -> Option<usize> {
+        std::str::from_utf8(line).ok()?.parse().ok()
+    }
+    let invalid = || redis::RedisError::from((ErrorKind::ClientError, "Invalid packed command"));
+
+    let mut commands = Vec::new();
+    while !data.is_empty() {
+        let header = line(&mut data).ok_or_else(invalid)?;
+        let argc = header
+            .strip_prefix(b"*")
+            .and_then(number)
+            .ok_or_else(invalid)?;
+
+        let mut args = Vec::with_capacity(argc);
+        for _ in 0..argc {
+            let len = line(&mut data)
+                .and_then(|l| l.strip_prefix(b"$"))
+                .and_then(number)
+                .ok_or_else(invalid)?;
+            if data.len() < len + 2 {
+                return Err(invalid());
+            }
+            args.push(data[..len].to_vec());
+            data = &data[len + 2..];
+        }
+        commands.push(args);
+    }
+    Ok(commands)
+}
+
+impl ConnectionLike for SnapshotConnection {
+    fn req_packed_command(&mut self, cmd: &[u8]) -> RedisResult<Value> {
+        let commands = parse_packed_commands(cmd)?;
+        let args = commands
+            .first()
+            .ok_or_else(|| redis::RedisError::from((ErrorKind::ClientError, "Empty command")))?;
+        Ok(self.execute(args).unwrap_or_else(error_value))
+    }
+
+    fn req_packed_commands(
+        &mut self,
+        cmd: &[u8],
+        offset: usize,
+        count: usize,
+    ) -> RedisResult<Vec<Value>> {
+        let mut replies = Vec::new();
+        let mut queued: Option<Vec<Value>> = None;
+
+        for args in parse_packed_commands(cmd)? {
+            let name = String::from_utf8_lossy(args.first().map(|a| a.as_slice()).unwrap_or(b""))
+                .to_uppercase();
+            // Atomic pipelines arrive wrapped in MULTI/EXEC
+            let reply = match (name.as_str(), queued.as_mut()) {
+                ("MULTI", None) => {
+                    queued = Some(Vec::new());
+                    Value::Okay
+                }
+                ("EXEC", Some(_)) => Value::Array(queued.take().unwrap_or_default()),
+                (_, Some(results)) => {
+                    results.push(self.execute(&args).unwrap_or_else(error_value));
+                    Value::SimpleString("QUEUED".to_string())
+                }
+                _ => self.execute(&args).unwrap_or_else(error_value),
+            };
+            replies.push(reply);
+        }
+
+        Ok(replies.into_iter().skip(offset).take(count).collect())
+    }
+
+    fn get_db(&self) -> i64 {
+        self.db as i64
+    }
+
+    fn check_connection(&mut self) -> bool {
+        true
+    }
+
+    fn is_open(&self) -> bool {
+        true
+    }
+}
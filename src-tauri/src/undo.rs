@@ -0,0 +1,323 @@
+use crate::audit::AuditLog;
+use crate::export::BinaryString;
+use crate::keyops;
+use redis::{Arg, ConnectionLike, FromRedisValue, RedisResult};
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const DEFAULT_UNDO_ENTRIES: usize = 50;
+const MAX_UNDO_ENTRIES: usize = 1000;
+// DUMP payloads held per connection; the oldest entries are evicted first and
+// a single key larger than this is not journaled
+const MAX_JOURNAL_BYTES: usize = 64 * 1024 * 1024;
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+// The state of one key before a destructive edit
+#[derive(Debug, Clone)]
+pub struct UndoEntry {
+    id: u64,
+    timestamp: u64,
+    operation: String,
+    key: BinaryString,
+    db: u32,
+    payload: Option<Vec<u8>>, // None if the key did not exist; undo deletes it
+    expire_at_ms: i64,        // Unix milliseconds; 0 if persistent
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UndoEntryInfo {
+    pub id: u64,
+    pub timestamp: u64, // Unix milliseconds
+    pub operation: String,
+    pub key: BinaryString,
+    pub db: u32,
+    pub existed: bool,
+    pub size_bytes: usize,
+    pub expire_at_ms: Option<i64>, // Unix milliseconds
+}
+
+#[derive(Debug, Serialize)]
+pub struct UndoJournalInfo {
+    pub enabled: bool,
+    pub max_entries: usize,
+    pub total_bytes: usize,
+    pub entries: Vec<UndoEntryInfo>, // Newest first
+}
+
+#[derive(Debug, Serialize)]
+pub struct UndoResult {
+    pub id: u64,
+    pub operation: String,
+    pub key: BinaryString,
+    pub db: u32,
+    pub restored: bool,
+    pub message: Option<String>, // Why the key could not be restored
+}
+
+struct ConnectionJournal {
+    max_entries: usize,
+    entries: VecDeque<UndoEntry>,
+    bytes: usize,
+}
+
+impl ConnectionJournal {
+    fn push(&mut self, entry: UndoEntry) {
+        self.bytes += entry.payload.as_ref().map_or(0, Vec::len);
+        self.entries.push_back(entry);
+        while self.entries.len() > self.max_entries || self.bytes > MAX_JOURNAL_BYTES {
+            let Some(evicted) = self.entries.pop_front() else {
+                break;
+            };
+            self.bytes -= evicted.payload.as_ref().map_or(0, Vec::len);
+        }
+    }
+
+    fn pop(&mut self) -> Option<UndoEntry> {
+        let entry = self.entries.pop_back()?;
+        self.bytes -= entry.payload.as_ref().map_or(0, Vec::len);
+        Some(entry)
+    }
+}
+
+// Opt-in, in-memory journal of DUMP snapshots taken before destructive
+// edits. Journals are per connection and dropped on disconnect, so undo
+// history does not survive a reconnect or a restart of the app.
+#[derive(Clone, Default)]
+pub struct UndoJournal {
+    journals: Arc<Mutex<HashMap<String, ConnectionJournal>>>,
+    next_id: Arc<Mutex<u64>>,
+}
+
+impl UndoJournal {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Enable with a maximum number of entries, or disable with None. Shrinking
+    // the limit keeps the newest entries.
+    pub fn configure(&self, connection_id: &str, max_entries: Option<usize>) {
+        let mut journals = self.journals.lock().unwrap();
+        let Some(max_entries) = max_entries else {
+            journals.remove(connection_id);
+            return;
+        };
+        let max_entries = max_entries.clamp(1, MAX_UNDO_ENTRIES);
+        let journal = journals
+            .entry(connection_id.to_string())
+            .or_insert_with(|| ConnectionJournal {
+                max_entries,
+                entries: VecDeque::new(),
+                bytes: 0,
+            });
+        journal.max_entries = max_entries;
+        while journal.entries.len() > max_entries {
+            if let Some(evicted) = journal.entries.pop_front() {
+                journal.bytes -= evicted.payload.as_ref().map_or(0, Vec::len);
+            }
+        }
+    }
+
+    pub fn remove(&self, connection_id: &str) {
+        self.journals.lock().unwrap().remove(connection_id);
+    }
+
+    // Snapshot `key` before `operation` modifies it. Returns None when the
+    // journal is off for this connection, the key is too large to keep, or
+    // the snapshot failed; the edit itself goes ahead either way.
+    pub fn capture(
+        &self,
+        connection_id: &str,
+        conn: &mut dyn ConnectionLike,
+        operation: &str,
+        key: &[u8],
+    ) -> Option<UndoEntry> {
+        if !self.journals.lock().unwrap().contains_key(connection_id) {
+            return None;
+        }
+
+        let snapshot: RedisResult<(Option<Vec<u8>>, i64)> = redis::pipe()
+            .cmd("DUMP")
+            .arg(key)
+            .cmd("PTTL")
+            .arg(key)
+            .query(conn);
+        let (payload, pttl) = match snapshot {
+            Ok(snapshot) => snapshot,
+            Err(e) => {
+                eprintln!(
+                    "Undo: not journaling {} on '{}': {}",
+                    operation,
+                    String::from_utf8_lossy(key),
+                    e
+                );
+                return None;
+            }
+        };
+        if payload
+            .as_ref()
+            .is_some_and(|p| p.len() > MAX_JOURNAL_BYTES)
+        {
+            return None;
+        }
+
+        let id = {
+            let mut next_id = self.next_id.lock().unwrap();
+            *next_id += 1;
+            *next_id
+        };
+        let timestamp = now_ms();
+        // Kept absolute, like backups, so a key undone later does not get its
+        // full TTL back
+        let expire_at_ms = if pttl >= 0 {
+            timestamp as i64 + pttl.max(1)
+        } else {
+            0
+        };
+        Some(UndoEntry {
+            id,
+            timestamp,
+            operation: operation.to_string(),
+            key: BinaryString(key.to_vec()),
+            db: conn.get_db() as u32,
+            payload,
+            expire_at_ms,
+        })
+    }
+
+    // Journal a captured snapshot once the edit it guarded has succeeded
+    pub fn commit<T>(
+        &self,
+        connection_id: &str,
+        entry: Option<UndoEntry>,
+        result: &Result<T, String>,
+    ) {
+        let (Some(entry), Ok(_)) = (entry, result) else {
+            return;
+        };
+        if let Some(journal) = self.journals.lock().unwrap().get_mut(connection_id) {
+            journal.push(entry);
+        }
+    }
+
+    // Run a destructive command through the audit log, journaling the key
+    // named by its first argument beforehand
+    pub fn run<T: FromRedisValue>(
+        &self,
+        audit: &AuditLog,
+        connection_id: &str,
+        conn: &mut dyn ConnectionLike,
+        cmd: &redis::Cmd,
+    ) -> Result<T, String> {
+        let args: Vec<&[u8]> = cmd
+            .args_iter()
+            .map(|arg| match arg {
+                Arg::Simple(bytes) => bytes,
+                Arg::Cursor => b"0".as_slice(),
+            })
+            .collect();
+        let [operation, key, ..] = args.as_slice() else {
+            return Err("Command has no key".to_string());
+        };
+
+        let operation = String::from_utf8_lossy(operation).to_uppercase();
+        let entry = self.capture(connection_id, conn, &operation, key);
+        let result = audit.run(connection_id, conn, cmd);
+        self.commit(connection_id, entry, &result);
+        result
+    }
+
+    pub fn info(&self, connection_id: &str) -> UndoJournalInfo {
+        let journals = self.journals.lock().unwrap();
+        let Some(journal) = journals.get(connection_id) else {
+            return UndoJournalInfo {
+                enabled: false,
+                max_entries: 0,
+                total_bytes: 0,
+                entries: Vec::new(),
+            };
+        };
+        UndoJournalInfo {
+            enabled: true,
+            max_entries: journal.max_entries,
+            total_bytes: journal.bytes,
+            entries: journal
+                .entries
+                .iter()
+                .rev()
+                .map(|entry| UndoEntryInfo {
+                    id: entry.id,
+                    timestamp: entry.timestamp,
+                    operation: entry.operation.clone(),
+                    key: entry.key.clone(),
+                    db: entry.db,
+                    existed: entry.payload.is_some(),
+                    size_bytes: entry.payload.as_ref().map_or(0, Vec::len),
+                    expire_at_ms: (entry.expire_at_ms > 0).then_some(entry.expire_at_ms),
+                })
+                .collect(),
+        }
+    }
+
+    // Undo the newest `count` operations, newest first. Keys expire when they
+    // would have without the edit; one whose expiry has since passed is
+    // deleted by the server instead of restored. RESTORE ... ABSTTL needs
+    // Redis 5.0. Stops at the first failure, which stays in the journal.
+    pub fn undo(
+        &self,
+        connection_id: &str,
+        conn: &mut dyn ConnectionLike,
+        count: usize,
+    ) -> Result<Vec<UndoResult>, String> {
+        let mut results = Vec::new();
+        for _ in 0..count {
+            let entry = {
+                let mut journals = self.journals.lock().unwrap();
+                let journal = journals
+                    .get_mut(connection_id)
+                    .ok_or("The undo journal is not enabled for this connection")?;
+                match journal.pop() {
+                    Some(entry) => entry,
+                    None => break,
+                }
+            };
+
+            let restored = keyops::in_db(conn, entry.db, |conn| match &entry.payload {
+                Some(payload) => redis::cmd("RESTORE")
+                    .arg(&entry.key)
+                    .arg(entry.expire_at_ms)
+                    .arg(payload)
+                    .arg("REPLACE")
+                    .arg("ABSTTL")
+                    .query::<()>(conn),
+                None => redis::cmd("DEL").arg(&entry.key).query::<()>(conn),
+            });
+
+            let mut result = UndoResult {
+                id: entry.id,
+                operation: entry.operation.clone(),
+                key: entry.key.clone(),
+                db: entry.db,
+                restored: restored.is_ok(),
+                message: None,
+            };
+            if let Err(e) = restored {
+                result.message = Some(e.to_string());
+                results.push(result);
+                if let Some(journal) = self.journals.lock().unwrap().get_mut(connection_id) {
+                    journal.push(entry);
+                }
+                break;
+            }
+            results.push(result);
+        }
+        Ok(results)
+    }
+}
@@ -8,7 +8,7 @@ import {
   ChevronRight,
 } from "lucide-react";
 import { useRedisStore } from "../store/useRedisStore";
-import { redisApi } from "../lib/tauri-api";
+import { redisApi, confirmationToken } from "../lib/tauri-api";
 import { useToast } from "../lib/toast-context";
 import { IconButton, Badge, ConfirmDialog } from "./ui";
 import { formatRedisResponse, highlightCommand } from "../lib/cli-formatter";
@@ -116,6 +116,8 @@ export function CliPanel() {
   const [historyIndex, setHistoryIndex] = useState(-1);
   const [showDangerConfirm, setShowDangerConfirm] = useState(false);
   const [pendingCommand, setPendingCommand] = useState("");
+  // Set when the connection's command policy asked for confirmation
+  const [pendingToken, setPendingToken] = useState<string | null>(null);
   const [collapsedIndices, setCollapsedIndices] = useState<Set<number>>(
     new Set(),
   );
@@ -144,11 +146,18 @@ export function CliPanel() {
     historyEndRef.current?.scrollIntoView({ behavior: "smooth" });
   }, [history]);
 
-  const executeCommand = async (cmd: string) => {
+  const executeCommand = async (
+    cmd: string,
+    approvedToken?: string | null,
+  ) => {
     if (!activeConnectionId) return;
 
     try {
-      const result = await redisApi.executeCommand(activeConnectionId, cmd);
+      const result = await redisApi.executeCommand(
+        activeConnectionId,
+        cmd,
+        approvedToken ?? undefined,
+      );
       setHistory((prev) => [
         ...prev,
         {
@@ -163,7 +172,19 @@ export function CliPanel() {
         `Successfully executed: ${cmd.split(" ")[0]}`,
       );
     } catch (error) {
-      const errorMsg = error instanceof Error ? error.message : "Unknown error";
+      const token = confirmationToken(error);
+      if (token) {
+        setPendingCommand(cmd);
+        setPendingToken(token);
+        setShowDangerConfirm(true);
+        return;
+      }
+      const errorMsg =
+        typeof error === "string"
+          ? error
+          : error instanceof Error
+            ? error.message
+            : "Unknown error";
       setHistory((prev) => [
         ...prev,
         {
@@ -210,10 +231,22 @@ export function CliPanel() {
   };
 
   const confirmDangerousCommand = async () => {
-    setCommandHistory((prev) => [...prev, pendingCommand]);
-    setHistoryIndex(-1);
-    await executeCommand(pendingCommand);
+    const token = pendingToken;
     setShowDangerConfirm(false);
+    setPendingToken(null);
+    if (token && activeConnectionId) {
+      try {
+        await redisApi.confirmCommand(activeConnectionId, token);
+      } catch (error) {
+        toast.error("Confirmation failed", String(error));
+        setPendingCommand("");
+        return;
+      }
+    } else {
+      setCommandHistory((prev) => [...prev, pendingCommand]);
+      setHistoryIndex(-1);
+    }
+    await executeCommand(pendingCommand, token);
     setPendingCommand("");
   };
 
@@ -524,6 +557,7 @@ export function CliPanel() {
         onClose={() => {
           setShowDangerConfirm(false);
           setPendingCommand("");
+          setPendingToken(null);
         }}
         onConfirm={confirmDangerousCommand}
         title="Dangerous Command"
@@ -536,8 +570,9 @@ export function CliPanel() {
               {pendingCommand}
             </p>
             <p className="text-xs text-warning-light dark:text-warning-dark">
-              This operation may affect your database or server. Are you sure
-              you want to continue?
+              {pendingToken
+                ? "This connection's command policy requires confirmation for this command. Are you sure you want to continue?"
+                : "This operation may affect your database or server. Are you sure you want to continue?"}
             </p>
           </div>
         }
@@ -4,17 +4,46 @@ import {
   Lock,
   Database as DatabaseIcon,
   Shield,
+  ShieldAlert,
+  Eye,
   Network,
   Key,
   FileKey,
 } from "lucide-react";
 import { ConnectionConfig } from "../types/redis";
-import type { StoredConnection } from "../types/redis";
+import type { CommandPolicy, StoredConnection } from "../types/redis";
 import { redisApi } from "../lib/tauri-api";
 import { useRedisStore } from "../store/useRedisStore";
 import { useToast } from "../lib/toast-context";
 import { Dialog, Button, Input } from "./ui";
 
+// Policy lists are edited as comma-separated command names
+const splitCommands = (value: string) =>
+  value
+    .split(",")
+    .map((command) => command.trim())
+    .filter(Boolean);
+
+function buildPolicy(form: {
+  policy_deny: string;
+  policy_allow: string;
+  policy_confirm: string;
+  policy_max_scan_count: string;
+}): CommandPolicy | undefined {
+  const policy: CommandPolicy = {
+    deny: splitCommands(form.policy_deny),
+    allow: splitCommands(form.policy_allow),
+    confirm: splitCommands(form.policy_confirm),
+    max_scan_count: parseInt(form.policy_max_scan_count) || undefined,
+  };
+  const empty =
+    !policy.deny.length &&
+    !policy.allow.length &&
+    !policy.confirm.length &&
+    !policy.max_scan_count;
+  return empty ? undefined : policy;
+}
+
 interface ConnectionDialogProps {
   isOpen: boolean;
   onClose: () => void;
@@ -37,6 +66,11 @@ export function ConnectionDialog({
     password: "",
     database: 0,
     use_tls: false,
+    read_only: false,
+    policy_deny: "",
+    policy_allow: "",
+    policy_confirm: "",
+    policy_max_scan_count: "",
     ssh_tunnel_enabled: false,
     ssh_host: "",
     ssh_port: 22,
@@ -64,6 +98,12 @@ export function ConnectionDialog({
         password: "", // Will be loaded from keychain
         database: editConnection.database,
         use_tls: editConnection.use_tls,
+        read_only: editConnection.read_only ?? false,
+        policy_deny: editConnection.policy?.deny.join(", ") || "",
+        policy_allow: editConnection.policy?.allow.join(", ") || "",
+        policy_confirm: editConnection.policy?.confirm.join(", ") || "",
+        policy_max_scan_count:
+          editConnection.policy?.max_scan_count?.toString() || "",
         ssh_tunnel_enabled: editConnection.ssh_tunnel?.enabled || false,
         ssh_host: editConnection.ssh_tunnel?.ssh_host || "",
         ssh_port: editConnection.ssh_tunnel?.ssh_port || 22,
@@ -113,6 +153,11 @@ export function ConnectionDialog({
         password: "",
         database: 0,
         use_tls: false,
+        read_only: false,
+        policy_deny: "",
+        policy_allow: "",
+        policy_confirm: "",
+        policy_max_scan_count: "",
         ssh_tunnel_enabled: false,
         ssh_host: "",
         ssh_port: 22,
@@ -143,6 +188,8 @@ export function ConnectionDialog({
         password: formData.password || undefined,
         database: formData.database,
         use_tls: formData.use_tls,
+        read_only: formData.read_only,
+        policy: buildPolicy(formData),
         ssh_tunnel: formData.ssh_tunnel_enabled
           ? {
               enabled: true,
@@ -201,6 +248,8 @@ export function ConnectionDialog({
         password: formData.password || undefined,
         database: formData.database,
         use_tls: formData.use_tls,
+        read_only: formData.read_only,
+        policy: buildPolicy(formData),
         ssh_tunnel: formData.ssh_tunnel_enabled
           ? {
               enabled: true,
@@ -452,6 +501,77 @@ export function ConnectionDialog({
                 </span>
               </div>
             </label>
+            <label className="mt-2 flex items-center gap-3 py-2.5 px-3 border border-neutral-300 dark:border-neutral-700 rounded-lg cursor-pointer hover:bg-neutral-50 dark:hover:bg-neutral-800 transition-colors">
+              <input
+                type="checkbox"
+                checked={formData.read_only}
+                onChange={(e) =>
+                  setFormData({ ...formData, read_only: e.target.checked })
+                }
+                className="w-4 h-4 text-brand-600 bg-neutral-100 border-neutral-300 rounded focus:ring-brand-500 focus:ring-2"
+              />
+              <div className="flex items-center gap-2">
+                <Eye className="w-4 h-4 text-neutral-500" />
+                <span className="text-sm font-medium text-neutral-700 dark:text-neutral-300">
+                  Read-only (block writes)
+                </span>
+              </div>
+            </label>
+          </div>
+        </div>
+
+        {/* Command Policy */}
+        <div className="space-y-4 pt-2">
+          <h3 className="text-sm font-semibold text-neutral-700 dark:text-neutral-300 flex items-center gap-2">
+            <ShieldAlert className="w-4 h-4" />
+            Command Policy (Optional)
+          </h3>
+
+          <Input
+            label="Denied commands"
+            type="text"
+            value={formData.policy_deny}
+            onChange={(e) =>
+              setFormData({ ...formData, policy_deny: e.target.value })
+            }
+            placeholder="FLUSHALL, KEYS, DEBUG, CONFIG SET"
+          />
+
+          <Input
+            label="Allowed commands"
+            type="text"
+            value={formData.policy_allow}
+            onChange={(e) =>
+              setFormData({ ...formData, policy_allow: e.target.value })
+            }
+            placeholder="Any"
+            helperText="When set, every other command is rejected"
+          />
+
+          <div className="grid grid-cols-2 gap-4">
+            <Input
+              label="Require confirmation"
+              type="text"
+              value={formData.policy_confirm}
+              onChange={(e) =>
+                setFormData({ ...formData, policy_confirm: e.target.value })
+              }
+              placeholder="FLUSHDB, DEL"
+            />
+
+            <Input
+              label="Max SCAN COUNT"
+              type="number"
+              value={formData.policy_max_scan_count}
+              onChange={(e) =>
+                setFormData({
+                  ...formData,
+                  policy_max_scan_count: e.target.value,
+                })
+              }
+              min="1"
+              placeholder="No limit"
+            />
           </div>
         </div>
 
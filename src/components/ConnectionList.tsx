@@ -1,6 +1,6 @@
 import { useState } from "react";
-import { Database, Trash2, Edit2 } from "lucide-react";
-import { Dialog, Button, Badge, IconButton } from "./ui";
+import { Database, Trash2, Edit2, FileArchive } from "lucide-react";
+import { Dialog, Button, Badge, IconButton, Input } from "./ui";
 import { ConfirmDialog } from "./ui/ConfirmDialog";
 import { useRedisStore } from "../store/useRedisStore";
 import { useToast } from "../lib/toast-context";
@@ -27,6 +27,8 @@ export function ConnectionList({
   } = useRedisStore();
   const toast = useToast();
   const [connectingId, setConnectingId] = useState<string | null>(null);
+  const [rdbPath, setRdbPath] = useState("");
+  const [openingRdb, setOpeningRdb] = useState(false);
   const [deleteConfirm, setDeleteConfirm] = useState<{
     show: boolean;
     connection: StoredConnection | null;
@@ -88,6 +90,49 @@ export function ConnectionList({
     }
   };
 
+  // Open a dump.rdb file as a read-only connection
+  const handleOpenRdb = async () => {
+    const path = rdbPath.trim();
+    if (!path) return;
+
+    setOpeningRdb(true);
+    try {
+      const info = await redisApi.openRdbFile(path);
+      const fileName = path.split(/[\\/]/).pop() || path;
+
+      addConnection({
+        id: info.connection_id,
+        name: `${fileName} (read-only)`,
+        host: info.path,
+        port: 0,
+        database: info.database,
+        use_tls: false,
+      });
+      setActiveConnection(info.connection_id);
+
+      const keyCount = info.databases.reduce((sum, db) => sum + db.keys, 0);
+      const skipped = Object.values(info.skipped_modules).reduce(
+        (sum, count) => sum + count,
+        0,
+      );
+      toast.success(
+        "RDB file opened",
+        `${keyCount} keys (RDB v${info.rdb_version}${
+          info.redis_version ? `, Redis ${info.redis_version}` : ""
+        })${skipped > 0 ? `, ${skipped} module values skipped` : ""}`,
+      );
+      setRdbPath("");
+      onClose();
+    } catch (error) {
+      toast.error(
+        "Failed to open RDB file",
+        error instanceof Error ? error.message : String(error),
+      );
+    } finally {
+      setOpeningRdb(false);
+    }
+  };
+
   const handleDelete = async () => {
     if (!deleteConfirm.connection) return;
 
@@ -219,6 +264,29 @@ export function ConnectionList({
           )}
         </div>
 
+        <div className="mt-6 pt-4 border-t border-neutral-200 dark:border-neutral-800">
+          <div className="flex items-end gap-2">
+            <Input
+              label="Open RDB file (read-only)"
+              placeholder="/path/to/dump.rdb"
+              value={rdbPath}
+              onChange={(e) => setRdbPath(e.target.value)}
+              onKeyDown={(e) => {
+                if (e.key === "Enter") handleOpenRdb();
+              }}
+              leftIcon={<FileArchive className="w-4 h-4" />}
+            />
+            <Button
+              onClick={handleOpenRdb}
+              variant="secondary"
+              loading={openingRdb}
+              disabled={openingRdb || !rdbPath.trim()}
+            >
+              Open
+            </Button>
+          </div>
+        </div>
+
         <div className="flex justify-end gap-3 mt-6 pt-4 border-t border-neutral-200 dark:border-neutral-800">
           <Button onClick={onClose} variant="secondary">
             Close
@@ -1,5 +1,5 @@
 import { useEffect, useState, useRef, useCallback, useMemo } from "react";
-import { Search, RefreshCw, Key, Database, Plus } from "lucide-react";
+import { Search, RefreshCw, Key, Database, Plus, X } from "lucide-react";
 import { VirtualList, VirtualListHandle } from "./VirtualList";
 import { useRedisStore } from "../store/useRedisStore";
 import { redisApi } from "../lib/tauri-api";
@@ -66,23 +66,74 @@ export function KeyBrowser({
     return keys.filter((key) => key.toLowerCase().includes(lowerPattern));
   }, [keys, localPattern]);
 
+  const [scanProgress, setScanProgress] = useState<{
+    scanned: number;
+    total: number;
+  } | null>(null);
+  const scanJobRef = useRef<string | null>(null);
+
+  const cancelScan = useCallback(() => {
+    if (scanJobRef.current) {
+      redisApi.cancelJob(scanJobRef.current).catch(() => {});
+      scanJobRef.current = null;
+    }
+  }, []);
+
+  // Stream keys from a background SCAN job so large keyspaces render
+  // incrementally and the scan can be cancelled
   const loadKeys = useCallback(async () => {
     if (!activeConnectionId) return;
 
+    cancelScan();
     setLoading(true);
+    setScanProgress(null);
+
+    // SCAN can repeat a key while the server rehashes; the set drops repeats
+    const loadedKeys = new Set<string>();
+    let jobId: string | null = null;
+    const isCurrent = () => scanJobRef.current === jobId;
+
+    setKeys([]);
     try {
-      const loadedKeys = await redisApi.getKeys(
+      jobId = await redisApi.startKeyScan(
         activeConnectionId,
         searchPattern,
+        (event) => {
+          if (!isCurrent()) return;
+
+          switch (event.event) {
+            case "keys":
+              event.data.keys.forEach((key) => loadedKeys.add(key));
+              setKeys([...loadedKeys].sort());
+              setScanProgress({
+                scanned: event.data.scanned,
+                total: event.data.estimated_total,
+              });
+              break;
+            case "finished":
+              scanJobRef.current = null;
+              setLoading(false);
+              setScanProgress(null);
+              break;
+            case "error":
+              console.error("Failed to load keys:", event.data.message);
+              scanJobRef.current = null;
+              setLoading(false);
+              setScanProgress(null);
+              break;
+          }
+        },
         keyTypeFilter,
       );
-      setKeys(loadedKeys);
+      scanJobRef.current = jobId;
     } catch (error) {
       console.error("Failed to load keys:", error);
-    } finally {
       setLoading(false);
     }
-  }, [activeConnectionId, searchPattern, keyTypeFilter, setKeys]);
+  }, [activeConnectionId, searchPattern, keyTypeFilter, setKeys, cancelScan]);
+
+  // Stop any running scan when the browser unmounts
+  useEffect(() => cancelScan, [cancelScan]);
 
   // Update search pattern when debounced value changes
   useEffect(() => {
@@ -147,6 +198,37 @@ export function KeyBrowser({
     [activeConnectionId, setSelectedKey, setSelectedKeyInfo],
   );
 
+  // Prefetch metadata for the rendered rows in one batched request, so
+  // selecting a key reads its info from cache instead of a round-trip per key
+  const prefetchTimerRef = useRef<ReturnType<typeof setTimeout> | null>(null);
+  const handleRangeChange = useCallback(
+    (startIndex: number, endIndex: number) => {
+      if (!activeConnectionId) return;
+
+      if (prefetchTimerRef.current) {
+        clearTimeout(prefetchTimerRef.current);
+      }
+      prefetchTimerRef.current = setTimeout(() => {
+        const visibleKeys = filteredKeys.slice(startIndex, endIndex);
+        if (visibleKeys.length === 0) return;
+        redisApi
+          .getKeysMetadata(activeConnectionId, visibleKeys)
+          .catch((error) =>
+            console.error("Failed to prefetch key metadata:", error),
+          );
+      }, 200);
+    },
+    [activeConnectionId, filteredKeys],
+  );
+
+  useEffect(() => {
+    return () => {
+      if (prefetchTimerRef.current) {
+        clearTimeout(prefetchTimerRef.current);
+      }
+    };
+  }, []);
+
   const handleSearch = (e: React.FormEvent) => {
     e.preventDefault();
     setSearchPattern(localPattern);
@@ -313,6 +395,25 @@ export function KeyBrowser({
             {filteredKeys.length} {filteredKeys.length === 1 ? "key" : "keys"}{" "}
             {keys.length !== filteredKeys.length && `(${keys.length} total)`}
           </span>
+          {loading && scanProgress && (
+            <span className="flex items-center gap-1 text-neutral-500 dark:text-neutral-400">
+              Scanned {scanProgress.scanned.toLocaleString()} /{" "}
+              {scanProgress.total.toLocaleString()}
+              <IconButton
+                type="button"
+                onClick={() => {
+                  cancelScan();
+                  setLoading(false);
+                  setScanProgress(null);
+                }}
+                variant="ghost"
+                size="sm"
+                title="Cancel scan"
+              >
+                <X className="w-3 h-3" />
+              </IconButton>
+            </span>
+          )}
         </div>
       </div>
 
@@ -323,7 +424,7 @@ export function KeyBrowser({
         onKeyDown={handleKeyNavigation}
         tabIndex={0}
       >
-        {loading ? (
+        {loading && filteredKeys.length === 0 ? (
           <div className="flex flex-col items-center justify-center h-48 text-center p-6">
             <RefreshCw className="w-8 h-8 animate-spin text-brand-600 mb-3" />
             <p className="text-sm text-neutral-500 dark:text-neutral-400">
@@ -349,6 +450,7 @@ export function KeyBrowser({
             height={listHeight}
             itemHeight={65}
             renderItem={renderKeyItem}
+            onRangeChange={handleRangeChange}
             className="scrollbar-thin"
           />
         )}
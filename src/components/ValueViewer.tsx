@@ -13,7 +13,7 @@ import {
   ClipboardCopy,
 } from "lucide-react";
 import { useRedisStore } from "../store/useRedisStore";
-import { redisApi } from "../lib/tauri-api";
+import { redisApi, confirmationToken } from "../lib/tauri-api";
 import { useTheme } from "../lib/theme-context";
 import { useToast } from "../lib/toast-context";
 import { Button, Badge, Input, ConfirmDialog } from "./ui";
@@ -46,6 +46,7 @@ export function ValueViewer() {
   const [editingTtl, setEditingTtl] = useState(false);
   const [newTtl, setNewTtl] = useState("");
   const [showDeleteConfirm, setShowDeleteConfirm] = useState(false);
+  const [deleteToken, setDeleteToken] = useState<string | null>(null);
   const [renamingKey, setRenamingKey] = useState(false);
   const [newKeyName, setNewKeyName] = useState("");
   const [copyingKey, setCopyingKey] = useState(false);
@@ -166,12 +167,28 @@ export function ValueViewer() {
     }
 
     try {
-      await redisApi.deleteKey(activeConnectionId, selectedKey);
+      if (deleteToken) {
+        await redisApi.confirmCommand(activeConnectionId, deleteToken);
+      }
+      await redisApi.deleteKey(
+        activeConnectionId,
+        selectedKey,
+        deleteToken ?? undefined,
+      );
       setKeys(keys.filter((k) => k !== selectedKey));
       setSelectedKey(null);
       toast.success("Key deleted", `Successfully deleted ${selectedKey}`);
+      setDeleteToken(null);
       setShowDeleteConfirm(false);
     } catch (error) {
+      const token = confirmationToken(error);
+      if (token && !deleteToken) {
+        // Ask again, this time for the connection's command policy
+        setDeleteToken(token);
+        setShowDeleteConfirm(true);
+        return;
+      }
+      setDeleteToken(null);
       const errorMsg =
         error instanceof Error ? error.message : "Failed to delete key";
       console.error("Failed to delete key:", error);
@@ -224,10 +241,15 @@ export function ValueViewer() {
     }
 
     try {
-      await redisApi.executeCommand(
+      const result = await redisApi.renameKey(
         activeConnectionId,
-        `RENAME "${selectedKey.replace(/"/g, '\\"')}" "${newKeyName.trim().replace(/"/g, '\\"')}"`,
+        selectedKey,
+        newKeyName.trim(),
       );
+      if (!result.applied) {
+        toast.error("Rename failed", result.message ?? "Key was not renamed");
+        return;
+      }
 
       // Update keys list
       const updatedKeys = keys.map((k) =>
@@ -261,25 +283,15 @@ export function ValueViewer() {
     }
 
     try {
-      // Use DUMP and RESTORE to copy the key with all its properties
-      const dumpResult = await redisApi.executeCommand(
+      // Copies the value and TTL; COPY on Redis 6.2+, DUMP/RESTORE before
+      const result = await redisApi.copyKey(
         activeConnectionId,
-        `DUMP "${selectedKey.replace(/"/g, '\\"')}"`,
+        selectedKey,
+        copyKeyName.trim(),
       );
-
-      if (dumpResult && dumpResult !== "(nil)") {
-        // Get TTL
-        const ttlResult = await redisApi.executeCommand(
-          activeConnectionId,
-          `PTTL "${selectedKey.replace(/"/g, '\\"')}"`,
-        );
-        const pttl = parseInt(ttlResult) > 0 ? parseInt(ttlResult) : 0;
-
-        // Restore to new key
-        await redisApi.executeCommand(
-          activeConnectionId,
-          `RESTORE "${copyKeyName.trim().replace(/"/g, '\\"')}" ${pttl} ${dumpResult}`,
-        );
+      if (!result.applied) {
+        toast.error("Copy failed", result.message ?? "Key was not copied");
+        return;
       }
 
       // Add to keys list and select
@@ -768,7 +780,10 @@ export function ValueViewer() {
       {/* Delete Confirmation Dialog */}
       <ConfirmDialog
         isOpen={showDeleteConfirm}
-        onClose={() => setShowDeleteConfirm(false)}
+        onClose={() => {
+          setShowDeleteConfirm(false);
+          setDeleteToken(null);
+        }}
         onConfirm={confirmDelete}
         title="Delete Key"
         message={
@@ -780,6 +795,12 @@ export function ValueViewer() {
             <p className="mt-2 text-xs text-neutral-500 dark:text-neutral-400">
               This action cannot be undone.
             </p>
+            {deleteToken && (
+              <p className="mt-2 text-xs text-warning-light dark:text-warning-dark">
+                This connection's command policy requires confirming DEL.
+                Confirm again to delete the key.
+              </p>
+            )}
           </div>
         }
         confirmText="Delete"
@@ -2,6 +2,7 @@ import {
   useRef,
   useState,
   useCallback,
+  useEffect,
   forwardRef,
   useImperativeHandle,
 } from "react";
@@ -13,6 +14,7 @@ interface VirtualListProps<T> {
   renderItem: (item: T, index: number) => React.ReactNode;
   className?: string;
   overscan?: number;
+  onRangeChange?: (startIndex: number, endIndex: number) => void;
 }
 
 function VirtualListInner<T>(
@@ -23,6 +25,7 @@ function VirtualListInner<T>(
     renderItem,
     className = "",
     overscan = 3,
+    onRangeChange,
   }: VirtualListProps<T>,
   ref: React.Ref<VirtualListHandle>,
 ) {
@@ -51,6 +54,11 @@ function VirtualListInner<T>(
     startIndex + visibleCount + overscan * 2,
   );
   const visibleItems = items.slice(startIndex, endIndex);
+
+  // Notify the parent of the rendered window (e.g. to prefetch metadata)
+  useEffect(() => {
+    onRangeChange?.(startIndex, endIndex);
+  }, [startIndex, endIndex, onRangeChange]);
   const offsetY = startIndex * itemHeight;
 
   return (
@@ -1,4 +1,4 @@
-import { invoke } from "@tauri-apps/api/core";
+import { invoke, Channel } from "@tauri-apps/api/core";
 import {
   ConnectionConfig,
   ConnectionStatus,
@@ -6,12 +6,59 @@ import {
   RedisValue,
   StoredConnection,
   ScanResult,
+  ScanEvent,
+  JobEvent,
+  JobInfo,
+  NamespaceLevel,
+  NamespaceAnalysisOptions,
+  BigKeysReport,
+  HotKeysReport,
+  ExpiryReport,
+  ExportSummary,
+  ImportConflictPolicy,
+  ImportReport,
+  BackupManifest,
+  BackupSummary,
+  RestoreReport,
+  MigrationRequest,
+  MigrationReport,
+  DbDiffRequest,
+  DiffEvent,
+  KeyElementDiff,
+  BinaryString,
+  KeyOperationResult,
+  BulkPreview,
+  BulkDeleteRequest,
+  BulkDeleteSummary,
+  BulkTtlRequest,
+  BulkTtlSummary,
+  TtlOperation,
+  TtlCondition,
+  RenameRule,
+  RenamePreview,
+  BulkRenameRequest,
+  RenameReport,
+  RdbFileInfo,
+  AofFilter,
+  AofReport,
+  AofExportSummary,
   PaginatedListResult,
   PaginatedSetResult,
   PaginatedZSetResult,
   PaginatedHashResult,
+  HashFieldExpiry,
   StreamRangeResult,
+  HyperLogLogInfo,
+  BitmapInfo,
+  BitmapRange,
+  BitfieldOperation,
   ServerInfo,
+  AuditEntry,
+  AuditQuery,
+  AuditExportFormat,
+  UndoJournalInfo,
+  UndoResult,
+  KeyspaceOverview,
   ClientInfo,
   SlowLogEntry,
   CommandStat,
@@ -29,6 +76,50 @@ export const redisApi = {
     return result;
   },
 
+  async openRdbFile(path: string, database?: number): Promise<RdbFileInfo> {
+    const result = await invoke<RdbFileInfo>("open_rdb_file", {
+      path,
+      database,
+    });
+    cache.clearPattern(`*${result.connection_id}*`);
+    return result;
+  },
+
+  async startAofInspection(
+    path: string,
+    onEvent: (event: JobEvent<AofReport>) => void,
+    options: {
+      filter?: AofFilter;
+      bucketSeconds?: number;
+      skip?: number;
+      limit?: number;
+    } = {},
+  ): Promise<string> {
+    const channel = new Channel<JobEvent<AofReport>>();
+    channel.onmessage = onEvent;
+    return invoke("start_aof_inspection", {
+      path,
+      ...options,
+      onEvent: channel,
+    });
+  },
+
+  async startAofExport(
+    path: string,
+    outputPath: string,
+    onEvent: (event: JobEvent<AofExportSummary>) => void,
+    options: { filter?: AofFilter; format?: "resp" | "cli" } = {},
+  ): Promise<string> {
+    const channel = new Channel<JobEvent<AofExportSummary>>();
+    channel.onmessage = onEvent;
+    return invoke("start_aof_export", {
+      path,
+      outputPath,
+      ...options,
+      onEvent: channel,
+    });
+  },
+
   async disconnect(connectionId: string): Promise<boolean> {
     const result = await invoke<boolean>("disconnect_from_redis", {
       connectionId,
@@ -75,8 +166,361 @@ export const redisApi = {
     pattern: string,
     cursor: number,
     count: number,
+    keyTypeFilter?: string,
   ): Promise<ScanResult> {
-    return invoke("scan_keys", { connectionId, pattern, cursor, count });
+    return invoke("scan_keys", {
+      connectionId,
+      pattern,
+      cursor,
+      count,
+      keyTypeFilter:
+        keyTypeFilter && keyTypeFilter !== "all" ? keyTypeFilter : null,
+    });
+  },
+
+  // Streams matching keys in chunks; returns the job id for cancelJob
+  async startKeyScan(
+    connectionId: string,
+    pattern: string,
+    onEvent: (event: ScanEvent) => void,
+    keyTypeFilter?: string,
+    count?: number,
+  ): Promise<string> {
+    const channel = new Channel<ScanEvent>();
+    channel.onmessage = onEvent;
+    return invoke("start_key_scan", {
+      connectionId,
+      pattern,
+      keyTypeFilter:
+        keyTypeFilter && keyTypeFilter !== "all" ? keyTypeFilter : null,
+      count,
+      onEvent: channel,
+    });
+  },
+
+  async cancelJob(jobId: string): Promise<boolean> {
+    return invoke("cancel_job", { jobId });
+  },
+
+  async listJobs(): Promise<JobInfo[]> {
+    return invoke("list_jobs");
+  },
+
+  // Keyspace analysis
+  async startNamespaceAnalysis(
+    connectionId: string,
+    onEvent: (event: JobEvent<NamespaceLevel | null>) => void,
+    options: NamespaceAnalysisOptions = {},
+  ): Promise<string> {
+    const channel = new Channel<JobEvent<NamespaceLevel | null>>();
+    channel.onmessage = onEvent;
+    return invoke("start_namespace_analysis", {
+      connectionId,
+      ...options,
+      onEvent: channel,
+    });
+  },
+
+  async getNamespaceLevel(
+    analysisId: string,
+    path: string[],
+    limit?: number,
+  ): Promise<NamespaceLevel> {
+    return invoke("get_namespace_level", { analysisId, path, limit });
+  },
+
+  async dropNamespaceAnalysis(analysisId: string): Promise<boolean> {
+    return invoke("drop_namespace_analysis", { analysisId });
+  },
+
+  async startBigKeysAnalysis(
+    connectionId: string,
+    onEvent: (event: JobEvent<BigKeysReport>) => void,
+    options: { pattern?: string; topN?: number; memorySamples?: number } = {},
+  ): Promise<string> {
+    const channel = new Channel<JobEvent<BigKeysReport>>();
+    channel.onmessage = onEvent;
+    return invoke("start_bigkeys_analysis", {
+      connectionId,
+      ...options,
+      onEvent: channel,
+    });
+  },
+
+  async startExpiryAnalysis(
+    connectionId: string,
+    onEvent: (event: JobEvent<ExpiryReport>) => void,
+    options: {
+      pattern?: string;
+      expiringWithinMinutes?: number;
+      expiringLimit?: number;
+      delimiter?: string;
+      prefixDepth?: number;
+      maxPrefixes?: number;
+    } = {},
+  ): Promise<string> {
+    const channel = new Channel<JobEvent<ExpiryReport>>();
+    channel.onmessage = onEvent;
+    return invoke("start_expiry_analysis", {
+      connectionId,
+      ...options,
+      onEvent: channel,
+    });
+  },
+
+  async startKeyExport(
+    connectionId: string,
+    outputPath: string,
+    onEvent: (event: JobEvent<ExportSummary>) => void,
+    options: { pattern?: string; keys?: BinaryString[] } = {},
+  ): Promise<string> {
+    const channel = new Channel<JobEvent<ExportSummary>>();
+    channel.onmessage = onEvent;
+    return invoke("start_key_export", {
+      connectionId,
+      outputPath,
+      ...options,
+      onEvent: channel,
+    });
+  },
+
+  async startKeyImport(
+    connectionId: string,
+    inputPath: string,
+    onEvent: (event: JobEvent<ImportReport>) => void,
+    options: {
+      format?: "ndjson" | "csv";
+      conflict?: ImportConflictPolicy;
+      renameSuffix?: string;
+      dryRun?: boolean;
+      confirmationToken?: string;
+    } = {},
+  ): Promise<string> {
+    const channel = new Channel<JobEvent<ImportReport>>();
+    channel.onmessage = (event) => {
+      if (event.event === "finished" && !options.dryRun) {
+        cache.clearPattern(`*${connectionId}*`);
+      }
+      onEvent(event);
+    };
+    return invoke("start_key_import", {
+      connectionId,
+      inputPath,
+      ...options,
+      onEvent: channel,
+    });
+  },
+
+  async startKeyBackup(
+    connectionId: string,
+    outputPath: string,
+    onEvent: (event: JobEvent<BackupSummary>) => void,
+    options: { pattern?: string; keys?: BinaryString[] } = {},
+  ): Promise<string> {
+    const channel = new Channel<JobEvent<BackupSummary>>();
+    channel.onmessage = onEvent;
+    return invoke("start_key_backup", {
+      connectionId,
+      outputPath,
+      ...options,
+      onEvent: channel,
+    });
+  },
+
+  async readBackupManifest(path: string): Promise<BackupManifest> {
+    return invoke("read_backup_manifest", { path });
+  },
+
+  async startBackupRestore(
+    connectionId: string,
+    inputPath: string,
+    onEvent: (event: JobEvent<RestoreReport>) => void,
+    options: {
+      replace?: boolean;
+      pattern?: string;
+      confirmationToken?: string;
+    } = {},
+  ): Promise<string> {
+    const channel = new Channel<JobEvent<RestoreReport>>();
+    channel.onmessage = (event) => {
+      if (event.event === "finished") {
+        cache.clearPattern(`*${connectionId}*`);
+      }
+      onEvent(event);
+    };
+    return invoke("start_backup_restore", {
+      connectionId,
+      inputPath,
+      ...options,
+      onEvent: channel,
+    });
+  },
+
+  async startKeyMigration(
+    sourceConnectionId: string,
+    targetConnectionId: string,
+    request: MigrationRequest,
+    onEvent: (event: JobEvent<MigrationReport>) => void,
+    confirmationToken?: string,
+  ): Promise<string> {
+    const channel = new Channel<JobEvent<MigrationReport>>();
+    channel.onmessage = (event) => {
+      if (event.event === "finished") {
+        cache.clearPattern(`*${targetConnectionId}*`);
+        if (request.delete_source) {
+          cache.clearPattern(`*${sourceConnectionId}*`);
+        }
+      }
+      onEvent(event);
+    };
+    return invoke("start_key_migration", {
+      sourceConnectionId,
+      targetConnectionId,
+      request,
+      confirmationToken,
+      onEvent: channel,
+    });
+  },
+  async startDbDiff(
+    aConnectionId: string,
+    bConnectionId: string,
+    request: DbDiffRequest,
+    onEvent: (event: DiffEvent) => void,
+  ): Promise<string> {
+    const channel = new Channel<DiffEvent>();
+    channel.onmessage = onEvent;
+    return invoke("start_db_diff", {
+      aConnectionId,
+      bConnectionId,
+      request,
+      onEvent: channel,
+    });
+  },
+
+  async diffKeys(
+    aConnectionId: string,
+    aKey: BinaryString,
+    bConnectionId: string,
+    bKey: BinaryString,
+    aDb?: number,
+    bDb?: number,
+  ): Promise<KeyElementDiff> {
+    return invoke("diff_keys", {
+      aConnectionId,
+      aKey,
+      bConnectionId,
+      bKey,
+      aDb,
+      bDb,
+    });
+  },
+  async previewBulkKeys(
+    connectionId: string,
+    pattern: string,
+    sampleSize?: number,
+  ): Promise<BulkPreview> {
+    return invoke("preview_bulk_keys", { connectionId, pattern, sampleSize });
+  },
+
+  async startBulkDelete(
+    connectionId: string,
+    request: BulkDeleteRequest,
+    onEvent: (event: JobEvent<BulkDeleteSummary>) => void,
+    confirmationToken?: string,
+  ): Promise<string> {
+    const channel = new Channel<JobEvent<BulkDeleteSummary>>();
+    channel.onmessage = (event) => {
+      if (event.event === "finished") {
+        cache.clearPattern(`*${connectionId}*`);
+      }
+      onEvent(event);
+    };
+    return invoke("start_bulk_delete", {
+      connectionId,
+      request,
+      confirmationToken,
+      onEvent: channel,
+    });
+  },
+  async startBulkTtl(
+    connectionId: string,
+    request: BulkTtlRequest,
+    onEvent: (event: JobEvent<BulkTtlSummary>) => void,
+    confirmationToken?: string,
+  ): Promise<string> {
+    const channel = new Channel<JobEvent<BulkTtlSummary>>();
+    channel.onmessage = (event) => {
+      if (event.event === "finished") {
+        cache.clearPattern(`*${connectionId}*`);
+      }
+      onEvent(event);
+    };
+    return invoke("start_bulk_ttl", {
+      connectionId,
+      request,
+      confirmationToken,
+      onEvent: channel,
+    });
+  },
+  async previewBulkRename(
+    connectionId: string,
+    rule: RenameRule,
+    pattern?: string,
+    limit?: number,
+  ): Promise<RenamePreview> {
+    return invoke("preview_bulk_rename", {
+      connectionId,
+      rule,
+      pattern,
+      limit,
+    });
+  },
+
+  async startBulkRename(
+    connectionId: string,
+    request: BulkRenameRequest,
+    onEvent: (event: JobEvent<RenameReport>) => void,
+    confirmationToken?: string,
+  ): Promise<string> {
+    const channel = new Channel<JobEvent<RenameReport>>();
+    channel.onmessage = (event) => {
+      if (event.event === "finished") {
+        cache.clearPattern(`*${connectionId}*`);
+      }
+      onEvent(event);
+    };
+    return invoke("start_bulk_rename", {
+      connectionId,
+      request,
+      confirmationToken,
+      onEvent: channel,
+    });
+  },
+
+
+
+
+
+  async startHotKeysAnalysis(
+    connectionId: string,
+    onEvent: (event: JobEvent<HotKeysReport>) => void,
+    options: {
+      pattern?: string;
+      mode?: "auto" | "frequency" | "idle";
+      topN?: number;
+    } = {},
+  ): Promise<string> {
+    const channel = new Channel<JobEvent<HotKeysReport>>();
+    channel.onmessage = onEvent;
+    return invoke("start_hotkeys_analysis", {
+      connectionId,
+      ...options,
+      onEvent: channel,
+    });
+  },
+
+  async saveAnalysisReport(path: string, report: unknown): Promise<void> {
+    return invoke("save_analysis_report", { path, report });
   },
 
   async getKeyInfo(
@@ -104,6 +548,30 @@ export const redisApi = {
     return keyInfo;
   },
 
+  // Fetch metadata for a page of keys in one round-trip and seed the
+  // per-key info cache so getKeyInfo calls for these keys are free
+  async getKeysMetadata(
+    connectionId: string,
+    keys: string[],
+  ): Promise<RedisKey[]> {
+    const uncached = keys.filter(
+      (key) => !cache.get<RedisKey>(cacheKeys.keyInfo(connectionId, key)),
+    );
+    if (uncached.length > 0) {
+      const fetched = await invoke<RedisKey[]>("get_keys_metadata", {
+        connectionId,
+        keys: uncached,
+      });
+      for (const keyInfo of fetched) {
+        cache.set(cacheKeys.keyInfo(connectionId, keyInfo.name), keyInfo, 10000);
+      }
+    }
+
+    return keys
+      .map((key) => cache.get<RedisKey>(cacheKeys.keyInfo(connectionId, key)))
+      .filter((info): info is RedisKey => info !== null);
+  },
+
   async getValue(
     connectionId: string,
     key: string,
@@ -126,36 +594,175 @@ export const redisApi = {
     return value;
   },
 
+  // Commands that write take an optional `confirmationToken`, as
+  // executeCommand does
   async setValue(
     connectionId: string,
     key: string,
     value: string,
+    confirmationToken?: string,
   ): Promise<void> {
-    await invoke("set_value", { connectionId, key, value });
+    await invoke("set_value", { connectionId, key, value, confirmationToken });
 
     // Invalidate cache for this key
     cache.delete(cacheKeys.value(connectionId, key));
     cache.delete(cacheKeys.keyInfo(connectionId, key));
   },
 
-  async deleteKey(connectionId: string, key: string): Promise<void> {
-    await invoke("delete_key", { connectionId, key });
+  async deleteKey(
+    connectionId: string,
+    key: string,
+    confirmationToken?: string,
+  ): Promise<void> {
+    await invoke("delete_key", { connectionId, key, confirmationToken });
 
     // Invalidate cache for this key and the keys list
     cache.delete(cacheKeys.value(connectionId, key));
     cache.delete(cacheKeys.keyInfo(connectionId, key));
     cache.clearPattern(cacheKeys.keys(connectionId, "*"));
   },
+  async renameKey(
+    connectionId: string,
+    key: BinaryString,
+    newKey: BinaryString,
+    replace?: boolean,
+    confirmationToken?: string,
+  ): Promise<KeyOperationResult> {
+    const result = await invoke<KeyOperationResult>("rename_key", {
+      connectionId,
+      key,
+      newKey,
+      replace,
+      confirmationToken,
+    });
+    if (result.applied) {
+      cache.clearPattern(`*${connectionId}*`);
+    }
+    return result;
+  },
+
+  async copyKey(
+    connectionId: string,
+    key: BinaryString,
+    newKey: BinaryString,
+    destinationDb?: number,
+    replace?: boolean,
+    confirmationToken?: string,
+  ): Promise<KeyOperationResult> {
+    const result = await invoke<KeyOperationResult>("copy_key", {
+      connectionId,
+      key,
+      newKey,
+      destinationDb,
+      replace,
+      confirmationToken,
+    });
+    if (result.applied) {
+      cache.clearPattern(`*${connectionId}*`);
+    }
+    return result;
+  },
+
+  async moveKey(
+    connectionId: string,
+    key: BinaryString,
+    destinationDb: number,
+    confirmationToken?: string,
+  ): Promise<KeyOperationResult> {
+    const result = await invoke<KeyOperationResult>("move_key", {
+      connectionId,
+      key,
+      destinationDb,
+      confirmationToken,
+    });
+    if (result.applied) {
+      cache.clearPattern(`*${connectionId}*`);
+    }
+    return result;
+  },
 
-  async setTtl(connectionId: string, key: string, ttl: number): Promise<void> {
-    await invoke("set_ttl", { connectionId, key, ttl });
+
+  async setTtl(
+    connectionId: string,
+    key: string,
+    ttl: number,
+    operation?: TtlOperation,
+    condition?: TtlCondition,
+    confirmationToken?: string,
+  ): Promise<boolean> {
+    const changed = await invoke<boolean>("set_ttl", {
+      connectionId,
+      key,
+      ttl,
+      operation,
+      condition,
+      confirmationToken,
+    });
 
     // Invalidate cache for this key
     cache.delete(cacheKeys.keyInfo(connectionId, key));
+    return changed;
+  },
+
+  // `confirmationToken` re-submits a command approved with confirmCommand
+  async executeCommand(
+    connectionId: string,
+    command: string,
+    confirmationToken?: string,
+  ): Promise<string> {
+    return invoke("execute_command", {
+      connectionId,
+      command,
+      confirmationToken,
+    });
   },
 
-  async executeCommand(connectionId: string, command: string): Promise<string> {
-    return invoke("execute_command", { connectionId, command });
+  // Approve the command behind a "CONFIRM: <token> ..." error; re-submit the
+  // same command once with the token afterwards
+  async confirmCommand(connectionId: string, token: string): Promise<string> {
+    return invoke("confirm_command", { connectionId, token });
+  },
+
+  async queryAuditLog(query?: AuditQuery): Promise<AuditEntry[]> {
+    return invoke("query_audit_log", { query });
+  },
+
+  // Format defaults to CSV for .csv paths, NDJSON otherwise
+  async exportAuditLog(
+    outputPath: string,
+    query?: AuditQuery,
+    format?: AuditExportFormat,
+  ): Promise<number> {
+    return invoke("export_audit_log", { outputPath, query, format });
+  },
+
+  // Undo journal: DUMP snapshots taken before destructive edits, held in
+  // memory until the connection is closed. Undo history does not survive a
+  // reconnect.
+  async setUndoJournal(
+    connectionId: string,
+    enabled: boolean,
+    maxEntries?: number,
+  ): Promise<UndoJournalInfo> {
+    return invoke("set_undo_journal", { connectionId, enabled, maxEntries });
+  },
+
+  async getUndoJournal(connectionId: string): Promise<UndoJournalInfo> {
+    return invoke("get_undo_journal", { connectionId });
+  },
+
+  async undoOperations(
+    connectionId: string,
+    count?: number,
+    confirmationToken?: string,
+  ): Promise<UndoResult[]> {
+    const result = await invoke<UndoResult[]>("undo_operations", {
+      connectionId,
+      count,
+      confirmationToken,
+    });
+    cache.clearPattern(`*${connectionId}*`);
+    return result;
   },
 
   // Connection Management
@@ -227,8 +834,15 @@ export const redisApi = {
     key: string,
     cursor: number,
     count: number,
+    includeTtl = false,
   ): Promise<PaginatedHashResult> {
-    return invoke("get_hash_fields", { connectionId, key, cursor, count });
+    return invoke("get_hash_fields", {
+      connectionId,
+      key,
+      cursor,
+      count,
+      includeTtl,
+    });
   },
 
   // Collection editing operations
@@ -239,8 +853,15 @@ export const redisApi = {
     key: string,
     field: string,
     value: string,
+    confirmationToken?: string,
   ): Promise<void> {
-    await invoke("hash_set_field", { connectionId, key, field, value });
+    await invoke("hash_set_field", {
+      connectionId,
+      key,
+      field,
+      value,
+      confirmationToken,
+    });
     // Invalidate cache
     cache.delete(cacheKeys.value(connectionId, key));
     cache.delete(cacheKeys.keyInfo(connectionId, key));
@@ -250,11 +871,102 @@ export const redisApi = {
     connectionId: string,
     key: string,
     field: string,
+    confirmationToken?: string,
   ): Promise<void> {
-    await invoke("hash_delete_field", { connectionId, key, field });
+    await invoke("hash_delete_field", {
+      connectionId,
+      key,
+      field,
+      confirmationToken,
+    });
+    // Invalidate cache
+    cache.delete(cacheKeys.value(connectionId, key));
+    cache.delete(cacheKeys.keyInfo(connectionId, key));
+  },
+
+  // Hash field expiration (Redis 7.4+)
+  async hashExpireFields(
+    connectionId: string,
+    key: string,
+    fields: string[],
+    ttl: number,
+    unit: "seconds" | "milliseconds" = "seconds",
+    condition?: "NX" | "XX" | "GT" | "LT",
+    confirmationToken?: string,
+  ): Promise<number[]> {
+    const results = await invoke<number[]>("hash_expire_fields", {
+      connectionId,
+      key,
+      fields,
+      ttl,
+      unit,
+      condition,
+      confirmationToken,
+    });
     // Invalidate cache
     cache.delete(cacheKeys.value(connectionId, key));
     cache.delete(cacheKeys.keyInfo(connectionId, key));
+    return results;
+  },
+
+  async hashPersistFields(
+    connectionId: string,
+    key: string,
+    fields: string[],
+    confirmationToken?: string,
+  ): Promise<number[]> {
+    const results = await invoke<number[]>("hash_persist_fields", {
+      connectionId,
+      key,
+      fields,
+      confirmationToken,
+    });
+    // Invalidate cache
+    cache.delete(cacheKeys.value(connectionId, key));
+    return results;
+  },
+
+  async hashGetFieldsEx(
+    connectionId: string,
+    key: string,
+    fields: string[],
+    expiry?: HashFieldExpiry,
+    confirmationToken?: string,
+  ): Promise<(string | null)[]> {
+    const values = await invoke<(string | null)[]>("hash_get_fields_ex", {
+      connectionId,
+      key,
+      fields,
+      expiry,
+      confirmationToken,
+    });
+    if (expiry) {
+      // Invalidate cache
+      cache.delete(cacheKeys.value(connectionId, key));
+    }
+    return values;
+  },
+
+  async hashSetFieldsEx(
+    connectionId: string,
+    key: string,
+    fields: Record<string, string>,
+    condition?: "FNX" | "FXX",
+    expiry?: HashFieldExpiry,
+    confirmationToken?: string,
+  ): Promise<boolean> {
+    const written = await invoke<boolean>("hash_set_fields_ex", {
+      connectionId,
+      key,
+      fields,
+      condition,
+      expiry,
+      confirmationToken,
+    });
+    // Invalidate cache
+    cache.delete(cacheKeys.value(connectionId, key));
+    cache.delete(cacheKeys.keyInfo(connectionId, key));
+    return written;
   },
 
   // List operations
@@ -263,8 +975,15 @@ export const redisApi = {
     key: string,
     value: string,
     side: "left" | "right",
+    confirmationToken?: string,
   ): Promise<void> {
-    await invoke("list_push", { connectionId, key, value, side });
+    await invoke("list_push", {
+      connectionId,
+      key,
+      value,
+      side,
+      confirmationToken,
+    });
     // Invalidate cache
     cache.delete(cacheKeys.value(connectionId, key));
     cache.delete(cacheKeys.keyInfo(connectionId, key));
@@ -274,11 +993,13 @@ export const redisApi = {
     connectionId: string,
     key: string,
     side: "left" | "right",
+    confirmationToken?: string,
   ): Promise<string | null> {
     const result = await invoke<string | null>("list_pop", {
       connectionId,
       key,
       side,
+      confirmationToken,
     });
     // Invalidate cache
     cache.delete(cacheKeys.value(connectionId, key));
@@ -291,8 +1012,15 @@ export const redisApi = {
     key: string,
     index: number,
     value: string,
+    confirmationToken?: string,
   ): Promise<void> {
-    await invoke("list_set_index", { connectionId, key, index, value });
+    await invoke("list_set_index", {
+      connectionId,
+      key,
+      index,
+      value,
+      confirmationToken,
+    });
     // Invalidate cache
     cache.delete(cacheKeys.value(connectionId, key));
   },
@@ -302,8 +1030,15 @@ export const redisApi = {
     key: string,
     count: number,
     value: string,
+    confirmationToken?: string,
   ): Promise<void> {
-    await invoke("list_remove", { connectionId, key, count, value });
+    await invoke("list_remove", {
+      connectionId,
+      key,
+      count,
+      value,
+      confirmationToken,
+    });
     // Invalidate cache
     cache.delete(cacheKeys.value(connectionId, key));
     cache.delete(cacheKeys.keyInfo(connectionId, key));
@@ -314,8 +1049,14 @@ export const redisApi = {
     connectionId: string,
     key: string,
     member: string,
+    confirmationToken?: string,
   ): Promise<void> {
-    await invoke("set_add_member", { connectionId, key, member });
+    await invoke("set_add_member", {
+      connectionId,
+      key,
+      member,
+      confirmationToken,
+    });
     // Invalidate cache
     cache.delete(cacheKeys.value(connectionId, key));
     cache.delete(cacheKeys.keyInfo(connectionId, key));
@@ -325,8 +1066,14 @@ export const redisApi = {
     connectionId: string,
     key: string,
     member: string,
+    confirmationToken?: string,
   ): Promise<void> {
-    await invoke("set_remove_member", { connectionId, key, member });
+    await invoke("set_remove_member", {
+      connectionId,
+      key,
+      member,
+      confirmationToken,
+    });
     // Invalidate cache
     cache.delete(cacheKeys.value(connectionId, key));
     cache.delete(cacheKeys.keyInfo(connectionId, key));
@@ -338,8 +1085,15 @@ export const redisApi = {
     key: string,
     member: string,
     score: number,
+    confirmationToken?: string,
   ): Promise<void> {
-    await invoke("zset_add_member", { connectionId, key, member, score });
+    await invoke("zset_add_member", {
+      connectionId,
+      key,
+      member,
+      score,
+      confirmationToken,
+    });
     // Invalidate cache
     cache.delete(cacheKeys.value(connectionId, key));
     cache.delete(cacheKeys.keyInfo(connectionId, key));
@@ -349,8 +1103,14 @@ export const redisApi = {
     connectionId: string,
     key: string,
     member: string,
+    confirmationToken?: string,
   ): Promise<void> {
-    await invoke("zset_remove_member", { connectionId, key, member });
+    await invoke("zset_remove_member", {
+      connectionId,
+      key,
+      member,
+      confirmationToken,
+    });
     // Invalidate cache
     cache.delete(cacheKeys.value(connectionId, key));
     cache.delete(cacheKeys.keyInfo(connectionId, key));
@@ -361,12 +1121,14 @@ export const redisApi = {
     key: string,
     member: string,
     increment: number,
+    confirmationToken?: string,
   ): Promise<number> {
     const newScore = await invoke<number>("zset_increment_score", {
       connectionId,
       key,
       member,
       increment,
+      confirmationToken,
     });
     // Invalidate cache
     cache.delete(cacheKeys.value(connectionId, key));
@@ -378,11 +1140,13 @@ export const redisApi = {
     connectionId: string,
     key: string,
     fields: Record<string, string>,
+    confirmationToken?: string,
   ): Promise<string> {
     const entryId = await invoke<string>("stream_add_entry", {
       connectionId,
       key,
       fields,
+      confirmationToken,
     });
     // Invalidate cache
     cache.delete(cacheKeys.value(connectionId, key));
@@ -394,8 +1158,14 @@ export const redisApi = {
     connectionId: string,
     key: string,
     entryId: string,
+    confirmationToken?: string,
   ): Promise<void> {
-    await invoke("stream_delete_entry", { connectionId, key, entryId });
+    await invoke("stream_delete_entry", {
+      connectionId,
+      key,
+      entryId,
+      confirmationToken,
+    });
     // Invalidate cache
     cache.delete(cacheKeys.value(connectionId, key));
     cache.delete(cacheKeys.keyInfo(connectionId, key));
@@ -423,6 +1193,7 @@ export const redisApi = {
     strategy: "MAXLEN" | "MINID",
     threshold: string,
     approximate: boolean,
+    confirmationToken?: string,
   ): Promise<number> {
     const removed = await invoke<number>("stream_trim", {
       connectionId,
@@ -430,6 +1201,7 @@ export const redisApi = {
       strategy,
       threshold,
       approximate,
+      confirmationToken,
     });
     // Invalidate cache
     cache.delete(cacheKeys.value(connectionId, key));
@@ -437,11 +1209,84 @@ export const redisApi = {
     return removed;
   },
 
+  // HyperLogLog & bitmap operations
+  async getHyperLogLogInfo(
+    connectionId: string,
+    key: string,
+  ): Promise<HyperLogLogInfo> {
+    return invoke("get_hyperloglog_info", { connectionId, key });
+  },
+
+  async getBitmapInfo(connectionId: string, key: string): Promise<BitmapInfo> {
+    return invoke("get_bitmap_info", { connectionId, key });
+  },
+
+  async getBitmapRange(
+    connectionId: string,
+    key: string,
+    byteOffset: number,
+    byteCount: number,
+  ): Promise<BitmapRange> {
+    return invoke("get_bitmap_range", {
+      connectionId,
+      key,
+      byteOffset,
+      byteCount,
+    });
+  },
+
+  async bitmapSetBit(
+    connectionId: string,
+    key: string,
+    offset: number,
+    value: boolean,
+    confirmationToken?: string,
+  ): Promise<boolean> {
+    const previous = await invoke<boolean>("bitmap_set_bit", {
+      connectionId,
+      key,
+      offset,
+      value,
+      confirmationToken,
+    });
+    // Invalidate cache
+    cache.delete(cacheKeys.value(connectionId, key));
+    cache.delete(cacheKeys.keyInfo(connectionId, key));
+    return previous;
+  },
+
+  async bitmapBitfield(
+    connectionId: string,
+    key: string,
+    operations: BitfieldOperation[],
+    confirmationToken?: string,
+  ): Promise<(number | null)[]> {
+    const results = await invoke<(number | null)[]>("bitmap_bitfield", {
+      connectionId,
+      key,
+      operations,
+      confirmationToken,
+    });
+    // Invalidate cache
+    cache.delete(cacheKeys.value(connectionId, key));
+    cache.delete(cacheKeys.keyInfo(connectionId, key));
+    return results;
+  },
+
   // Monitoring APIs
   async getServerInfo(connectionId: string): Promise<ServerInfo> {
     return invoke("get_server_info", { connectionId });
   },
 
+  async getKeyspaceOverview(connectionId: string): Promise<KeyspaceOverview> {
+    return invoke("get_keyspace_overview", { connectionId });
+  },
+
+  async selectDatabase(connectionId: string, db: number): Promise<void> {
+    await invoke("select_database", { connectionId, db });
+    cache.clearPattern(`*${connectionId}*`);
+  },
+
   async getClientList(connectionId: string): Promise<ClientInfo[]> {
     return invoke("get_client_list", { connectionId });
   },
@@ -461,3 +1306,10 @@ export const redisApi = {
     return invoke("get_pubsub_stats", { connectionId });
   },
 };
+
+// Token from a command policy "CONFIRM: <token> <command> requires
+// confirmation" error, or null for any other error
+export function confirmationToken(error: unknown): string | null {
+  const message = typeof error === "string" ? error : String(error);
+  return message.match(/CONFIRM: (\w+) /)?.[1] ?? null;
+}
@@ -10,6 +10,16 @@ export interface SshTunnelConfig {
   local_port?: number;
 }
 
+// Entries name a command ("FLUSHALL") or a subcommand ("CONFIG SET")
+export interface CommandPolicy {
+  deny: string[];
+  // When non-empty, only these commands run (COMMAND INFO always does). Must
+  // include DUMP and PTTL while the undo journal is enabled.
+  allow: string[];
+  confirm: string[];
+  max_scan_count?: number;
+}
+
 export interface ConnectionConfig {
   id: string;
   name: string;
@@ -20,6 +30,491 @@ export interface ConnectionConfig {
   database: number;
   use_tls: boolean;
   ssh_tunnel?: SshTunnelConfig;
+  read_only?: boolean;
+  policy?: CommandPolicy;
+  // Undo entries kept while connected; unset disables it. History is dropped
+  // on disconnect and does not survive a reconnect.
+  undo_journal?: number;
+}
+
+export interface RdbDatabaseSummary {
+  db: number;
+  keys: number;
+  expires: number;
+}
+
+export interface RdbFileInfo {
+  connection_id: string;
+  path: string;
+  rdb_version: number;
+  redis_version: string | null;
+  created_at_ms: number | null;
+  file_size: number;
+  database: number;
+  databases: RdbDatabaseSummary[];
+  skipped_modules: Record<string, number>;
+  functions: number;
+  aux: Record<string, string>;
+}
+
+export interface ExportSummary {
+  output_path: string;
+  exported_keys: number;
+  skipped_keys: number;
+  bytes_written: number;
+  cancelled: boolean;
+}
+
+export type ImportConflictPolicy = "skip" | "overwrite" | "rename" | "fail";
+
+export interface ImportChange {
+  key: string;
+  key_type: string;
+  action: "create" | "overwrite" | "rename" | "skip";
+  target_key: string;
+}
+
+export interface ImportError {
+  line: number;
+  key: string | null;
+  message: string;
+}
+
+export interface ImportReport {
+  input_path: string;
+  dry_run: boolean;
+  records: number;
+  created: number;
+  overwritten: number;
+  renamed: number;
+  skipped: number;
+  failed: number;
+  conflicts: number;
+  changes: ImportChange[];
+  errors: ImportError[];
+  cancelled: boolean;
+}
+
+export interface BackupManifest {
+  format_version: number;
+  created_at_ms: number;
+  server_version: string | null;
+  rdb_version: number | null;
+  db: number | null;
+  pattern: string | null;
+  key_count: number;
+  payload_bytes: number;
+  complete: boolean;
+}
+
+export interface BackupSummary {
+  output_path: string;
+  manifest: BackupManifest;
+  skipped_keys: number;
+  cancelled: boolean;
+}
+
+export interface RestoreReport {
+  input_path: string;
+  restored: number;
+  skipped_existing: number;
+  skipped_expired: number;
+  filtered: number;
+  failed: number;
+  errors: { key: BinaryString; message: string }[];
+  cancelled: boolean;
+}
+
+export interface MigrationRequest {
+  source_db?: number;
+  target_db?: number;
+  pattern?: string;
+  keys?: BinaryString[];
+  mode?: "dump_restore" | "migrate";
+  migrate_host?: string;
+  migrate_port?: number;
+  conflict?: ImportConflictPolicy;
+  rename_suffix?: string;
+  delete_source?: boolean;
+  max_keys_per_second?: number;
+}
+
+export interface MigrationKeyResult {
+  key: BinaryString;
+  target_key: BinaryString;
+  outcome: "migrated" | "renamed" | "overwritten" | "skipped" | "failed";
+  error: string | null;
+}
+
+export interface MigrationReport {
+  mode: "dump_restore" | "migrate";
+  scanned: number;
+  migrated: number;
+  renamed: number;
+  overwritten: number;
+  skipped_existing: number;
+  vanished: number;
+  failed: number;
+  deleted_from_source: number;
+  duration_ms: number;
+  results: MigrationKeyResult[];
+  aborted: string | null;
+  cancelled: boolean;
+}
+
+// Database diff
+
+// Binary-safe value: plain text when valid UTF-8
+export type BinaryString = string | { base64: string };
+
+export interface DbDiffRequest {
+  a_db?: number;
+  b_db?: number;
+  pattern?: string;
+  compare_values?: boolean;
+}
+
+export interface KeyDifference {
+  key: BinaryString;
+  kind: "only_in_a" | "only_in_b" | "type_mismatch" | "value_differs";
+  type_a: string | null;
+  type_b: string | null;
+}
+
+export interface DiffSummary {
+  compared_keys: number;
+  identical_keys: number;
+  only_in_a: number;
+  only_in_b: number;
+  type_mismatches: number;
+  value_differences: number;
+  skipped_keys: number;
+  cancelled: boolean;
+}
+
+export type DiffEvent =
+  | { event: "started"; data: { job_id: string; total: number } }
+  | {
+      event: "differences";
+      data: { differences: KeyDifference[]; summary: DiffSummary };
+    }
+  | { event: "progress"; data: { processed: number; total: number } }
+  | { event: "finished"; data: { summary: DiffSummary; cancelled: boolean } }
+  | { event: "error"; data: { message: string } };
+
+export interface DiffLine {
+  op: "equal" | "added" | "removed";
+  line_a: number | null;
+  line_b: number | null;
+  text: string;
+}
+
+export interface JsonChange {
+  path: string;
+  kind: "added" | "removed" | "changed";
+  a: unknown;
+  b: unknown;
+}
+
+export type StringDiff =
+  | { format: "text"; lines: DiffLine[] }
+  | { format: "json"; changes: JsonChange[] }
+  | {
+      format: "binary";
+      len_a: number;
+      len_b: number;
+      first_difference: number | null;
+    };
+
+// "added" is present in B but not A, "removed" the reverse
+export type ElementDiff =
+  | ({ type: "string" } & StringDiff)
+  | {
+      type: "hash";
+      added: { field: BinaryString; value: BinaryString }[];
+      removed: { field: BinaryString; value: BinaryString }[];
+      changed: { field: BinaryString; a: BinaryString; b: BinaryString }[];
+    }
+  | { type: "set"; added: BinaryString[]; removed: BinaryString[] }
+  | {
+      type: "zset";
+      added: { member: BinaryString; score: number }[];
+      removed: { member: BinaryString; score: number }[];
+      changed: { member: BinaryString; a: number; b: number }[];
+    }
+  | {
+      type: "list";
+      len_a: number;
+      len_b: number;
+      changed: {
+        index: number;
+        a: BinaryString | null;
+        b: BinaryString | null;
+      }[];
+    };
+
+export interface KeyElementDiff {
+  key_a: BinaryString;
+  key_b: BinaryString;
+  key_type: string;
+  identical: boolean;
+  diff: ElementDiff;
+  truncated: boolean;
+}
+
+// Bulk operations
+
+export interface BulkPreview {
+  pattern: string;
+  estimated: boolean; // Counts extrapolated from part of the keyspace
+  matched_keys: number;
+  keys_without_ttl: number;
+  sample_keys: BinaryString[];
+  sampled_memory_bytes: number | null;
+  estimated_memory_bytes: number | null;
+}
+
+export interface BulkKeyError {
+  key: BinaryString;
+  message: string;
+}
+
+export interface BulkDeleteRequest {
+  pattern?: string;
+  keys?: BinaryString[];
+  batch_size?: number;
+  max_keys_per_second?: number;
+}
+
+export interface BulkDeleteSummary {
+  pattern: string;
+  command: "UNLINK" | "DEL";
+  deleted: number;
+  already_gone: number;
+  failed: number;
+  errors: BulkKeyError[];
+  duration_ms: number;
+  cancelled: boolean;
+}
+
+export type TtlOperation = "expire" | "pexpire" | "expireat" | "persist";
+
+// Redis 7 EXPIRE flags; emulated with a PTTL check on older servers
+export type TtlCondition = "nx" | "xx" | "gt" | "lt";
+
+export interface TtlChange {
+  operation: TtlOperation;
+  value?: number; // Seconds, milliseconds or Unix seconds; unused for persist
+  condition?: TtlCondition | null;
+}
+
+export interface BulkTtlRequest {
+  pattern?: string;
+  keys?: BinaryString[];
+  change: TtlChange;
+  batch_size?: number;
+  max_keys_per_second?: number;
+}
+
+export interface BulkTtlSummary {
+  pattern: string;
+  change: TtlChange;
+  emulated_condition: boolean;
+  updated: number;
+  unchanged: number;
+  already_gone: number;
+  failed: number;
+  errors: BulkKeyError[];
+  duration_ms: number;
+  cancelled: boolean;
+}
+
+export type RenameRule =
+  | { mode: "prefix"; from: string; to: string }
+  | { mode: "suffix"; from: string; to: string }
+  // `replacement` may refer to captures as $1 or ${name}
+  | { mode: "regex"; pattern: string; replacement: string };
+
+export interface RenamePair {
+  from: BinaryString;
+  to: BinaryString;
+  target_exists: boolean;
+  cross_slot: boolean;
+}
+
+export interface RenamePreview {
+  pattern: string;
+  cluster: boolean;
+  estimated: boolean; // Counts extrapolated from part of the keyspace
+  matched_keys: number;
+  collisions: number;
+  duplicate_targets: number;
+  cross_slot: number;
+  renames: RenamePair[];
+}
+
+export interface BulkRenameRequest {
+  rule: RenameRule;
+  pattern?: string;
+  overwrite?: boolean;
+  batch_size?: number;
+  max_keys_per_second?: number;
+}
+
+export interface RenameKeyResult {
+  from: BinaryString;
+  to: BinaryString;
+  outcome: "renamed" | "overwritten" | "collision" | "gone" | "failed";
+  error: string | null;
+}
+
+export interface RenameReport {
+  pattern: string;
+  cluster: boolean;
+  renamed: number;
+  overwritten: number;
+  moved_across_slots: number;
+  collisions: number;
+  already_gone: number;
+  failed: number;
+  results: RenameKeyResult[];
+  duration_ms: number;
+  cancelled: boolean;
+}
+
+// Key operations
+
+export interface KeyOperationResult {
+  operation: "rename" | "copy" | "move";
+  source: BinaryString;
+  destination: BinaryString;
+  source_db: number;
+  destination_db: number;
+  applied: boolean;
+  replaced: boolean;
+  ttl_ms: number | null;
+  message: string | null;
+}
+
+export interface DbKeyspace {
+  db: number;
+  keys: number;
+  expires: number;
+  avg_ttl_ms: number;
+  subexpiry: number | null;
+}
+
+export interface KeyspaceOverview {
+  current_db: number | null;
+  database_count: number | null;
+  databases: DbKeyspace[];
+}
+
+export interface AuditEntry {
+  timestamp: number; // Unix milliseconds
+  connection_id: string;
+  connection_name: string | null;
+  db: number | null;
+  command: string; // Redis command, or the job kind for bulk jobs
+  key: string | null;
+  args: string[];
+  success: boolean;
+  error: string | null;
+}
+
+export interface AuditQuery {
+  connection_id?: string;
+  command?: string;
+  text?: string; // Matches key, args, error and connection name
+  since?: number;
+  until?: number;
+  success?: boolean;
+  limit?: number;
+}
+
+export type AuditExportFormat = "ndjson" | "csv";
+
+export interface UndoEntryInfo {
+  id: number;
+  timestamp: number; // Unix milliseconds
+  operation: string;
+  key: BinaryString;
+  db: number;
+  existed: boolean; // false: undo deletes the key
+  size_bytes: number;
+  expire_at_ms: number | null; // Unix milliseconds
+}
+
+export interface UndoJournalInfo {
+  enabled: boolean;
+  max_entries: number;
+  total_bytes: number;
+  entries: UndoEntryInfo[]; // Newest first
+}
+
+export interface UndoResult {
+  id: number;
+  operation: string;
+  key: BinaryString;
+  db: number;
+  restored: boolean;
+  message: string | null;
+}
+
+export interface AofFilter {
+  key_pattern?: string;
+  commands?: string[];
+  db?: number;
+  from_timestamp?: number;
+  to_timestamp?: number;
+}
+
+export interface AofFileSummary {
+  name: string;
+  kind: "plain" | "base" | "incr";
+  size: number;
+  commands: number;
+  rdb_preamble: { rdb_version: number; keys: number; bytes: number } | null;
+  truncated: boolean;
+  error: string | null;
+}
+
+export interface AofEntry {
+  file: string;
+  offset: number;
+  db: number;
+  timestamp: number | null;
+  args: string[];
+}
+
+export interface AofCommandStats {
+  command: string;
+  count: number;
+  first_timestamp: number | null;
+  last_timestamp: number | null;
+}
+
+export interface AofTimelineBucket {
+  start: number;
+  counts: Record<string, number>;
+}
+
+export interface AofReport {
+  path: string;
+  files: AofFileSummary[];
+  total_commands: number;
+  matched_commands: number;
+  has_timestamps: boolean;
+  commands: AofCommandStats[];
+  timeline: AofTimelineBucket[];
+  entries: AofEntry[];
+  cancelled: boolean;
+}
+
+export interface AofExportSummary {
+  output_path: string;
+  commands_written: number;
+  cancelled: boolean;
 }
 
 export interface StoredConnection {
@@ -31,11 +526,17 @@ export interface StoredConnection {
   database: number;
   use_tls: boolean;
   ssh_tunnel?: SshTunnelConfig;
+  read_only?: boolean;
+  policy?: CommandPolicy;
+  // Undo entries kept while connected; unset disables it. History is dropped
+  // on disconnect and does not survive a reconnect.
+  undo_journal?: number;
 }
 
 export interface ConnectionStatus {
   id: string;
   connected: boolean;
+  read_only: boolean; // Configured read-only, or connected to a replica
   error?: string;
 }
 
@@ -60,6 +561,148 @@ export interface ScanResult {
   has_more: boolean;
 }
 
+// Background jobs (scans, analysis, bulk operations)
+export interface JobInfo {
+  id: string;
+  kind: string;
+  connection_id: string;
+  started_at: number;
+}
+
+export type ScanEvent =
+  | { event: "started"; data: { job_id: string; estimated_total: number } }
+  | {
+      event: "keys";
+      data: {
+        keys: string[];
+        scanned: number;
+        matched: number;
+        estimated_total: number;
+      };
+    }
+  | {
+      event: "finished";
+      data: { scanned: number; matched: number; cancelled: boolean };
+    }
+  | { event: "error"; data: { message: string } };
+
+export type JobEvent<T> =
+  | { event: "started"; data: { job_id: string; total: number } }
+  | { event: "progress"; data: { processed: number; total: number } }
+  | { event: "finished"; data: { result: T; cancelled: boolean } }
+  | { event: "error"; data: { message: string } };
+
+// Keyspace analysis
+export interface NamespaceNodeSummary {
+  name: string;
+  path: string[];
+  pattern: string;
+  key_count: number;
+  direct_keys: number;
+  types: Record<string, number>;
+  keys_with_ttl: number;
+  ttl_coverage: number;
+  sampled_keys: number;
+  avg_memory: number | null;
+  estimated_memory: number | null;
+  child_count: number;
+}
+
+export interface NamespaceLevel {
+  node: NamespaceNodeSummary;
+  children: NamespaceNodeSummary[];
+  cancelled: boolean;
+}
+
+export interface NamespaceAnalysisOptions {
+  pattern?: string;
+  delimiter?: string;
+  memorySampleRate?: number;
+  maxDepth?: number;
+}
+
+export interface BigKeyEntry {
+  key: string;
+  key_type: string;
+  memory_bytes: number | null;
+  elements: number | null;
+}
+
+export interface BigKeysTypeSummary {
+  key_type: string;
+  key_count: number;
+  total_memory: number;
+  total_elements: number;
+  avg_memory: number;
+  biggest_by_memory: BigKeyEntry[];
+  biggest_by_elements: BigKeyEntry[];
+}
+
+export interface BigKeysReport {
+  pattern: string;
+  generated_at: number;
+  scanned_keys: number;
+  total_memory: number;
+  types: BigKeysTypeSummary[];
+  top_by_memory: BigKeyEntry[];
+  cancelled: boolean;
+}
+
+export interface TtlBucket {
+  label: string;
+  min_seconds: number;
+  max_seconds: number | null;
+  key_count: number;
+  memory_bytes: number;
+}
+
+export interface ExpiringKey {
+  key: string;
+  ttl_ms: number;
+  memory_bytes: number | null;
+}
+
+export interface PrefixTtlCoverage {
+  prefix: string;
+  key_count: number;
+  keys_with_ttl: number;
+  ttl_coverage: number;
+  memory_bytes: number;
+  memory_without_ttl: number;
+}
+
+export interface ExpiryReport {
+  pattern: string;
+  generated_at: number;
+  scanned_keys: number;
+  total_memory: number;
+  keys_without_ttl: number;
+  memory_without_ttl: number;
+  memory_share_without_ttl: number;
+  buckets: TtlBucket[];
+  expiring_within_minutes: number;
+  expiring_soon_count: number;
+  expiring_soon: ExpiringKey[];
+  prefixes: PrefixTtlCoverage[];
+  cancelled: boolean;
+}
+
+export interface HotKeyEntry {
+  key: string;
+  key_type: string;
+  frequency: number | null;
+  idle_seconds: number | null;
+  memory_bytes: number | null;
+}
+
+export interface HotKeysReport {
+  mode: "frequency" | "idle";
+  maxmemory_policy: string;
+  scanned_keys: number;
+  keys: HotKeyEntry[];
+  cancelled: boolean;
+}
+
 export type RedisDataType =
   | "string"
   | "list"
@@ -67,6 +710,7 @@ export type RedisDataType =
   | "zset"
   | "hash"
   | "stream"
+  | "hyperloglog"
   | "none";
 
 export interface PaginatedListResult {
@@ -91,6 +735,17 @@ export interface PaginatedHashResult {
   fields: Record<string, string>;
   cursor: number;
   has_more: boolean;
+  field_ttls?: Record<string, HashFieldTtl> | null;
+}
+
+export interface HashFieldTtl {
+  ttl: number;
+  expires_at_ms: number | null;
+}
+
+export interface HashFieldExpiry {
+  mode: "EX" | "PX" | "EXAT" | "PXAT" | "PERSIST" | "KEEPTTL";
+  value?: number;
 }
 
 export interface StreamEntry {
@@ -103,6 +758,38 @@ export interface StreamRangeResult {
   count: number;
 }
 
+// HyperLogLog & Bitmap Types
+export interface HyperLogLogInfo {
+  cardinality: number;
+  encoding: "dense" | "sparse";
+  size_bytes: number;
+}
+
+export interface BitmapInfo {
+  byte_length: number;
+  bit_length: number;
+  set_bits: number;
+  first_set_bit: number | null;
+  first_clear_bit: number | null;
+}
+
+export interface BitmapRange {
+  byte_offset: number;
+  bytes: number[];
+  bits: string;
+  total_bytes: number;
+  has_more: boolean;
+}
+
+export interface BitfieldOperation {
+  op: "GET" | "SET" | "INCRBY";
+  signed: boolean;
+  bits: number;
+  offset: string;
+  value?: number;
+  overflow?: "WRAP" | "SAT" | "FAIL";
+}
+
 // Monitoring Types
 export interface ServerInfo {
   version: string;